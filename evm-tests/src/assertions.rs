@@ -1,444 +1,279 @@
 use crate::config::TestConfig;
-use crate::types::Spec;
-use crate::types::{InvalidTxReason, PostState};
+use crate::execution_results::UnexpectedOutcome;
+use crate::types::transaction::TxType;
+use crate::types::{PostState, Spec};
+use aurora_evm::executor::transaction::InvalidTransaction;
 use aurora_evm::{ExitError, ExitReason};
 
-/// Assert vicinity validation to ensure that the test expected validation error
-#[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
-pub fn assert_vicinity_validation(
-    reason: &InvalidTxReason,
+/// Return an `UnexpectedOutcome` with the formatted message unless `cond`.
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err(UnexpectedOutcome(format!($($arg)+)));
+        }
+    };
+}
+
+/// Whether the test expects one of the `exceptions`.
+fn is_expected(exceptions: &[&str], expect_exception: Option<&String>) -> bool {
+    expect_exception.is_some_and(|exception| exceptions.contains(&exception.as_str()))
+}
+
+/// Check vicinity validation to ensure that the test expected validation error
+pub fn check_vicinity_validation(
+    reason: &InvalidTransaction,
     states: &[PostState],
-    spec: &Spec,
+    spec: Spec,
     test_config: &TestConfig,
-) {
+) -> Result<(), UnexpectedOutcome> {
     let name = &test_config.name;
     let file_name = &test_config.file_name;
-    match *spec {
-        Spec::Istanbul | Spec::Berlin => match reason {
-            InvalidTxReason::GasPriceEip1559 => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!(
-                            "expected error message for test: [{spec:?}] {name}:{i}\n{file_name:?}"
-                        )
-                    });
-
-                    let is_checked = expected == "TR_TypeNotSupported"
-                        || expected == "TR_TypeNotSupportedBlob"
-                        || expected == "TransactionException.TYPE_2_TX_PRE_FORK";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            _ => panic!("Unexpected validation reason: {reason:?} [{name}]\n{file_name:?}"),
-        },
-        Spec::London => match reason {
-            InvalidTxReason::PriorityFeeTooLarge => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-                    let is_checked = expected == "tipTooHigh" || expected == "TR_TipGtFeeCap";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            InvalidTxReason::GasPriceLessThanBlockBaseFee => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-                    let is_checked =
-                        expected == "lowFeeCap" || expected == "TR_FeeCapLessThanBlocks";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            _ => {
-                panic!("Unexpected validation reason: {reason:?} [{spec:?}] {name}\n{file_name:?}")
-            }
-        },
-        Spec::Merge => match reason {
-            InvalidTxReason::PriorityFeeTooLarge => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-                    let is_checked = expected == "TR_TipGtFeeCap";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            InvalidTxReason::GasPriceLessThanBlockBaseFee => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-                    let is_checked = expected == "TR_FeeCapLessThanBlocks";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            _ => {
-                panic!("Unexpected validation reason: {reason:?} [{spec:?}] {name}\n{file_name:?}")
-            }
-        },
-        Spec::Shanghai => match reason {
-            InvalidTxReason::PriorityFeeTooLarge => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-                    let is_checked = expected == "TR_TipGtFeeCap";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            InvalidTxReason::GasPriceLessThanBlockBaseFee => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-
-                    let is_checked = expected == "TR_FeeCapLessThanBlocks";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            _ => {
-                panic!("Unexpected validation reason: {reason:?} [{spec:?}] {name}\n{file_name:?}")
-            }
-        },
-        Spec::Cancun => match reason {
-            InvalidTxReason::PriorityFeeTooLarge => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-
-                    let is_checked = expected == "TR_TipGtFeeCap"
-                        || expected == "TransactionException.PRIORITY_GREATER_THAN_MAX_FEE_PER_GAS";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            InvalidTxReason::GasPriceLessThanBlockBaseFee => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
+    for (i, state) in states.iter().enumerate() {
+        let expected = state.expect_exception.as_ref();
+        ensure!(
+            is_expected(expected_exceptions(*reason), expected),
+            "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
+        );
+    }
+    Ok(())
+}
 
-                    let is_checked = expected == "TR_FeeCapLessThanBlocks"
-                        || expected == "TransactionException.INSUFFICIENT_MAX_FEE_PER_GAS";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            _ => {
-                panic!("Unexpected validation reason: {reason:?} [{spec:?}] {name}\n{file_name:?}")
-            }
-        },
-        Spec::Prague => match reason {
-            InvalidTxReason::PriorityFeeTooLarge => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
+/// Exceptions of the tests expecting a transaction rejected with `reason`.
+#[must_use]
+pub const fn expected_exceptions(reason: InvalidTransaction) -> &'static [&'static str] {
+    match reason {
+        InvalidTransaction::TypeNotSupported => &[
+            "TR_TypeNotSupported",
+            "TR_TypeNotSupportedBlob",
+            "TransactionException.TYPE_1_TX_PRE_FORK",
+            "TransactionException.TYPE_2_TX_PRE_FORK",
+            "TransactionException.TYPE_3_TX_PRE_FORK",
+            "TransactionException.TYPE_3_TX_PRE_FORK|TransactionException.TYPE_3_TX_ZERO_BLOBS",
+            "TransactionException.TYPE_4_TX_PRE_FORK",
+        ],
+        InvalidTransaction::GasLimitExceeded => {
+            &["TransactionException.GAS_LIMIT_EXCEEDS_MAXIMUM"]
+        }
+        InvalidTransaction::NonceTooLow => &["TransactionException.NONCE_MISMATCH_TOO_LOW"],
+        InvalidTransaction::NonceTooHigh => &["TransactionException.NONCE_MISMATCH_TOO_HIGH"],
+        InvalidTransaction::InsufficientFunds => &[
+            "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS",
+            "TR_NoFunds",
+            "TR_NoFundsX",
+            "TransactionException.INSUFFICIENT_MAX_FEE_PER_BLOB_GAS",
+            "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS|TransactionException.GASLIMIT_PRICE_PRODUCT_OVERFLOW",
+        ],
+        InvalidTransaction::CostOverflow => &[
+            "TR_NoFunds",
+            "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS",
+            "TransactionException.GASLIMIT_PRICE_PRODUCT_OVERFLOW",
+            "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS|TransactionException.GASLIMIT_PRICE_PRODUCT_OVERFLOW",
+        ],
+        InvalidTransaction::BlockGasLimitExceeded => &[
+            "TR_GasLimitReached",
+            "TransactionException.GAS_ALLOWANCE_EXCEEDED",
+        ],
+        InvalidTransaction::IntrinsicGasTooLow => &[
+            "TR_NoFundsOrGas",
+            "TR_IntrinsicGas",
+            "TransactionException.INTRINSIC_GAS_TOO_LOW",
+            "IntrinsicGas",
+            "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS|TransactionException.INTRINSIC_GAS_TOO_LOW",
+            "TransactionException.INTRINSIC_GAS_TOO_LOW|TransactionException.INTRINSIC_GAS_BELOW_FLOOR_GAS_COST",
+        ],
+        InvalidTransaction::GasFloorMoreThanGasLimit => &[
+            "TransactionException.INTRINSIC_GAS_TOO_LOW",
+            "TransactionException.INTRINSIC_GAS_BELOW_FLOOR_GAS_COST",
+            "TransactionException.INTRINSIC_GAS_TOO_LOW|TransactionException.INTRINSIC_GAS_BELOW_FLOOR_GAS_COST",
+        ],
+        InvalidTransaction::SenderNotEoa => {
+            &["SenderNotEOA", "TransactionException.SENDER_NOT_EOA"]
+        }
+        InvalidTransaction::AccessListNotSupported => &["TransactionException.TYPE_1_TX_PRE_FORK"],
+        InvalidTransaction::BlobVersionNotSupported => &[
+            "TransactionException.TYPE_3_TX_INVALID_BLOB_VERSIONED_HASH",
+            "TR_BLOBVERSION_INVALID",
+        ],
+        InvalidTransaction::BlobCreateTransaction => &[
+            "TR_BLOBCREATE",
+            "TransactionException.TYPE_3_TX_CONTRACT_CREATION",
+        ],
+        InvalidTransaction::BlobGasPriceGreaterThanMax => {
+            &["TransactionException.INSUFFICIENT_MAX_FEE_PER_BLOB_GAS"]
+        }
+        InvalidTransaction::TooManyBlobs => &[
+            "TR_BLOBLIST_OVERSIZE",
+            "TransactionException.TYPE_3_TX_BLOB_COUNT_EXCEEDED",
+            "TransactionException.TYPE_3_TX_MAX_BLOB_GAS_ALLOWANCE_EXCEEDED|TransactionException.TYPE_3_TX_BLOB_COUNT_EXCEEDED",
+        ],
+        InvalidTransaction::EmptyBlobs => &[
+            "TransactionException.TYPE_3_TX_ZERO_BLOBS",
+            "TR_EMPTYBLOB",
+        ],
+        InvalidTransaction::MaxFeePerBlobGasNotSupported => {
+            &["TransactionException.TYPE_3_TX_PRE_FORK|TransactionException.TYPE_3_TX_ZERO_BLOBS"]
+        }
+        InvalidTransaction::BlobVersionedHashesNotSupported => &[
+            "TransactionException.TYPE_3_TX_PRE_FORK",
+            "TR_TypeNotSupportedBlob",
+        ],
+        InvalidTransaction::InvalidAuthorizationChain => {
+            &["TransactionException.TYPE_4_INVALID_AUTHORIZATION_FORMAT"]
+        }
+        InvalidTransaction::InvalidAuthorizationSignature => {
+            &["TransactionException.TYPE_4_INVALID_AUTHORITY_SIGNATURE"]
+        }
+        InvalidTransaction::EmptyAuthorizationList => &[
+            "TransactionException.TYPE_4_EMPTY_AUTHORIZATION_LIST",
+            "TransactionException.TYPE_4_TX_CONTRACT_CREATION",
+        ],
+        InvalidTransaction::SetCodeCreateTransaction => {
+            &["TransactionException.TYPE_4_TX_CONTRACT_CREATION"]
+        }
+        InvalidTransaction::AuthorizationListNotSupported => {
+            &["TransactionException.TYPE_4_TX_PRE_FORK"]
+        }
+        InvalidTransaction::DynamicFeeNotSupported => &[
+            "TR_TypeNotSupported",
+            "TR_TypeNotSupportedBlob",
+            "TransactionException.TYPE_2_TX_PRE_FORK",
+        ],
+        InvalidTransaction::PriorityFeeTooLarge => &[
+            "tipTooHigh",
+            "TR_TipGtFeeCap",
+            "TransactionException.PRIORITY_GREATER_THAN_MAX_FEE_PER_GAS",
+        ],
+        InvalidTransaction::GasPriceLessThanBaseFee => &[
+            "lowFeeCap",
+            "TR_FeeCapLessThanBlocks",
+            "TransactionException.INSUFFICIENT_MAX_FEE_PER_GAS",
+        ],
+        InvalidTransaction::InvalidSignature => &["TransactionException.INVALID_SIGNATURE_VRS"],
+        InvalidTransaction::InitCodeSizeExceeded => &[
+            "TR_InitCodeLimitExceeded",
+            "TransactionException.INITCODE_SIZE_EXCEEDED",
+        ],
+    }
+}
 
-                    let is_checked =
-                        expected == "TransactionException.PRIORITY_GREATER_THAN_MAX_FEE_PER_GAS";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
+/// Check the expected exception of a transaction rejected by validation.
+pub fn check_validate_exit_reason(
+    reason: &InvalidTransaction,
+    expect_exception: Option<&String>,
+    name: &str,
+    spec: Spec,
+) -> Result<(), UnexpectedOutcome> {
+    ensure!(
+        is_expected(expected_exceptions(*reason), expect_exception),
+        "unexpected exception {expect_exception:?} for {reason:?} for test: [{spec:?}] {name}"
+    );
+    Ok(())
+}
 
-            InvalidTxReason::GasPriceLessThanBlockBaseFee => {
-                for (i, state) in states.iter().enumerate() {
-                    let expected = state.expect_exception.as_deref().unwrap_or_else(|| {
-                        panic!("expected error message for test: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}")
-                    });
-                    let is_checked = expected == "TR_FeeCapLessThanBlocks"
-                        || expected == "TransactionException.INSUFFICIENT_MAX_FEE_PER_GAS";
-                    assert!(
-                        is_checked,
-                        "unexpected error message {expected:?} for: {reason:?} [{spec:?}] {name}:{i}\n{file_name:?}",
-                    );
-                }
-            }
-            _ => {
-                panic!("Unexpected validation reason: {reason:?} [{spec:?}] {name}\n{file_name:?}")
-            }
-        },
-        _ => panic!("Unexpected validation reason: {reason:?} [{spec:?}] {name}\n{file_name:?}"),
+/// Exceptions of the tests expecting a transaction of `tx_type` rejected by a
+/// fork that does not support its type, `None` for an unknown type.
+#[must_use]
+pub const fn type_not_supported_exceptions(tx_type: Option<TxType>) -> &'static [&'static str] {
+    match tx_type {
+        Some(TxType::Legacy) => &[],
+        Some(TxType::AccessList) => &[
+            "TR_TypeNotSupported",
+            "TransactionException.TYPE_1_TX_PRE_FORK",
+        ],
+        Some(TxType::DynamicFee) => &[
+            "TR_TypeNotSupported",
+            "TransactionException.TYPE_2_TX_PRE_FORK",
+        ],
+        Some(TxType::ShardBlob) => &[
+            "TR_TypeNotSupportedBlob",
+            "TransactionException.TYPE_3_TX_PRE_FORK",
+            "TransactionException.TYPE_3_TX_PRE_FORK|TransactionException.TYPE_3_TX_ZERO_BLOBS",
+        ],
+        Some(TxType::EOAAccountCode) => &["TransactionException.TYPE_4_TX_PRE_FORK"],
+        None => &["TR_TypeNotSupported"],
     }
 }
 
-/// Check Exit Reason of EVM execution
-#[allow(clippy::too_many_lines)]
-pub fn check_validate_exit_reason(
-    reason: &InvalidTxReason,
+/// Check the expected exception of a transaction of `tx_type` rejected as
+/// not supported by the fork.
+pub fn check_type_not_supported(
+    tx_type: Option<TxType>,
     expect_exception: Option<&String>,
     name: &str,
-    spec: &Spec,
-) -> bool {
-    expect_exception.map_or_else(
-        || {
-            panic!("unexpected validation error reason: {reason:?} {name}");
-        },
-        |exception| {
-            match reason {
-                InvalidTxReason::OutOfFund => {
-                    let check_result = exception
-                        == "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS"
-                        || exception == "TR_NoFunds"
-                        || exception == "TR_NoFundsX"
-                        || exception == "TransactionException.INSUFFICIENT_MAX_FEE_PER_BLOB_GAS"
-                        || exception == "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS|TransactionException.GASLIMIT_PRICE_PRODUCT_OVERFLOW";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for OutOfFund for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::GasLimitReached => {
-                    let check_result = exception == "TR_GasLimitReached"
-                        || exception == "TransactionException.GAS_ALLOWANCE_EXCEEDED";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for GasLimitReached for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::IntrinsicGas => {
-                    let check_result = exception == "TR_NoFundsOrGas"
-                        || exception == "TR_IntrinsicGas"
-                        || exception == "TransactionException.INTRINSIC_GAS_TOO_LOW"
-                        || exception == "IntrinsicGas"
-                        || exception == "TransactionException.INSUFFICIENT_ACCOUNT_FUNDS|TransactionException.INTRINSIC_GAS_TOO_LOW"
-                        || exception == "TransactionException.INTRINSIC_GAS_TOO_LOW|TransactionException.INTRINSIC_GAS_BELOW_FLOOR_GAS_COST";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for IntrinsicGas for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::BlobVersionNotSupported => {
-                    let check_result = exception
-                        == "TransactionException.TYPE_3_TX_INVALID_BLOB_VERSIONED_HASH"
-                        || exception == "TR_BLOBVERSION_INVALID";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for BlobVersionNotSupported for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::BlobCreateTransaction => {
-                    let check_result = exception == "TR_BLOBCREATE"
-                        || exception == "TransactionException.TYPE_3_TX_CONTRACT_CREATION";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for BlobCreateTransaction for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::BlobGasPriceGreaterThanMax => {
-                    let check_result =
-                        exception == "TransactionException.INSUFFICIENT_MAX_FEE_PER_BLOB_GAS";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for BlobGasPriceGreaterThanMax for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::TooManyBlobs => {
-                    let check_result = exception == "TR_BLOBLIST_OVERSIZE"
-                        || exception == "TransactionException.TYPE_3_TX_BLOB_COUNT_EXCEEDED"
-                        || exception == "TransactionException.TYPE_3_TX_MAX_BLOB_GAS_ALLOWANCE_EXCEEDED|TransactionException.TYPE_3_TX_BLOB_COUNT_EXCEEDED";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for TooManyBlobs for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::EmptyBlobs => {
-                    let check_result = exception == "TransactionException.TYPE_3_TX_ZERO_BLOBS"
-                        || exception == "TR_EMPTYBLOB";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for EmptyBlobs for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::MaxFeePerBlobGasNotSupported => {
-                    let check_result =
-                        exception == "TransactionException.TYPE_3_TX_PRE_FORK|TransactionException.TYPE_3_TX_ZERO_BLOBS";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for MaxFeePerBlobGasNotSupported for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::BlobVersionedHashesNotSupported => {
-                    let check_result = exception == "TransactionException.TYPE_3_TX_PRE_FORK"
-                        || exception == "TR_TypeNotSupportedBlob";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for BlobVersionedHashesNotSupported for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::InvalidAuthorizationChain => {
-                    let check_result = exception == "TransactionException.TYPE_4_INVALID_AUTHORIZATION_FORMAT";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for InvalidAuthorizationChain for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::InvalidAuthorizationSignature => {
-                    let check_result = exception == "TransactionException.TYPE_4_INVALID_AUTHORITY_SIGNATURE";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for InvalidAuthorizationSignature for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::AuthorizationListNotExist => {
-                    let check_result = exception == "TransactionException.TYPE_4_EMPTY_AUTHORIZATION_LIST" || exception == "TransactionException.TYPE_4_TX_CONTRACT_CREATION";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for AuthorizationListNotExist for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::CreateTransaction => {
-                    let check_result = exception == "TransactionException.TYPE_4_TX_CONTRACT_CREATION";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for CreateTransaction for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::GasFloorMoreThanGasLimit => {
-                    let check_result = exception == "TransactionException.INTRINSIC_GAS_TOO_LOW"
-                        || exception == "TransactionException.INTRINSIC_GAS_BELOW_FLOOR_GAS_COST"
-                        || exception == "TransactionException.INTRINSIC_GAS_TOO_LOW|TransactionException.INTRINSIC_GAS_BELOW_FLOOR_GAS_COST";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for GasFloorMoreThanGasLimit for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::AuthorizationListNotSupportedForCreate => {
-                    let check_result = exception == "TransactionException.TYPE_4_TX_CONTRACT_CREATION";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for AuthorizationListNotSupportedForCreate for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::AuthorizationListNotSupported => {
-                    let check_result = exception == "TransactionException.TYPE_4_TX_PRE_FORK";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for AuthorizationListNotSupported for test: [{spec:?}] {name}"
-                    );
-                }
-                InvalidTxReason::AccessListNotSupported => {
-                    let check_result = exception == "TransactionException.TYPE_1_TX_PRE_FORK";
-                    assert!(
-                        check_result,
-                        "unexpected exception {exception:?} for AccessListNotSupported for test: [{spec:?}] {name}"
-                    );
-                }
-                _ => {
-                    panic!(
-                        "unexpected exception {exception:?} for reason {reason:?} for test: [{spec:?}] {name}"
-                    );
-                }
-            }
-            true
-        },
-    )
+    spec: Spec,
+) -> Result<(), UnexpectedOutcome> {
+    ensure!(
+        is_expected(type_not_supported_exceptions(tx_type), expect_exception),
+        "unexpected exception {expect_exception:?} for transaction type {tx_type:?} for test: [{spec:?}] {name}"
+    );
+    Ok(())
 }
 
 /// Validate EIP-3607 - empty create caller
-pub fn assert_empty_create_caller(expect_exception: Option<&String>, name: &str) {
-    let exception = expect_exception.expect("expected evm-json-test exception");
-    let check_exception =
-        exception == "SenderNotEOA" || exception == "TransactionException.SENDER_NOT_EOA";
-    assert!(
-        check_exception,
+pub fn check_empty_create_caller(
+    expect_exception: Option<&String>,
+    name: &str,
+) -> Result<(), UnexpectedOutcome> {
+    ensure!(
+        is_expected(
+            expected_exceptions(InvalidTransaction::SenderNotEoa),
+            expect_exception
+        ),
         "expected EmptyCaller exception for test: {name}: {expect_exception:?}"
     );
+    Ok(())
 }
 
 /// Check call expected exception
-pub fn assert_call_exit_exception(expect_exception: Option<&String>, name: &str, spec: &Spec) {
-    assert!(
+pub fn check_call_exit_exception(
+    expect_exception: Option<&String>,
+    name: &str,
+    spec: Spec,
+) -> Result<(), UnexpectedOutcome> {
+    ensure!(
         expect_exception.is_none(),
         "unexpected call exception: {expect_exception:?} for test: {name} [{spec:?}]"
     );
+    Ok(())
 }
 
-/// Check Exit Reason of EVM execution
+/// Check Exit Reason of EVM execution. Return whether the creation failed
+/// with the exception expected by the test.
 pub fn check_create_exit_reason(
     reason: &ExitReason,
     expect_exception: Option<&String>,
     name: &str,
-) -> bool {
+) -> Result<bool, UnexpectedOutcome> {
     match reason {
         ExitReason::Error(err) => {
-            if let Some(exception) = expect_exception {
-                match err {
-                    ExitError::CreateContractLimit => {
-                        let check_result = exception == "TR_InitCodeLimitExceeded"
-                            || exception == "TransactionException.INITCODE_SIZE_EXCEEDED";
-                        assert!(
-                            check_result,
-                            "unexpected exception {exception:?} for CreateContractLimit error for test: {name}"
-                        );
-                        return true;
-                    }
-                    ExitError::MaxNonce => {
-                        let check_result = exception == "TR_NonceHasMaxValue"
-                            || exception == "TransactionException.NONCE_IS_MAX";
-                        assert!(check_result,
-                                "unexpected exception {exception:?} for MaxNonce error for test: {name}"
-                        );
-                        return true;
-                    }
-                    ExitError::OutOfGas => {
-                        let check_result =
-                            exception == "TransactionException.INTRINSIC_GAS_TOO_LOW";
-                        assert!(check_result,
-                                "unexpected exception {exception:?} for OutOfGas error for test: {name}"
-                        );
-                        return true;
-                    }
-                    _ => {
-                        panic!(
-                            "unexpected error: {err:?} for exception: {exception} for test: {name}"
-                        )
-                    }
+            let Some(exception) = expect_exception else {
+                return Ok(false);
+            };
+            let expected: &[&str] = match err {
+                ExitError::CreateContractLimit => {
+                    expected_exceptions(InvalidTransaction::InitCodeSizeExceeded)
                 }
-            } else {
-                return false;
-            }
-        }
-        ExitReason::Fatal(err) => {
-            panic!("Unexpected error: {err:?}")
+                ExitError::MaxNonce => {
+                    &["TR_NonceHasMaxValue", "TransactionException.NONCE_IS_MAX"]
+                }
+                ExitError::OutOfGas => &["TransactionException.INTRINSIC_GAS_TOO_LOW"],
+                _ => &[],
+            };
+            ensure!(
+                expected.contains(&exception.as_str()),
+                "unexpected exception {exception:?} for {err:?} error for test: {name}"
+            );
+            Ok(true)
         }
+        ExitReason::Fatal(err) => Err(UnexpectedOutcome(format!(
+            "Unexpected error: {err:?} for test: {name}"
+        ))),
         _ => {
-            assert!(
+            ensure!(
                 expect_exception.is_none(),
                 "Unexpected json-test error: {expect_exception:?} with reason {reason:?} for: {name}"
             );
+            Ok(false)
         }
     }
-    false
 }
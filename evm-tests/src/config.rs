@@ -1,5 +1,7 @@
 use crate::types::Spec;
+use regex::Regex;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Default, Debug, Clone)]
 #[allow(clippy::struct_excessive_bools)]
@@ -18,4 +20,157 @@ pub struct TestConfig {
     pub spec: Option<Spec>,
     pub file_name: PathBuf,
     pub name: String,
+    /// Collect the executed transactions in `TestExecutionResult::dump_successful_txs`.
+    pub collect_transactions: bool,
+}
+
+/// Options of [`crate::run_case`].
+#[derive(Default, Debug, Clone)]
+pub struct RunOptions {
+    /// Name of the test case, reported in [`crate::CaseResult::failed`].
+    pub name: String,
+    /// Check only the post states of this fork, all supported forks if `None`.
+    pub spec: Option<Spec>,
+    /// Collect the executed transactions in [`crate::CaseResult::transactions`].
+    pub collect_transactions: bool,
+}
+
+impl From<&RunOptions> for TestConfig {
+    fn from(options: &RunOptions) -> Self {
+        Self {
+            verbose_output: VerboseOutput::default(),
+            spec: options.spec,
+            file_name: PathBuf::new(),
+            name: options.name.clone(),
+            collect_transactions: options.collect_transactions,
+        }
+    }
+}
+
+/// Selection of the state tests to run.
+#[derive(Default, Debug, Clone)]
+pub struct TestFilter {
+    /// Run only the tests whose name contains this string.
+    pub test_name: Option<String>,
+    /// Run only the tests whose name or file path matches.
+    pub include: Option<Regex>,
+    /// Skip the tests whose name or file path matches.
+    pub exclude: Option<Regex>,
+    /// Run only the tests of this shard.
+    pub shard: Option<Shard>,
+}
+
+impl TestFilter {
+    /// Whether the test `name` of the file `path` is selected.
+    #[must_use]
+    pub fn matches(&self, path: &str, name: &str) -> bool {
+        let is_match = |regex: &Regex| regex.is_match(name) || regex.is_match(path);
+        self.test_name.as_ref().is_none_or(|t| name.contains(t))
+            && self.include.as_ref().is_none_or(is_match)
+            && !self.exclude.as_ref().is_some_and(is_match)
+            && self.shard.is_none_or(|shard| shard.contains(name))
+    }
+}
+
+/// Shard `index` of `count`, parsed from `index/count`, with tests assigned
+/// to shards by the hash of their name. The hash does not depend on the
+/// platform or on the location of the test files, so that CI machines can
+/// each run one shard of the same suites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    /// Index of the shard, lower than `count`.
+    pub index: u64,
+    /// Number of shards.
+    pub count: u64,
+}
+
+impl Shard {
+    /// Whether the test `name` belongs to the shard.
+    #[must_use]
+    pub fn contains(self, name: &str) -> bool {
+        // FNV-1a
+        let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        hash % self.count == self.index
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid shard `{s}`, expected `i/n` with 0 <= i < n");
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index = index.trim().parse().map_err(|_| invalid())?;
+        let count = count.trim().parse().map_err(|_| invalid())?;
+        if index >= count {
+            return Err(invalid());
+        }
+        Ok(Self { index, count })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shard_from_str() {
+        assert_eq!(" 1 / 3 ".parse(), Ok(Shard { index: 1, count: 3 }));
+        assert_eq!("0/1".parse(), Ok(Shard { index: 0, count: 1 }));
+        for invalid in ["0/0", "2/2", "3/2", "1", "a/2", "1/2/3", "-1/2"] {
+            assert!(invalid.parse::<Shard>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_shards_cover_every_test_once() {
+        let count = 4;
+        for test in 0..1000 {
+            let name = format!("test{test}");
+            let shards = (0..count)
+                .filter(|&index| Shard { index, count }.contains(&name))
+                .count();
+            assert_eq!(shards, 1, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let path = "GeneralStateTests/stCreate2/create2collision.json";
+        let filter = TestFilter {
+            include: Some(Regex::new("stCreate2").unwrap()),
+            ..TestFilter::default()
+        };
+        assert!(filter.matches(path, "create2collision_d0g0v0"));
+        assert!(!filter.matches("stCall/call.json", "call_d0g0v0"));
+
+        let filter = TestFilter {
+            include: Some(Regex::new("collision_d1").unwrap()),
+            ..TestFilter::default()
+        };
+        assert!(filter.matches(path, "create2collision_d1g0v0"));
+        assert!(!filter.matches(path, "create2collision_d0g0v0"));
+
+        let filter = TestFilter {
+            exclude: Some(Regex::new("stCreate2").unwrap()),
+            ..TestFilter::default()
+        };
+        assert!(!filter.matches(path, "create2collision_d0g0v0"));
+        let filter = TestFilter {
+            exclude: Some(Regex::new("_d0").unwrap()),
+            ..TestFilter::default()
+        };
+        assert!(!filter.matches(path, "create2collision_d0g0v0"));
+        assert!(filter.matches(path, "create2collision_d1g0v0"));
+
+        let filter = TestFilter {
+            test_name: Some("collision".to_owned()),
+            shard: Some(Shard { index: 0, count: 1 }),
+            ..TestFilter::default()
+        };
+        assert!(filter.matches(path, "create2collision_d0g0v0"));
+        assert!(!filter.matches(path, "create2_d0g0v0"));
+    }
 }
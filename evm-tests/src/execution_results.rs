@@ -4,6 +4,7 @@ use aurora_evm::executor::stack::Authorization;
 use primitive_types::{H160, H256, U256};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::fmt;
 use std::time::Duration;
 
 #[derive(Clone, Debug)]
@@ -25,6 +26,51 @@ pub struct TestExecutionResult {
     pub dump_successful_txs: Vec<RawInput>,
 }
 
+/// Transaction outcome not matching the exception expected by a state test,
+/// which makes its post states meaningless.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnexpectedOutcome(pub String);
+
+impl fmt::Display for UnexpectedOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UnexpectedOutcome {}
+
+/// Outcome of [`crate::run_case`].
+#[derive(Clone, Debug, Default)]
+pub struct CaseResult {
+    /// Number of post states checked.
+    pub total: u64,
+    /// Post states whose resulting state doesn't match the expected hash.
+    pub failed: Vec<FailedTestDetails>,
+    /// Executed transactions, if [`crate::RunOptions::collect_transactions`]
+    /// is set.
+    pub transactions: Vec<RawInput>,
+}
+
+impl CaseResult {
+    /// Whether all the post states match.
+    #[must_use]
+    // TODO: rust-v1.87 - const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl From<TestExecutionResult> for CaseResult {
+    fn from(result: TestExecutionResult) -> Self {
+        Self {
+            total: result.total,
+            failed: result.failed_tests,
+            transactions: result.dump_successful_txs,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RawInput {
     pub spec: RawSpec,
@@ -99,7 +145,7 @@ impl From<Spec> for RawSpec {
         match spec {
             Spec::Frontier => Self::Frontier,
             Spec::Homestead => Self::Homestead,
-            Spec::Tangerine => Self::TangerineWhistle,
+            Spec::TangerineWhistle => Self::TangerineWhistle,
             Spec::SpuriousDragon => Self::SpuriousDragon,
             Spec::Byzantium => Self::Byzantium,
             Spec::Constantinople => Self::Constantinople,
@@ -0,0 +1,21 @@
+//! Ethereum JSON tests runner.
+//!
+//! The `aurora-evm-jsontests` binary is built on top of this library, so
+//! other tools can run a single state test with exactly the same semantics
+//! through [`run_case`].
+
+#![allow(clippy::too_long_first_doc_paragraph, clippy::missing_panics_doc)]
+
+pub mod config;
+pub mod execution_results;
+pub mod state;
+pub mod types;
+pub mod vm;
+
+mod assertions;
+mod precompiles;
+mod state_dump;
+
+pub use config::RunOptions;
+pub use execution_results::{CaseResult, UnexpectedOutcome};
+pub use state::run_case;
@@ -1,27 +1,19 @@
 #![allow(clippy::too_long_first_doc_paragraph, clippy::missing_panics_doc)]
 
-use crate::config::{TestConfig, VerboseOutput};
-use crate::execution_results::TestExecutionResult;
-use crate::types::Spec;
-use crate::types::StateTestCase;
-use crate::types::VmTestCase;
+use aurora_evm_jsontests::config::{Shard, TestConfig, TestFilter, VerboseOutput};
+use aurora_evm_jsontests::execution_results::TestExecutionResult;
+use aurora_evm_jsontests::types::spec::parse_spec;
+use aurora_evm_jsontests::types::Spec;
+use aurora_evm_jsontests::types::StateTestCase;
+use aurora_evm_jsontests::types::VmTestCase;
+use aurora_evm_jsontests::{state, vm};
 use clap::{arg, command, value_parser, ArgAction, Command};
+use regex::Regex;
 use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Component, Path, PathBuf};
-use std::str::FromStr;
-
-pub mod state;
-pub mod types;
-pub mod vm;
-
-mod assertions;
-mod config;
-mod execution_results;
-mod precompiles;
-mod state_dump;
 
 #[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
 fn main() -> Result<(), String> {
@@ -62,6 +54,21 @@ fn main() -> Result<(), String> {
                         .required(false)
                         .value_parser(value_parser!(String))
                 )
+                .arg(
+                    arg!(--filter <REGEX> "Run only the tests whose name or file path matches the regex, for ex: \"eip7702|eip2537\"")
+                        .required(false)
+                        .value_parser(value_parser!(Regex)),
+                )
+                .arg(
+                    arg!(--exclude <REGEX> "Skip the tests whose name or file path matches the regex")
+                        .required(false)
+                        .value_parser(value_parser!(Regex)),
+                )
+                .arg(
+                    arg!(--shard <SHARD> "Run only the shard `i/n` of the tests, with 0 <= i < n, split by the hash of the test names")
+                        .required(false)
+                        .value_parser(value_parser!(Shard)),
+                )
                 .arg(arg!(-s --spec <SPEC> "Ethereum hard fork"))
                 .arg(
                     arg!(-v --verbose "Verbose output")
@@ -125,9 +132,14 @@ fn main() -> Result<(), String> {
     if let Some(matches) = matches.subcommand_matches("state") {
         let spec: Option<Spec> = matches
             .get_one::<String>("spec")
-            .and_then(|spec| Spec::from_str(spec).ok());
+            .and_then(|spec| parse_spec(spec).ok());
 
-        let test_name: Option<&String> = matches.get_one::<String>("test-name");
+        let filter = TestFilter {
+            test_name: matches.get_one::<String>("test-name").cloned(),
+            include: matches.get_one::<Regex>("filter").cloned(),
+            exclude: matches.get_one::<Regex>("exclude").cloned(),
+            shard: matches.get_one::<Shard>("shard").copied(),
+        };
 
         let verbose_output = VerboseOutput {
             verbose: matches.get_flag("verbose"),
@@ -150,7 +162,7 @@ fn main() -> Result<(), String> {
                     &verbose_output,
                     src_path,
                     &mut tests_result,
-                    test_name,
+                    &filter,
                 );
             } else if src_path.is_dir() {
                 run_test_for_dir(
@@ -158,7 +170,7 @@ fn main() -> Result<(), String> {
                     &verbose_output,
                     src_path,
                     &mut tests_result,
-                    test_name,
+                    &filter,
                 );
             }
         }
@@ -258,7 +270,7 @@ fn run_test_for_dir<P: AsRef<Path>>(
     verbose_output: &VerboseOutput,
     dir_name: &P,
     tests_result: &mut TestExecutionResult,
-    test_name: Option<&String>,
+    filter: &TestFilter,
 ) {
     if should_skip(dir_name.as_ref()) {
         println!("Skipping the test case {}", dir_name.as_ref().display());
@@ -273,9 +285,9 @@ fn run_test_for_dir<P: AsRef<Path>>(
         }
         let path = entry.path();
         if path.is_dir() {
-            run_test_for_dir(spec, verbose_output, &path, tests_result, test_name);
+            run_test_for_dir(spec, verbose_output, &path, tests_result, filter);
         } else {
-            run_test_for_file(spec, verbose_output, &path, tests_result, test_name);
+            run_test_for_file(spec, verbose_output, &path, tests_result, filter);
         }
     }
 }
@@ -285,7 +297,7 @@ fn run_test_for_file<P: AsRef<Path>>(
     verbose_output: &VerboseOutput,
     file_path: &P,
     tests_result: &mut TestExecutionResult,
-    test_name: Option<&String>,
+    filter: &TestFilter,
 ) {
     if should_skip(file_path.as_ref()) {
         if verbose_output.verbose {
@@ -306,17 +318,16 @@ fn run_test_for_file<P: AsRef<Path>>(
         .expect("Parse test cases failed");
 
     for (name, test) in test_suite {
-        if let Some(t) = test_name {
-            if !name.contains(t) {
-                continue;
-            }
+        if !filter.matches(file_name, &name) {
+            continue;
         }
 
         let test_config = TestConfig {
             verbose_output: verbose_output.clone(),
-            spec: spec.cloned(),
+            spec: spec.copied(),
             file_name: file_path.as_ref().to_path_buf(),
             name,
+            collect_transactions: verbose_output.dump_transactions.is_some(),
         };
         let test_res = state::test(test_config, test);
 
@@ -368,8 +379,6 @@ const SKIPPED_CASES: &[&str] = &[
     "eip7702_set_code_tx/set_code_txs/invalid_tx_invalid_auth_signature",
     // Wrong json field `chain_id` for EIP-7702
     "eip7702_set_code_tx/set_code_txs/tx_validity_nonce",
-    // EIP-7702: for non empty storage fails evm state hash check
-    "eip7702_set_code_tx/set_code_txs/set_code_to_non_empty_storage",
 ];
 
 #[cfg(not(feature = "enable-slow-tests"))]
@@ -390,8 +399,6 @@ const SKIPPED_CASES: &[&str] = &[
     "eip7702_set_code_tx/set_code_txs/invalid_tx_invalid_auth_signature",
     // Wrong json field `chain_id` for EIP-7702
     "eip7702_set_code_tx/set_code_txs/tx_validity_nonce",
-    // EIP-7702: for non empty storage fails evm state hash check
-    "eip7702_set_code_tx/set_code_txs/set_code_to_non_empty_storage",
 ];
 
 /// Check if a path should be skipped.
@@ -22,29 +22,37 @@ use aurora_evm::{ExitError, ExitSucceed, Opcode};
 use primitive_types::H160;
 use std::collections::BTreeMap;
 
-pub struct Precompiles(BTreeMap<H160, Box<dyn Precompile>>);
+pub struct Precompiles {
+    precompiles: BTreeMap<H160, Box<dyn Precompile>>,
+    /// Call stack limit of the hard fork, reported by `CallTooDeep` errors.
+    call_stack_limit: usize,
+}
 
 impl PrecompileSet for Precompiles {
     fn execute(
         &self,
         handle: &mut impl PrecompileHandle,
     ) -> Option<Result<PrecompileOutput, PrecompileFailure>> {
-        let p = self.0.get(&handle.code_address())?;
-        let result = process_precompile(p.as_ref(), handle);
+        let p = self.precompiles.get(&handle.code_address())?;
+        let result = process_precompile(p.as_ref(), handle, self.call_stack_limit);
         Some(result.and_then(|output| post_process(output, handle)))
     }
 
     fn is_precompile(&self, address: H160) -> bool {
-        self.0.contains_key(&address)
+        self.precompiles.contains_key(&address)
+    }
+
+    fn precompile_addresses(&self) -> Vec<H160> {
+        self.precompiles.keys().copied().collect()
     }
 }
 
 impl Precompiles {
-    pub fn new(spec: &Spec) -> Self {
-        match *spec {
+    pub fn new(spec: Spec, call_stack_limit: usize) -> Self {
+        let precompiles = match spec {
             Spec::Frontier
             | Spec::Homestead
-            | Spec::Tangerine
+            | Spec::TangerineWhistle
             | Spec::SpuriousDragon
             | Spec::Byzantium
             | Spec::Constantinople
@@ -54,10 +62,14 @@ impl Precompiles {
             Spec::Cancun => Self::new_cancun(),
             Spec::Prague => Self::new_prague(),
             Spec::Osaka => Self::new_osaka(),
+        };
+        Self {
+            precompiles,
+            call_stack_limit,
         }
     }
 
-    pub fn new_istanbul() -> Self {
+    fn new_istanbul() -> BTreeMap<H160, Box<dyn Precompile>> {
         let mut map = BTreeMap::new();
         map.insert(
             ECRecover::ADDRESS.raw(),
@@ -83,10 +95,10 @@ impl Precompiles {
             Box::new(Bn256Pair::<Istanbul>::new()),
         );
         map.insert(Blake2F::ADDRESS.raw(), Box::new(Blake2F));
-        Self(map)
+        map
     }
 
-    pub fn new_berlin() -> Self {
+    fn new_berlin() -> BTreeMap<H160, Box<dyn Precompile>> {
         let mut map = BTreeMap::new();
         map.insert(
             ECRecover::ADDRESS.raw(),
@@ -112,17 +124,17 @@ impl Precompiles {
             Box::new(Bn256Pair::<Istanbul>::new()),
         );
         map.insert(Blake2F::ADDRESS.raw(), Box::new(Blake2F));
-        Self(map)
+        map
     }
 
-    pub fn new_cancun() -> Self {
-        let mut map = Self::new_berlin().0;
+    fn new_cancun() -> BTreeMap<H160, Box<dyn Precompile>> {
+        let mut map = Self::new_berlin();
         map.insert(Kzg::ADDRESS, Box::new(Kzg));
-        Self(map)
+        map
     }
 
-    pub fn new_prague() -> Self {
-        let mut map = Self::new_cancun().0;
+    fn new_prague() -> BTreeMap<H160, Box<dyn Precompile>> {
+        let mut map = Self::new_cancun();
         map.insert(BlsG1Add::ADDRESS.raw(), Box::new(BlsG1Add));
         map.insert(BlsG1Msm::ADDRESS.raw(), Box::new(BlsG1Msm));
         map.insert(BlsG2Add::ADDRESS.raw(), Box::new(BlsG2Add));
@@ -130,10 +142,10 @@ impl Precompiles {
         map.insert(BlsPairingCheck::ADDRESS.raw(), Box::new(BlsPairingCheck));
         map.insert(BlsMapFpToG1::ADDRESS.raw(), Box::new(BlsMapFpToG1));
         map.insert(BlsMapFp2ToG2::ADDRESS.raw(), Box::new(BlsMapFp2ToG2));
-        Self(map)
+        map
     }
 
-    pub fn new_osaka() -> Self {
+    fn new_osaka() -> BTreeMap<H160, Box<dyn Precompile>> {
         let mut map = BTreeMap::new();
         map.insert(
             ECRecover::ADDRESS.raw(),
@@ -168,7 +180,7 @@ impl Precompiles {
         map.insert(BlsPairingCheck::ADDRESS.raw(), Box::new(BlsPairingCheck));
         map.insert(BlsMapFpToG1::ADDRESS.raw(), Box::new(BlsMapFpToG1));
         map.insert(BlsMapFp2ToG2::ADDRESS.raw(), Box::new(BlsMapFp2ToG2));
-        Self(map)
+        map
     }
 }
 
@@ -192,6 +204,7 @@ pub struct PrecompileStandalone {
 fn process_precompile(
     p: &dyn Precompile,
     handle: &impl PrecompileHandle,
+    call_stack_limit: usize,
 ) -> Result<aurora_engine_precompiles::PrecompileOutput, PrecompileFailure> {
     let input = handle.input();
     let gas_limit = handle.gas_limit();
@@ -206,7 +219,7 @@ fn process_precompile(
     let output = p
         .run(input, gas_limit.map(EthGas::new), &context, is_static)
         .map_err(|err| PrecompileFailure::Error {
-            exit_status: get_exit_error(err),
+            exit_status: get_exit_error(err, handle.code_address(), call_stack_limit),
         });
     #[cfg(feature = "dump-state")]
     if let Ok(_out) = &output {
@@ -235,15 +248,27 @@ fn post_process(
     })
 }
 
-fn get_exit_error(exit_error: aurora_engine_precompiles::ExitError) -> ExitError {
+fn get_exit_error(
+    exit_error: aurora_engine_precompiles::ExitError,
+    code_address: H160,
+    call_stack_limit: usize,
+) -> ExitError {
     match exit_error {
         aurora_engine_precompiles::ExitError::StackUnderflow => ExitError::StackUnderflow,
         aurora_engine_precompiles::ExitError::StackOverflow => ExitError::StackOverflow,
         aurora_engine_precompiles::ExitError::InvalidJump => ExitError::InvalidJump,
         aurora_engine_precompiles::ExitError::InvalidRange => ExitError::InvalidRange,
         aurora_engine_precompiles::ExitError::DesignatedInvalid => ExitError::DesignatedInvalid,
-        aurora_engine_precompiles::ExitError::CallTooDeep => ExitError::CallTooDeep,
-        aurora_engine_precompiles::ExitError::CreateCollision => ExitError::CreateCollision,
+        // The precompile error carries no depth, report the first depth past
+        // the limit
+        aurora_engine_precompiles::ExitError::CallTooDeep => {
+            ExitError::CallTooDeep(u64::try_from(call_stack_limit + 1).unwrap_or(u64::MAX))
+        }
+        // Precompiles don't create contracts, the collision is reported at
+        // the precompile itself
+        aurora_engine_precompiles::ExitError::CreateCollision => {
+            ExitError::CreateCollision(code_address)
+        }
         aurora_engine_precompiles::ExitError::CreateContractLimit => ExitError::CreateContractLimit,
         aurora_engine_precompiles::ExitError::InvalidCode(op) => {
             ExitError::InvalidCode(Opcode(op.0))
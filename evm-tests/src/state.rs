@@ -1,25 +1,29 @@
 use crate::assertions::{
-    self, assert_call_exit_exception, assert_empty_create_caller, assert_vicinity_validation,
-    check_create_exit_reason,
+    self, check_call_exit_exception, check_create_exit_reason, check_empty_create_caller,
+    check_vicinity_validation,
+};
+use crate::config::{RunOptions, TestConfig};
+use crate::execution_results::{
+    CaseResult, FailedTestDetails, RawInput, TestBench, TestExecutionResult, UnexpectedOutcome,
 };
-use crate::config::TestConfig;
-use crate::execution_results::{FailedTestDetails, RawInput, TestBench, TestExecutionResult};
 use crate::precompiles::Precompiles;
 use crate::state_dump::{StateTestsDump, StateTestsDumper};
 use crate::types::account_state::MemoryAccountsState;
 use crate::types::blob::{calc_data_fee, calc_max_data_fee, BlobExcessGasAndPrice};
+use crate::types::spec::get_gasometer_config;
 use crate::types::transaction::TxType;
 use crate::types::{Spec, StateTestCase};
 use aurora_evm::backend::{Apply, ApplyBackend, MemoryBackend};
-use aurora_evm::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
-use aurora_evm::utils::U256_ZERO;
-use primitive_types::H160;
-use std::str::FromStr;
+use aurora_evm::executor::fees;
+use aurora_evm::executor::stack::{StackExecutorBuilder, StackState};
+use aurora_evm::executor::transaction::{self, InvalidTransaction};
+use primitive_types::U256;
 
 /// Runs a test in a separate thread with a specified stack size.
 ///
 /// # Panics
-/// This function will panic if thread spawning or joining fails.
+/// This function will panic if thread spawning or joining fails, or if the
+/// transaction outcome doesn't match the exception expected by the test.
 #[must_use]
 pub fn test(test_config: TestConfig, test: StateTestCase) -> TestExecutionResult {
     use std::thread;
@@ -33,22 +37,46 @@ pub fn test(test_config: TestConfig, test: StateTestCase) -> TestExecutionResult
         .unwrap();
 
     // Wait for the thread to join
-    child.join().unwrap()
+    child
+        .join()
+        .unwrap()
+        .unwrap_or_else(|outcome| panic!("{outcome}"))
+}
+
+/// Runs a single state test case on the current thread, without printing
+/// anything, with the same semantics as the `state` command of the runner.
+///
+/// Each post state of the case is executed on its own copy of the pre-state,
+/// and the resulting state is compared to the expected hash.
+///
+/// The execution needs a large stack, see [`test`].
+///
+/// # Errors
+/// Return `UnexpectedOutcome` if the transaction outcome doesn't match the
+/// exception expected by the test, where the runner panics.
+pub fn run_case(
+    test: &StateTestCase,
+    options: &RunOptions,
+) -> Result<CaseResult, UnexpectedOutcome> {
+    test_run(&options.into(), test).map(Into::into)
 }
 
 #[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
-fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResult {
+fn test_run(
+    test_config: &TestConfig,
+    test: &StateTestCase,
+) -> Result<TestExecutionResult, UnexpectedOutcome> {
     let mut tests_result = TestExecutionResult::new();
-    for (spec, states) in &test.post_states {
+    for (&spec, states) in &test.post_states {
         // Run tests for the specific EVM hard fork (Spec)
         if let Some(s) = test_config.spec.as_ref() {
-            if s != spec {
+            if *s != spec {
                 continue;
             }
         }
 
         // Geet gasometer config for the current spec
-        let Some(gasometer_config) = spec.get_gasometer_config() else {
+        let Some(gasometer_config) = get_gasometer_config(spec) else {
             // If the spec is not supported, skip the test
             continue;
         };
@@ -66,56 +94,59 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
         let original_state = test.pre_state.as_ref().to_memory_accounts_state();
         let vicinity = test.get_memory_vicinity(spec, blob_gas_price);
 
-        if let Err(tx_err) = vicinity {
-            tests_result.total += states.len() as u64;
-            let h = states.first().unwrap().hash;
-            // if vicinity could not be computed, then the transaction was invalid, so we simply
-            // check the original state and move on
-            let (is_valid_hash, actual_hash) = original_state.check_valid_hash(&h);
-            if !is_valid_hash {
-                tests_result.failed_tests.push(FailedTestDetails {
-                    expected_hash: h,
-                    actual_hash,
-                    index: 0,
-                    name: String::from_str(&test_config.name).unwrap(),
-                    spec: spec.clone(),
-                    state: original_state.0,
-                });
-                if test_config.verbose_output.verbose_failed {
-                    println!(
-                        " [{spec:?}] {}: {tx_err:?} ... validation failed\t<----",
-                        test_config.name
-                    );
+        let vicinity = match vicinity {
+            Ok(vicinity) => vicinity,
+            Err(tx_err) => {
+                let Some(h) = states.first().map(|state| state.hash) else {
+                    continue;
+                };
+                tests_result.total += states.len() as u64;
+                // if vicinity could not be computed, then the transaction was invalid, so we simply
+                // check the original state and move on
+                let (is_valid_hash, actual_hash) = original_state.check_valid_hash(&h);
+                if !is_valid_hash {
+                    tests_result.failed_tests.push(FailedTestDetails {
+                        expected_hash: h,
+                        actual_hash,
+                        index: 0,
+                        name: test_config.name.clone(),
+                        spec,
+                        state: original_state.0,
+                    });
+                    if test_config.verbose_output.verbose_failed {
+                        println!(
+                            " [{spec:?}] {}: {tx_err:?} ... validation failed\t<----",
+                            test_config.name
+                        );
+                    }
+                    tests_result.failed += 1;
+                    continue;
                 }
-                tests_result.failed += 1;
+                check_vicinity_validation(&tx_err, states, spec, test_config)?;
+                // As it's an expected validation error-skip the test run
                 continue;
             }
-            assert_vicinity_validation(&tx_err, states, spec, test_config);
-            // As it's an expected validation error-skip the test run
-            continue;
-        }
-
-        let vicinity = vicinity.unwrap();
-        let caller = test.transaction.get_caller_from_secret_key();
+        };
+        let caller = vicinity.origin;
 
         let caller_balance = original_state.caller_balance(caller);
-        // EIP-3607
-        let caller_code = original_state.caller_code(caller);
-        // EIP-7702 - check if it's delegated designation. If it's a delegation designation, then,
-        // even if `caller_code` is non-empty, the transaction should be executed.
-        let is_delegated = original_state.is_delegated(caller);
 
         for (i, state) in states.iter().enumerate() {
             let mut backend = MemoryBackend::new(&vicinity, original_state.0.clone());
             tests_result.total += 1;
 
-            // Test case may be expected to fail with an unsupported tx type if the current fork is
-            // older than Berlin (see EIP-2718). However, this is not implemented in sputnik itself and rather
-            // in the code hosting sputnik. https://github.com/rust-blockchain/evm/pull/40
-            if spec.is_filtered_spec_for_skip()
-                && TxType::from_tx_bytes(&state.tx_bytes) != TxType::Legacy
-                && state.expect_exception.as_deref() == Some("TR_TypeNotSupported")
-            {
+            // Typed transactions are rejected by forks that don't support them (see EIP-2718)
+            let tx_type = TxType::from_tx_bytes(&state.tx_bytes);
+            let type_check = tx_type.map_or(Err(InvalidTransaction::TypeNotSupported), |tx_type| {
+                transaction::validate_type(tx_type.into(), &gasometer_config)
+            });
+            if type_check.is_err() {
+                assertions::check_type_not_supported(
+                    tx_type,
+                    state.expect_exception.as_ref(),
+                    test_config.name.as_str(),
+                    spec,
+                )?;
                 continue;
             }
 
@@ -135,25 +166,24 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
             // Only execute valid transactions
             let authorization_list = match valid_tx {
                 Ok(list) => list,
-                Err(err)
-                    if assertions::check_validate_exit_reason(
+                Err(err) => {
+                    assertions::check_validate_exit_reason(
                         &err,
                         state.expect_exception.as_ref(),
                         test_config.name.as_str(),
                         spec,
-                    ) =>
-                {
-                    continue
+                    )?;
+                    continue;
                 }
-                Err(err) => panic!("transaction validation error: {err:?}"),
             };
 
-            // We do not check overflow after TX validation
-            let total_fee = if let Some(data_fee) = data_fee {
-                vicinity.effective_gas_price * gas_limit + data_fee
-            } else {
-                vicinity.effective_gas_price * gas_limit
-            };
+            // Overflow is already rejected by TX validation
+            let total_fee = fees::max_fee(
+                U256::from(gas_limit),
+                vicinity.effective_gas_price,
+                data_fee,
+            )
+            .unwrap_or(U256::MAX);
 
             // Dump state transaction data
             let mut state_tests_dump = StateTestsDump::default();
@@ -164,19 +194,24 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
 
             let iter_start = std::time::Instant::now();
 
-            let metadata = StackSubstateMetadata::new(gas_limit, &gasometer_config);
-            let executor_state = MemoryStackState::new(metadata, &backend);
             // let precompile = JsonPrecompile::precompile(spec).unwrap();
-            let precompile = Precompiles::new(spec);
-            let mut executor =
-                StackExecutor::new_with_precompiles(executor_state, &gasometer_config, &precompile);
-            executor.state_mut().withdraw(caller, total_fee).unwrap();
+            let precompile = Precompiles::new(spec, gasometer_config.call_stack_limit);
+            let mut executor = StackExecutorBuilder::new(&gasometer_config, gas_limit)
+                .backend(&backend)
+                .precompiles(&precompile)
+                .build();
+            executor.state_mut().debit(caller, total_fee).map_err(|e| {
+                UnexpectedOutcome(format!(
+                    "can't charge the fee for test: {}: {e:?}",
+                    test_config.name
+                ))
+            })?;
 
             let value = test.transaction.get_value(state);
 
             // EIP-3607: Reject transactions from senders with deployed code
             // EIP-7702: Accept transaction even if the caller has code.
-            if caller_code.is_empty() || is_delegated {
+            if transaction::validate_sender(&backend, caller).is_ok() {
                 if let Some(to) = test.transaction.to {
                     state_tests_dump.set_tx_data(
                         to,
@@ -196,11 +231,11 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
                         access_list.clone(),
                         authorization_list.clone(),
                     );
-                    assert_call_exit_exception(
+                    check_call_exit_exception(
                         state.expect_exception.as_ref(),
                         &test_config.name,
                         spec,
-                    );
+                    )?;
                 } else {
                     let code = data.clone();
 
@@ -215,7 +250,7 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
                         &reason.0,
                         state.expect_exception.as_ref(),
                         &format!("{spec:?}-{}-{i}", test_config.name),
-                    ) {
+                    )? {
                         continue;
                     }
                 }
@@ -224,10 +259,10 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
                 // allow EOAs whose code is a valid delegation designation, i.e. `0xef0100 || address`,
                 // to continue to originate transactions.
                 #[allow(clippy::collapsible_if)]
-                if !(*spec >= Spec::Prague
-                    && TxType::from_tx_bytes(&state.tx_bytes) == TxType::EOAAccountCode)
+                if !(spec >= Spec::Prague
+                    && TxType::from_tx_bytes(&state.tx_bytes) == Some(TxType::EOAAccountCode))
                 {
-                    assert_empty_create_caller(state.expect_exception.as_ref(), &test_config.name);
+                    check_empty_create_caller(state.expect_exception.as_ref(), &test_config.name)?;
                 }
             }
 
@@ -236,34 +271,20 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
                 println!("gas_limit: {gas_limit}\nused_gas: {used_gas}");
             }
 
-            let actual_fee = executor.fee(vicinity.effective_gas_price);
-            // Forks after London burn miner rewards and thus have different gas fee
-            // calculation (see EIP-1559)
-            let miner_reward = if *spec > Spec::Berlin {
-                let coinbase_gas_price = vicinity
-                    .effective_gas_price
-                    .saturating_sub(vicinity.block_base_fee_per_gas);
-                executor.fee(coinbase_gas_price)
-            } else {
-                actual_fee
-            };
-
-            executor
-                .state_mut()
-                .deposit(vicinity.block_coinbase, miner_reward);
-
-            let amount_to_return_for_caller = data_fee.map_or_else(
-                || total_fee - actual_fee,
-                |data_fee| total_fee - actual_fee - data_fee,
-            );
+            // Forks after London burn the base fee (see EIP-1559)
             executor
-                .state_mut()
-                .deposit(caller, amount_to_return_for_caller);
+                .settle_fees(caller, total_fee, data_fee)
+                .map_err(|e| {
+                    UnexpectedOutcome(format!(
+                        "can't settle the fees for test: {}: {e:?}",
+                        test_config.name
+                    ))
+                })?;
 
             let (values, logs) = executor.into_state().deconstruct();
 
             // Separate Apply and dump logic to avoid dumping transactions
-            if test_config.verbose_output.dump_transactions.is_some() {
+            if test_config.collect_transactions {
                 // As Apply iterator do not contains cloned values, we need to clone them to be able to dump them in the test results. And as Apply contains references, we need to convert them into owned values.
                 let apply_values: Vec<_> = values
                     .into_iter()
@@ -287,7 +308,7 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
 
                 backend.apply(apply_values.clone(), logs, true);
                 tests_result.dump_successful_txs.push(RawInput {
-                    spec: spec.clone().into(),
+                    spec: spec.into(),
                     caller,
                     value,
                     data,
@@ -303,30 +324,12 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
             if test_config.verbose_output.print_slow {
                 let elapsed = iter_start.elapsed();
                 tests_result.set_benchmark(TestBench {
-                    spec: spec.clone(),
+                    spec,
                     name: test_config.name.clone(),
                     elapsed,
                 });
             }
 
-            // It's a special case for hard forks: London and before,
-            // According to EIP-160, an empty account should be removed. But in that particular test - original test state
-            // contains account 0x03 (it's a precompile), and when precompile 0x03 was called it exit with
-            // OutOfGas result. And after exit of the substate, the account is not marked as touched, as exit reason
-            // is not a success. And it means that it doesn't appear in Apply::Modify, then as untouched it
-            // can't be removed by the backend.apply event. In that particular case we should manage it manually.
-            // NOTE: it's not realistic situation for real life flow.
-            if *spec <= Spec::London && test_config.name == "failed_tx_xcf416c53" {
-                let state = backend.state_mut();
-                state.retain(|addr, account| {
-                    // Check if the account is empty for the precompile `0x03`
-                    !(addr == &H160::from_low_u64_be(3)
-                        && account.balance == U256_ZERO
-                        && account.nonce == U256_ZERO
-                        && account.code.is_empty())
-                });
-            }
-
             let backend_state = MemoryAccountsState(backend.state().clone());
             let (is_valid_hash, actual_hash) = backend_state.check_valid_hash(&state.hash);
             if !is_valid_hash {
@@ -335,7 +338,7 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
                     actual_hash,
                     index: i,
                     name: test_config.name.clone(),
-                    spec: spec.clone(),
+                    spec,
                     state: backend.state().clone(),
                 };
                 tests_result.failed_tests.push(failed_res);
@@ -378,5 +381,5 @@ fn test_run(test_config: &TestConfig, test: &StateTestCase) -> TestExecutionResu
             state_tests_dump.dump_to_file(spec);
         }
     }
-    tests_result
+    Ok(tests_result)
 }
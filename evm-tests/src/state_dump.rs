@@ -36,7 +36,7 @@ pub trait StateTestsDumper {
     }
     fn set_state_hash(&mut self, _state_hash: H256) {}
     fn set_result_state(&mut self, _state: &BTreeMap<H160, MemoryAccount>) {}
-    fn dump_to_file(&self, _spec: &Spec) {}
+    fn dump_to_file(&self, _spec: Spec) {}
 }
 
 #[cfg(not(feature = "dump-state"))]
@@ -81,7 +81,7 @@ impl StateTestsDumper for StateTestsDump {
         self.result_state = state.clone();
     }
 
-    fn dump_to_file(&self, spec: &Spec) {
+    fn dump_to_file(&self, spec: Spec) {
         use std::time::{SystemTime, UNIX_EPOCH};
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -3,7 +3,6 @@ use super::json_utils::{
     h160_from_hex_str, strip_0x_prefix,
 };
 use aurora_evm::backend::MemoryAccount;
-use aurora_evm::executor::stack::Authorization;
 use primitive_types::{H160, H256, U256};
 use serde::{Deserialize, Deserializer};
 use sha3::{Digest, Keccak256};
@@ -182,17 +181,4 @@ impl MemoryAccountsState {
             .get(&caller)
             .map_or_else(U256::zero, |acc| acc.balance)
     }
-
-    pub fn caller_code(&self, caller: H160) -> Vec<u8> {
-        self.0
-            .get(&caller)
-            .map_or_else(Vec::new, |acc| acc.code.clone())
-    }
-
-    #[must_use]
-    pub fn is_delegated(&self, caller: H160) -> bool {
-        self.0
-            .get(&caller)
-            .is_some_and(|c| Authorization::is_delegated(&c.code))
-    }
 }
@@ -2,7 +2,7 @@
 
 use crate::types::transaction::Transaction;
 use crate::types::StateEnv;
-use aurora_evm::Config;
+use aurora_evm::{backend, Config};
 use primitive_types::U256;
 use serde::Deserialize;
 
@@ -112,44 +112,13 @@ pub const fn calc_excess_blob_gas(parent_excess_blob_gas: u64, parent_blob_gas_u
 #[inline]
 #[must_use]
 pub fn calc_blob_gas_price(excess_blob_gas: u64) -> u128 {
-    fake_exponential(
+    backend::fake_exponential(
         MIN_BLOB_GASPRICE,
         excess_blob_gas,
         BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN,
     )
 }
 
-/// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion.
-///
-/// This is used to calculate the blob price.
-///
-/// See also [the EIP-4844 helpers](https://eips.ethereum.org/EIPS/eip-4844#helpers)
-/// (`fake_exponential`).
-///
-/// # Panics
-///
-/// This function panics if `denominator` is zero.
-#[inline]
-#[must_use]
-pub fn fake_exponential(factor: u64, numerator: u64, denominator: u64) -> u128 {
-    assert_ne!(denominator, 0, "attempt to divide by zero");
-    let factor = u128::from(factor);
-    let numerator = u128::from(numerator);
-    let denominator = u128::from(denominator);
-
-    let mut i = 1;
-    let mut output = 0;
-    let mut numerator_accum = factor * denominator;
-    while numerator_accum > 0 {
-        output += numerator_accum;
-
-        // Denominator is asserted as not zero at the start of the function.
-        numerator_accum = (numerator_accum * numerator) / (denominator * i);
-        i += 1;
-    }
-    output / denominator
-}
-
 /// Calculates the [EIP-4844] `data_fee` of the transaction.
 ///
 /// [EIP-4844]: https://eips.ethereum.org/EIPS/eip-4844
@@ -1,4 +1,5 @@
 //! [EIP-4844]: https://eips.ethereum.org/EIPS/eip-4844
+use aurora_evm::backend;
 use primitive_types::U256;
 
 /// EIP-4844 constants
@@ -33,7 +34,7 @@ pub const fn calc_excess_blob_gas(parent_excess_blob_gas: u64, parent_blob_gas_u
 #[inline]
 #[must_use]
 pub fn calc_blob_gas_price(excess_blob_gas: u64) -> u128 {
-    fake_exponential(
+    backend::fake_exponential(
         MIN_BLOB_GASPRICE,
         excess_blob_gas,
         BLOB_GASPRICE_UPDATE_FRACTION,
@@ -66,37 +67,3 @@ pub fn calc_max_data_fee(max_fee_per_blob_gas: U256, blob_hashes_len: usize) ->
 pub fn calc_data_fee(blob_gas_price: u128, blob_hashes_len: usize) -> U256 {
     U256::from(blob_gas_price).saturating_mul(U256::from(get_total_blob_gas(blob_hashes_len)))
 }
-
-/// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion.
-///
-/// This is used to calculate the blob price.
-///
-/// See also [the EIP-4844 helpers](https://eips.ethereum.org/EIPS/eip-4844#helpers)
-/// (`fake_exponential`).
-///
-/// # Panics
-///
-/// This function panics if `denominator` is zero.
-///
-/// # NOTES
-/// PLEASE DO NOT USE IN PRODUCTION as not checked overflow. For tests only.
-#[inline]
-#[must_use]
-pub fn fake_exponential(factor: u64, numerator: u64, denominator: u64) -> u128 {
-    assert_ne!(denominator, 0, "attempt to divide by zero");
-    let factor = u128::from(factor);
-    let numerator = u128::from(numerator);
-    let denominator = u128::from(denominator);
-
-    let mut i = 1;
-    let mut output = 0;
-    let mut numerator_accum = factor * denominator;
-    while numerator_accum > 0 {
-        output += numerator_accum;
-
-        // Denominator is asserted as not zero at the start of the function.
-        numerator_accum = (numerator_accum * numerator) / (denominator * i);
-        i += 1;
-    }
-    output / denominator
-}
@@ -8,16 +8,6 @@ use rlp::RlpStream;
 use sha3::{Digest, Keccak256};
 
 pub const MAGIC: u8 = 0x5;
-/// The order of the secp256k1 curve, divided by two. Signatures that should be checked according
-/// to EIP-2 should have an S value less than or equal to this.
-///
-/// `57896044618658097711785492504343953926418782139537452191302581570759080747168`
-pub const SECP256K1N_HALF: U256 = U256([
-    0xDFE9_2F46_681B_20A0,
-    0x5D57_6E73_57A4_501D,
-    0xFFFF_FFFF_FFFF_FFFF,
-    0x7FFF_FFFF_FFFF_FFFF,
-]);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Authorization {
@@ -6,7 +6,9 @@ use crate::types::json_utils::{
     deserialize_h256_from_u256_str, deserialize_h256_from_u256_str_opt, deserialize_u256_from_str,
     deserialize_u64_from_str_opt,
 };
-use aurora_evm::backend::MemoryVicinity;
+use aurora_evm::backend::{BlobParams, MemoryVicinity};
+use aurora_evm::executor::eip1559;
+use aurora_evm::executor::transaction::InvalidTransaction;
 use primitive_types::{H160, H256, U256};
 use serde::Deserialize;
 use std::collections::BTreeMap;
@@ -41,7 +43,7 @@ pub struct StateTestCase {
     /// Maps fork specifications to a list of possible outcomes (results).
     ///
     /// NOTE: field `config` skipped as it is not used in the current context.
-    #[serde(rename = "post")]
+    #[serde(rename = "post", deserialize_with = "spec::deserialize_spec_map")]
     pub post_states: BTreeMap<Spec, Vec<PostState>>,
 
     /// The transaction(s) to be executed in the test case.
@@ -63,46 +65,36 @@ impl StateTestCase {
     /// Invalid transaction error status.
     pub fn get_memory_vicinity(
         &self,
-        spec: &Spec,
+        spec: Spec,
         blob_gas_price: Option<BlobExcessGasAndPrice>,
-    ) -> Result<MemoryVicinity, InvalidTxReason> {
+    ) -> Result<MemoryVicinity, InvalidTransaction> {
         let block_base_fee_per_gas = self.env.block_base_fee_per_gas;
         let tx = &self.transaction;
         // Validation for EIP-1559 that was introduced in London hard fork
-        let gas_price = if *spec >= Spec::London {
+        let gas_price = if spec >= Spec::London {
             tx.gas_price.or(tx.max_fee_per_gas).unwrap_or_default()
         } else {
             if tx.max_fee_per_gas.is_some() {
-                return Err(InvalidTxReason::GasPriceEip1559);
+                return Err(InvalidTransaction::DynamicFeeNotSupported);
             }
-            tx.gas_price.expect("expect gas price")
+            tx.gas_price.unwrap_or_default()
         };
 
-        // EIP-1559: priority fee must be lower than gas_price
-        if let Some(max_priority_fee_per_gas) = tx.max_priority_fee_per_gas {
-            if max_priority_fee_per_gas > gas_price {
-                return Err(InvalidTxReason::PriorityFeeTooLarge);
-            }
-        }
-
-        let effective_gas_price = self.transaction.max_priority_fee_per_gas.map_or(
+        let effective_gas_price = eip1559::effective_gas_price(
+            block_base_fee_per_gas,
             gas_price,
-            |max_priority_fee_per_gas| {
-                gas_price.min(max_priority_fee_per_gas + block_base_fee_per_gas)
-            },
-        );
-
-        // the gas price cannot be lower than the base fee
-        if gas_price < block_base_fee_per_gas {
-            return Err(InvalidTxReason::GasPriceLessThanBlockBaseFee);
-        }
+            tx.max_priority_fee_per_gas,
+        )?;
 
         let blob_hashes = tx.blob_versioned_hashes.clone();
 
         Ok(MemoryVicinity {
             gas_price,
             effective_gas_price,
-            origin: self.transaction.get_caller_from_secret_key(),
+            origin: self
+                .transaction
+                .get_caller_from_secret_key()
+                .ok_or(InvalidTransaction::InvalidSignature)?,
             block_hashes: Vec::new(),
             block_number: self.env.block_number,
             block_coinbase: self.env.block_coinbase,
@@ -112,7 +104,10 @@ impl StateTestCase {
             chain_id: U256::one(),
             block_base_fee_per_gas,
             block_randomness: self.env.random,
-            blob_gas_price: blob_gas_price.map(|bgp| bgp.blob_gas_price),
+            // Priced like `calc_blob_gas_price`
+            blob_params: blob_gas_price.map(|_| BlobParams::CANCUN),
+            block_excess_blob_gas: blob_gas_price.map(|bgp| bgp.excess_blob_gas),
+            block_blob_gas_used: None,
             blob_hashes,
         })
     }
@@ -197,7 +192,9 @@ impl From<StateEnv> for MemoryVicinity {
             chain_id: U256::zero(), // Chain ID is not used in state tests
             block_base_fee_per_gas: env.block_base_fee_per_gas,
             block_randomness: env.random,
-            blob_gas_price: None,    // Blob gas price is not used in state tests
+            blob_params: None, // Blob gas price is not used in state tests
+            block_excess_blob_gas: None,
+            block_blob_gas_used: None,
             blob_hashes: Vec::new(), // Blob hashes are not used in state tests
         }
     }
@@ -250,28 +247,3 @@ pub struct PostStateIndexes {
     /// Index into transaction value set.
     pub value: usize,
 }
-
-#[derive(Debug)]
-pub enum InvalidTxReason {
-    IntrinsicGas,
-    OutOfFund,
-    GasLimitReached,
-    PriorityFeeTooLarge,
-    GasPriceLessThanBlockBaseFee,
-    BlobCreateTransaction,
-    BlobVersionNotSupported,
-    TooManyBlobs,
-    EmptyBlobs,
-    BlobGasPriceGreaterThanMax,
-    BlobVersionedHashesNotSupported,
-    MaxFeePerBlobGasNotSupported,
-    GasPriceEip1559,
-    AuthorizationListNotExist,
-    AuthorizationListNotSupported,
-    AuthorizationListNotSupportedForCreate,
-    InvalidAuthorizationChain,
-    InvalidAuthorizationSignature,
-    CreateTransaction,
-    GasFloorMoreThanGasLimit,
-    AccessListNotSupported,
-}
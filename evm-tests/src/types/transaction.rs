@@ -4,9 +4,10 @@ use crate::types::json_utils::{
     deserialize_h256_from_u256_str_opt, deserialize_u256_from_str, deserialize_u256_from_str_opt,
     deserialize_u8_from_str_opt, deserialize_vec_of_hex, deserialize_vec_u256_from_str,
 };
-use crate::types::{eip_4844, eip_7702, InvalidTxReason, PostState, Spec};
+use crate::types::{eip_4844, eip_7702, PostState, Spec};
 use aurora_evm::backend::MemoryVicinity;
 use aurora_evm::executor::stack::Authorization;
+use aurora_evm::executor::transaction::{self, InvalidTransaction, Signature, TransactionType};
 use aurora_evm::gasometer::Gasometer;
 use primitive_types::{H160, H256, U256};
 use serde::Deserialize;
@@ -102,23 +103,18 @@ impl Transaction {
         }
     }
 
-    /// Get caller from transaction's secret key.
-    ///
-    /// # Panics
-    /// If the transaction secret is missing or if parsing the secret key fails.
+    /// Get caller from transaction's secret key, `None` if the secret key is
+    /// missing or invalid.
     #[must_use]
-    pub fn get_caller_from_secret_key(&self) -> H160 {
-        let hash = self.secret_key.unwrap();
-        let mut secret_key = [0; 32];
-        secret_key.copy_from_slice(hash.as_bytes());
-        let secret = libsecp256k1::SecretKey::parse(&secret_key);
-        let public = libsecp256k1::PublicKey::from_secret_key(&secret.unwrap());
+    pub fn get_caller_from_secret_key(&self) -> Option<H160> {
+        let secret = libsecp256k1::SecretKey::parse(&self.secret_key?.0).ok()?;
+        let public = libsecp256k1::PublicKey::from_secret_key(&secret);
         let mut res = [0u8; 64];
         res.copy_from_slice(&public.serialize()[1..65]);
 
-        H160::from(H256::from_slice(
+        Some(H160::from(H256::from_slice(
             <[u8; 32]>::from(sha3::Keccak256::digest(res)).as_slice(),
-        ))
+        )))
     }
 
     fn intrinsic_gas_and_gas_floor(
@@ -144,7 +140,7 @@ impl Transaction {
     /// Validate the transaction against block, payment, and EIP constraints.
     ///
     /// # Errors
-    /// Returns `InvalidTxReason` if validation fails.
+    /// Returns `InvalidTransaction` if validation fails.
     #[allow(clippy::too_many_lines, clippy::too_many_arguments)]
     pub fn validate(
         &self,
@@ -154,45 +150,49 @@ impl Transaction {
         vicinity: &MemoryVicinity,
         blob_gas_price: Option<BlobExcessGasAndPrice>,
         data_fee: Option<U256>,
-        spec: &Spec,
+        spec: Spec,
         state: &PostState,
-    ) -> Result<Vec<Authorization>, InvalidTxReason> {
+    ) -> Result<Vec<Authorization>, InvalidTransaction> {
         let gas_limit = self.get_gas_limit(state);
         let mut authorization_list: Vec<Authorization> = vec![];
 
         let (intrinsic_gas, floor_gas) = self.intrinsic_gas_and_gas_floor(config, state);
-        if gas_limit < U256::from(intrinsic_gas) {
-            return Err(InvalidTxReason::IntrinsicGas);
-        }
+        // A gas limit beyond `u64` is rejected by the block gas limit below
+        transaction::validate_gas_limit_covers(
+            u64::try_from(gas_limit).unwrap_or(u64::MAX),
+            intrinsic_gas,
+            floor_gas,
+        )?;
 
         if block_gas_limit < gas_limit {
-            return Err(InvalidTxReason::GasLimitReached);
+            return Err(InvalidTransaction::BlockGasLimitExceeded);
         }
 
         let required_funds = gas_limit
             .checked_mul(vicinity.gas_price)
-            .ok_or(InvalidTxReason::OutOfFund)?
+            .ok_or(InvalidTransaction::CostOverflow)?
             .checked_add(self.get_value(state))
-            .ok_or(InvalidTxReason::OutOfFund)?;
+            .ok_or(InvalidTransaction::CostOverflow)?;
 
         let required_funds = if let Some(data_fee) = data_fee {
             required_funds
                 .checked_add(data_fee)
-                .ok_or(InvalidTxReason::OutOfFund)?
+                .ok_or(InvalidTransaction::CostOverflow)?
         } else {
             required_funds
         };
         if caller_balance < required_funds {
-            return Err(InvalidTxReason::OutOfFund);
+            return Err(InvalidTransaction::InsufficientFunds);
         }
 
-        if TxType::from_tx_bytes(&state.tx_bytes) == TxType::AccessList && *spec < Spec::Berlin {
-            return Err(InvalidTxReason::AccessListNotSupported);
+        if TxType::from_tx_bytes(&state.tx_bytes) == Some(TxType::AccessList) && spec < Spec::Berlin
+        {
+            return Err(InvalidTransaction::AccessListNotSupported);
         }
 
         // CANCUN tx validation
         // Presence of max_fee_per_blob_gas means that this is a blob transaction.
-        if *spec >= Spec::Cancun {
+        if spec >= Spec::Cancun {
             if let Some(max) = self.max_fee_per_blob_gas {
                 // ensure that the user was willing to at least pay the current blob gasprice
                 if U256::from(
@@ -201,12 +201,12 @@ impl Transaction {
                         .blob_gas_price,
                 ) > max
                 {
-                    return Err(InvalidTxReason::BlobGasPriceGreaterThanMax);
+                    return Err(InvalidTransaction::BlobGasPriceGreaterThanMax);
                 }
 
                 // there must be at least one blob
                 if self.blob_versioned_hashes.is_empty() {
-                    return Err(InvalidTxReason::EmptyBlobs);
+                    return Err(InvalidTransaction::EmptyBlobs);
                 }
 
                 // The field `to` deviates slightly from the semantics with the exception
@@ -214,58 +214,54 @@ impl Transaction {
                 // a 20-byte address. This means that blob transactions cannot
                 // have the form of a `create` transaction.
                 if self.to.is_none() {
-                    return Err(InvalidTxReason::BlobCreateTransaction);
+                    return Err(InvalidTransaction::BlobCreateTransaction);
                 }
 
                 // all versioned blob hashes must start with VERSIONED_HASH_VERSION_KZG
                 for blob in &self.blob_versioned_hashes {
                     let blob_hash = H256(blob.to_big_endian());
                     if blob_hash[0] != eip_4844::VERSIONED_HASH_VERSION_KZG {
-                        return Err(InvalidTxReason::BlobVersionNotSupported);
+                        return Err(InvalidTransaction::BlobVersionNotSupported);
                     }
                 }
 
                 // ensure the total blob gas spent is at most equal to the limit
                 // assert blob_gas_used <= MAX_BLOB_GAS_PER_BLOCK
                 // EIP-7691
-                let max_blob_len = if *spec == Spec::Cancun {
+                let max_blob_len = if spec == Spec::Cancun {
                     eip_4844::MAX_BLOBS_PER_BLOCK_CANCUN
                 } else {
                     eip_4844::MAX_BLOBS_PER_BLOCK_ELECTRA
                 };
                 if self.blob_versioned_hashes.len() > usize::try_from(max_blob_len).unwrap() {
-                    return Err(InvalidTxReason::TooManyBlobs);
+                    return Err(InvalidTransaction::TooManyBlobs);
                 }
             }
         } else {
             if !self.blob_versioned_hashes.is_empty() {
-                return Err(InvalidTxReason::BlobVersionedHashesNotSupported);
+                return Err(InvalidTransaction::BlobVersionedHashesNotSupported);
             }
             if self.max_fee_per_blob_gas.is_some() {
-                return Err(InvalidTxReason::MaxFeePerBlobGasNotSupported);
+                return Err(InvalidTransaction::MaxFeePerBlobGasNotSupported);
             }
         }
 
-        if *spec >= Spec::Prague {
-            // EIP-7623 validation
-            if floor_gas > gas_limit.as_u64() {
-                return Err(InvalidTxReason::GasFloorMoreThanGasLimit);
-            }
-
+        if spec >= Spec::Prague {
             let tx_authorization_list = self.authorization_list.clone().unwrap_or_default();
 
             // EIP-7702 - if transaction type is EOAAccountCode then
             // `authorization_list` must be present
-            if TxType::from_tx_bytes(&state.tx_bytes) == TxType::EOAAccountCode
+            if TxType::from_tx_bytes(&state.tx_bytes) == Some(TxType::EOAAccountCode)
                 && tx_authorization_list.is_empty()
             {
-                return Err(InvalidTxReason::AuthorizationListNotExist);
+                return Err(InvalidTransaction::EmptyAuthorizationList);
             }
 
             // EIP-7702 - if transaction is contract creation - validation fails
-            if TxType::from_tx_bytes(&state.tx_bytes) == TxType::EOAAccountCode && self.to.is_none()
+            if TxType::from_tx_bytes(&state.tx_bytes) == Some(TxType::EOAAccountCode)
+                && self.to.is_none()
             {
-                return Err(InvalidTxReason::AuthorizationListNotSupportedForCreate);
+                return Err(InvalidTransaction::SetCodeCreateTransaction);
             }
 
             // Check EIP-7702 Spec validation steps: 1 and 2
@@ -277,14 +273,13 @@ impl Transaction {
 
                 // 3. `authority = ecrecover(keccak(MAGIC || rlp([chain_id, address, nonce])), y_parity, r, s]`
                 // Validate the signature, as in tests it is possible to have invalid signatures values.
-                // Value `v` shouldn't be greater then 1
-                let v = auth.v;
-                if v > U256::from(1) {
-                    is_valid = false;
-                }
-
-                // EIP-2 validation
-                if auth.s > eip_7702::SECP256K1N_HALF {
+                // Value `v` shouldn't be greater then 1, and `s` is checked as of EIP-2
+                let signature = Signature {
+                    v: u64::try_from(auth.v).unwrap_or(u64::MAX),
+                    r: auth.r,
+                    s: auth.s,
+                };
+                if !signature.is_well_formed(TransactionType::SetCode, config) {
                     is_valid = false;
                 }
 
@@ -310,7 +305,7 @@ impl Transaction {
                 });
             }
         } else if self.authorization_list.is_some() {
-            return Err(InvalidTxReason::AuthorizationListNotSupported);
+            return Err(InvalidTransaction::AuthorizationListNotSupported);
         }
         Ok(authorization_list)
     }
@@ -360,7 +355,7 @@ pub struct AuthorizationItem {
 }
 
 /// Denotes the type of transaction.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TxType {
     /// All transactions before EIP-2718 are legacy.
     Legacy,
@@ -378,17 +373,28 @@ impl TxType {
     /// Whether this is a legacy, access list, dynamic fee, etc. transaction
     /// Taken from geth's core/types/transaction.go/UnmarshalBinary, but we only detect the transaction
     /// type rather than unmarshal the entire payload.
+    /// Return `None` for an empty payload or an unknown enveloped transaction type.
     #[must_use]
-    pub const fn from_tx_bytes(tx_bytes: &[u8]) -> Self {
-        match tx_bytes[0] {
-            b if b > 0x7f => Self::Legacy,
-            1 => Self::AccessList,
-            2 => Self::DynamicFee,
-            3 => Self::ShardBlob,
-            4 => Self::EOAAccountCode,
-            _ => panic!(
-                "Unknown tx type. You may need to update the TxType enum if Ethereum introduced new enveloped transaction types."
-            ),
+    pub const fn from_tx_bytes(tx_bytes: &[u8]) -> Option<Self> {
+        match tx_bytes.first() {
+            Some(b) if *b > 0x7f => Some(Self::Legacy),
+            Some(1) => Some(Self::AccessList),
+            Some(2) => Some(Self::DynamicFee),
+            Some(3) => Some(Self::ShardBlob),
+            Some(4) => Some(Self::EOAAccountCode),
+            _ => None,
+        }
+    }
+}
+
+impl From<TxType> for TransactionType {
+    fn from(tx_type: TxType) -> Self {
+        match tx_type {
+            TxType::Legacy => Self::Legacy,
+            TxType::AccessList => Self::AccessList,
+            TxType::DynamicFee => Self::DynamicFee,
+            TxType::ShardBlob => Self::Blob,
+            TxType::EOAAccountCode => Self::SetCode,
         }
     }
 }
@@ -87,7 +87,9 @@ impl VmTestCase {
             chain_id: U256::zero(),
             block_base_fee_per_gas: self.transaction.gas_price,
             block_randomness: self.env.random,
-            blob_gas_price: None,
+            blob_params: None,
+            block_excess_blob_gas: None,
+            block_blob_gas_used: None,
             blob_hashes: Vec::new(),
         }
     }
@@ -151,6 +153,8 @@ impl ExecutionTransaction {
             address: self.address,
             caller: self.sender,
             apparent_value: self.value,
+            code_address: self.address,
+            scheme: Some(aurora_evm::CallScheme::Call),
         }
     }
 }
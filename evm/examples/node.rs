@@ -0,0 +1,257 @@
+//! Minimal node loop: blocks are built from a mempool of typed transactions,
+//! executed one transaction at a time, and persisted to the reference
+//! key-value backend. `eth_call` is answered with dry runs over the latest
+//! state.
+//!
+//! ```sh
+//! cargo run -p aurora-evm --example node --features persistent
+//! # Serve `eth_call` as `<to> <data>` lines, e.g. `0x…02 0x`
+//! cargo run -p aurora-evm --example node --features persistent -- --serve
+//! ```
+
+use aurora_evm::backend::{
+    Apply, ApplyBackend, Backend, Basic, BlockEnv, KeyValueStore, MemoryVicinity,
+    PersistentBackend, TxEnv,
+};
+use aurora_evm::executor::stack::StackExecutorBuilder;
+use aurora_evm::executor::transaction::{Transaction, TransactionType};
+use aurora_evm::{Config, ExitReason};
+use primitive_types::{H160, H256, U256};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+type Store = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Counter contract: increment slot 0, and return the new value.
+const COUNTER_CODE: [u8; 18] = [
+    0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60,
+    0x00, 0xf3,
+];
+
+/// Outcome of an executed transaction.
+struct Receipt {
+    reason: ExitReason,
+    used_gas: u64,
+    logs: usize,
+}
+
+struct Node {
+    config: Config,
+    store: Store,
+    blocks: Vec<H256>,
+    mempool: VecDeque<(TransactionType, Transaction)>,
+}
+
+impl Node {
+    fn new(config: Config, genesis: Vec<(H160, Basic, Vec<u8>)>) -> Self {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let mut backend = PersistentBackend::new(&vicinity, Store::new());
+        let values = genesis
+            .into_iter()
+            .map(|(address, basic, code)| Apply::Modify {
+                address,
+                basic,
+                code: Some(code),
+                storage: Vec::new(),
+                reset_storage: false,
+            });
+        backend.apply(values, Vec::new(), false);
+        Self {
+            config,
+            store: backend.into_store(),
+            blocks: Vec::new(),
+            mempool: VecDeque::new(),
+        }
+    }
+
+    /// Environment of the next block.
+    fn next_block_env(&self) -> BlockEnv {
+        BlockEnv {
+            chain_id: U256::from(1_313_161_554),
+            hashes: self.blocks.iter().rev().copied().collect(),
+            number: U256::from(self.blocks.len()),
+            gas_limit: U256::from(30_000_000),
+            base_fee_per_gas: U256::one(),
+            ..BlockEnv::default()
+        }
+    }
+
+    /// Build and execute a block from the mempool, up to the block gas
+    /// limit, and persist its state.
+    fn produce_block(&mut self) -> Vec<Receipt> {
+        let block = self.next_block_env();
+        let vicinity = MemoryVicinity::new(block.clone(), TxEnv::default());
+        let mut backend = PersistentBackend::new(&vicinity, std::mem::take(&mut self.store));
+        let mut remaining_gas = block.gas_limit.low_u64();
+        let mut receipts = Vec::new();
+        let mut log_index = 0;
+        while let Some((tx_type, tx)) = self.mempool.front() {
+            if tx.gas_limit > remaining_gas {
+                break;
+            }
+            let (tx_type, tx) = (*tx_type, tx.clone());
+            self.mempool.pop_front();
+            if !tx_type.is_supported(&self.config) {
+                continue;
+            }
+            if let Some(receipt) = execute(&mut backend, &self.config, &block, tx, log_index) {
+                remaining_gas = remaining_gas.saturating_sub(receipt.used_gas);
+                log_index += receipt.logs;
+                receipts.push(receipt);
+            }
+        }
+        self.store = backend.into_store();
+        // Stand-in hash: the number of the block
+        self.blocks.push(H256(block.number.to_big_endian()));
+        receipts
+    }
+
+    /// `eth_call` over the latest state.
+    fn call(&mut self, to: H160, data: Vec<u8>) -> (ExitReason, Vec<u8>) {
+        let vicinity = MemoryVicinity::new(self.next_block_env(), TxEnv::default());
+        let backend = PersistentBackend::new(&vicinity, std::mem::take(&mut self.store));
+        let executor = StackExecutorBuilder::new(&self.config, u64::MAX)
+            .backend(&backend)
+            .build();
+        let dry_run = executor.transact_call_dry_run(
+            H160::zero(),
+            to,
+            U256::zero(),
+            data,
+            10_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        drop(executor);
+        self.store = backend.into_store();
+        (dry_run.reason, dry_run.output)
+    }
+}
+
+/// Execute `tx` on top of `backend`, and apply its changes. Return `None`
+/// if the transaction is invalid, leaving the state unchanged.
+fn execute<S: KeyValueStore>(
+    backend: &mut PersistentBackend<'_, S>,
+    config: &Config,
+    block: &BlockEnv,
+    tx: Transaction,
+    first_log_index: usize,
+) -> Option<Receipt> {
+    let mut executor = StackExecutorBuilder::new(config, tx.gas_limit)
+        .backend(&*backend)
+        .build();
+    executor.set_tx_env(TxEnv {
+        gas_price: block.base_fee_per_gas,
+        effective_gas_price: block.base_fee_per_gas,
+        origin: tx.caller,
+        blob_hashes: Vec::new(),
+    });
+    executor.set_block_log_index(Some(first_log_index));
+    let (reason, _) = executor.transact(tx).ok()?;
+    let used_gas = executor.used_gas();
+    let logs = executor.log_positions().len();
+    let (values, logs_to_apply) = executor.into_state().deconstruct();
+    backend.apply(values, logs_to_apply, config.delete_empty_accounts());
+    Some(Receipt {
+        reason,
+        used_gas,
+        logs,
+    })
+}
+
+fn parse_hex(value: &str) -> Option<Vec<u8>> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    if value.len() % 2 != 0 {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{digits}")
+}
+
+fn serve(node: &mut Node) -> std::io::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:8545")?;
+    println!("serving eth_call on {}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        let mut parts = line.split_whitespace().map(parse_hex);
+        let response = match (parts.next().flatten(), parts.next().flatten()) {
+            (Some(to), Some(data)) if to.len() == 20 => {
+                let (reason, output) = node.call(H160::from_slice(&to), data);
+                format!("{reason:?} {}", to_hex(&output))
+            }
+            _ => "usage: <to> <data>".to_owned(),
+        };
+        writeln!(stream, "{response}")?;
+    }
+    Ok(())
+}
+
+fn main() -> std::io::Result<()> {
+    let alice = H160::from_low_u64_be(1);
+    let counter = H160::from_low_u64_be(2);
+    let bob = H160::from_low_u64_be(3);
+    let mut node = Node::new(
+        Config::cancun(),
+        vec![
+            (
+                alice,
+                Basic {
+                    balance: U256::from(10).pow(U256::from(18)),
+                    nonce: U256::zero(),
+                },
+                Vec::new(),
+            ),
+            (counter, Basic::default(), COUNTER_CODE.to_vec()),
+        ],
+    );
+
+    for nonce in 0..4 {
+        let tx_type = if nonce % 2 == 0 {
+            TransactionType::Legacy
+        } else {
+            TransactionType::DynamicFee
+        };
+        let to = if nonce == 3 { bob } else { counter };
+        node.mempool.push_back((
+            tx_type,
+            Transaction {
+                caller: alice,
+                to: Some(to),
+                value: U256::from(nonce),
+                gas_limit: 100_000,
+                nonce,
+                ..Transaction::default()
+            },
+        ));
+    }
+
+    for number in 0..2 {
+        for receipt in node.produce_block() {
+            println!(
+                "block {number}: {:?}, {} gas, {} logs",
+                receipt.reason, receipt.used_gas, receipt.logs
+            );
+        }
+    }
+
+    let (reason, output) = node.call(counter, Vec::new());
+    println!("eth_call counter: {reason:?} {}", to_hex(&output));
+    let vicinity = MemoryVicinity::new(node.next_block_env(), TxEnv::default());
+    let backend = PersistentBackend::new(&vicinity, node.store.clone());
+    println!("bob balance: {}", backend.basic(bob).balance);
+
+    if std::env::args().any(|arg| arg == "--serve") {
+        serve(&mut node)?;
+    }
+    Ok(())
+}
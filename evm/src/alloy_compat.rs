@@ -0,0 +1,123 @@
+//! Conversions between the crate types, based on `primitive-types`, and
+//! `alloy-primitives` types.
+//!
+//! Both sides of the primitive conversions are foreign types, so they are
+//! provided by the [`ToAlloy`] and [`FromAlloy`] traits instead of `From`.
+
+use crate::backend::Log;
+use alloy_primitives::{Address, Bytes, LogData, B256};
+use primitive_types::{H160, H256, U256};
+
+/// Convert a value into its `alloy-primitives` counterpart.
+pub trait ToAlloy {
+    /// `alloy-primitives` type.
+    type Alloy;
+
+    /// Convert into the `alloy-primitives` type.
+    fn to_alloy(self) -> Self::Alloy;
+}
+
+/// Convert an `alloy-primitives` value into the crate counterpart.
+pub trait FromAlloy<T> {
+    /// Convert from the `alloy-primitives` type.
+    fn from_alloy(value: T) -> Self;
+}
+
+impl ToAlloy for H160 {
+    type Alloy = Address;
+
+    fn to_alloy(self) -> Address {
+        Address::from(self.0)
+    }
+}
+
+impl FromAlloy<Address> for H160 {
+    fn from_alloy(value: Address) -> Self {
+        Self(value.into_array())
+    }
+}
+
+impl ToAlloy for H256 {
+    type Alloy = B256;
+
+    fn to_alloy(self) -> B256 {
+        B256::from(self.0)
+    }
+}
+
+impl FromAlloy<B256> for H256 {
+    fn from_alloy(value: B256) -> Self {
+        Self(value.0)
+    }
+}
+
+impl ToAlloy for U256 {
+    type Alloy = alloy_primitives::U256;
+
+    fn to_alloy(self) -> alloy_primitives::U256 {
+        // Both types store little-endian 64-bit limbs
+        alloy_primitives::U256::from_limbs(self.0)
+    }
+}
+
+impl FromAlloy<alloy_primitives::U256> for U256 {
+    fn from_alloy(value: alloy_primitives::U256) -> Self {
+        Self(value.into_limbs())
+    }
+}
+
+impl From<Log> for alloy_primitives::Log {
+    fn from(log: Log) -> Self {
+        Self {
+            address: log.address.to_alloy(),
+            data: LogData::new_unchecked(
+                log.topics.into_iter().map(ToAlloy::to_alloy).collect(),
+                Bytes::from(log.data),
+            ),
+        }
+    }
+}
+
+impl From<alloy_primitives::Log> for Log {
+    fn from(log: alloy_primitives::Log) -> Self {
+        let (topics, data) = log.data.split();
+        Self {
+            address: H160::from_alloy(log.address),
+            topics: topics.into_iter().map(H256::from_alloy).collect(),
+            data: data.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitives_round_trip() {
+        let address = H160::repeat_byte(0x11);
+        assert_eq!(address.to_alloy(), Address::repeat_byte(0x11));
+        assert_eq!(H160::from_alloy(address.to_alloy()), address);
+
+        let word = H256::repeat_byte(0x22);
+        assert_eq!(H256::from_alloy(word.to_alloy()), word);
+
+        let value = U256::MAX - U256::from(0x1234_5678_u64) * U256::from(u64::MAX);
+        assert_eq!(value.to_alloy().to_string(), value.to_string());
+        assert_eq!(U256::from_alloy(value.to_alloy()), value);
+    }
+
+    #[test]
+    fn test_log_round_trip() {
+        let log = Log {
+            address: H160::repeat_byte(1),
+            topics: vec![H256::repeat_byte(2), H256::repeat_byte(3)],
+            data: vec![4, 5, 6],
+        };
+        let alloy_log = alloy_primitives::Log::from(log.clone());
+        assert_eq!(alloy_log.address, Address::repeat_byte(1));
+        assert_eq!(alloy_log.data.topics()[1], B256::repeat_byte(3));
+        assert_eq!(alloy_log.data.data.as_ref(), &[4, 5, 6]);
+        assert_eq!(Log::from(alloy_log), log);
+    }
+}
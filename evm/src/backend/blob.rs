@@ -0,0 +1,165 @@
+//! Blob gas market of [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844).
+
+use primitive_types::U256;
+
+/// Gas consumed by a single blob, its size in bytes.
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+/// Minimum blob gas price.
+pub const MIN_BLOB_GAS_PRICE: u64 = 1;
+/// Version byte of the versioned hashes of KZG commitments, the first byte
+/// of every blob versioned hash.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Blob gas parameters of a fork, which determine the excess blob gas of
+/// the blocks and their blob gas price.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlobParams {
+    /// Blob gas targeted per block, above which the blob gas price rises.
+    pub target_blob_gas_per_block: u64,
+    /// Maximum blob gas per block.
+    pub max_blob_gas_per_block: u64,
+    /// Controls the maximum rate of change of the blob gas price.
+    pub update_fraction: u64,
+}
+
+impl BlobParams {
+    /// Cancun parameters: 3 target and 6 maximum blobs per block.
+    pub const CANCUN: Self = Self {
+        target_blob_gas_per_block: 3 * GAS_PER_BLOB,
+        max_blob_gas_per_block: 6 * GAS_PER_BLOB,
+        update_fraction: 3_338_477,
+    };
+
+    /// Prague parameters: 6 target and 9 maximum blobs per block.
+    /// See [EIP-7691](https://eips.ethereum.org/EIPS/eip-7691).
+    pub const PRAGUE: Self = Self {
+        target_blob_gas_per_block: 6 * GAS_PER_BLOB,
+        max_blob_gas_per_block: 9 * GAS_PER_BLOB,
+        update_fraction: 5_007_716,
+    };
+
+    /// Excess blob gas of a block, from the excess blob gas and the blob gas
+    /// used of its parent.
+    #[must_use]
+    pub const fn excess_blob_gas(
+        &self,
+        parent_excess_blob_gas: u64,
+        parent_blob_gas_used: u64,
+    ) -> u64 {
+        parent_excess_blob_gas
+            .saturating_add(parent_blob_gas_used)
+            .saturating_sub(self.target_blob_gas_per_block)
+    }
+
+    /// Blob gas price of a block with `excess_blob_gas`, returned by
+    /// `BLOBBASEFEE`.
+    #[must_use]
+    pub fn blob_gas_price(&self, excess_blob_gas: u64) -> u128 {
+        fake_exponential(MIN_BLOB_GAS_PRICE, excess_blob_gas, self.update_fraction)
+    }
+}
+
+/// Approximation of `factor * e ** (numerator / denominator)` by Taylor
+/// expansion, as specified by EIP-4844.
+///
+/// Saturates to `u128::MAX` when the result does not fit, or when
+/// `denominator` is zero.
+#[must_use]
+pub fn fake_exponential(factor: u64, numerator: u64, denominator: u64) -> u128 {
+    fn approximate(factor: U256, numerator: U256, denominator: U256) -> Option<U256> {
+        let mut i = U256::one();
+        let mut output = U256::zero();
+        let mut numerator_accum = factor.checked_mul(denominator)?;
+        while !numerator_accum.is_zero() {
+            output = output.checked_add(numerator_accum)?;
+            numerator_accum = numerator_accum
+                .checked_mul(numerator)?
+                .checked_div(denominator.checked_mul(i)?)?;
+            i += U256::one();
+        }
+        output.checked_div(denominator)
+    }
+
+    approximate(
+        U256::from(factor),
+        U256::from(numerator),
+        U256::from(denominator),
+    )
+    .and_then(|output| u128::try_from(output).ok())
+    .unwrap_or(u128::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{Backend, BlockEnv, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_fake_exponential() {
+        // Test vectors of the EIP-4844 reference implementation
+        for (factor, numerator, denominator, expected) in [
+            (1, 0, 1, 1),
+            (38_493, 0, 1_000, 38_493),
+            (0, 1_234, 2_345, 0),
+            (1, 2, 1, 6),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (1, 6, 2, 18),
+            (1, 4, 1, 49),
+            (1, 8, 2, 50),
+            (10, 8, 2, 542),
+            (11, 8, 2, 596),
+            (1, 5, 1, 136),
+            (1, 5, 2, 11),
+            (2, 5, 2, 23),
+            (1, 50_000_000, 2_225_652, 5_709_098_764),
+        ] {
+            assert_eq!(fake_exponential(factor, numerator, denominator), expected);
+        }
+        assert_eq!(fake_exponential(1, 1, 0), u128::MAX);
+        assert_eq!(fake_exponential(1, u64::MAX, 1), u128::MAX);
+    }
+
+    #[test]
+    fn test_blob_params() {
+        let params = BlobParams::CANCUN;
+        assert_eq!(params.excess_blob_gas(0, GAS_PER_BLOB), 0);
+        assert_eq!(
+            params.excess_blob_gas(GAS_PER_BLOB, params.max_blob_gas_per_block),
+            4 * GAS_PER_BLOB
+        );
+        assert_eq!(params.blob_gas_price(0), 1);
+        assert!(BlobParams::PRAGUE.blob_gas_price(10_000_000) < params.blob_gas_price(10_000_000));
+    }
+
+    #[test]
+    fn test_backend_blob_gas() {
+        let mut block = BlockEnv {
+            blob_gas_used: Some(2 * GAS_PER_BLOB),
+            ..BlockEnv::default()
+        };
+        block.set_excess_blob_gas(10_000_000, &BlobParams::CANCUN);
+        let vicinity = MemoryVicinity::new(block, TxEnv::default());
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        assert_eq!(backend.block_excess_blob_gas(), Some(10_000_000));
+        assert_eq!(backend.blob_gas_used(), Some(2 * GAS_PER_BLOB));
+        assert_eq!(
+            backend.blob_gas_price(),
+            Some(BlobParams::CANCUN.blob_gas_price(10_000_000))
+        );
+        assert_eq!(backend.blob_gas_price(), Some(19));
+
+        // The price follows the excess blob gas
+        let mut vicinity = vicinity;
+        vicinity.block_excess_blob_gas = Some(0);
+        assert_eq!(vicinity.blob_gas_price(), Some(1));
+        vicinity.blob_params = None;
+        assert_eq!(vicinity.blob_gas_price(), None);
+    }
+}
@@ -0,0 +1,292 @@
+use super::{slice_code, Apply, ApplyBackend, Backend, Basic, Log};
+use crate::prelude::*;
+use core::ops::Range;
+use primitive_types::{H160, H256, U256};
+
+/// Number of account and storage reads served by a [`CachedBackend`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Reads served from the cache.
+    pub hits: usize,
+    /// Reads forwarded to the wrapped backend.
+    pub misses: usize,
+}
+
+/// Backend memoizing the account and storage reads of the wrapped backend,
+/// e.g. one querying a database or a remote node.
+///
+/// `MemoryStackState` only keeps the accounts and slots an execution wrote,
+/// so every read of the others, e.g. the balance of a token contract read by
+/// all its calls, goes to the backend. The cache removes the repeated reads
+/// of a transaction, or of a sequence of transactions if nothing else writes
+/// to the wrapped backend.
+///
+/// The writes of an execution are held by its state, which the reads check
+/// first, so the cache is only invalidated when changes are applied: entries
+/// of the applied accounts are dropped by [`ApplyBackend::apply`]. Changes
+/// made to the wrapped backend directly require [`Self::invalidate`] or
+/// [`Self::clear`].
+///
+/// Each kind of read is cached up to a capacity of entries, see
+/// [`Self::with_capacity`]: a full cache is emptied before the next entry is
+/// inserted.
+#[derive(Debug)]
+pub struct CachedBackend<B> {
+    backend: B,
+    capacity: usize,
+    exists: RefCell<BTreeMap<H160, bool>>,
+    basics: RefCell<BTreeMap<H160, Basic>>,
+    codes: RefCell<BTreeMap<H160, Vec<u8>>>,
+    empty_storages: RefCell<BTreeMap<H160, bool>>,
+    storages: RefCell<BTreeMap<(H160, H256), H256>>,
+    original_storages: RefCell<BTreeMap<(H160, H256), Option<H256>>>,
+    stats: RefCell<CacheStats>,
+}
+
+impl<B: Backend> CachedBackend<B> {
+    /// Entries of each cache of [`Self::new`].
+    pub const DEFAULT_CAPACITY: usize = 4096;
+
+    /// Cache the reads of `backend`, up to [`Self::DEFAULT_CAPACITY`]
+    /// entries of each kind.
+    #[must_use]
+    pub const fn new(backend: B) -> Self {
+        Self::with_capacity(backend, Self::DEFAULT_CAPACITY)
+    }
+
+    /// Cache the reads of `backend`, up to `capacity` entries of each kind.
+    #[must_use]
+    pub const fn with_capacity(backend: B, capacity: usize) -> Self {
+        Self {
+            backend,
+            capacity,
+            exists: RefCell::new(BTreeMap::new()),
+            basics: RefCell::new(BTreeMap::new()),
+            codes: RefCell::new(BTreeMap::new()),
+            empty_storages: RefCell::new(BTreeMap::new()),
+            storages: RefCell::new(BTreeMap::new()),
+            original_storages: RefCell::new(BTreeMap::new()),
+            stats: RefCell::new(CacheStats { hits: 0, misses: 0 }),
+        }
+    }
+
+    /// Wrapped backend.
+    #[must_use]
+    pub const fn inner(&self) -> &B {
+        &self.backend
+    }
+
+    /// Unwrap the backend, dropping the cache.
+    #[must_use]
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    /// Reads served from the cache and forwarded to the backend so far.
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        *self.stats.borrow()
+    }
+
+    /// Drop the cached entries of `address`, e.g. after it was changed in
+    /// the wrapped backend.
+    pub fn invalidate(&mut self, address: H160) {
+        self.exists.get_mut().remove(&address);
+        self.basics.get_mut().remove(&address);
+        self.codes.get_mut().remove(&address);
+        self.empty_storages.get_mut().remove(&address);
+        self.storages
+            .get_mut()
+            .retain(|(cached, _), _| *cached != address);
+        self.original_storages
+            .get_mut()
+            .retain(|(cached, _), _| *cached != address);
+    }
+
+    /// Drop all cached entries.
+    pub fn clear(&mut self) {
+        self.exists.get_mut().clear();
+        self.basics.get_mut().clear();
+        self.codes.get_mut().clear();
+        self.empty_storages.get_mut().clear();
+        self.storages.get_mut().clear();
+        self.original_storages.get_mut().clear();
+    }
+
+    /// Value of `key` in `cache`, read with `read` on a miss.
+    fn cached<K: Ord, V: Clone>(
+        &self,
+        cache: &RefCell<BTreeMap<K, V>>,
+        key: K,
+        read: impl FnOnce(&B) -> V,
+    ) -> V {
+        let hit = cache.borrow().get(&key).cloned();
+        if let Some(value) = hit {
+            self.stats.borrow_mut().hits += 1;
+            return value;
+        }
+        self.stats.borrow_mut().misses += 1;
+        let value = read(&self.backend);
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= self.capacity {
+            cache.clear();
+        }
+        cache.insert(key, value.clone());
+        value
+    }
+}
+
+impl<B: Backend> Backend for CachedBackend<B> {
+    delegate_environment!(backend: backend);
+
+    fn exists(&self, address: H160) -> bool {
+        self.cached(&self.exists, address, |backend| backend.exists(address))
+    }
+
+    fn basic(&self, address: H160) -> Basic {
+        self.cached(&self.basics, address, |backend| backend.basic(address))
+    }
+
+    fn code(&self, address: H160) -> Vec<u8> {
+        self.cached(&self.codes, address, |backend| backend.code(address))
+    }
+
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        // Slices are not cached, so the whole code is not loaded for them
+        let hit = self
+            .codes
+            .borrow()
+            .get(&address)
+            .map(|code| slice_code(code, range.clone()));
+        if let Some(slice) = hit {
+            self.stats.borrow_mut().hits += 1;
+            return slice;
+        }
+        self.stats.borrow_mut().misses += 1;
+        self.backend.code_slice(address, range)
+    }
+
+    fn storage(&self, address: H160, index: H256) -> H256 {
+        self.cached(&self.storages, (address, index), |backend| {
+            backend.storage(address, index)
+        })
+    }
+
+    fn is_empty_storage(&self, address: H160) -> bool {
+        self.cached(&self.empty_storages, address, |backend| {
+            backend.is_empty_storage(address)
+        })
+    }
+
+    fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+        self.cached(&self.original_storages, (address, index), |backend| {
+            backend.original_storage(address, index)
+        })
+    }
+
+    #[cfg(feature = "std")]
+    fn iter_storage(&self, address: H160) -> Option<Box<dyn Iterator<Item = (H256, H256)> + '_>> {
+        self.backend.iter_storage(address)
+    }
+}
+
+impl<B: Backend + ApplyBackend> ApplyBackend for CachedBackend<B> {
+    fn apply<A, I, L>(&mut self, values: A, logs: L, delete_empty: bool)
+    where
+        A: IntoIterator<Item = Apply<I>>,
+        I: IntoIterator<Item = (H256, H256)>,
+        L: IntoIterator<Item = Log>,
+    {
+        let values: Vec<_> = values.into_iter().collect();
+        for apply in &values {
+            self.invalidate(apply.address());
+        }
+        self.backend.apply(values, logs, delete_empty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::executor::stack::StackExecutorBuilder;
+    use crate::{Config, ExitReason, ExitSucceed};
+
+    #[test]
+    fn test_cached_backend() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        // SLOAD(0) three times, then SSTORE(0, 1)
+        let code = vec![
+            0x60, 0x00, 0x54, 0x60, 0x00, 0x54, 0x60, 0x00, 0x54, 0x60, 0x01, 0x60, 0x00, 0x55,
+        ];
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code,
+                    storage: BTreeMap::from([(H256::zero(), H256::repeat_byte(7))]),
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let mut cached = CachedBackend::new(backend);
+        let config = Config::cancun();
+
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&cached)
+            .build();
+        let (reason, _) = executor.transact_call(
+            caller,
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        let (values, logs) = executor.into_state().deconstruct();
+        let stats = cached.stats();
+        assert!(stats.hits > 0);
+
+        // Applying the changes drops the stale entries
+        cached.apply(values, logs, true);
+        assert_eq!(
+            cached.storage(contract, H256::zero()),
+            H256::from_low_u64_be(1)
+        );
+        assert_eq!(cached.stats().misses, stats.misses + 1);
+        assert_eq!(
+            cached.storage(contract, H256::zero()),
+            H256::from_low_u64_be(1)
+        );
+        assert_eq!(cached.stats().hits, stats.hits + 1);
+
+        cached.clear();
+        assert_eq!(cached.basic(contract), cached.inner().basic(contract));
+        assert_eq!(cached.stats().misses, stats.misses + 2);
+    }
+
+    #[test]
+    fn test_cache_capacity() {
+        let (first, second) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let cached =
+            CachedBackend::with_capacity(MemoryBackend::new(&vicinity, BTreeMap::new()), 1);
+
+        cached.basic(first);
+        cached.basic(first);
+        assert_eq!(cached.stats(), CacheStats { hits: 1, misses: 1 });
+        // Caching the second account evicts the first
+        cached.basic(second);
+        cached.basic(first);
+        assert_eq!(cached.stats(), CacheStats { hits: 1, misses: 3 });
+        // The caches of the other reads are separate
+        cached.code(second);
+        cached.basic(first);
+        assert_eq!(cached.stats(), CacheStats { hits: 2, misses: 4 });
+    }
+}
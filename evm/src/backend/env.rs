@@ -0,0 +1,86 @@
+use super::BlobParams;
+use crate::core::utils::U256_ONE;
+use crate::prelude::*;
+use primitive_types::{H160, H256, U256};
+
+/// Block-level environment, shared by all transactions of a block.
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockEnv {
+    /// Chain ID.
+    pub chain_id: U256,
+    /// Hashes of the previous blocks, the most recent first.
+    pub hashes: Vec<H256>,
+    /// Block number.
+    pub number: U256,
+    /// Block coinbase.
+    pub coinbase: H160,
+    /// Block timestamp.
+    pub timestamp: U256,
+    /// Block difficulty.
+    pub difficulty: U256,
+    /// Block gas limit.
+    pub gas_limit: U256,
+    /// Base fee per gas.
+    pub base_fee_per_gas: U256,
+    /// Randomness, only enabled post Merge.
+    pub randomness: Option<H256>,
+    /// EIP-4844 blob gas parameters of the fork, from which the blob gas
+    /// price is derived, see [`BlockEnv::blob_gas_price`].
+    pub blob_params: Option<BlobParams>,
+    /// EIP-4844 excess blob gas, see [`BlockEnv::set_excess_blob_gas`].
+    pub excess_blob_gas: Option<u64>,
+    /// EIP-4844 blob gas used by the block.
+    pub blob_gas_used: Option<u64>,
+}
+
+impl BlockEnv {
+    /// Set the excess blob gas of the block, and the blob parameters of the
+    /// fork its blob gas price is derived with.
+    pub const fn set_excess_blob_gas(&mut self, excess_blob_gas: u64, params: &BlobParams) {
+        self.excess_blob_gas = Some(excess_blob_gas);
+        self.blob_params = Some(*params);
+    }
+
+    /// Blob gas price of the block, derived from its excess blob gas.
+    #[must_use]
+    pub fn blob_gas_price(&self) -> Option<u128> {
+        Some(self.blob_params?.blob_gas_price(self.excess_blob_gas?))
+    }
+
+    /// Hash of the block `number`. Return zero hash if the block is not
+    /// one of the known previous blocks.
+    #[must_use]
+    pub fn block_hash(&self, number: U256) -> H256 {
+        if number >= self.number {
+            return H256::default();
+        }
+        let depth = self.number - number - U256_ONE;
+        if depth >= U256::from(self.hashes.len()) {
+            return H256::default();
+        }
+        self.hashes[depth.as_usize()]
+    }
+}
+
+/// Transaction-level environment.
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TxEnv {
+    /// Gas price.
+    pub gas_price: U256,
+    /// Effective gas price, returned by `GASPRICE`.
+    pub effective_gas_price: U256,
+    /// Origin.
+    pub origin: H160,
+    /// EIP-4844
+    pub blob_hashes: Vec<U256>,
+}
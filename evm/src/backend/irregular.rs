@@ -0,0 +1,138 @@
+use super::{Apply, ApplyBackend, Backend, Basic};
+use crate::prelude::*;
+use primitive_types::{H160, H256, U256};
+
+/// Move of the whole balance of an account to another account.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalanceMove {
+    /// Drained account.
+    pub from: H160,
+    /// Beneficiary account.
+    pub to: H160,
+}
+
+/// Irregular state changes applied at the beginning of a block, outside of
+/// any transaction, e.g. the DAO fork at mainnet block 1,920,000.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IrregularStateTransition {
+    /// Block at which the transition is applied.
+    pub block_number: U256,
+    /// Balance moves, applied in order.
+    pub balance_moves: Vec<BalanceMove>,
+    /// Code replacements, applied after the balance moves.
+    pub code_sets: Vec<(H160, Vec<u8>)>,
+}
+
+impl IrregularStateTransition {
+    /// Transition draining the balance of all `accounts` to `beneficiary`,
+    /// the shape of the DAO fork.
+    #[must_use]
+    pub fn drain(block_number: U256, accounts: &[H160], beneficiary: H160) -> Self {
+        Self {
+            block_number,
+            balance_moves: accounts
+                .iter()
+                .map(|&from| BalanceMove {
+                    from,
+                    to: beneficiary,
+                })
+                .collect(),
+            code_sets: Vec::new(),
+        }
+    }
+
+    /// Apply the transition to `backend` if `block_number` is the configured
+    /// block. Return whether the transition was applied.
+    pub fn apply_at<B: Backend + ApplyBackend>(&self, backend: &mut B, block_number: U256) -> bool {
+        if block_number != self.block_number {
+            return false;
+        }
+        self.apply(backend);
+        true
+    }
+
+    /// Apply the transition to `backend` unconditionally.
+    pub fn apply<B: Backend + ApplyBackend>(&self, backend: &mut B) {
+        let mut accounts = BTreeMap::new();
+        for BalanceMove { from, to } in &self.balance_moves {
+            let from = account(&mut accounts, backend, *from);
+            let value = core::mem::take(&mut from.0.balance);
+            let to = account(&mut accounts, backend, *to);
+            to.0.balance = to.0.balance.saturating_add(value);
+        }
+        for (address, code) in &self.code_sets {
+            account(&mut accounts, backend, *address).1 = Some(code.clone());
+        }
+
+        let applies: Vec<_> = accounts
+            .into_iter()
+            .map(|(address, (basic, code))| Apply::Modify {
+                address,
+                basic,
+                code,
+                storage: Vec::<(H256, H256)>::new(),
+                reset_storage: false,
+            })
+            .collect();
+        backend.apply(applies, Vec::new(), false);
+    }
+}
+
+/// Pending account changes, loaded from the backend on first access.
+fn account<'a, B: Backend>(
+    accounts: &'a mut BTreeMap<H160, (Basic, Option<Vec<u8>>)>,
+    backend: &B,
+    address: H160,
+) -> &'a mut (Basic, Option<Vec<u8>>) {
+    accounts
+        .entry(address)
+        .or_insert_with(|| (backend.basic(address), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+
+    #[test]
+    fn test_dao_like_drain() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let dao = [H160::from_low_u64_be(1), H160::from_low_u64_be(2)];
+        let refund = H160::from_low_u64_be(3);
+        let state = dao
+            .iter()
+            .map(|&address| {
+                (
+                    address,
+                    MemoryAccount {
+                        balance: U256::from(100),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        let mut backend = MemoryBackend::new(&vicinity, state);
+
+        let mut transition = IrregularStateTransition::drain(U256::from(10), &dao, refund);
+        transition.code_sets.push((refund, vec![0x00]));
+
+        assert!(!transition.apply_at(&mut backend, U256::from(9)));
+        assert_eq!(backend.state()[&dao[0]].balance, U256::from(100));
+
+        assert!(transition.apply_at(&mut backend, U256::from(10)));
+        assert_eq!(backend.state()[&dao[0]].balance, U256::zero());
+        assert_eq!(backend.state()[&dao[1]].balance, U256::zero());
+        assert_eq!(backend.state()[&refund].balance, U256::from(200));
+        assert_eq!(backend.state()[&refund].code, vec![0x00]);
+    }
+}
@@ -1,6 +1,7 @@
-use super::{Apply, ApplyBackend, Backend, Basic, Log};
+use super::{slice_code, Apply, ApplyBackend, Backend, Basic, BlobParams, BlockEnv, Log, TxEnv};
 use crate::core::utils::{U256_ONE, U256_ZERO};
 use crate::prelude::*;
+use core::ops::Range;
 use primitive_types::{H160, H256, U256};
 
 /// Vicinity value of a memory backend.
@@ -39,12 +40,81 @@ pub struct MemoryVicinity {
     /// In Ethereum, this is the randomness beacon provided by the beacon
     /// chain and is only enabled post Merge.
     pub block_randomness: Option<H256>,
+    /// EIP-4844 blob gas parameters of the fork, see
+    /// [`MemoryVicinity::blob_gas_price`].
+    pub blob_params: Option<BlobParams>,
     /// EIP-4844
-    pub blob_gas_price: Option<u128>,
+    pub block_excess_blob_gas: Option<u64>,
+    /// EIP-4844
+    pub block_blob_gas_used: Option<u64>,
     /// EIP-4844
     pub blob_hashes: Vec<U256>,
 }
 
+impl MemoryVicinity {
+    /// Create a vicinity from block and transaction environments.
+    #[must_use]
+    pub fn new(block: BlockEnv, tx: TxEnv) -> Self {
+        Self {
+            gas_price: tx.gas_price,
+            effective_gas_price: tx.effective_gas_price,
+            origin: tx.origin,
+            chain_id: block.chain_id,
+            block_hashes: block.hashes,
+            block_number: block.number,
+            block_coinbase: block.coinbase,
+            block_timestamp: block.timestamp,
+            block_difficulty: block.difficulty,
+            block_gas_limit: block.gas_limit,
+            block_base_fee_per_gas: block.base_fee_per_gas,
+            block_randomness: block.randomness,
+            blob_params: block.blob_params,
+            block_excess_blob_gas: block.excess_blob_gas,
+            block_blob_gas_used: block.blob_gas_used,
+            blob_hashes: tx.blob_hashes,
+        }
+    }
+
+    /// Block-level part of the vicinity.
+    #[must_use]
+    pub fn block_env(&self) -> BlockEnv {
+        BlockEnv {
+            chain_id: self.chain_id,
+            hashes: self.block_hashes.clone(),
+            number: self.block_number,
+            coinbase: self.block_coinbase,
+            timestamp: self.block_timestamp,
+            difficulty: self.block_difficulty,
+            gas_limit: self.block_gas_limit,
+            base_fee_per_gas: self.block_base_fee_per_gas,
+            randomness: self.block_randomness,
+            blob_params: self.blob_params,
+            excess_blob_gas: self.block_excess_blob_gas,
+            blob_gas_used: self.block_blob_gas_used,
+        }
+    }
+
+    /// Transaction-level part of the vicinity.
+    #[must_use]
+    pub fn tx_env(&self) -> TxEnv {
+        TxEnv {
+            gas_price: self.gas_price,
+            effective_gas_price: self.effective_gas_price,
+            origin: self.origin,
+            blob_hashes: self.blob_hashes.clone(),
+        }
+    }
+
+    /// Blob gas price of the block, derived from its excess blob gas.
+    #[must_use]
+    pub fn blob_gas_price(&self) -> Option<u128> {
+        Some(
+            self.blob_params?
+                .blob_gas_price(self.block_excess_blob_gas?),
+        )
+    }
+}
+
 /// Account information of a memory backend.
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(
@@ -85,6 +155,12 @@ impl<'vicinity> MemoryBackend<'vicinity> {
         }
     }
 
+    /// Get the vicinity of the backend.
+    #[must_use]
+    pub const fn vicinity(&self) -> &'vicinity MemoryVicinity {
+        self.vicinity
+    }
+
     /// Get the underlying `BTreeMap` storing the state.
     #[must_use]
     pub const fn state(&self) -> &BTreeMap<H160, MemoryAccount> {
@@ -95,52 +171,148 @@ impl<'vicinity> MemoryBackend<'vicinity> {
     pub const fn state_mut(&mut self) -> &mut BTreeMap<H160, MemoryAccount> {
         &mut self.state
     }
-}
 
-impl Backend for MemoryBackend<'_> {
-    #[allow(clippy::misnamed_getters)]
-    fn gas_price(&self) -> U256 {
-        self.vicinity.effective_gas_price
-    }
-    fn origin(&self) -> H160 {
-        self.vicinity.origin
+    /// Apply given values and logs, like [`ApplyBackend::apply`], and return
+    /// the applied delta.
+    pub fn apply_with_delta<A, I, L>(
+        &mut self,
+        values: A,
+        logs: L,
+        delete_empty: bool,
+    ) -> AppliedDelta
+    where
+        A: IntoIterator<Item = Apply<I>>,
+        I: IntoIterator<Item = (H256, H256)>,
+        L: IntoIterator<Item = Log>,
+    {
+        let delta = Self::apply_into(values, &mut self.state, delete_empty);
+        self.logs.extend(logs);
+        delta
     }
-    fn block_hash(&self, number: U256) -> H256 {
-        if number >= self.vicinity.block_number
-            || self.vicinity.block_number - number - U256_ONE
-                >= U256::from(self.vicinity.block_hashes.len())
-        {
-            H256::default()
-        } else {
-            let index = (self.vicinity.block_number - number - U256_ONE).as_usize();
-            self.vicinity.block_hashes[index]
+
+    /// Apply given values to the `target` state instead of the backend
+    /// state, and return the applied delta.
+    ///
+    /// It allows to apply the execution results to a copy of the state, or
+    /// to collect the changed accounts to persist them or replicate them.
+    pub fn apply_into<A, I>(
+        values: A,
+        target: &mut BTreeMap<H160, MemoryAccount>,
+        delete_empty: bool,
+    ) -> AppliedDelta
+    where
+        A: IntoIterator<Item = Apply<I>>,
+        I: IntoIterator<Item = (H256, H256)>,
+    {
+        let mut delta = AppliedDelta::default();
+        for apply in values {
+            match apply {
+                Apply::Modify {
+                    address,
+                    basic,
+                    code,
+                    storage,
+                    reset_storage,
+                } => {
+                    let is_empty = {
+                        let account = target.entry(address).or_default();
+                        account.balance = basic.balance;
+                        account.nonce = basic.nonce;
+                        if let Some(code) = code {
+                            account.code = code;
+                        }
+
+                        if reset_storage {
+                            account.storage = BTreeMap::new();
+                        }
+
+                        let zeros = account
+                            .storage
+                            .iter()
+                            .filter(|(_, v)| v == &&H256::default())
+                            .map(|(k, _)| *k)
+                            .collect::<Vec<H256>>();
+
+                        for zero in zeros {
+                            account.storage.remove(&zero);
+                        }
+
+                        for (index, value) in storage {
+                            if value == H256::default() {
+                                account.storage.remove(&index);
+                            } else {
+                                account.storage.insert(index, value);
+                            }
+                        }
+
+                        account.balance == U256_ZERO
+                            && account.nonce == U256_ZERO
+                            && account.code.is_empty()
+                    };
+
+                    if is_empty && delete_empty {
+                        target.remove(&address);
+                        delta.mark_deleted(address);
+                    } else {
+                        delta.mark_modified(address);
+                    }
+                }
+                Apply::Delete { address } => {
+                    target.remove(&address);
+                    delta.mark_deleted(address);
+                }
+            }
         }
+        delta
     }
-    fn block_number(&self) -> U256 {
-        self.vicinity.block_number
-    }
-    fn block_coinbase(&self) -> H160 {
-        self.vicinity.block_coinbase
-    }
-    fn block_timestamp(&self) -> U256 {
-        self.vicinity.block_timestamp
-    }
-    fn block_difficulty(&self) -> U256 {
-        self.vicinity.block_difficulty
-    }
-    fn block_randomness(&self) -> Option<H256> {
-        self.vicinity.block_randomness
-    }
-    fn block_gas_limit(&self) -> U256 {
-        self.vicinity.block_gas_limit
+}
+
+/// Accounts changed by applying execution results to a memory state.
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppliedDelta {
+    /// Accounts created or modified, and present in the state.
+    pub modified: BTreeSet<H160>,
+    /// Accounts removed from the state.
+    pub deleted: BTreeSet<H160>,
+}
+
+impl AppliedDelta {
+    /// Whether no account was changed.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.modified.is_empty() && self.deleted.is_empty()
     }
-    fn block_base_fee_per_gas(&self) -> U256 {
-        self.vicinity.block_base_fee_per_gas
+
+    fn mark_modified(&mut self, address: H160) {
+        self.deleted.remove(&address);
+        self.modified.insert(address);
     }
 
-    fn chain_id(&self) -> U256 {
-        self.vicinity.chain_id
+    fn mark_deleted(&mut self, address: H160) {
+        self.modified.remove(&address);
+        self.deleted.insert(address);
     }
+}
+
+/// Non-zero storage entries of `account`, if any.
+#[cfg(feature = "std")]
+pub(crate) fn iter_account_storage(
+    account: Option<&MemoryAccount>,
+) -> impl Iterator<Item = (H256, H256)> + '_ {
+    account
+        .into_iter()
+        .flat_map(|account| account.storage.iter())
+        .filter(|(_, value)| !value.is_zero())
+        .map(|(index, value)| (*index, *value))
+}
+
+impl Backend for MemoryBackend<'_> {
+    delegate_environment!(vicinity: vicinity);
 
     fn exists(&self, address: H160) -> bool {
         self.state.contains_key(&address)
@@ -163,6 +335,13 @@ impl Backend for MemoryBackend<'_> {
             .unwrap_or_default()
     }
 
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        self.state
+            .get(&address)
+            .map(|v| slice_code(&v.code, range))
+            .unwrap_or_default()
+    }
+
     fn storage(&self, address: H160, index: H256) -> H256 {
         self.state
             .get(&address)
@@ -179,11 +358,10 @@ impl Backend for MemoryBackend<'_> {
     fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
         Some(self.storage(address, index))
     }
-    fn blob_gas_price(&self) -> Option<u128> {
-        self.vicinity.blob_gas_price
-    }
-    fn get_blob_hash(&self, index: usize) -> Option<U256> {
-        self.vicinity.blob_hashes.get(index).copied()
+
+    #[cfg(feature = "std")]
+    fn iter_storage(&self, address: H160) -> Option<Box<dyn Iterator<Item = (H256, H256)> + '_>> {
+        Some(Box::new(iter_account_storage(self.state.get(&address))))
     }
 }
 
@@ -194,63 +372,102 @@ impl ApplyBackend for MemoryBackend<'_> {
         I: IntoIterator<Item = (H256, H256)>,
         L: IntoIterator<Item = Log>,
     {
-        for apply in values {
-            match apply {
-                Apply::Modify {
-                    address,
-                    basic,
-                    code,
-                    storage,
-                    reset_storage,
-                } => {
-                    let is_empty = {
-                        let account = self.state.entry(address).or_default();
-                        account.balance = basic.balance;
-                        account.nonce = basic.nonce;
-                        if let Some(code) = code {
-                            account.code = code;
-                        }
-
-                        if reset_storage {
-                            account.storage = BTreeMap::new();
-                        }
+        self.apply_with_delta(values, logs, delete_empty);
+    }
+}
 
-                        let zeros = account
-                            .storage
-                            .iter()
-                            .filter(|(_, v)| v == &&H256::default())
-                            .map(|(k, _)| *k)
-                            .collect::<Vec<H256>>();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                        for zero in zeros {
-                            account.storage.remove(&zero);
-                        }
+    fn modify(address: H160, balance: u64) -> Apply<Vec<(H256, H256)>> {
+        Apply::Modify {
+            address,
+            basic: Basic {
+                balance: U256::from(balance),
+                nonce: U256_ZERO,
+            },
+            code: None,
+            storage: Vec::new(),
+            reset_storage: false,
+        }
+    }
 
-                        for (index, value) in storage {
-                            if value == H256::default() {
-                                account.storage.remove(&index);
-                            } else {
-                                account.storage.insert(index, value);
-                            }
-                        }
+    #[cfg(feature = "test-utils")]
+    #[test]
+    fn test_backend_conformance() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        crate::backend::tests::conformance(|| MemoryBackend::new(&vicinity, BTreeMap::new()));
+    }
 
-                        account.balance == U256_ZERO
-                            && account.nonce == U256_ZERO
-                            && account.code.is_empty()
-                    };
+    #[test]
+    fn test_vicinity_split_into_block_and_tx_env() {
+        let vicinity = MemoryVicinity::new(
+            BlockEnv {
+                chain_id: U256::from(1),
+                hashes: vec![H256::repeat_byte(2), H256::repeat_byte(1)],
+                number: U256::from(10),
+                ..Default::default()
+            },
+            TxEnv {
+                gas_price: U256::from(3),
+                origin: H160::repeat_byte(4),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            MemoryVicinity::new(vicinity.block_env(), vicinity.tx_env()),
+            vicinity
+        );
 
-                    if is_empty && delete_empty {
-                        self.state.remove(&address);
-                    }
-                }
-                Apply::Delete { address } => {
-                    self.state.remove(&address);
-                }
-            }
+        let block = vicinity.block_env();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        for number in 7..12 {
+            assert_eq!(
+                block.block_hash(U256::from(number)),
+                backend.block_hash(U256::from(number))
+            );
         }
+        assert_eq!(block.block_hash(U256::from(9)), H256::repeat_byte(2));
+        assert_eq!(block.block_hash(U256::from(8)), H256::repeat_byte(1));
+    }
 
-        for log in logs {
-            self.logs.push(log);
-        }
+    #[test]
+    fn test_apply_into_reports_delta() {
+        let mut target = BTreeMap::new();
+        let (a, b, c) = (
+            H160::from_low_u64_be(1),
+            H160::from_low_u64_be(2),
+            H160::from_low_u64_be(3),
+        );
+        target.insert(c, MemoryAccount::default());
+
+        let delta = MemoryBackend::apply_into(
+            [modify(a, 1), modify(b, 0), Apply::Delete { address: c }],
+            &mut target,
+            true,
+        );
+
+        assert_eq!(delta.modified, [a].into_iter().collect());
+        assert_eq!(delta.deleted, [b, c].into_iter().collect());
+        assert_eq!(target.len(), 1);
+        assert_eq!(target[&a].balance, U256_ONE);
+    }
+
+    #[test]
+    fn test_apply_into_later_delete_overrides_modify() {
+        let mut target = BTreeMap::new();
+        let a = H160::from_low_u64_be(1);
+
+        let delta = MemoryBackend::apply_into(
+            [modify(a, 1), Apply::Delete { address: a }],
+            &mut target,
+            false,
+        );
+
+        assert!(delta.modified.is_empty());
+        assert_eq!(delta.deleted, [a].into_iter().collect());
+        assert!(target.is_empty());
+        assert!(AppliedDelta::default().is_empty());
     }
 }
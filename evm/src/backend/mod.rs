@@ -2,11 +2,149 @@
 //!
 //! Backends store state information of the VM, and exposes it to runtime.
 use crate::prelude::*;
+use crate::LogRef;
+use core::ops::Range;
 use primitive_types::{H160, H256, U256};
 
-pub use self::memory::{MemoryAccount, MemoryBackend, MemoryVicinity};
+pub use self::blob::{
+    fake_exponential, BlobParams, GAS_PER_BLOB, MIN_BLOB_GAS_PRICE, VERSIONED_HASH_VERSION_KZG,
+};
+pub use self::cached::{CacheStats, CachedBackend};
+pub use self::env::{BlockEnv, TxEnv};
+pub use self::irregular::{BalanceMove, IrregularStateTransition};
+pub use self::memory::{AppliedDelta, MemoryAccount, MemoryBackend, MemoryVicinity};
+#[cfg(feature = "persistent")]
+pub use self::persistent::{KeyValueStore, PersistentBackend};
+pub use self::precondition::{Conflict, Precondition, PreconditionRecorder};
+pub use self::rewards::BlockRewards;
+#[cfg(feature = "concurrent")]
+pub use self::shared::SharedMemoryBackend;
 
+/// Implement the block and transaction environment methods of [`Backend`]
+/// by delegating them to a wrapped backend, `backend: <field>`, or by reading
+/// them from a [`MemoryVicinity`], `vicinity: <field or getter call>`. The
+/// primitive types, and `U256_ONE` for a vicinity, must be in scope.
+macro_rules! delegate_environment {
+    (backend: $($backend:tt)+) => {
+        fn gas_price(&self) -> U256 {
+            self.$($backend)+.gas_price()
+        }
+        fn origin(&self) -> H160 {
+            self.$($backend)+.origin()
+        }
+        fn block_hash(&self, number: U256) -> H256 {
+            self.$($backend)+.block_hash(number)
+        }
+        fn block_number(&self) -> U256 {
+            self.$($backend)+.block_number()
+        }
+        fn block_coinbase(&self) -> H160 {
+            self.$($backend)+.block_coinbase()
+        }
+        fn block_timestamp(&self) -> U256 {
+            self.$($backend)+.block_timestamp()
+        }
+        fn block_difficulty(&self) -> U256 {
+            self.$($backend)+.block_difficulty()
+        }
+        fn block_randomness(&self) -> Option<H256> {
+            self.$($backend)+.block_randomness()
+        }
+        fn block_gas_limit(&self) -> U256 {
+            self.$($backend)+.block_gas_limit()
+        }
+        fn block_base_fee_per_gas(&self) -> U256 {
+            self.$($backend)+.block_base_fee_per_gas()
+        }
+        fn chain_id(&self) -> U256 {
+            self.$($backend)+.chain_id()
+        }
+        fn blob_gas_price(&self) -> Option<u128> {
+            self.$($backend)+.blob_gas_price()
+        }
+        fn block_excess_blob_gas(&self) -> Option<u64> {
+            self.$($backend)+.block_excess_blob_gas()
+        }
+        fn blob_gas_used(&self) -> Option<u64> {
+            self.$($backend)+.blob_gas_used()
+        }
+        fn get_blob_hash(&self, index: usize) -> Option<U256> {
+            self.$($backend)+.get_blob_hash(index)
+        }
+    };
+    (vicinity: $($vicinity:tt)+) => {
+        #[allow(clippy::misnamed_getters)]
+        fn gas_price(&self) -> U256 {
+            self.$($vicinity)+.effective_gas_price
+        }
+        fn origin(&self) -> H160 {
+            self.$($vicinity)+.origin
+        }
+        fn block_hash(&self, number: U256) -> H256 {
+            let vicinity = &self.$($vicinity)+;
+            if number >= vicinity.block_number
+                || vicinity.block_number - number - U256_ONE
+                    >= U256::from(vicinity.block_hashes.len())
+            {
+                H256::default()
+            } else {
+                let index = (vicinity.block_number - number - U256_ONE).as_usize();
+                vicinity.block_hashes[index]
+            }
+        }
+        fn block_number(&self) -> U256 {
+            self.$($vicinity)+.block_number
+        }
+        fn block_coinbase(&self) -> H160 {
+            self.$($vicinity)+.block_coinbase
+        }
+        fn block_timestamp(&self) -> U256 {
+            self.$($vicinity)+.block_timestamp
+        }
+        fn block_difficulty(&self) -> U256 {
+            self.$($vicinity)+.block_difficulty
+        }
+        fn block_randomness(&self) -> Option<H256> {
+            self.$($vicinity)+.block_randomness
+        }
+        fn block_gas_limit(&self) -> U256 {
+            self.$($vicinity)+.block_gas_limit
+        }
+        fn block_base_fee_per_gas(&self) -> U256 {
+            self.$($vicinity)+.block_base_fee_per_gas
+        }
+        fn chain_id(&self) -> U256 {
+            self.$($vicinity)+.chain_id
+        }
+        fn blob_gas_price(&self) -> Option<u128> {
+            self.$($vicinity)+.blob_gas_price()
+        }
+        fn block_excess_blob_gas(&self) -> Option<u64> {
+            self.$($vicinity)+.block_excess_blob_gas
+        }
+        fn blob_gas_used(&self) -> Option<u64> {
+            self.$($vicinity)+.block_blob_gas_used
+        }
+        fn get_blob_hash(&self, index: usize) -> Option<U256> {
+            self.$($vicinity)+.blob_hashes.get(index).copied()
+        }
+    };
+}
+pub(crate) use delegate_environment;
+
+mod blob;
+mod cached;
+mod env;
+mod irregular;
 mod memory;
+#[cfg(feature = "persistent")]
+mod persistent;
+mod precondition;
+mod rewards;
+#[cfg(feature = "concurrent")]
+mod shared;
+#[cfg(feature = "test-utils")]
+pub mod tests;
 
 /// Basic account information.
 ///
@@ -35,6 +173,16 @@ pub struct Log {
     pub data: Vec<u8>,
 }
 
+impl From<LogRef<'_>> for Log {
+    fn from(log: LogRef<'_>) -> Self {
+        Self {
+            address: log.address,
+            topics: log.topics.to_vec(),
+            data: log.data.into_owned(),
+        }
+    }
+}
+
 /// Apply state operation.
 #[derive(Clone, Debug)]
 pub enum Apply<I> {
@@ -86,21 +234,50 @@ pub trait Backend {
     fn chain_id(&self) -> U256;
 
     /// Whether account at address exists.
+    ///
+    /// An account exists once it is created by [`ApplyBackend::apply`], even
+    /// if it is empty, i.e. with zero nonce and balance and no code, and until
+    /// it is deleted. Backends must not treat empty accounts as nonexistent:
+    /// the executor applies EIP-161 itself according to the config. See
+    /// `tests::exists_conformance` (feature `test-utils`) for the checked
+    /// semantics.
     fn exists(&self, address: H160) -> bool;
     /// Get basic account information.
     fn basic(&self, address: H160) -> Basic;
     /// Get account code.
     fn code(&self, address: H160) -> Vec<u8>;
+    /// Get the part of account code within `range`.
+    ///
+    /// The range is clamped to the code length, so the result can be shorter than
+    /// requested. The default implementation loads the whole code via [`Backend::code`];
+    /// backends with expensive code access should override it.
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        slice_code(&self.code(address), range)
+    }
     /// Get storage value of address at index.
     fn storage(&self, address: H160, index: H256) -> H256;
     /// Check if the storage of the address is empty.
     fn is_empty_storage(&self, address: H160) -> bool;
     /// Get original storage value of address at index, if available.
     fn original_storage(&self, address: H160, index: H256) -> Option<H256>;
+    /// Enumerate the non-zero storage entries of address, as `(index, value)`
+    /// pairs, e.g. to dump the state. Return `None` if the backend can't
+    /// enumerate storage, which is the default. The iterator is boxed so
+    /// `Backend` stays dyn-compatible.
+    #[cfg(feature = "std")]
+    fn iter_storage(&self, _address: H160) -> Option<Box<dyn Iterator<Item = (H256, H256)> + '_>> {
+        None
+    }
     /// CANCUN hard fork
     /// [EIP-4844]: Shard Blob Transactions
     /// [EIP-7516]: BLOBBASEFEE instruction
     fn blob_gas_price(&self) -> Option<u128>;
+    /// Excess blob gas of the block, from which its blob gas price is
+    /// derived, see [`BlobParams::blob_gas_price`].
+    fn block_excess_blob_gas(&self) -> Option<u64>;
+    /// Blob gas used by the block, from which the excess blob gas of the
+    /// next block is derived, see [`BlobParams::excess_blob_gas`].
+    fn blob_gas_used(&self) -> Option<u64>;
     /// Get `blob_hash` from `blob_versioned_hashes` by index
     /// [EIP-4844]: BLOBHASH - https://eips.ethereum.org/EIPS/eip-4844#opcode-to-get-versioned-hashes
     fn get_blob_hash(&self, index: usize) -> Option<U256>;
@@ -109,9 +286,128 @@ pub trait Backend {
 /// EVM backend that can apply changes.
 pub trait ApplyBackend {
     /// Apply given values and logs at backend.
+    ///
+    /// With `delete_empty`, accounts left empty by a `Modify` are deleted
+    /// instead of being created or updated, see
+    /// [`crate::Config::delete_empty_accounts`].
     fn apply<A, I, L>(&mut self, values: A, logs: L, delete_empty: bool)
     where
         A: IntoIterator<Item = Apply<I>>,
         I: IntoIterator<Item = (H256, H256)>,
         L: IntoIterator<Item = Log>;
+
+    /// Apply given values and logs like [`ApplyBackend::apply`], if all
+    /// `preconditions` still hold in the backend.
+    ///
+    /// The preconditions are the values the execution producing the changes
+    /// read, e.g. recorded by a [`PreconditionRecorder`] over the snapshot it
+    /// ran on. If the state moved on since, the changes are stale and
+    /// applying them would overwrite the newer state.
+    ///
+    /// # Errors
+    /// Return all conflicts, without applying anything, if any precondition
+    /// does not hold.
+    fn apply_checked<A, I, L>(
+        &mut self,
+        preconditions: &[Precondition],
+        values: A,
+        logs: L,
+        delete_empty: bool,
+    ) -> Result<(), Vec<Conflict>>
+    where
+        Self: Backend,
+        A: IntoIterator<Item = Apply<I>>,
+        I: IntoIterator<Item = (H256, H256)>,
+        L: IntoIterator<Item = Log>,
+    {
+        let conflicts: Vec<Conflict> = preconditions
+            .iter()
+            .filter_map(|precondition| precondition.check(&*self).err())
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        self.apply(values, logs, delete_empty);
+        Ok(())
+    }
+}
+
+/// Logs in canonical order: the order in which they were emitted during the
+/// execution, with logs of reverted call frames removed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderedLogs(Vec<Log>);
+
+impl OrderedLogs {
+    /// Wrap logs which are already in emission order.
+    #[must_use]
+    pub const fn new(logs: Vec<Log>) -> Self {
+        Self(logs)
+    }
+
+    /// Logs in emission order.
+    #[must_use]
+    pub fn as_slice(&self) -> &[Log] {
+        &self.0
+    }
+
+    /// Unwrap logs in emission order.
+    #[must_use]
+    pub fn into_vec(self) -> Vec<Log> {
+        self.0
+    }
+}
+
+impl IntoIterator for OrderedLogs {
+    type Item = Log;
+    type IntoIter = <Vec<Log> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<I> Apply<I> {
+    /// Address of the account the operation applies to.
+    #[must_use]
+    pub const fn address(&self) -> H160 {
+        match self {
+            Self::Modify { address, .. } | Self::Delete { address } => *address,
+        }
+    }
+
+    /// Canonical sort key: all `Modify` operations in ascending address order,
+    /// followed by all `Delete` operations in ascending address order.
+    const fn canonical_key(&self) -> (bool, H160) {
+        (matches!(self, Self::Delete { .. }), self.address())
+    }
+}
+
+/// Stable sort of apply operations into the canonical order, see
+/// [`is_canonical_order`]. Operations produced by `MemoryStackState::deconstruct`
+/// are already in canonical order.
+pub fn sort_applies<I>(applies: &mut [Apply<I>]) {
+    applies.sort_by_key(Apply::canonical_key);
+}
+
+/// Check that apply operations are in the canonical order: all `Modify`
+/// operations in ascending address order, followed by all `Delete` operations
+/// in ascending address order.
+#[must_use]
+pub fn is_canonical_order<I>(applies: &[Apply<I>]) -> bool {
+    applies
+        .windows(2)
+        .all(|pair| pair[0].canonical_key() <= pair[1].canonical_key())
+}
+
+/// Copy the part of `code` within `range`, clamped to the code length.
+#[must_use]
+pub(crate) fn slice_code(code: &[u8], Range { start, end }: Range<usize>) -> Vec<u8> {
+    let end = end.min(code.len());
+    let start = start.min(end);
+    code[start..end].to_vec()
 }
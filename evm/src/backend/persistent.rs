@@ -0,0 +1,297 @@
+use super::{slice_code, Apply, ApplyBackend, Backend, Basic, Log, MemoryVicinity};
+use crate::core::utils::{U256_ONE, U256_ZERO};
+use crate::prelude::*;
+use core::ops::Range;
+use primitive_types::{H160, H256, U256};
+
+/// Key prefix of the account nonce and balance.
+const ACCOUNT_PREFIX: u8 = b'a';
+/// Key prefix of the account code.
+const CODE_PREFIX: u8 = b'c';
+/// Key prefix of the account storage slots.
+const STORAGE_PREFIX: u8 = b's';
+
+/// Minimal key-value store interface, implemented by adapters over
+/// persistent databases such as sled or `RocksDB`.
+///
+/// `Backend` is infallible, so adapters are expected to handle I/O errors
+/// themselves, e.g. by panicking or by retrying.
+pub trait KeyValueStore {
+    /// Get the value of `key`.
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+    /// Set the value of `key`.
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>);
+    /// Remove `key`.
+    fn delete(&mut self, key: &[u8]);
+    /// Remove all keys starting with `prefix`.
+    fn delete_prefix(&mut self, prefix: &[u8]);
+    /// Whether any key starts with `prefix`.
+    fn contains_prefix(&self, prefix: &[u8]) -> bool;
+}
+
+impl KeyValueStore for BTreeMap<Vec<u8>, Vec<u8>> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        BTreeMap::get(self, key).cloned()
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.insert(key, value);
+    }
+
+    fn delete(&mut self, key: &[u8]) {
+        self.remove(key);
+    }
+
+    fn delete_prefix(&mut self, prefix: &[u8]) {
+        self.retain(|key, _| !key.starts_with(prefix));
+    }
+
+    fn contains_prefix(&self, prefix: &[u8]) -> bool {
+        self.range(prefix.to_vec()..)
+            .next()
+            .is_some_and(|(key, _)| key.starts_with(prefix))
+    }
+}
+
+/// Reference backend persisting the state in a key-value store.
+///
+/// Accounts are stored under three kinds of keys:
+/// - `a ++ address`: RLP list of nonce and balance;
+/// - `c ++ address`: code, absent for accounts without code;
+/// - `s ++ address ++ index`: non-zero storage values.
+///
+/// Logs are not persisted, they belong to transaction receipts.
+#[derive(Clone, Debug)]
+pub struct PersistentBackend<'vicinity, S> {
+    vicinity: &'vicinity MemoryVicinity,
+    store: S,
+}
+
+impl<'vicinity, S: KeyValueStore> PersistentBackend<'vicinity, S> {
+    /// Create a new backend over `store`.
+    #[must_use]
+    pub const fn new(vicinity: &'vicinity MemoryVicinity, store: S) -> Self {
+        Self { vicinity, store }
+    }
+
+    /// Get the underlying store.
+    #[must_use]
+    pub const fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Consume the backend and return the underlying store.
+    #[must_use]
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    fn account_key(address: H160) -> Vec<u8> {
+        prefixed_key(ACCOUNT_PREFIX, address)
+    }
+
+    fn code_key(address: H160) -> Vec<u8> {
+        prefixed_key(CODE_PREFIX, address)
+    }
+
+    fn storage_key(address: H160, index: H256) -> Vec<u8> {
+        let mut key = prefixed_key(STORAGE_PREFIX, address);
+        key.extend_from_slice(index.as_bytes());
+        key
+    }
+
+    fn set_basic(&mut self, address: H160, basic: &Basic) {
+        let mut stream = rlp::RlpStream::new_list(2);
+        stream.append(&basic.nonce);
+        stream.append(&basic.balance);
+        self.store
+            .put(Self::account_key(address), stream.out().to_vec());
+    }
+
+    fn delete_account(&mut self, address: H160) {
+        self.store.delete(&Self::account_key(address));
+        self.store.delete(&Self::code_key(address));
+        self.store
+            .delete_prefix(&prefixed_key(STORAGE_PREFIX, address));
+    }
+}
+
+fn prefixed_key(prefix: u8, address: H160) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + 20 + 32);
+    key.push(prefix);
+    key.extend_from_slice(address.as_bytes());
+    key
+}
+
+impl<S: KeyValueStore> Backend for PersistentBackend<'_, S> {
+    delegate_environment!(vicinity: vicinity);
+
+    fn exists(&self, address: H160) -> bool {
+        self.store.get(&Self::account_key(address)).is_some()
+    }
+
+    fn basic(&self, address: H160) -> Basic {
+        self.store
+            .get(&Self::account_key(address))
+            .and_then(|value| {
+                let rlp = rlp::Rlp::new(&value);
+                Some(Basic {
+                    nonce: rlp.val_at(0).ok()?,
+                    balance: rlp.val_at(1).ok()?,
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    fn code(&self, address: H160) -> Vec<u8> {
+        self.store.get(&Self::code_key(address)).unwrap_or_default()
+    }
+
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        slice_code(&self.code(address), range)
+    }
+
+    fn storage(&self, address: H160, index: H256) -> H256 {
+        self.store
+            .get(&Self::storage_key(address, index))
+            .filter(|value| value.len() == 32)
+            .map(|value| H256::from_slice(&value))
+            .unwrap_or_default()
+    }
+
+    fn is_empty_storage(&self, address: H160) -> bool {
+        !self
+            .store
+            .contains_prefix(&prefixed_key(STORAGE_PREFIX, address))
+    }
+
+    fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+        Some(self.storage(address, index))
+    }
+}
+
+impl<S: KeyValueStore> ApplyBackend for PersistentBackend<'_, S> {
+    fn apply<A, I, L>(&mut self, values: A, _logs: L, delete_empty: bool)
+    where
+        A: IntoIterator<Item = Apply<I>>,
+        I: IntoIterator<Item = (H256, H256)>,
+        L: IntoIterator<Item = Log>,
+    {
+        for apply in values {
+            match apply {
+                Apply::Modify {
+                    address,
+                    basic,
+                    code,
+                    storage,
+                    reset_storage,
+                } => {
+                    if reset_storage {
+                        self.store
+                            .delete_prefix(&prefixed_key(STORAGE_PREFIX, address));
+                    }
+                    for (index, value) in storage {
+                        let key = Self::storage_key(address, index);
+                        if value == H256::default() {
+                            self.store.delete(&key);
+                        } else {
+                            self.store.put(key, value.as_bytes().to_vec());
+                        }
+                    }
+                    if let Some(code) = code {
+                        if code.is_empty() {
+                            self.store.delete(&Self::code_key(address));
+                        } else {
+                            self.store.put(Self::code_key(address), code);
+                        }
+                    }
+
+                    let is_empty = basic.balance == U256_ZERO
+                        && basic.nonce == U256_ZERO
+                        && self.store.get(&Self::code_key(address)).is_none();
+                    if is_empty && delete_empty {
+                        self.delete_account(address);
+                    } else {
+                        self.set_basic(address, &basic);
+                    }
+                }
+                Apply::Delete { address } => self.delete_account(address),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, TxEnv};
+
+    fn modify(address: H160, balance: u64, storage: Vec<(H256, H256)>) -> Apply<Vec<(H256, H256)>> {
+        Apply::Modify {
+            address,
+            basic: Basic {
+                balance: U256::from(balance),
+                nonce: U256::one(),
+            },
+            code: Some(vec![0x60, 0x00]),
+            storage,
+            reset_storage: false,
+        }
+    }
+
+    #[test]
+    fn test_apply_and_read_back() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let mut backend = PersistentBackend::new(&vicinity, BTreeMap::new());
+        let address = H160::from_low_u64_be(1);
+        let index = H256::from_low_u64_be(2);
+        let value = H256::repeat_byte(0x33);
+
+        backend.apply(
+            vec![modify(address, 10, vec![(index, value)])],
+            Vec::new(),
+            false,
+        );
+        assert!(backend.exists(address));
+        assert_eq!(backend.basic(address).balance, U256::from(10));
+        assert_eq!(backend.basic(address).nonce, U256::one());
+        assert_eq!(backend.code(address), vec![0x60, 0x00]);
+        assert_eq!(backend.code_slice(address, 1..2), vec![0x00]);
+        assert_eq!(backend.storage(address, index), value);
+        assert!(!backend.is_empty_storage(address));
+
+        // Zero value clears the slot
+        backend.apply(
+            vec![modify(address, 10, vec![(index, H256::zero())])],
+            Vec::new(),
+            false,
+        );
+        assert_eq!(backend.storage(address, index), H256::zero());
+        assert!(backend.is_empty_storage(address));
+
+        // Re-opening the store keeps the state
+        let store = backend.into_store();
+        let mut backend = PersistentBackend::new(&vicinity, store);
+        assert_eq!(backend.basic(address).balance, U256::from(10));
+
+        backend.apply(
+            vec![
+                modify(address, 10, vec![(index, value)]),
+                Apply::Delete { address },
+            ],
+            Vec::new(),
+            false,
+        );
+        assert!(!backend.exists(address));
+        assert!(backend.code(address).is_empty());
+        assert!(backend.is_empty_storage(address));
+        assert!(backend.store().is_empty());
+    }
+
+    #[cfg(feature = "test-utils")]
+    #[test]
+    fn test_backend_conformance() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        crate::backend::tests::conformance(|| PersistentBackend::new(&vicinity, BTreeMap::new()));
+    }
+}
@@ -0,0 +1,539 @@
+use super::{Backend, Basic};
+use crate::prelude::*;
+use core::ops::Range;
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+/// Value of the state an execution read, which must be unchanged for the
+/// changes of the execution to be applied, see [`super::ApplyBackend::apply_checked`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Precondition {
+    /// Basic information of the account at `address`.
+    Basic {
+        /// Address.
+        address: H160,
+        /// Expected basic information.
+        basic: Basic,
+    },
+    /// Existence of the account at `address`.
+    Exists {
+        /// Address.
+        address: H160,
+        /// Whether the account is expected to exist.
+        exists: bool,
+    },
+    /// Code of the account at `address`, read in full or in part.
+    Code {
+        /// Address.
+        address: H160,
+        /// Expected hash of the whole code.
+        code_hash: H256,
+    },
+    /// Emptiness of the storage of the account at `address`.
+    EmptyStorage {
+        /// Address.
+        address: H160,
+        /// Whether the storage is expected to be empty.
+        empty: bool,
+    },
+    /// Storage value of the account at `address`.
+    Storage {
+        /// Address.
+        address: H160,
+        /// Storage index.
+        index: H256,
+        /// Expected storage value.
+        value: H256,
+    },
+}
+
+/// Precondition which does not hold anymore, with the value found in the
+/// backend.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Conflict {
+    /// Basic information of the account changed, e.g. its nonce moved on.
+    Basic {
+        /// Address.
+        address: H160,
+        /// Basic information read by the execution.
+        expected: Basic,
+        /// Basic information in the backend.
+        actual: Basic,
+    },
+    /// The account was created or deleted.
+    Exists {
+        /// Address.
+        address: H160,
+        /// Existence read by the execution.
+        expected: bool,
+        /// Existence in the backend.
+        actual: bool,
+    },
+    /// Code of the account changed.
+    Code {
+        /// Address.
+        address: H160,
+        /// Hash of the code read by the execution.
+        expected: H256,
+        /// Hash of the code in the backend.
+        actual: H256,
+    },
+    /// Storage of the account became empty or non-empty.
+    EmptyStorage {
+        /// Address.
+        address: H160,
+        /// Emptiness read by the execution.
+        expected: bool,
+        /// Emptiness in the backend.
+        actual: bool,
+    },
+    /// Storage value of the account changed.
+    Storage {
+        /// Address.
+        address: H160,
+        /// Storage index.
+        index: H256,
+        /// Storage value read by the execution.
+        expected: H256,
+        /// Storage value in the backend.
+        actual: H256,
+    },
+}
+
+impl Precondition {
+    /// Check the precondition against `backend`.
+    ///
+    /// # Errors
+    /// Return the `Conflict` if the value in the backend differs.
+    pub fn check<B: Backend + ?Sized>(&self, backend: &B) -> Result<(), Conflict> {
+        match self {
+            Self::Basic { address, basic } => {
+                let actual = backend.basic(*address);
+                if actual == *basic {
+                    Ok(())
+                } else {
+                    Err(Conflict::Basic {
+                        address: *address,
+                        expected: basic.clone(),
+                        actual,
+                    })
+                }
+            }
+            Self::Exists { address, exists } => {
+                let actual = backend.exists(*address);
+                if actual == *exists {
+                    Ok(())
+                } else {
+                    Err(Conflict::Exists {
+                        address: *address,
+                        expected: *exists,
+                        actual,
+                    })
+                }
+            }
+            Self::Code { address, code_hash } => {
+                let actual = code_hash_of(&backend.code(*address));
+                if actual == *code_hash {
+                    Ok(())
+                } else {
+                    Err(Conflict::Code {
+                        address: *address,
+                        expected: *code_hash,
+                        actual,
+                    })
+                }
+            }
+            Self::EmptyStorage { address, empty } => {
+                let actual = backend.is_empty_storage(*address);
+                if actual == *empty {
+                    Ok(())
+                } else {
+                    Err(Conflict::EmptyStorage {
+                        address: *address,
+                        expected: *empty,
+                        actual,
+                    })
+                }
+            }
+            Self::Storage {
+                address,
+                index,
+                value,
+            } => {
+                let actual = backend.storage(*address, *index);
+                if actual == *value {
+                    Ok(())
+                } else {
+                    Err(Conflict::Storage {
+                        address: *address,
+                        index: *index,
+                        expected: *value,
+                        actual,
+                    })
+                }
+            }
+        }
+    }
+}
+
+fn code_hash_of(code: &[u8]) -> H256 {
+    H256::from_slice(<[u8; 32]>::from(Keccak256::digest(code)).as_slice())
+}
+
+/// Backend recording the first value of every read through it: account
+/// basic information, existence, code, storage emptiness and storage slots,
+/// as the preconditions of an execution on top of it.
+///
+/// Executions running on a snapshot of the state can then be applied
+/// optimistically: the snapshot is wrapped in the recorder, and the changes
+/// are applied to the live state with [`super::ApplyBackend::apply_checked`]
+/// and [`PreconditionRecorder::preconditions`], which fails if the execution
+/// would have read different values.
+#[derive(Debug)]
+pub struct PreconditionRecorder<B> {
+    backend: B,
+    basics: RefCell<BTreeMap<H160, Basic>>,
+    exists: RefCell<BTreeMap<H160, bool>>,
+    code_hashes: RefCell<BTreeMap<H160, H256>>,
+    empty_storages: RefCell<BTreeMap<H160, bool>>,
+    storages: RefCell<BTreeMap<(H160, H256), H256>>,
+}
+
+impl<B: Backend> PreconditionRecorder<B> {
+    /// Record the reads of `backend`.
+    #[must_use]
+    pub const fn new(backend: B) -> Self {
+        Self {
+            backend,
+            basics: RefCell::new(BTreeMap::new()),
+            exists: RefCell::new(BTreeMap::new()),
+            code_hashes: RefCell::new(BTreeMap::new()),
+            empty_storages: RefCell::new(BTreeMap::new()),
+            storages: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Preconditions recorded so far, in the order of the [`Precondition`]
+    /// variants, each in ascending order.
+    #[must_use]
+    pub fn preconditions(&self) -> Vec<Precondition> {
+        let basics = self
+            .basics
+            .borrow()
+            .iter()
+            .map(|(address, basic)| Precondition::Basic {
+                address: *address,
+                basic: basic.clone(),
+            })
+            .collect::<Vec<_>>();
+        let exists = self
+            .exists
+            .borrow()
+            .iter()
+            .map(|(address, exists)| Precondition::Exists {
+                address: *address,
+                exists: *exists,
+            })
+            .collect::<Vec<_>>();
+        let codes = self
+            .code_hashes
+            .borrow()
+            .iter()
+            .map(|(address, code_hash)| Precondition::Code {
+                address: *address,
+                code_hash: *code_hash,
+            })
+            .collect::<Vec<_>>();
+        let empty_storages = self
+            .empty_storages
+            .borrow()
+            .iter()
+            .map(|(address, empty)| Precondition::EmptyStorage {
+                address: *address,
+                empty: *empty,
+            })
+            .collect::<Vec<_>>();
+        let storages = self
+            .storages
+            .borrow()
+            .iter()
+            .map(|((address, index), value)| Precondition::Storage {
+                address: *address,
+                index: *index,
+                value: *value,
+            })
+            .collect::<Vec<_>>();
+        basics
+            .into_iter()
+            .chain(exists)
+            .chain(codes)
+            .chain(empty_storages)
+            .chain(storages)
+            .collect()
+    }
+
+    /// Unwrap the backend, dropping the recorded preconditions.
+    #[must_use]
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    fn record_code(&self, address: H160, code: Option<&[u8]>) {
+        if self.code_hashes.borrow().contains_key(&address) {
+            return;
+        }
+        let code_hash = match code {
+            Some(code) => code_hash_of(code),
+            None => code_hash_of(&self.backend.code(address)),
+        };
+        self.code_hashes.borrow_mut().insert(address, code_hash);
+    }
+
+    fn record_storage(&self, address: H160, index: H256, value: H256) {
+        self.storages
+            .borrow_mut()
+            .entry((address, index))
+            .or_insert(value);
+    }
+}
+
+impl<B: Backend> Backend for PreconditionRecorder<B> {
+    delegate_environment!(backend: backend);
+
+    fn exists(&self, address: H160) -> bool {
+        let exists = self.backend.exists(address);
+        self.exists.borrow_mut().entry(address).or_insert(exists);
+        exists
+    }
+
+    fn basic(&self, address: H160) -> Basic {
+        let basic = self.backend.basic(address);
+        self.basics
+            .borrow_mut()
+            .entry(address)
+            .or_insert_with(|| basic.clone());
+        basic
+    }
+
+    fn code(&self, address: H160) -> Vec<u8> {
+        let code = self.backend.code(address);
+        self.record_code(address, Some(&code));
+        code
+    }
+
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        // The rest of the code is read once, to record its hash
+        self.record_code(address, None);
+        self.backend.code_slice(address, range)
+    }
+
+    fn storage(&self, address: H160, index: H256) -> H256 {
+        let value = self.backend.storage(address, index);
+        self.record_storage(address, index, value);
+        value
+    }
+
+    fn is_empty_storage(&self, address: H160) -> bool {
+        let empty = self.backend.is_empty_storage(address);
+        self.empty_storages
+            .borrow_mut()
+            .entry(address)
+            .or_insert(empty);
+        empty
+    }
+
+    fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+        let value = self.backend.original_storage(address, index);
+        if let Some(value) = value {
+            self.record_storage(address, index, value);
+        }
+        value
+    }
+
+    #[cfg(feature = "std")]
+    fn iter_storage(&self, address: H160) -> Option<Box<dyn Iterator<Item = (H256, H256)> + '_>> {
+        let storage = self.backend.iter_storage(address)?;
+        Some(Box::new(storage.inspect(move |(index, value)| {
+            self.record_storage(address, *index, *value);
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{
+        Apply, ApplyBackend, BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv,
+    };
+    use crate::executor::stack::StackExecutorBuilder;
+    use crate::{Config, ExitReason, ExitSucceed};
+
+    #[test]
+    fn test_apply_checked() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        // SSTORE(0, SLOAD(0) + 1)
+        let code = vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55];
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let mut backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code,
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let config = Config::cancun();
+
+        // Execute on a snapshot, which the live state moves away from
+        let snapshot = backend.clone();
+        let recorder = PreconditionRecorder::new(&snapshot);
+        let executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&recorder)
+            .build();
+        let dry_run = executor.transact_call_dry_run(
+            caller,
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        drop(executor);
+        assert_eq!(dry_run.reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        let preconditions = recorder.preconditions();
+        assert!(preconditions.contains(&Precondition::Storage {
+            address: contract,
+            index: H256::zero(),
+            value: H256::zero(),
+        }));
+
+        backend
+            .apply_checked(
+                &preconditions,
+                dry_run.changes.clone(),
+                dry_run.logs.clone(),
+                true,
+            )
+            .unwrap();
+        assert_eq!(
+            backend.storage(contract, H256::zero()),
+            H256::from_low_u64_be(1)
+        );
+
+        // Applying the same changes again would lose the first increment
+        let conflicts = backend
+            .apply_checked(&preconditions, dry_run.changes, dry_run.logs, true)
+            .unwrap_err();
+        assert!(conflicts.contains(&Conflict::Storage {
+            address: contract,
+            index: H256::zero(),
+            expected: H256::zero(),
+            actual: H256::from_low_u64_be(1),
+        }));
+        assert!(conflicts.contains(&Conflict::Basic {
+            address: caller,
+            expected: Basic::default(),
+            actual: Basic {
+                balance: U256::zero(),
+                nonce: U256::one(),
+            },
+        }));
+        assert_eq!(backend.basic(caller).nonce, U256::one());
+    }
+
+    #[test]
+    fn test_every_read_is_recorded() {
+        let contract = H160::from_low_u64_be(2);
+        let missing = H160::from_low_u64_be(3);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let mut backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code: vec![0x60, 0x00],
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let snapshot = backend.clone();
+        let recorder = PreconditionRecorder::new(&snapshot);
+        assert!(recorder.exists(contract));
+        assert!(!recorder.exists(missing));
+        assert_eq!(recorder.code_slice(contract, 0..1), [0x60]);
+        assert!(recorder.is_empty_storage(contract));
+        let code_hash = code_hash_of(&[0x60, 0x00]);
+        let preconditions = recorder.preconditions();
+        assert_eq!(
+            preconditions,
+            [
+                Precondition::Exists {
+                    address: contract,
+                    exists: true,
+                },
+                Precondition::Exists {
+                    address: missing,
+                    exists: false,
+                },
+                Precondition::Code {
+                    address: contract,
+                    code_hash,
+                },
+                Precondition::EmptyStorage {
+                    address: contract,
+                    empty: true,
+                },
+            ]
+        );
+
+        // Change the code beyond the part read, and fill the storage
+        backend.apply(
+            [Apply::Modify {
+                address: contract,
+                basic: Basic::default(),
+                code: Some(vec![0x60, 0x01]),
+                storage: [(H256::zero(), H256::from_low_u64_be(1))],
+                reset_storage: false,
+            }],
+            Vec::new(),
+            false,
+        );
+        let conflicts = backend
+            .apply_checked(
+                &preconditions,
+                Vec::<Apply<Vec<(H256, H256)>>>::new(),
+                Vec::new(),
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(
+            conflicts,
+            [
+                Conflict::Code {
+                    address: contract,
+                    expected: code_hash,
+                    actual: code_hash_of(&[0x60, 0x01]),
+                },
+                Conflict::EmptyStorage {
+                    address: contract,
+                    expected: true,
+                    actual: false,
+                },
+            ]
+        );
+    }
+}
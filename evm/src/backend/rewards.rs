@@ -0,0 +1,153 @@
+use super::{Apply, ApplyBackend, Backend, Basic};
+use crate::prelude::*;
+use primitive_types::{H160, H256, U256};
+
+/// Block and uncle rewards of proof-of-work blocks, to replay pre-Merge chains.
+///
+/// Rewards are not part of transaction execution, they are credited to the
+/// miners after all transactions of the block are applied.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlockRewards {
+    /// Static reward of the block miner.
+    pub block_reward: U256,
+}
+
+impl BlockRewards {
+    /// Frontier and Homestead block reward: 5 ether.
+    #[must_use]
+    pub const fn frontier() -> Self {
+        Self {
+            block_reward: U256([5_000_000_000_000_000_000, 0, 0, 0]),
+        }
+    }
+
+    /// Byzantium block reward: 3 ether.
+    /// See [EIP-649](https://eips.ethereum.org/EIPS/eip-649).
+    #[must_use]
+    pub const fn byzantium() -> Self {
+        Self {
+            block_reward: U256([3_000_000_000_000_000_000, 0, 0, 0]),
+        }
+    }
+
+    /// Constantinople block reward: 2 ether.
+    /// See [EIP-1234](https://eips.ethereum.org/EIPS/eip-1234).
+    #[must_use]
+    pub const fn constantinople() -> Self {
+        Self {
+            block_reward: U256([2_000_000_000_000_000_000, 0, 0, 0]),
+        }
+    }
+
+    /// Reward of the block miner: the block reward plus 1/32 of the block
+    /// reward for each included uncle.
+    #[must_use]
+    pub fn miner_reward(&self, uncles: usize) -> U256 {
+        let inclusion_reward = self.block_reward / 32;
+        self.block_reward
+            .saturating_add(inclusion_reward.saturating_mul(U256::from(uncles)))
+    }
+
+    /// Reward of an uncle miner: `(uncle_number + 8 - block_number) / 8` of
+    /// the block reward. Uncles older than 7 blocks are not rewarded.
+    #[must_use]
+    pub fn uncle_reward(&self, block_number: U256, uncle_number: U256) -> U256 {
+        let eighths = uncle_number
+            .saturating_add(U256::from(8))
+            .saturating_sub(block_number);
+        if eighths > U256::from(8) {
+            return U256::zero();
+        }
+        self.block_reward.saturating_mul(eighths) / 8
+    }
+
+    /// Rewards of the block miner and uncle miners, merged by address in
+    /// ascending address order. `uncles` are `(miner, number)` pairs.
+    #[must_use]
+    pub fn rewards(
+        &self,
+        block_number: U256,
+        coinbase: H160,
+        uncles: &[(H160, U256)],
+    ) -> BTreeMap<H160, U256> {
+        let mut rewards = BTreeMap::new();
+        rewards.insert(coinbase, self.miner_reward(uncles.len()));
+        for &(miner, number) in uncles {
+            let reward = rewards.entry(miner).or_insert_with(U256::zero);
+            *reward = reward.saturating_add(self.uncle_reward(block_number, number));
+        }
+        rewards
+    }
+
+    /// Credit the block rewards to the miners' accounts of `backend`.
+    pub fn apply<B: Backend + ApplyBackend>(
+        &self,
+        backend: &mut B,
+        block_number: U256,
+        coinbase: H160,
+        uncles: &[(H160, U256)],
+    ) {
+        let applies: Vec<_> = self
+            .rewards(block_number, coinbase, uncles)
+            .into_iter()
+            .map(|(address, reward)| {
+                let basic = backend.basic(address);
+                Apply::Modify {
+                    address,
+                    basic: Basic {
+                        balance: basic.balance.saturating_add(reward),
+                        nonce: basic.nonce,
+                    },
+                    code: None,
+                    storage: Vec::<(H256, H256)>::new(),
+                    reset_storage: false,
+                }
+            })
+            .collect();
+        backend.apply(applies, Vec::new(), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryBackend, MemoryVicinity, TxEnv};
+
+    const ETHER: U256 = U256([1_000_000_000_000_000_000, 0, 0, 0]);
+
+    #[test]
+    fn test_uncle_rewards() {
+        let rewards = BlockRewards::byzantium();
+        let reward = rewards.block_reward;
+        assert_eq!(reward, ETHER * 3);
+        assert_eq!(BlockRewards::constantinople().block_reward, ETHER * 2);
+        assert_eq!(BlockRewards::frontier().block_reward, ETHER * 5);
+
+        assert_eq!(rewards.miner_reward(0), reward);
+        assert_eq!(rewards.miner_reward(2), reward + reward / 16);
+
+        let block = U256::from(100);
+        assert_eq!(rewards.uncle_reward(block, U256::from(99)), reward * 7 / 8);
+        assert_eq!(rewards.uncle_reward(block, U256::from(93)), reward / 8);
+        assert_eq!(rewards.uncle_reward(block, U256::from(92)), U256::zero());
+    }
+
+    #[test]
+    fn test_apply_rewards() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let mut backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let coinbase = H160::from_low_u64_be(1);
+        let uncle_miner = H160::from_low_u64_be(2);
+        let rewards = BlockRewards::frontier();
+        let block = U256::from(10);
+        let uncles = [(uncle_miner, U256::from(9)), (coinbase, U256::from(8))];
+
+        rewards.apply(&mut backend, block, coinbase, &uncles);
+        rewards.apply(&mut backend, block, coinbase, &uncles);
+
+        let reward = rewards.block_reward;
+        let coinbase_reward = reward + reward / 16 + reward * 6 / 8;
+        assert_eq!(backend.state()[&coinbase].balance, coinbase_reward * 2);
+        assert_eq!(backend.state()[&uncle_miner].balance, reward * 7 / 4);
+    }
+}
@@ -0,0 +1,166 @@
+use super::memory::iter_account_storage;
+use super::{slice_code, Backend, Basic, MemoryAccount, MemoryBackend, MemoryVicinity};
+use crate::core::utils::U256_ONE;
+use crate::prelude::*;
+use core::ops::Range;
+use primitive_types::{H160, H256, U256};
+use std::sync::Arc;
+
+/// Immutable snapshot of a memory state, which can be shared between threads.
+///
+/// Cloning is cheap, so every thread can own a clone and run read-only
+/// executions, e.g. `eth_call`, over the same snapshot in parallel. Changes
+/// made by an execution are never applied to the snapshot.
+#[derive(Clone, Debug)]
+pub struct SharedMemoryBackend {
+    inner: Arc<SharedMemoryState>,
+}
+
+#[derive(Debug)]
+struct SharedMemoryState {
+    vicinity: MemoryVicinity,
+    state: BTreeMap<H160, MemoryAccount>,
+}
+
+impl SharedMemoryBackend {
+    /// Create a new shared memory backend.
+    #[must_use]
+    pub fn new(vicinity: MemoryVicinity, state: BTreeMap<H160, MemoryAccount>) -> Self {
+        Self {
+            inner: Arc::new(SharedMemoryState { vicinity, state }),
+        }
+    }
+
+    /// Snapshot the current state of a memory backend.
+    #[must_use]
+    pub fn from_memory_backend(backend: &MemoryBackend<'_>) -> Self {
+        Self::new(backend.vicinity().clone(), backend.state().clone())
+    }
+
+    /// Get the vicinity of the snapshot.
+    #[must_use]
+    pub fn vicinity(&self) -> &MemoryVicinity {
+        &self.inner.vicinity
+    }
+
+    /// Get the underlying `BTreeMap` storing the state.
+    #[must_use]
+    pub fn state(&self) -> &BTreeMap<H160, MemoryAccount> {
+        &self.inner.state
+    }
+}
+
+impl Backend for SharedMemoryBackend {
+    delegate_environment!(vicinity: vicinity());
+
+    fn exists(&self, address: H160) -> bool {
+        self.state().contains_key(&address)
+    }
+
+    fn basic(&self, address: H160) -> Basic {
+        self.state()
+            .get(&address)
+            .map(|a| Basic {
+                balance: a.balance,
+                nonce: a.nonce,
+            })
+            .unwrap_or_default()
+    }
+
+    fn code(&self, address: H160) -> Vec<u8> {
+        self.state()
+            .get(&address)
+            .map(|v| v.code.clone())
+            .unwrap_or_default()
+    }
+
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        self.state()
+            .get(&address)
+            .map(|v| slice_code(&v.code, range))
+            .unwrap_or_default()
+    }
+
+    fn storage(&self, address: H160, index: H256) -> H256 {
+        self.state()
+            .get(&address)
+            .map(|v| v.storage.get(&index).copied().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    fn is_empty_storage(&self, address: H160) -> bool {
+        self.state()
+            .get(&address)
+            .is_none_or(|v| v.storage.is_empty())
+    }
+
+    fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
+        Some(self.storage(address, index))
+    }
+
+    fn iter_storage(&self, address: H160) -> Option<Box<dyn Iterator<Item = (H256, H256)> + '_>> {
+        Some(Box::new(iter_account_storage(self.state().get(&address))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::stack::StackExecutor;
+    use crate::{Config, ExitReason, ExitSucceed};
+
+    const fn assert_send_sync<T: Send + Sync>() {}
+    const _: () = assert_send_sync::<SharedMemoryBackend>();
+
+    #[test]
+    fn test_parallel_read_only_calls() {
+        let contract = H160::from_low_u64_be(0xc0);
+        let mut state = BTreeMap::new();
+        // Return SLOAD(0)
+        state.insert(
+            contract,
+            MemoryAccount {
+                code: vec![
+                    0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+                ],
+                storage: [(H256::zero(), H256::repeat_byte(0x11))]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+        );
+        let vicinity = MemoryVicinity::new(
+            crate::backend::BlockEnv::default(),
+            crate::backend::TxEnv::default(),
+        );
+        let backend = SharedMemoryBackend::new(vicinity, state);
+        let config = Config::cancun();
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4u64)
+                .map(|i| {
+                    let backend = backend.clone();
+                    let config = &config;
+                    scope.spawn(move || {
+                        let mut executor =
+                            StackExecutor::new_shared(&backend, 100_000, config, &());
+                        executor.transact_call(
+                            H160::from_low_u64_be(i + 1),
+                            contract,
+                            U256::zero(),
+                            Vec::new(),
+                            100_000,
+                            Vec::new(),
+                            Vec::new(),
+                        )
+                    })
+                })
+                .collect();
+            for handle in handles {
+                let (reason, output) = handle.join().unwrap();
+                assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+                assert_eq!(output, H256::repeat_byte(0x11).as_bytes());
+            }
+        });
+    }
+}
@@ -0,0 +1,373 @@
+//! Conformance checks for custom backends.
+//!
+//! The executor relies on backend semantics which are not expressed by the
+//! [`Backend`] and [`ApplyBackend`] signatures. The checks of this module
+//! exercise a backend created by a factory and panic with a description of
+//! the first violated expectation, so they can be called from the tests of
+//! a backend implementation.
+
+use super::{Apply, ApplyBackend, Backend, Basic, Log};
+use crate::prelude::*;
+use primitive_types::{H160, H256, U256};
+
+/// Modify operation setting the account at `address`.
+fn modify(
+    address: H160,
+    balance: u64,
+    nonce: u64,
+    code: Option<Vec<u8>>,
+) -> Apply<Vec<(H256, H256)>> {
+    Apply::Modify {
+        address,
+        basic: Basic {
+            balance: U256::from(balance),
+            nonce: U256::from(nonce),
+        },
+        code,
+        storage: Vec::new(),
+        reset_storage: false,
+    }
+}
+
+fn apply<B: ApplyBackend>(
+    backend: &mut B,
+    values: Vec<Apply<Vec<(H256, H256)>>>,
+    delete_empty: bool,
+) {
+    backend.apply(values, Vec::<Log>::new(), delete_empty);
+}
+
+/// Run all the checks of this module.
+///
+/// `factory` must return a backend without accounts.
+///
+/// # Panics
+/// Panic if the backend violates an expectation.
+pub fn conformance<B, F>(mut factory: F)
+where
+    B: Backend + ApplyBackend,
+    F: FnMut() -> B,
+{
+    exists_conformance(&mut factory);
+    apply_conformance(&mut factory);
+    storage_conformance(&mut factory);
+    code_conformance(&mut factory);
+    block_hash_conformance(&factory());
+}
+
+/// Check the `exists` semantics, including empty accounts, which are created
+/// by touching them before EIP-161.
+///
+/// `factory` must return a backend without accounts.
+///
+/// # Panics
+/// Panic if the backend violates an expectation.
+pub fn exists_conformance<B, F>(mut factory: F)
+where
+    B: Backend + ApplyBackend,
+    F: FnMut() -> B,
+{
+    let address = H160::from_low_u64_be(0x1000);
+
+    let backend = factory();
+    assert!(!backend.exists(address), "account of a new backend exists");
+    assert_eq!(
+        backend.basic(address),
+        Basic::default(),
+        "missing account has a non-default nonce or balance"
+    );
+    assert!(backend.code(address).is_empty(), "missing account has code");
+    assert!(
+        backend.is_empty_storage(address),
+        "missing account has storage"
+    );
+
+    // Before EIP-161, touched empty accounts are created
+    let mut backend = factory();
+    apply(&mut backend, vec![modify(address, 0, 0, None)], false);
+    assert!(
+        backend.exists(address),
+        "empty account is not created without `delete_empty`"
+    );
+    assert_eq!(backend.basic(address), Basic::default());
+
+    // Since EIP-161, touched empty accounts are deleted
+    apply(&mut backend, vec![modify(address, 0, 0, None)], true);
+    assert!(
+        !backend.exists(address),
+        "empty account is not deleted with `delete_empty`"
+    );
+
+    let mut backend = factory();
+    apply(&mut backend, vec![modify(address, 1, 0, None)], true);
+    assert!(
+        backend.exists(address),
+        "account with balance is not created"
+    );
+    apply(&mut backend, vec![modify(address, 0, 0, None)], true);
+    assert!(
+        !backend.exists(address),
+        "account emptied by a modification is not deleted with `delete_empty`"
+    );
+
+    // Accounts with a nonce or code are not empty
+    let mut backend = factory();
+    apply(&mut backend, vec![modify(address, 0, 1, None)], true);
+    assert!(backend.exists(address), "account with nonce is deleted");
+    let other = H160::from_low_u64_be(0x1001);
+    apply(
+        &mut backend,
+        vec![modify(other, 0, 0, Some(vec![0x00]))],
+        true,
+    );
+    assert!(backend.exists(other), "account with code is deleted");
+    // `None` code leaves the code unchanged
+    apply(&mut backend, vec![modify(other, 0, 0, None)], true);
+    assert!(
+        backend.exists(other),
+        "account with unchanged code is deleted"
+    );
+
+    apply(&mut backend, vec![Apply::Delete { address }], false);
+    assert!(!backend.exists(address), "deleted account exists");
+    assert_eq!(
+        backend.basic(address),
+        Basic::default(),
+        "deleted account has a non-default nonce or balance"
+    );
+}
+
+/// Check that modifications overwrite the nonce and balance, and that
+/// deletions remove the whole account.
+///
+/// `factory` must return a backend without accounts.
+///
+/// # Panics
+/// Panic if the backend violates an expectation.
+pub fn apply_conformance<B, F>(mut factory: F)
+where
+    B: Backend + ApplyBackend,
+    F: FnMut() -> B,
+{
+    let address = H160::from_low_u64_be(0x2000);
+    let other = H160::from_low_u64_be(0x2001);
+    let index = H256::from_low_u64_be(1);
+    let value = H256::repeat_byte(0x11);
+
+    let mut backend = factory();
+    apply(
+        &mut backend,
+        vec![
+            modify(address, 10, 2, Some(vec![0x60, 0x00])),
+            modify(other, 20, 0, None),
+        ],
+        true,
+    );
+    assert_eq!(
+        backend.basic(address),
+        Basic {
+            balance: U256::from(10),
+            nonce: U256::from(2),
+        },
+        "modification is not applied"
+    );
+    assert_eq!(backend.basic(other).balance, U256::from(20));
+
+    apply(&mut backend, vec![modify(address, 5, 3, None)], true);
+    assert_eq!(
+        backend.basic(address),
+        Basic {
+            balance: U256::from(5),
+            nonce: U256::from(3),
+        },
+        "modification does not overwrite the nonce and balance"
+    );
+
+    let basic = backend.basic(address);
+    apply(
+        &mut backend,
+        vec![Apply::Modify {
+            address,
+            basic,
+            code: None,
+            storage: vec![(index, value)],
+            reset_storage: false,
+        }],
+        true,
+    );
+    apply(&mut backend, vec![Apply::Delete { address }], true);
+    assert!(!backend.exists(address), "deleted account exists");
+    assert!(backend.code(address).is_empty(), "deleted account has code");
+    assert_eq!(
+        backend.storage(address, index),
+        H256::zero(),
+        "deleted account has storage"
+    );
+    assert!(
+        backend.is_empty_storage(address),
+        "deleted account has storage"
+    );
+    assert!(backend.exists(other), "deletion removes another account");
+
+    // Re-creating a deleted account starts from an empty storage
+    apply(&mut backend, vec![modify(address, 1, 0, None)], true);
+    assert!(
+        backend.is_empty_storage(address),
+        "re-created account has the storage of the deleted one"
+    );
+}
+
+/// Check storage writes, zero values and `reset_storage`.
+///
+/// `factory` must return a backend without accounts.
+///
+/// # Panics
+/// Panic if the backend violates an expectation.
+pub fn storage_conformance<B, F>(mut factory: F)
+where
+    B: Backend + ApplyBackend,
+    F: FnMut() -> B,
+{
+    let address = H160::from_low_u64_be(0x3000);
+    let first = H256::from_low_u64_be(1);
+    let second = H256::from_low_u64_be(2);
+    let value = H256::repeat_byte(0x22);
+    let write = |storage: Vec<(H256, H256)>, reset_storage: bool| Apply::Modify {
+        address,
+        basic: Basic {
+            balance: U256::one(),
+            nonce: U256::zero(),
+        },
+        code: None,
+        storage,
+        reset_storage,
+    };
+
+    let mut backend = factory();
+    apply(
+        &mut backend,
+        vec![write(vec![(first, value), (second, value)], false)],
+        true,
+    );
+    assert_eq!(
+        backend.storage(address, first),
+        value,
+        "storage not written"
+    );
+    assert!(
+        !backend.is_empty_storage(address),
+        "written storage is empty"
+    );
+    if let Some(original) = backend.original_storage(address, first) {
+        assert_eq!(
+            original, value,
+            "original storage differs from the committed storage"
+        );
+    }
+
+    // Zero values remove the slot
+    apply(
+        &mut backend,
+        vec![write(vec![(first, H256::zero())], false)],
+        true,
+    );
+    assert_eq!(backend.storage(address, first), H256::zero());
+    assert_eq!(
+        backend.storage(address, second),
+        value,
+        "writing a slot changes another slot"
+    );
+    apply(
+        &mut backend,
+        vec![write(vec![(second, H256::zero())], false)],
+        true,
+    );
+    assert!(
+        backend.is_empty_storage(address),
+        "storage with zero values only is not empty"
+    );
+
+    // Reset storage wipes the slots before writing the new ones
+    apply(&mut backend, vec![write(vec![(first, value)], false)], true);
+    apply(&mut backend, vec![write(vec![(second, value)], true)], true);
+    assert_eq!(
+        backend.storage(address, first),
+        H256::zero(),
+        "`reset_storage` keeps the previous slots"
+    );
+    assert_eq!(
+        backend.storage(address, second),
+        value,
+        "`reset_storage` drops the new slots"
+    );
+}
+
+/// Check code writes and reads. The executor hashes the code returned by the
+/// backend, so existing accounts without code must return an empty code to
+/// get the empty code hash.
+///
+/// `factory` must return a backend without accounts.
+///
+/// # Panics
+/// Panic if the backend violates an expectation.
+pub fn code_conformance<B, F>(mut factory: F)
+where
+    B: Backend + ApplyBackend,
+    F: FnMut() -> B,
+{
+    let address = H160::from_low_u64_be(0x4000);
+    let code = vec![0x60, 0x01, 0x60, 0x02, 0x01];
+
+    let mut backend = factory();
+    apply(&mut backend, vec![modify(address, 1, 0, None)], true);
+    assert!(
+        backend.code(address).is_empty(),
+        "account created without code has code"
+    );
+
+    apply(
+        &mut backend,
+        vec![modify(address, 1, 1, Some(code.clone()))],
+        true,
+    );
+    assert_eq!(backend.code(address), code, "code not written");
+    assert_eq!(backend.code_slice(address, 1..3), code[1..3]);
+    assert_eq!(
+        backend.code_slice(address, 3..100),
+        code[3..],
+        "code slice is not clamped to the code length"
+    );
+    assert!(
+        backend.code_slice(address, 100..200).is_empty(),
+        "code slice out of the code is not empty"
+    );
+
+    apply(&mut backend, vec![modify(address, 1, 1, None)], true);
+    assert_eq!(backend.code(address), code, "`None` code changes the code");
+}
+
+/// Check that the block hashes are only available for the 256 most recent
+/// blocks, excluding the current one.
+///
+/// # Panics
+/// Panic if the backend violates an expectation.
+pub fn block_hash_conformance<B: Backend>(backend: &B) {
+    let number = backend.block_number();
+    assert_eq!(
+        backend.block_hash(number),
+        H256::zero(),
+        "hash of the current block is available"
+    );
+    assert_eq!(
+        backend.block_hash(number.saturating_add(U256::one())),
+        H256::zero(),
+        "hash of a future block is available"
+    );
+    if number > U256::from(256) {
+        assert_eq!(
+            backend.block_hash(number - U256::from(257)),
+            H256::zero(),
+            "hash of a block older than 256 blocks is available"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use super::prelude::*;
+use super::{Opcode, Valids};
+use core::ops::Range;
+use primitive_types::U256;
+
+/// `JUMP` or `JUMPI` whose target, pushed by the preceding instruction, is
+/// not a valid jump destination.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidJump {
+    /// Position of the jump in the code.
+    pub position: usize,
+    /// `JUMP` or `JUMPI`.
+    pub opcode: Opcode,
+    /// Target of the jump.
+    pub target: U256,
+}
+
+/// Result of [`analyze_jump_targets`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct JumpAnalysis {
+    /// Reachable jumps to statically known invalid targets, in code order.
+    pub invalid_jumps: Vec<InvalidJump>,
+    /// Code regions following a halting instruction or an unconditional
+    /// jump up to the next `JUMPDEST`, which can't be executed. Data
+    /// appended to the code, e.g. the Solidity metadata, is reported here.
+    pub unreachable: Vec<Range<usize>>,
+}
+
+impl JumpAnalysis {
+    /// Whether no invalid jump was found.
+    #[must_use]
+    // TODO: rust-v1.87 - const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn has_valid_jumps(&self) -> bool {
+        self.invalid_jumps.is_empty()
+    }
+}
+
+/// Statically analyze the jumps of `code`, without executing it.
+///
+/// Only the jumps whose target is pushed right before them are checked, as
+/// emitted by the compilers for most jumps. Jumps to computed targets are
+/// assumed valid, so an empty [`JumpAnalysis::invalid_jumps`] doesn't prove
+/// that the execution can't fail with `InvalidJump`.
+#[must_use]
+pub fn analyze_jump_targets(code: &[u8]) -> JumpAnalysis {
+    let valids = Valids::new(code);
+    let mut analysis = JumpAnalysis::default();
+    let mut unreachable_start = None;
+    // Value pushed by the previous instruction
+    let mut pushed = None;
+
+    let mut position = 0;
+    while position < code.len() {
+        let opcode = Opcode(code[position]);
+        if opcode == Opcode::JUMPDEST {
+            if let Some(start) = unreachable_start.take() {
+                if start < position {
+                    analysis.unreachable.push(start..position);
+                }
+            }
+        }
+        let reachable = unreachable_start.is_none();
+
+        let (next_pushed, next_position) = if let Some(len) = opcode.is_push() {
+            let len = usize::from(len);
+            let start = position + 1;
+            let end = code.len().min(start + len);
+            // The bytes past the end of the code are zeros
+            let mut bytes = [0u8; 32];
+            bytes[..end - start].copy_from_slice(&code[start..end]);
+            (Some(U256::from_big_endian(&bytes[..len])), start + len)
+        } else if opcode == Opcode::PUSH0 {
+            (Some(U256::zero()), position + 1)
+        } else {
+            if reachable && (opcode == Opcode::JUMP || opcode == Opcode::JUMPI) {
+                if let Some(target) = pushed {
+                    let is_valid = usize::try_from(target).is_ok_and(|t| valids.is_valid(t));
+                    if !is_valid {
+                        analysis.invalid_jumps.push(InvalidJump {
+                            position,
+                            opcode,
+                            target,
+                        });
+                    }
+                }
+            }
+            if reachable && is_halting(opcode) {
+                unreachable_start = Some(position + 1);
+            }
+            (None, position + 1)
+        };
+        pushed = next_pushed;
+        position = next_position;
+    }
+
+    if let Some(start) = unreachable_start {
+        if start < code.len() {
+            analysis.unreachable.push(start..code.len());
+        }
+    }
+    analysis
+}
+
+/// Whether the execution never continues with the next instruction.
+const fn is_halting(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::STOP
+            | Opcode::JUMP
+            | Opcode::RETURN
+            | Opcode::REVERT
+            | Opcode::INVALID
+            | Opcode::SELFDESTRUCT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_jump_targets() {
+        let code = [
+            0x60, 0x05, 0x56, // PUSH1 5, JUMP
+            0x60, 0x01, // PUSH1 1, unreachable
+            0x5b, // JUMPDEST
+            0x60, 0x04, 0x57, // PUSH1 4, JUMPI into push data
+            0x61, 0x01, 0x00, 0x56, // PUSH2 256, JUMP out of the code
+            0x60, 0x00, 0x56, // PUSH1 0, unreachable jump
+            0x5b, // JUMPDEST
+            0x5f, 0x56, // PUSH0, JUMP
+            0xa1, 0xb2, // Metadata
+        ];
+        let analysis = analyze_jump_targets(&code);
+        assert_eq!(
+            analysis.invalid_jumps,
+            [
+                InvalidJump {
+                    position: 8,
+                    opcode: Opcode::JUMPI,
+                    target: U256::from(4),
+                },
+                InvalidJump {
+                    position: 12,
+                    opcode: Opcode::JUMP,
+                    target: U256::from(256),
+                },
+                InvalidJump {
+                    position: 18,
+                    opcode: Opcode::JUMP,
+                    target: U256::zero(),
+                },
+            ]
+        );
+        assert_eq!(analysis.unreachable, [3..5, 13..16, 19..21]);
+        assert!(!analysis.has_valid_jumps());
+
+        // Computed targets and truncated pushes
+        let analysis = analyze_jump_targets(&[0x5b, 0x80, 0x56, 0x61, 0x00]);
+        assert!(analysis.has_valid_jumps());
+        assert_eq!(analysis.unreachable, [3..5]);
+        let analysis = analyze_jump_targets(&[0x61, 0x01, 0x56]);
+        assert!(analysis.invalid_jumps.is_empty());
+        assert!(analyze_jump_targets(&[0x63, 0x00, 0x00, 0x00]).has_valid_jumps());
+        assert_eq!(analyze_jump_targets(&[]), JumpAnalysis::default());
+    }
+}
@@ -0,0 +1,439 @@
+//! EVM Object Format (EOF) scaffolding: containers and the `EOFCREATE` and
+//! `RETURNCONTRACT` semantics, following the drafts of [EIP-3540] and
+//! [EIP-7620].
+//!
+//! **Unstable**: enabled by the `eof-experimental` feature, to run the EOF
+//! execution spec tests early. The drafts still change, and so does this
+//! module, without notice. EOF code is not executed by the interpreter yet,
+//! which still only runs legacy code: `EOFCREATE` and `RETURNCONTRACT` are
+//! neither dispatched nor priced, so the EOF spec tests can't run yet.
+//!
+//! [EIP-3540]: https://eips.ethereum.org/EIPS/eip-3540
+//! [EIP-7620]: https://eips.ethereum.org/EIPS/eip-7620
+
+use super::prelude::*;
+use super::Opcode;
+use primitive_types::{H160, H256};
+use sha3::{Digest, Keccak256};
+
+/// Magic prefixing EOF containers. Legacy code can't start with `0xef` since
+/// EIP-3541.
+pub const MAGIC: [u8; 2] = [0xef, 0x00];
+/// Supported EOF version.
+pub const VERSION: u8 = 1;
+
+/// Create a contract from a subcontainer of the current container.
+pub const EOFCREATE: Opcode = Opcode::EOFCREATE;
+/// Deploy a subcontainer of the current container, with auxiliary data
+/// appended to its data section, and halt an initcode execution.
+pub const RETURNCONTRACT: Opcode = Opcode::RETURNCONTRACT;
+
+/// Maximum number of code sections.
+pub const MAX_CODE_SECTIONS: usize = 1024;
+/// Maximum number of container sections.
+pub const MAX_CONTAINER_SECTIONS: usize = 256;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+/// Size of a type section entry.
+const TYPE_SIZE: usize = 4;
+
+/// Reason of an invalid EOF container, or of a failed `RETURNCONTRACT`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EofError {
+    /// The container doesn't start with [`MAGIC`].
+    InvalidMagic,
+    /// The container version is not [`VERSION`].
+    InvalidVersion,
+    /// The header ends early, or has a section kind out of order.
+    InvalidHeader,
+    /// A section count or size is zero or exceeds its maximum.
+    InvalidSectionSize,
+    /// The type section size is not 4 bytes per code section.
+    InvalidTypesSize,
+    /// The body is shorter than the header declares, excluding the data
+    /// section, which may be truncated.
+    TruncatedBody,
+    /// The body is longer than the header declares.
+    TrailingBytes,
+    /// The data section is still shorter than declared after appending the
+    /// auxiliary data, or too large to be declared.
+    InvalidDataSize,
+    /// `RETURNCONTRACT` of a container section which doesn't exist.
+    InvalidContainerIndex,
+}
+
+/// Entry of the type section, describing a code section.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TypeEntry {
+    /// Number of stack inputs.
+    pub inputs: u8,
+    /// Number of stack outputs, `0x80` for non-returning sections.
+    pub outputs: u8,
+    /// Maximum stack height increase of the section.
+    pub max_stack_increase: u16,
+}
+
+/// Decoded EOF container. The sections are not validated beyond their
+/// sizes: the code is not checked for invalid instructions.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EofContainer {
+    /// Type of every code section.
+    pub types: Vec<TypeEntry>,
+    /// Code sections.
+    pub code_sections: Vec<Vec<u8>>,
+    /// Subcontainers, deployed by `EOFCREATE` or `RETURNCONTRACT`.
+    pub container_sections: Vec<Vec<u8>>,
+    /// Data section, which may be shorter than `data_size` in initcode.
+    pub data: Vec<u8>,
+    /// Data section size declared by the header.
+    pub data_size: u16,
+}
+
+/// Reader of the big-endian header fields.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], EofError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(EofError::InvalidHeader)?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, EofError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, EofError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn expect(&mut self, kind: u8) -> Result<(), EofError> {
+        if self.u8()? == kind {
+            Ok(())
+        } else {
+            Err(EofError::InvalidHeader)
+        }
+    }
+
+    /// Section count followed by the size of every section.
+    fn sizes(&mut self, max_count: usize) -> Result<Vec<usize>, EofError> {
+        let count = usize::from(self.u16()?);
+        if count == 0 || count > max_count {
+            return Err(EofError::InvalidSectionSize);
+        }
+        (0..count)
+            .map(|_| match self.u16()? {
+                0 => Err(EofError::InvalidSectionSize),
+                size => Ok(usize::from(size)),
+            })
+            .collect()
+    }
+}
+
+impl EofContainer {
+    /// Whether `code` is an EOF container, i.e. starts with [`MAGIC`].
+    #[must_use]
+    pub fn is_eof(code: &[u8]) -> bool {
+        code.starts_with(&MAGIC)
+    }
+
+    /// Decode a container.
+    ///
+    /// # Errors
+    /// Return `EofError` if the header is invalid, or the body doesn't
+    /// match it.
+    pub fn decode(bytes: &[u8]) -> Result<Self, EofError> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(2).map_err(|_| EofError::InvalidMagic)? != MAGIC {
+            return Err(EofError::InvalidMagic);
+        }
+        if reader.u8()? != VERSION {
+            return Err(EofError::InvalidVersion);
+        }
+
+        reader.expect(KIND_TYPES)?;
+        let types_size = usize::from(reader.u16()?);
+        reader.expect(KIND_CODE)?;
+        let code_sizes = reader.sizes(MAX_CODE_SECTIONS)?;
+        if types_size != code_sizes.len() * TYPE_SIZE {
+            return Err(EofError::InvalidTypesSize);
+        }
+        let container_sizes = match reader.u8()? {
+            KIND_CONTAINER => {
+                let sizes = reader.sizes(MAX_CONTAINER_SECTIONS)?;
+                reader.expect(KIND_DATA)?;
+                sizes
+            }
+            KIND_DATA => Vec::new(),
+            _ => return Err(EofError::InvalidHeader),
+        };
+        let data_size = reader.u16()?;
+        reader.expect(TERMINATOR)?;
+
+        let mut body = |len| reader.take(len).map_err(|_| EofError::TruncatedBody);
+        let types = body(types_size)?
+            .chunks_exact(TYPE_SIZE)
+            .map(|entry| TypeEntry {
+                inputs: entry[0],
+                outputs: entry[1],
+                max_stack_increase: u16::from_be_bytes([entry[2], entry[3]]),
+            })
+            .collect();
+        let code_sections = code_sizes
+            .iter()
+            .map(|&size| body(size).map(<[u8]>::to_vec))
+            .collect::<Result<Vec<_>, EofError>>()?;
+        let container_sections = container_sizes
+            .iter()
+            .map(|&size| body(size).map(<[u8]>::to_vec))
+            .collect::<Result<Vec<_>, EofError>>()?;
+        let data = &bytes[reader.position..];
+        if data.len() > usize::from(data_size) {
+            return Err(EofError::TrailingBytes);
+        }
+
+        Ok(Self {
+            types,
+            code_sections,
+            container_sections,
+            data: data.to_vec(),
+            data_size,
+        })
+    }
+
+    /// Encode the container, declaring `data_size` as the data section size.
+    ///
+    /// # Panics
+    /// Panic if a section is larger than its size field, which
+    /// [`Self::decode`] never returns.
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        fn size(len: usize) -> [u8; 2] {
+            u16::try_from(len)
+                .expect("EOF section size fits in 16 bits")
+                .to_be_bytes()
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(KIND_TYPES);
+        bytes.extend_from_slice(&size(self.types.len() * TYPE_SIZE));
+        bytes.push(KIND_CODE);
+        bytes.extend_from_slice(&size(self.code_sections.len()));
+        for code in &self.code_sections {
+            bytes.extend_from_slice(&size(code.len()));
+        }
+        if !self.container_sections.is_empty() {
+            bytes.push(KIND_CONTAINER);
+            bytes.extend_from_slice(&size(self.container_sections.len()));
+            for container in &self.container_sections {
+                bytes.extend_from_slice(&size(container.len()));
+            }
+        }
+        bytes.push(KIND_DATA);
+        bytes.extend_from_slice(&self.data_size.to_be_bytes());
+        bytes.push(TERMINATOR);
+
+        for entry in &self.types {
+            bytes.push(entry.inputs);
+            bytes.push(entry.outputs);
+            bytes.extend_from_slice(&entry.max_stack_increase.to_be_bytes());
+        }
+        for section in self.code_sections.iter().chain(&self.container_sections) {
+            bytes.extend_from_slice(section);
+        }
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Whether the data section is shorter than declared, which is only
+    /// allowed before deployment.
+    #[must_use]
+    // TODO: rust-v1.87 - const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn is_truncated(&self) -> bool {
+        self.data.len() < usize::from(self.data_size)
+    }
+
+    /// Code deployed by `RETURNCONTRACT`: the container section at `index`
+    /// with `aux_data` appended to its data section, which must then be
+    /// complete. Its declared data size is updated to the new length.
+    ///
+    /// # Errors
+    /// Return `EofError` if there is no container section at `index`, it is
+    /// invalid, or its data section is not complete with `aux_data`.
+    pub fn return_contract(&self, index: usize, aux_data: &[u8]) -> Result<Vec<u8>, EofError> {
+        let section = self
+            .container_sections
+            .get(index)
+            .ok_or(EofError::InvalidContainerIndex)?;
+        let mut deployed = Self::decode(section)?;
+        deployed.data.extend_from_slice(aux_data);
+        if deployed.is_truncated() {
+            return Err(EofError::InvalidDataSize);
+        }
+        deployed.data_size =
+            u16::try_from(deployed.data.len()).map_err(|_| EofError::InvalidDataSize)?;
+        Ok(deployed.encode())
+    }
+}
+
+/// Address of a contract created by `EOFCREATE`:
+/// `keccak256(0xff || sender || salt)[12..]`, where `sender` is left-padded
+/// to 32 bytes.
+#[must_use]
+pub fn eofcreate_address(sender: H160, salt: H256) -> H160 {
+    let mut hasher = Keccak256::new();
+    hasher.update([0xff]);
+    hasher.update(H256::from(sender));
+    hasher.update(salt);
+    H160::from_slice(&hasher.finalize()[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Container returning `RETURNCONTRACT` of its single subcontainer, with
+    /// a data section of `data_size` bytes, of which `data` are present.
+    fn container(subcontainer: Vec<u8>, data: Vec<u8>, data_size: u16) -> EofContainer {
+        EofContainer {
+            types: vec![TypeEntry {
+                inputs: 0,
+                outputs: 0x80,
+                max_stack_increase: 2,
+            }],
+            code_sections: vec![vec![0x60, 0x00, 0x80, RETURNCONTRACT.0, 0x00]],
+            container_sections: vec![subcontainer],
+            data,
+            data_size,
+        }
+    }
+
+    fn runtime(data: Vec<u8>, data_size: u16) -> EofContainer {
+        EofContainer {
+            types: vec![TypeEntry {
+                inputs: 0,
+                outputs: 0x80,
+                max_stack_increase: 0,
+            }],
+            code_sections: vec![vec![0x00]],
+            container_sections: Vec::new(),
+            data,
+            data_size,
+        }
+    }
+
+    #[test]
+    fn test_decode_encode() {
+        let runtime = runtime(vec![1, 2], 2);
+        let bytes = runtime.encode();
+        assert_eq!(
+            bytes,
+            [
+                0xef, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01, 0x00, 0x01, 0x04, 0x00, 0x02,
+                0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x01, 0x02
+            ]
+        );
+        assert!(EofContainer::is_eof(&bytes));
+        assert_eq!(EofContainer::decode(&bytes), Ok(runtime.clone()));
+
+        let initcode = container(runtime.encode(), Vec::new(), 0);
+        assert_eq!(EofContainer::decode(&initcode.encode()), Ok(initcode));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let bytes = runtime(vec![1, 2], 2).encode();
+        assert_eq!(
+            EofContainer::decode(&[0x60, 0x00]),
+            Err(EofError::InvalidMagic)
+        );
+        let mut version = bytes.clone();
+        version[2] = 2;
+        assert_eq!(
+            EofContainer::decode(&version),
+            Err(EofError::InvalidVersion)
+        );
+        assert_eq!(
+            EofContainer::decode(&bytes[..10]),
+            Err(EofError::InvalidHeader)
+        );
+        // Truncated data is allowed, a truncated code section is not
+        let truncated = EofContainer::decode(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(truncated.is_truncated());
+        assert_eq!(
+            EofContainer::decode(&bytes[..19]),
+            Err(EofError::TruncatedBody)
+        );
+        let mut trailing = bytes;
+        trailing.push(3);
+        assert_eq!(
+            EofContainer::decode(&trailing),
+            Err(EofError::TrailingBytes)
+        );
+    }
+
+    #[test]
+    fn test_return_contract() {
+        // Two of the four data bytes are auxiliary data
+        let initcode = container(runtime(vec![1, 2], 4).encode(), Vec::new(), 0);
+        let deployed = initcode.return_contract(0, &[3, 4, 5]).unwrap();
+        assert_eq!(
+            EofContainer::decode(&deployed),
+            Ok(runtime(vec![1, 2, 3, 4, 5], 5))
+        );
+
+        assert_eq!(
+            initcode.return_contract(0, &[3]),
+            Err(EofError::InvalidDataSize)
+        );
+        assert_eq!(
+            initcode.return_contract(1, &[3, 4]),
+            Err(EofError::InvalidContainerIndex)
+        );
+        let oversized = vec![0; usize::from(u16::MAX)];
+        assert_eq!(
+            initcode.return_contract(0, &oversized),
+            Err(EofError::InvalidDataSize)
+        );
+    }
+
+    #[test]
+    fn test_eofcreate_address() {
+        assert_eq!(
+            eofcreate_address(H160::repeat_byte(1), H256::zero()),
+            H160([
+                0x12, 0xf7, 0xfa, 0x69, 0x8d, 0x9b, 0x70, 0x78, 0x62, 0x12, 0x79, 0xe1, 0x32, 0xec,
+                0x48, 0x6c, 0x8e, 0x37, 0xf2, 0xf8,
+            ])
+        );
+        assert_eq!(
+            eofcreate_address(
+                H160::from_low_u64_be(0xdead_beef),
+                H256::from_low_u64_be(42)
+            ),
+            H160([
+                0x6b, 0x32, 0x10, 0xd7, 0x8b, 0x2b, 0x62, 0xfc, 0x18, 0x66, 0x36, 0xf1, 0x70, 0x4b,
+                0x73, 0xcc, 0xe8, 0x34, 0xeb, 0x96,
+            ])
+        );
+    }
+}
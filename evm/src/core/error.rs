@@ -1,5 +1,6 @@
 use super::prelude::*;
 use super::Opcode;
+use primitive_types::H160;
 
 /// Trap which indicates that an `ExternalOpcode` has to be handled.
 pub type Trap = Opcode;
@@ -57,6 +58,113 @@ impl ExitReason {
     pub const fn is_fatal(&self) -> bool {
         matches!(self, Self::Fatal(_))
     }
+
+    /// Stable numeric code of the exit reason, see [`ExitCode`].
+    #[must_use]
+    pub const fn to_code(&self) -> ExitCode {
+        match self {
+            Self::Succeed(s) => ExitCode::new(ExitCode::SUCCEED, s.to_code()),
+            Self::Revert(r) => ExitCode::new(ExitCode::REVERT, r.to_code()),
+            Self::Error(e) => ExitCode::new(ExitCode::ERROR, e.to_code()),
+            Self::Fatal(f) => ExitCode::new(ExitCode::FATAL, f.to_code()),
+        }
+    }
+
+    /// Exit reason of a numeric code. `None` if the code is unknown to this
+    /// version, or if its variant carries data, which codes do not encode.
+    #[must_use]
+    pub fn from_code(code: ExitCode) -> Option<Self> {
+        let variant = code.variant();
+        match code.kind() {
+            ExitCode::SUCCEED => ExitSucceed::from_code(variant).map(Self::Succeed),
+            ExitCode::REVERT => ExitRevert::from_code(variant).map(Self::Revert),
+            ExitCode::ERROR => ExitError::from_code(variant).map(Self::Error),
+            ExitCode::FATAL => ExitFatal::from_code(variant).map(Self::Fatal),
+            _ => None,
+        }
+    }
+}
+
+/// Stable numeric code of an [`ExitReason`], for systems persisting exit
+/// reasons.
+///
+/// The high byte is the kind of exit, one of [`Self::SUCCEED`],
+/// [`Self::REVERT`], [`Self::ERROR`] and [`Self::FATAL`], and the low byte
+/// the variant within that kind. Codes are never reassigned, and new variants
+/// get new codes, so persisted codes stay valid across versions, and the kind
+/// of a code from a newer version is known even when its variant is not.
+///
+/// Codes identify variants only: the data carried by a variant, such as the
+/// message of [`ExitError::Other`], is not part of its code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(
+    feature = "with-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct ExitCode(pub u16);
+
+impl ExitCode {
+    /// Kind of the [`ExitReason::Succeed`] codes.
+    pub const SUCCEED: u8 = 0;
+    /// Kind of the [`ExitReason::Revert`] codes.
+    pub const REVERT: u8 = 1;
+    /// Kind of the [`ExitReason::Error`] codes.
+    pub const ERROR: u8 = 2;
+    /// Kind of the [`ExitReason::Fatal`] codes.
+    pub const FATAL: u8 = 3;
+
+    /// Code of the `variant` of the exit `kind`.
+    #[must_use]
+    pub const fn new(kind: u8, variant: u8) -> Self {
+        Self(u16::from_be_bytes([kind, variant]))
+    }
+
+    /// Kind of the exit.
+    #[must_use]
+    pub const fn kind(self) -> u8 {
+        self.0.to_be_bytes()[0]
+    }
+
+    /// Variant within the kind of the exit.
+    #[must_use]
+    pub const fn variant(self) -> u8 {
+        self.0.to_be_bytes()[1]
+    }
+
+    /// Whether the code is of a succeeded exit.
+    #[must_use]
+    pub const fn is_succeed(self) -> bool {
+        self.kind() == Self::SUCCEED
+    }
+
+    /// Whether the code is of a revert.
+    #[must_use]
+    pub const fn is_revert(self) -> bool {
+        self.kind() == Self::REVERT
+    }
+
+    /// Whether the code is of an error.
+    #[must_use]
+    pub const fn is_error(self) -> bool {
+        self.kind() == Self::ERROR
+    }
+
+    /// Whether the code is of a fatal exit.
+    #[must_use]
+    pub const fn is_fatal(self) -> bool {
+        self.kind() == Self::FATAL
+    }
+}
+
+impl From<&ExitReason> for ExitCode {
+    fn from(reason: &ExitReason) -> Self {
+        reason.to_code()
+    }
 }
 
 /// Exit succeed reason.
@@ -75,6 +183,29 @@ pub enum ExitSucceed {
     Suicided,
 }
 
+impl ExitSucceed {
+    /// Stable code of the variant within [`ExitCode::SUCCEED`].
+    #[must_use]
+    pub const fn to_code(self) -> u8 {
+        match self {
+            Self::Stopped => 0,
+            Self::Returned => 1,
+            Self::Suicided => 2,
+        }
+    }
+
+    /// Variant of a code, `None` if unknown.
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Stopped),
+            1 => Some(Self::Returned),
+            2 => Some(Self::Suicided),
+            _ => None,
+        }
+    }
+}
+
 impl From<ExitSucceed> for ExitReason {
     fn from(s: ExitSucceed) -> Self {
         Self::Succeed(s)
@@ -93,6 +224,25 @@ pub enum ExitRevert {
     Reverted,
 }
 
+impl ExitRevert {
+    /// Stable code of the variant within [`ExitCode::REVERT`].
+    #[must_use]
+    pub const fn to_code(self) -> u8 {
+        match self {
+            Self::Reverted => 0,
+        }
+    }
+
+    /// Variant of a code, `None` if unknown.
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Reverted),
+            _ => None,
+        }
+    }
+}
+
 impl From<ExitRevert> for ExitReason {
     fn from(s: ExitRevert) -> Self {
         Self::Revert(s)
@@ -122,12 +272,14 @@ pub enum ExitError {
     /// Encountered the designated invalid opcode.
     #[cfg_attr(feature = "with-codec", codec(index = 4))]
     DesignatedInvalid,
-    /// Call stack is too deep (runtime).
+    /// Call stack is too deep (runtime). Carries the depth of the rejected
+    /// call frame, which exceeds `Config::call_stack_limit`.
     #[cfg_attr(feature = "with-codec", codec(index = 5))]
-    CallTooDeep,
-    /// Create opcode encountered collision (runtime).
+    CallTooDeep(u64),
+    /// Create opcode encountered collision with the existing account at the
+    /// given address (runtime). See [EIP-7610](https://eips.ethereum.org/EIPS/eip-7610).
     #[cfg_attr(feature = "with-codec", codec(index = 6))]
-    CreateCollision,
+    CreateCollision(H160),
     /// Create init code exceeds limit (runtime).
     #[cfg_attr(feature = "with-codec", codec(index = 7))]
     CreateContractLimit,
@@ -170,6 +322,74 @@ pub enum ExitError {
     UsizeOverflow,
     #[cfg_attr(feature = "with-codec", codec(index = 16))]
     CreateContractStartingWithEF,
+
+    /// State-mutating operation (e.g. a log) attempted by a precompile that
+    /// was invoked in a static context.
+    #[cfg_attr(feature = "with-codec", codec(index = 17))]
+    StaticModeViolation,
+
+    /// Crediting an account would overflow its balance.
+    #[cfg_attr(feature = "with-codec", codec(index = 18))]
+    BalanceOverflow,
+
+    /// Transaction gas limit exceeds `Config::max_transaction_gas`.
+    #[cfg_attr(feature = "with-codec", codec(index = 19))]
+    TransactionGasLimitExceeded,
+}
+
+impl ExitError {
+    /// Stable code of the variant within [`ExitCode::ERROR`].
+    #[must_use]
+    pub const fn to_code(&self) -> u8 {
+        match self {
+            Self::StackUnderflow => 0,
+            Self::StackOverflow => 1,
+            Self::InvalidJump => 2,
+            Self::InvalidRange => 3,
+            Self::DesignatedInvalid => 4,
+            Self::CallTooDeep(_) => 5,
+            Self::CreateCollision(_) => 6,
+            Self::CreateContractLimit => 7,
+            Self::OutOfOffset => 8,
+            Self::OutOfGas => 9,
+            Self::OutOfFund => 10,
+            Self::PCUnderflow => 11,
+            Self::CreateEmpty => 12,
+            Self::Other(_) => 13,
+            Self::MaxNonce => 14,
+            Self::UsizeOverflow => 15,
+            Self::CreateContractStartingWithEF => 16,
+            Self::StaticModeViolation => 17,
+            Self::BalanceOverflow => 18,
+            Self::TransactionGasLimitExceeded => 19,
+            Self::InvalidCode(_) => 20,
+        }
+    }
+
+    /// Variant of a code, `None` if unknown or if the variant carries data.
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::StackUnderflow),
+            1 => Some(Self::StackOverflow),
+            2 => Some(Self::InvalidJump),
+            3 => Some(Self::InvalidRange),
+            4 => Some(Self::DesignatedInvalid),
+            7 => Some(Self::CreateContractLimit),
+            8 => Some(Self::OutOfOffset),
+            9 => Some(Self::OutOfGas),
+            10 => Some(Self::OutOfFund),
+            11 => Some(Self::PCUnderflow),
+            12 => Some(Self::CreateEmpty),
+            14 => Some(Self::MaxNonce),
+            15 => Some(Self::UsizeOverflow),
+            16 => Some(Self::CreateContractStartingWithEF),
+            17 => Some(Self::StaticModeViolation),
+            18 => Some(Self::BalanceOverflow),
+            19 => Some(Self::TransactionGasLimitExceeded),
+            _ => None,
+        }
+    }
 }
 
 impl From<ExitError> for ExitReason {
@@ -195,6 +415,37 @@ pub enum ExitFatal {
 
     /// Other fatal errors.
     Other(Cow<'static, str>),
+    /// The execution was cancelled by its cancellation token.
+    Cancelled,
+    /// The transaction exceeded `Config::step_limit`.
+    StepLimitReached,
+}
+
+impl ExitFatal {
+    /// Stable code of the variant within [`ExitCode::FATAL`].
+    #[must_use]
+    pub const fn to_code(&self) -> u8 {
+        match self {
+            Self::NotSupported => 0,
+            Self::UnhandledInterrupt => 1,
+            Self::CallErrorAsFatal(_) => 2,
+            Self::Other(_) => 3,
+            Self::Cancelled => 4,
+            Self::StepLimitReached => 5,
+        }
+    }
+
+    /// Variant of a code, `None` if unknown or if the variant carries data.
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::NotSupported),
+            1 => Some(Self::UnhandledInterrupt),
+            4 => Some(Self::Cancelled),
+            5 => Some(Self::StepLimitReached),
+            _ => None,
+        }
+    }
 }
 
 impl From<ExitFatal> for ExitReason {
@@ -202,3 +453,57 @@ impl From<ExitFatal> for ExitReason {
         Self::Fatal(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_stable() {
+        for (reason, code) in [
+            (ExitReason::Succeed(ExitSucceed::Stopped), 0x0000),
+            (ExitReason::Succeed(ExitSucceed::Suicided), 0x0002),
+            (ExitReason::Revert(ExitRevert::Reverted), 0x0100),
+            (ExitReason::Error(ExitError::StackUnderflow), 0x0200),
+            (ExitReason::Error(ExitError::OutOfGas), 0x0209),
+            (
+                ExitReason::Error(ExitError::TransactionGasLimitExceeded),
+                0x0213,
+            ),
+            (ExitReason::Fatal(ExitFatal::NotSupported), 0x0300),
+            (ExitReason::Fatal(ExitFatal::Cancelled), 0x0304),
+            (ExitReason::Fatal(ExitFatal::StepLimitReached), 0x0305),
+        ] {
+            assert_eq!(reason.to_code(), ExitCode(code));
+            assert_eq!(ExitReason::from_code(ExitCode(code)), Some(reason));
+        }
+    }
+
+    #[test]
+    fn test_exit_codes_round_trip() {
+        let mut known = 0;
+        for code in 0..=u16::MAX {
+            let code = ExitCode(code);
+            if let Some(reason) = ExitReason::from_code(code) {
+                assert_eq!(reason.to_code(), code);
+                known += 1;
+            }
+        }
+        // All the variants without data
+        assert_eq!(known, 3 + 1 + 17 + 3);
+
+        let depth = u64::try_from(crate::Config::cancun().call_stack_limit + 1).unwrap();
+        let reason = ExitReason::Error(ExitError::CallTooDeep(depth));
+        assert_eq!(reason.to_code(), ExitCode(0x0205));
+        assert_eq!(ExitReason::from_code(reason.to_code()), None);
+        assert_eq!(
+            ExitCode::from(&ExitReason::Error(ExitError::InvalidCode(Opcode::STOP))),
+            ExitCode::new(ExitCode::ERROR, 20)
+        );
+
+        // Codes of variants unknown to this version keep their kind
+        let unknown = ExitCode::new(ExitCode::FATAL, 0xff);
+        assert_eq!(ExitReason::from_code(unknown), None);
+        assert!(unknown.is_fatal());
+    }
+}
@@ -299,10 +299,13 @@ fn eval_table<H: InterpreterHandler>(
         match handler.before_bytecode(op, pc, state, address) {
             Ok(()) => (),
             Err(e) => {
-                state.exit(e.clone().into());
-                return Control::Exit(ExitReason::Error(e));
+                state.exit(e.clone());
+                return Control::Exit(e);
             }
         }
+        // The changes were reported with the step
+        #[cfg(feature = "tracing")]
+        state.memory_mut().clear_changes();
         let control = TABLE[op.as_usize()](state, op, pc);
 
         #[cfg(feature = "tracing")]
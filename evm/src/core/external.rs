@@ -11,3 +11,75 @@ pub enum ExternalOperation {
     /// Writing to storage (Number of bytes written).
     Write(U256),
 }
+
+/// Cost of an operation in the resources metered by the embedder besides
+/// gas, e.g. the weight of a Substrate chain. Dimensions left unset are not
+/// charged.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ExternalCost {
+    /// Execution time.
+    pub ref_time: Option<u64>,
+    /// Size of the storage proof.
+    pub proof_size: Option<u64>,
+    /// Growth of the storage, in bytes. Unlike the other dimensions, it is
+    /// exact rather than an estimate, so it is not refunded.
+    pub storage_growth: Option<u64>,
+}
+
+impl ExternalCost {
+    /// Cost charging no dimension.
+    pub const NONE: Self = Self {
+        ref_time: None,
+        proof_size: None,
+        storage_growth: None,
+    };
+
+    /// Charge `ref_time` of execution time.
+    #[must_use]
+    pub const fn with_ref_time(mut self, ref_time: u64) -> Self {
+        self.ref_time = Some(ref_time);
+        self
+    }
+
+    /// Charge `proof_size` of storage proof.
+    #[must_use]
+    pub const fn with_proof_size(mut self, proof_size: u64) -> Self {
+        self.proof_size = Some(proof_size);
+        self
+    }
+
+    /// Charge `storage_growth` bytes of storage.
+    #[must_use]
+    pub const fn with_storage_growth(mut self, storage_growth: u64) -> Self {
+        self.storage_growth = Some(storage_growth);
+        self
+    }
+
+    /// Whether no dimension is charged.
+    #[must_use]
+    pub const fn is_none(&self) -> bool {
+        self.ref_time.is_none() && self.proof_size.is_none() && self.storage_growth.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_cost_builder() {
+        assert!(ExternalCost::NONE.is_none());
+        assert_eq!(ExternalCost::default(), ExternalCost::NONE);
+        let cost = ExternalCost::NONE.with_ref_time(10).with_proof_size(20);
+        assert!(!cost.is_none());
+        assert_eq!(
+            cost,
+            ExternalCost {
+                ref_time: Some(10),
+                proof_size: Some(20),
+                storage_growth: None,
+            }
+        );
+        assert_eq!(cost.with_storage_growth(30).storage_growth, Some(30));
+    }
+}
@@ -2,7 +2,7 @@ use super::prelude::*;
 use super::utils::USIZE_MAX;
 use crate::{ExitError, ExitFatal};
 use core::cmp::min;
-use core::ops::{BitAnd, Not};
+use core::ops::{BitAnd, Not, Range};
 use primitive_types::{H256, U256};
 
 /// A sequential memory. It uses Rust's `Vec` for internal
@@ -15,6 +15,9 @@ pub struct Memory {
     effective_len: usize,
     /// Memory limit
     limit: usize,
+    /// Ranges written since the changes were cleared.
+    #[cfg(feature = "tracing")]
+    changes: Vec<Range<usize>>,
 }
 
 impl Memory {
@@ -25,6 +28,8 @@ impl Memory {
             data: Vec::new(),
             effective_len: 0,
             limit,
+            #[cfg(feature = "tracing")]
+            changes: Vec::new(),
         }
     }
 
@@ -56,6 +61,39 @@ impl Memory {
         self.len() == 0
     }
 
+    /// Remove all data, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.effective_len = 0;
+        #[cfg(feature = "tracing")]
+        self.changes.clear();
+    }
+
+    /// Ranges written since the last [`Self::clear_changes`], in write
+    /// order. They can overlap.
+    #[cfg(feature = "tracing")]
+    #[must_use]
+    pub fn changes(&self) -> &[Range<usize>] {
+        &self.changes
+    }
+
+    /// Forget the written ranges, e.g. once they were traced.
+    #[cfg(feature = "tracing")]
+    pub fn clear_changes(&mut self) {
+        self.changes.clear();
+    }
+
+    #[cfg_attr(
+        not(feature = "tracing"),
+        allow(clippy::missing_const_for_fn, clippy::needless_pass_by_ref_mut)
+    )]
+    fn record_change(&mut self, range: Range<usize>) {
+        #[cfg(feature = "tracing")]
+        self.changes.push(range);
+        #[cfg(not(feature = "tracing"))]
+        let _ = range;
+    }
+
     /// Return the full memory.
     #[must_use]
     pub const fn data(&self) -> &Vec<u8> {
@@ -112,6 +150,17 @@ impl Memory {
         ret
     }
 
+    /// Get memory region at given offset, like [`Self::get`], but borrowed
+    /// when the region is within the memory, e.g. after
+    /// [`Self::resize_offset`].
+    #[must_use]
+    pub fn get_cow(&self, offset: usize, size: usize) -> Cow<'_, [u8]> {
+        match offset.checked_add(size) {
+            Some(end) if end <= self.data.len() => Cow::Borrowed(&self.data[offset..end]),
+            _ => Cow::Owned(self.get(offset, size)),
+        }
+    }
+
     /// Get `H256` value from a specific offset in memory.
     #[must_use]
     pub fn get_h256(&self, offset: usize) -> H256 {
@@ -161,6 +210,7 @@ impl Memory {
         if target_size > copy_len {
             dest_slice[copy_len..].fill(0);
         }
+        self.record_change(offset..end_offset);
 
         Ok(())
     }
@@ -199,6 +249,7 @@ impl Memory {
 
         self.data
             .copy_within(src_offset..src_offset + length, dst_offset);
+        self.record_change(dst_offset..dst_offset + length);
         Ok(())
     }
 
@@ -236,6 +287,8 @@ impl Memory {
             self.data.resize(dest_end_offset, 0);
         }
 
+        self.record_change(memory_offset..dest_end_offset);
+
         // 4. Preparing the copy and padding directly into self.data
         // Get the mutable slice of the exact destination region length
         // This is safe because we resized self.data to at least `dest_end_offset`
@@ -275,7 +328,8 @@ fn next_multiple_of_32(x: usize) -> Option<usize> {
 
 #[cfg(test)]
 mod tests {
-    use super::next_multiple_of_32;
+    use super::{next_multiple_of_32, Memory};
+    use crate::core::prelude::Cow;
 
     #[test]
     fn test_next_multiple_of_32() {
@@ -305,4 +359,17 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_get_cow_borrows_resized_region() {
+        let mut memory = Memory::new(1024);
+        memory.resize_offset(0, 40).unwrap();
+        memory.set(4, &[1, 2], 2).unwrap();
+        assert!(matches!(memory.get_cow(4, 2), Cow::Borrowed(&[1, 2])));
+        // Past the end, zero-padded as `get`
+        let padded = memory.get_cow(60, 8);
+        assert!(matches!(padded, Cow::Owned(_)));
+        assert_eq!(padded.as_ref(), memory.get(60, 8).as_slice());
+        assert!(matches!(memory.get_cow(usize::MAX, 2), Cow::Owned(_)));
+    }
 }
@@ -2,24 +2,32 @@
 
 #[cfg(not(feature = "std"))]
 pub mod prelude {
-    pub use alloc::{borrow::Cow, rc::Rc, vec, vec::Vec};
+    pub use alloc::{borrow::Cow, rc::Rc, string::String, vec, vec::Vec};
 }
 #[cfg(feature = "std")]
 pub mod prelude {
-    pub use std::{borrow::Cow, rc::Rc, vec::Vec};
+    pub use std::{borrow::Cow, rc::Rc, string::String, vec::Vec};
 }
 
+mod analysis;
+#[cfg(feature = "eof-experimental")]
+pub mod eof;
 mod error;
 mod eval;
 mod external;
 mod memory;
 mod opcode;
 mod stack;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 pub mod utils;
 mod valids;
 
-pub use error::{Capture, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Trap};
-pub use external::ExternalOperation;
+pub use analysis::{analyze_jump_targets, InvalidJump, JumpAnalysis};
+pub use error::{
+    Capture, ExitCode, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Trap,
+};
+pub use external::{ExternalCost, ExternalOperation};
 pub use memory::Memory;
 pub use opcode::Opcode;
 pub use stack::Stack;
@@ -52,15 +60,18 @@ pub struct Machine {
 
 /// EVM interpreter handler.
 pub trait InterpreterHandler {
+    /// Called before each opcode is executed.
+    ///
     /// # Errors
-    /// Return `ExitError`
+    /// Return the `ExitReason` the machine exits with instead of executing
+    /// the opcode, e.g. an `ExitFatal` aborting all the call frames.
     fn before_bytecode(
         &mut self,
         opcode: Opcode,
         pc: usize,
         machine: &Machine,
         address: &H160,
-    ) -> Result<(), ExitError>;
+    ) -> Result<(), ExitReason>;
 
     // Only invoked for tracing
     #[cfg(feature = "tracing")]
@@ -86,6 +97,11 @@ impl Machine {
     pub const fn memory_mut(&mut self) -> &mut Memory {
         &mut self.memory
     }
+    /// Jump destinations of the code.
+    #[must_use]
+    pub const fn valids(&self) -> &Valids {
+        &self.valids
+    }
     /// Return a reference of the program counter.
     pub const fn position(&self) -> &Result<usize, ExitReason> {
         &self.position
@@ -100,7 +116,21 @@ impl Machine {
         memory_limit: usize,
     ) -> Self {
         let valids = Valids::new(&code[..]);
+        Self::new_with_valids(code, data, valids, stack_limit, memory_limit)
+    }
 
+    /// Create a new machine with given code and data, and the jump
+    /// destinations of the code analyzed beforehand, e.g. restored with
+    /// [`Valids::from_cached`].
+    #[must_use]
+    pub fn new_with_valids(
+        code: Rc<Vec<u8>>,
+        data: Rc<Vec<u8>>,
+        valids: Valids,
+        stack_limit: usize,
+        memory_limit: usize,
+    ) -> Self {
+        debug_assert_eq!(valids.len(), code.len(), "valids of another code");
         Self {
             data,
             code,
@@ -112,6 +142,18 @@ impl Machine {
         }
     }
 
+    /// Reset the machine to run `code` with `data`, as if it was created
+    /// with [`Self::new`], but reusing the memory and stack allocations.
+    pub fn reset(&mut self, code: Rc<Vec<u8>>, data: Rc<Vec<u8>>) {
+        self.valids = Valids::new(&code[..]);
+        self.code = code;
+        self.data = data;
+        self.position = Ok(0);
+        self.return_range = U256_ZERO..U256_ZERO;
+        self.memory.clear();
+        self.stack.clear();
+    }
+
     /// Explicit exit of the machine. Further step will return error.
     pub fn exit(&mut self, reason: ExitReason) {
         self.position = Err(reason);
@@ -150,6 +192,20 @@ impl Machine {
         }
     }
 
+    /// Memory range of the return value, as set by `RETURN` or `REVERT`.
+    /// The range may exceed the memory limit, in which case the missing
+    /// bytes of the return value are zero.
+    #[must_use]
+    pub const fn return_range(&self) -> &Range<U256> {
+        &self.return_range
+    }
+
+    /// Length of the return value.
+    #[must_use]
+    pub fn return_value_len(&self) -> U256 {
+        self.return_range.end - self.return_range.start
+    }
+
     /// Step the machine, executing until exit or trap.
     ///
     /// # Errors
@@ -52,6 +52,12 @@ impl Stack {
         &self.data
     }
 
+    /// Remove all values, keeping the allocated capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
     /// Pop a value from the stack. If the stack is already empty, returns the
     /// `StackUnderflow` error.
     ///
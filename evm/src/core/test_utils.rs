@@ -0,0 +1,181 @@
+//! Utilities to run single opcodes on a machine with a preset state, for
+//! per-opcode unit and property tests of the interpreter without executor
+//! scaffolding.
+
+use super::prelude::*;
+use super::{Capture, ExitError, ExitReason, InterpreterHandler, Machine, Opcode, Trap};
+use primitive_types::{H160, U256};
+
+/// Stack limit of the machines created by [`step_code`].
+pub const STACK_LIMIT: usize = 1024;
+/// Memory limit of the machines created by [`step_code`].
+pub const MEMORY_LIMIT: usize = usize::MAX;
+
+/// Interpreter handler accepting every opcode.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopInterpreterHandler;
+
+impl InterpreterHandler for NoopInterpreterHandler {
+    fn before_bytecode(
+        &mut self,
+        _opcode: Opcode,
+        _pc: usize,
+        _machine: &Machine,
+        _address: &H160,
+    ) -> Result<(), ExitReason> {
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    fn after_bytecode(
+        &mut self,
+        _result: &Result<(), Capture<ExitReason, Trap>>,
+        _machine: &Machine,
+    ) {
+    }
+}
+
+impl Machine {
+    /// Create a machine running `code` with `data`, with a preset stack,
+    /// given from bottom to top, and a preset memory.
+    ///
+    /// # Errors
+    /// Return `ExitError::StackOverflow` if `stack` exceeds `stack_limit`,
+    /// or `ExitFatal::NotSupported` if `memory` exceeds `memory_limit`.
+    pub fn with_state(
+        code: Rc<Vec<u8>>,
+        data: Rc<Vec<u8>>,
+        stack: &[U256],
+        memory: &[u8],
+        stack_limit: usize,
+        memory_limit: usize,
+    ) -> Result<Self, ExitReason> {
+        let mut machine = Self::new(code, data, stack_limit, memory_limit);
+        for value in stack {
+            machine.stack.push(*value)?;
+        }
+        machine.memory.set(0, memory, memory.len())?;
+        machine.memory.resize_end(memory.len())?;
+        Ok(machine)
+    }
+}
+
+/// Machine state after a single step.
+pub struct StepOutcome {
+    /// Result of the step.
+    pub result: Result<(), Capture<ExitReason, Trap>>,
+    /// Machine after the step.
+    pub machine: Machine,
+}
+
+impl StepOutcome {
+    /// Stack after the step, from bottom to top.
+    #[must_use]
+    pub fn stack(&self) -> &[U256] {
+        self.machine.stack().data()
+    }
+
+    /// Memory after the step.
+    #[must_use]
+    pub fn memory(&self) -> &[u8] {
+        self.machine.memory().data()
+    }
+
+    /// Assert the stack after the step, given from bottom to top.
+    ///
+    /// # Panics
+    /// Panic if the stack differs.
+    #[track_caller]
+    pub fn assert_stack(&self, expected: &[U256]) {
+        assert_eq!(self.stack(), expected, "unexpected stack contents");
+    }
+
+    /// Assert the number of stack items after the step.
+    ///
+    /// # Panics
+    /// Panic if the stack depth differs.
+    #[track_caller]
+    pub fn assert_stack_depth(&self, expected: usize) {
+        assert_eq!(self.stack().len(), expected, "unexpected stack depth");
+    }
+}
+
+/// Run the first opcode of `code`, including its immediate data, on a
+/// machine with the given stack and memory.
+///
+/// # Panics
+/// Panic if the stack exceeds [`STACK_LIMIT`].
+#[must_use]
+pub fn step_code(code: &[u8], stack: &[U256], memory: &[u8]) -> StepOutcome {
+    let mut machine = Machine::with_state(
+        Rc::new(code.to_vec()),
+        Rc::new(Vec::new()),
+        stack,
+        memory,
+        STACK_LIMIT,
+        MEMORY_LIMIT,
+    )
+    .expect("preset state within limits");
+    let result = machine.step(&mut NoopInterpreterHandler, &H160::zero());
+    StepOutcome { result, machine }
+}
+
+/// Run `opcode` on a machine with the given stack and memory.
+///
+/// # Panics
+/// Panic if the stack exceeds [`STACK_LIMIT`].
+#[must_use]
+pub fn step_opcode(opcode: Opcode, stack: &[U256], memory: &[u8]) -> StepOutcome {
+    step_code(&[opcode.0], stack, memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ExitSucceed;
+
+    #[test]
+    fn test_step_opcode() {
+        // Operands are popped from the top of the stack
+        let outcome = step_opcode(Opcode::SUB, &[U256::from(2), U256::from(7)], &[]);
+        assert!(outcome.result.is_ok());
+        outcome.assert_stack(&[U256::from(5)]);
+
+        let outcome = step_opcode(Opcode::MLOAD, &[U256::from(1)], &[0xff; 33]);
+        outcome.assert_stack(&[U256::MAX]);
+        assert_eq!(outcome.memory(), &[0xff; 33]);
+
+        // Preset memory is rounded up to words
+        step_opcode(Opcode::MSIZE, &[], &[0; 33]).assert_stack(&[U256::from(64)]);
+
+        let outcome = step_code(&[Opcode::PUSH2.0, 0x12, 0x34], &[], &[]);
+        outcome.assert_stack(&[U256::from(0x1234)]);
+        assert_eq!(outcome.machine.position(), &Ok(3));
+
+        let outcome = step_opcode(Opcode::ADD, &[U256::one()], &[]);
+        assert_eq!(
+            outcome.result,
+            Err(Capture::Exit(ExitError::StackUnderflow.into()))
+        );
+
+        let outcome = step_opcode(Opcode::STOP, &[U256::one()], &[]);
+        assert_eq!(
+            outcome.result,
+            Err(Capture::Exit(ExitSucceed::Stopped.into()))
+        );
+        outcome.assert_stack_depth(1);
+
+        assert_eq!(
+            step_opcode(Opcode::SLOAD, &[U256::zero()], &[]).result,
+            Err(Capture::Trap(Opcode::SLOAD))
+        );
+    }
+
+    #[test]
+    fn test_with_state_limits() {
+        let code = Rc::new(Vec::new());
+        let stack = [U256::zero(); 2];
+        assert!(Machine::with_state(code.clone(), code.clone(), &stack, &[], 1, 0).is_err());
+        assert!(Machine::with_state(code.clone(), code, &[], &[1], 1, 0).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+pub mod revert;
+
 use core::cmp::Ordering;
 use core::ops::{Div, Rem};
 use primitive_types::U256;
@@ -0,0 +1,146 @@
+//! Decoding of revert data.
+//!
+//! See: <https://docs.soliditylang.org/en/latest/control-structures.html#revert>
+
+use crate::core::prelude::*;
+use primitive_types::U256;
+
+/// Selector of `Error(string)`.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`.
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Decoded revert data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RevertReason {
+    /// No revert data, e.g. `revert()`.
+    Empty,
+    /// `Error(string)`, e.g. `require(condition, "reason")`.
+    Error(String),
+    /// `Panic(uint256)`, e.g. a failed `assert` or an arithmetic overflow.
+    Panic(U256),
+    /// Custom error, or data not matching the standard selectors.
+    Custom(Vec<u8>),
+}
+
+impl RevertReason {
+    /// Human readable reason, if any. For panics it is the description of
+    /// the panic code, see [`panic_reason`].
+    #[must_use]
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Error(reason) => Some(reason.as_str()),
+            Self::Panic(code) => panic_reason(*code),
+            Self::Empty | Self::Custom(_) => None,
+        }
+    }
+}
+
+/// Decode revert data. Data with a standard selector but invalid encoding
+/// is returned as [`RevertReason::Custom`].
+#[must_use]
+pub fn decode(data: &[u8]) -> RevertReason {
+    if data.is_empty() {
+        return RevertReason::Empty;
+    }
+    let decoded = match data.split_at_checked(4) {
+        Some((selector, payload)) if selector == ERROR_SELECTOR => {
+            decode_string(payload).map(RevertReason::Error)
+        }
+        Some((selector, payload)) if selector == PANIC_SELECTOR && payload.len() == 32 => {
+            Some(RevertReason::Panic(U256::from_big_endian(payload)))
+        }
+        _ => None,
+    };
+    decoded.unwrap_or_else(|| RevertReason::Custom(data.to_vec()))
+}
+
+/// Encode `reason` as `Error(string)` revert data, as `revert("reason")`
+/// does in Solidity.
+#[must_use]
+pub fn encode_error(reason: &str) -> Vec<u8> {
+    let mut data = ERROR_SELECTOR.to_vec();
+    data.extend_from_slice(&U256::from(32).to_big_endian());
+    data.extend_from_slice(&U256::from(reason.len()).to_big_endian());
+    data.extend_from_slice(reason.as_bytes());
+    data.resize(4 + 64 + reason.len().div_ceil(32) * 32, 0);
+    data
+}
+
+/// Description of a Solidity panic code, as reported by geth.
+#[must_use]
+pub fn panic_reason(code: U256) -> Option<&'static str> {
+    if code > U256::from(u8::MAX) {
+        return None;
+    }
+    Some(match code.low_u64() {
+        0x00 => "generic panic",
+        0x01 => "assert(false)",
+        0x11 => "arithmetic underflow or overflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "enum overflow",
+        0x22 => "invalid encoded storage byte array accessed",
+        0x31 => "out-of-bounds array access; popping on an empty array",
+        0x32 => "out-of-bounds access of an array or bytesN",
+        0x41 => "out of memory",
+        0x51 => "uninitialized function",
+        _ => return None,
+    })
+}
+
+/// Decode an ABI encoded `string` argument.
+fn decode_string(payload: &[u8]) -> Option<String> {
+    let offset = read_usize(payload, 0)?;
+    let len = read_usize(payload, offset)?;
+    let start = offset.checked_add(32)?;
+    let bytes = payload.get(start..start.checked_add(len)?)?;
+    core::str::from_utf8(bytes).ok().map(String::from)
+}
+
+/// Read an ABI encoded word at `offset` as `usize`.
+fn read_usize(payload: &[u8], offset: usize) -> Option<usize> {
+    let word = payload.get(offset..offset.checked_add(32)?)?;
+    let value = U256::from_big_endian(word);
+    usize::try_from(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_error_string() {
+        let reason = decode(&encode_error("insufficient allowance"));
+        assert_eq!(
+            reason,
+            RevertReason::Error(String::from("insufficient allowance"))
+        );
+        assert_eq!(reason.message(), Some("insufficient allowance"));
+    }
+
+    #[test]
+    fn test_decode_panic() {
+        let mut data = PANIC_SELECTOR.to_vec();
+        data.extend_from_slice(&U256::from(0x11).to_big_endian());
+        let reason = decode(&data);
+        assert_eq!(reason, RevertReason::Panic(U256::from(0x11)));
+        assert_eq!(reason.message(), Some("arithmetic underflow or overflow"));
+        assert_eq!(panic_reason(U256::from(0x99)), None);
+    }
+
+    #[test]
+    fn test_decode_custom_and_malformed() {
+        assert_eq!(decode(&[]), RevertReason::Empty);
+        let custom = vec![0xde, 0xad, 0xbe, 0xef, 0x01];
+        assert_eq!(decode(&custom), RevertReason::Custom(custom.clone()));
+        // Truncated string payload
+        let mut truncated = encode_error("reason");
+        truncated.truncate(4 + 64 + 2);
+        assert_eq!(decode(&truncated), RevertReason::Custom(truncated.clone()));
+        // Short data
+        assert_eq!(
+            decode(&[0x08, 0xc3]),
+            RevertReason::Custom(vec![0x08, 0xc3])
+        );
+    }
+}
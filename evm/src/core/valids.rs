@@ -1,22 +1,32 @@
 use super::prelude::*;
 use super::Opcode;
 
-/// Mapping of valid jump destination from code.
+/// Mapping of valid jump destination from code, one bit per code byte.
+///
+/// The analysis only depends on the code, so it can be cached alongside it,
+/// e.g. in the database of a node, with [`Valids::to_cached`] and restored
+/// with [`Valids::from_cached`] instead of analyzing the code again.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Valids(Vec<bool>);
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Valids {
+    /// Length of the code.
+    len: usize,
+    /// Bit `i % 8` of byte `i / 8` is set if position `i` is valid.
+    bits: Vec<u8>,
+}
 
 impl Valids {
     /// Create a new valid mapping from given code bytes.
     #[must_use]
     pub fn new(code: &[u8]) -> Self {
-        let mut valids: Vec<bool> = Vec::with_capacity(code.len());
-        valids.resize(code.len(), false);
+        let mut bits: Vec<u8> = Vec::with_capacity(code.len().div_ceil(8));
+        bits.resize(code.len().div_ceil(8), 0);
 
         let mut i = 0;
         while i < code.len() {
             let opcode = Opcode(code[i]);
             if opcode == Opcode::JUMPDEST {
-                valids[i] = true;
+                bits[i / 8] |= 1 << (i % 8);
                 i += 1;
             } else if let Some(v) = opcode.is_push() {
                 i += usize::from(v) + 1;
@@ -25,25 +35,57 @@ impl Valids {
             }
         }
 
-        Self(valids)
+        Self {
+            len: code.len(),
+            bits,
+        }
+    }
+
+    /// Restore a mapping encoded by [`Valids::to_cached`]. The mapping must
+    /// have been created from the code it is used with, as it is not checked
+    /// against it.
+    ///
+    /// Returns `None` if `bytes` is not a valid encoding.
+    #[must_use]
+    pub fn from_cached(bytes: &[u8]) -> Option<Self> {
+        let (len, bits) = bytes.split_first_chunk::<8>()?;
+        let len = usize::try_from(u64::from_le_bytes(*len)).ok()?;
+        if bits.len() != len.div_ceil(8) {
+            return None;
+        }
+        // The padding bits of the last byte are never set
+        if len % 8 != 0 && bits.last().is_some_and(|last| last >> (len % 8) != 0) {
+            return None;
+        }
+        Some(Self {
+            len,
+            bits: bits.to_vec(),
+        })
+    }
+
+    /// Encode the mapping as the little-endian `u64` code length followed by
+    /// the bitmap, see [`Valids::from_cached`].
+    #[must_use]
+    pub fn to_cached(&self) -> Vec<u8> {
+        let len = u64::try_from(self.len).unwrap_or(u64::MAX);
+        let mut bytes = Vec::with_capacity(8 + self.bits.len());
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&self.bits);
+        bytes
     }
 
     /// Get the length of the valid mapping. This is the same as the
     /// code bytes.
     #[inline]
     #[must_use]
-    // TODO: rust-v1.87 - const fn
-    #[allow(clippy::missing_const_for_fn)]
-    pub fn len(&self) -> usize {
-        self.0.len()
+    pub const fn len(&self) -> usize {
+        self.len
     }
 
     /// Returns true if the valids list is empty
     #[inline]
     #[must_use]
-    // TODO: rust-v1.87 - const fn
-    #[allow(clippy::missing_const_for_fn)]
-    pub fn is_empty(&self) -> bool {
+    pub const fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
@@ -51,14 +93,50 @@ impl Valids {
     /// not, returns `false`.
     #[must_use]
     pub fn is_valid(&self, position: usize) -> bool {
-        if position >= self.0.len() {
+        if position >= self.len {
             return false;
         }
 
-        if !self.0[position] {
-            return false;
-        }
+        self.bits
+            .get(position / 8)
+            .is_some_and(|byte| byte & (1 << (position % 8)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valids() {
+        // JUMPDEST, PUSH2 0x5b5b, JUMPDEST, 8 STOPs, JUMPDEST
+        let mut code = vec![0x5b, 0x61, 0x5b, 0x5b, 0x5b];
+        code.extend_from_slice(&[0x00; 8]);
+        code.push(0x5b);
+        let valids = Valids::new(&code);
+        assert_eq!(valids.len(), code.len());
+        let positions: Vec<_> = (0..=code.len()).filter(|&i| valids.is_valid(i)).collect();
+        assert_eq!(positions, [0, 4, 13]);
+        assert!(Valids::new(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_cached_valids() {
+        let code = [0x5b, 0x60, 0x5b, 0x5b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5b];
+        let valids = Valids::new(&code);
+        let cached = valids.to_cached();
+        assert_eq!(cached.len(), 8 + 2);
+        assert_eq!(Valids::from_cached(&cached), Some(valids));
+        assert_eq!(
+            Valids::from_cached(&Valids::new(&[]).to_cached()),
+            Some(Valids::new(&[]))
+        );
 
-        true
+        // Truncated, or bits set past the code length
+        assert_eq!(Valids::from_cached(&cached[..9]), None);
+        assert_eq!(Valids::from_cached(&cached[..7]), None);
+        let mut padded = cached;
+        padded[9] |= 0x80;
+        assert_eq!(Valids::from_cached(&padded), None);
     }
 }
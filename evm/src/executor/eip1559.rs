@@ -0,0 +1,98 @@
+//! EIP-1559 gas price calculation.
+//!
+//! See: <https://eips.ethereum.org/EIPS/eip-1559>
+
+use primitive_types::U256;
+
+/// Invalid EIP-1559 fee parameters of a transaction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Eip1559Error {
+    /// `max_priority_fee_per_gas` is greater than `max_fee_per_gas`.
+    PriorityFeeTooLarge,
+    /// `max_fee_per_gas` is lower than the block base fee.
+    MaxFeeLessThanBaseFee,
+}
+
+/// Validate transaction fee parameters against the block base fee.
+///
+/// For legacy transactions `max_fee` is the gas price, and `max_priority_fee`
+/// is `None`.
+///
+/// # Errors
+/// Return `Eip1559Error` if the priority fee is greater than the max fee, or
+/// if the max fee is lower than the base fee.
+pub fn validate(
+    base_fee: U256,
+    max_fee: U256,
+    max_priority_fee: Option<U256>,
+) -> Result<(), Eip1559Error> {
+    if max_priority_fee.is_some_and(|priority_fee| priority_fee > max_fee) {
+        return Err(Eip1559Error::PriorityFeeTooLarge);
+    }
+    if max_fee < base_fee {
+        return Err(Eip1559Error::MaxFeeLessThanBaseFee);
+    }
+    Ok(())
+}
+
+/// Validate fee parameters and calculate the effective gas price paid by
+/// the transaction: `min(max_fee, base_fee + max_priority_fee)`.
+///
+/// Without a priority fee (legacy transactions), the effective gas price is
+/// `max_fee`.
+///
+/// # Errors
+/// Return `Eip1559Error` if fee parameters are invalid, see [`validate`].
+pub fn effective_gas_price(
+    base_fee: U256,
+    max_fee: U256,
+    max_priority_fee: Option<U256>,
+) -> Result<U256, Eip1559Error> {
+    validate(base_fee, max_fee, max_priority_fee)?;
+    Ok(max_priority_fee.map_or(max_fee, |priority_fee| {
+        max_fee.min(priority_fee.saturating_add(base_fee))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_gas_price() {
+        let base_fee = U256::from(10);
+        assert_eq!(
+            effective_gas_price(base_fee, U256::from(20), Some(U256::from(3))),
+            Ok(U256::from(13))
+        );
+        assert_eq!(
+            effective_gas_price(base_fee, U256::from(12), Some(U256::from(5))),
+            Ok(U256::from(12))
+        );
+        assert_eq!(
+            effective_gas_price(base_fee, U256::from(15), None),
+            Ok(U256::from(15))
+        );
+        assert_eq!(
+            effective_gas_price(U256::MAX, U256::MAX, Some(U256::one())),
+            Ok(U256::MAX)
+        );
+    }
+
+    #[test]
+    fn test_invalid_fees() {
+        let base_fee = U256::from(10);
+        assert_eq!(
+            effective_gas_price(base_fee, U256::from(20), Some(U256::from(21))),
+            Err(Eip1559Error::PriorityFeeTooLarge)
+        );
+        assert_eq!(
+            effective_gas_price(base_fee, U256::from(9), None),
+            Err(Eip1559Error::MaxFeeLessThanBaseFee)
+        );
+        assert_eq!(
+            validate(base_fee, U256::from(9), Some(U256::from(10))),
+            Err(Eip1559Error::PriorityFeeTooLarge)
+        );
+    }
+}
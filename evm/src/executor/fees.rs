@@ -0,0 +1,126 @@
+//! Transaction fee arithmetic.
+//!
+//! Helpers to charge a transaction sender upfront, reward the block
+//! beneficiary and return unused gas back to the sender. Fallible values that
+//! depend on user input are checked, while values derived from an already
+//! validated upfront charge are saturating.
+
+use crate::backend::GAS_PER_BLOB;
+use crate::FeeMarket;
+use primitive_types::U256;
+
+/// Maximum fee the sender is charged upfront: `gas_limit * gas_price + data_fee`.
+///
+/// Returns `None` on overflow, which means the transaction can't be paid for.
+#[must_use]
+pub fn max_fee(gas_limit: U256, gas_price: U256, data_fee: Option<U256>) -> Option<U256> {
+    let fee = gas_limit.checked_mul(gas_price)?;
+    data_fee.map_or(Some(fee), |data_fee| fee.checked_add(data_fee))
+}
+
+/// EIP-4844: fee of the blob gas of `blob_count` blobs, charged upfront as
+/// the data fee of a blob transaction.
+#[must_use]
+pub fn blob_fee(blob_count: usize, blob_gas_price: U256) -> U256 {
+    U256::from(GAS_PER_BLOB)
+        .saturating_mul(U256::from(blob_count))
+        .saturating_mul(blob_gas_price)
+}
+
+/// Actual fee for the gas used by the transaction: `used_gas * gas_price`.
+#[must_use]
+pub fn actual_fee(used_gas: u64, gas_price: U256) -> U256 {
+    U256::from(used_gas).saturating_mul(gas_price)
+}
+
+/// Reward of the block beneficiary for the gas used by the transaction.
+///
+/// With the EIP-1559 fee market the base fee is burnt and only the priority
+/// fee is paid to the miner. Otherwise, the whole gas price goes to the miner.
+#[must_use]
+pub fn miner_tip(
+    used_gas: u64,
+    effective_gas_price: U256,
+    base_fee: U256,
+    fee_market: FeeMarket,
+) -> U256 {
+    let price = match fee_market {
+        FeeMarket::Eip1559 => effective_gas_price.saturating_sub(base_fee),
+        FeeMarket::Legacy | FeeMarket::Custom => effective_gas_price,
+    };
+    actual_fee(used_gas, price)
+}
+
+/// Amount returned to the sender after execution: the upfront charge minus the
+/// actual fee and the data fee, which is never refunded.
+#[must_use]
+pub fn caller_refund(max_fee: U256, actual_fee: U256, data_fee: Option<U256>) -> U256 {
+    max_fee
+        .saturating_sub(actual_fee)
+        .saturating_sub(data_fee.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_fee_overflow() {
+        assert_eq!(
+            max_fee(U256::from(21_000), U256::from(10), None),
+            Some(U256::from(210_000))
+        );
+        assert_eq!(
+            max_fee(U256::from(21_000), U256::from(10), Some(U256::from(5))),
+            Some(U256::from(210_005))
+        );
+        assert_eq!(max_fee(U256::MAX, U256::from(2), None), None);
+        assert_eq!(max_fee(U256::MAX, U256::one(), Some(U256::one())), None);
+    }
+
+    #[test]
+    fn test_blob_fee() {
+        assert_eq!(blob_fee(0, U256::from(3)), U256::zero());
+        assert_eq!(blob_fee(2, U256::from(3)), U256::from(6 * GAS_PER_BLOB));
+        assert_eq!(blob_fee(1, U256::MAX), U256::MAX);
+    }
+
+    #[test]
+    fn test_actual_fee_saturates() {
+        assert_eq!(actual_fee(3, U256::from(7)), U256::from(21));
+        assert_eq!(actual_fee(2, U256::MAX), U256::MAX);
+    }
+
+    #[test]
+    fn test_miner_tip() {
+        let price = U256::from(12);
+        let base_fee = U256::from(10);
+        assert_eq!(
+            miner_tip(100, price, base_fee, FeeMarket::Eip1559),
+            U256::from(200)
+        );
+        assert_eq!(
+            miner_tip(100, price, base_fee, FeeMarket::Legacy),
+            U256::from(1200)
+        );
+        assert_eq!(
+            miner_tip(100, price, base_fee, FeeMarket::Custom),
+            U256::from(1200)
+        );
+        assert_eq!(
+            miner_tip(100, base_fee, price, FeeMarket::Eip1559),
+            U256::zero()
+        );
+    }
+
+    #[test]
+    fn test_caller_refund() {
+        let max = U256::from(1000);
+        assert_eq!(caller_refund(max, U256::from(300), None), U256::from(700));
+        assert_eq!(
+            caller_refund(max, U256::from(300), Some(U256::from(100))),
+            U256::from(600)
+        );
+        assert_eq!(caller_refund(max, U256::from(2000), None), U256::zero());
+    }
+}
@@ -5,4 +5,7 @@
 //!
 //! Currently only a stack-based (customizable) executor is provided.
 
+pub mod eip1559;
+pub mod fees;
 pub mod stack;
+pub mod transaction;
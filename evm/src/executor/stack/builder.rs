@@ -0,0 +1,205 @@
+//! Builder of a [`StackExecutor`] over a memory state.
+
+use crate::backend::Backend;
+use crate::executor::stack::{
+    MemoryStackState, PrecompileSet, StackExecutor, StackSubstateMetadata,
+};
+use crate::Config;
+
+/// State of a [`StackExecutorBuilder`] without a backend yet.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoBackend;
+
+/// Builder of a [`StackExecutor`] running over a [`MemoryStackState`],
+/// wiring the substate metadata, the state and the precompiles.
+///
+/// The executor can only be built once the backend is set, and runs
+/// without precompiles unless a set is given:
+///
+/// ```
+/// # use aurora_evm::backend::{BlockEnv, MemoryBackend, MemoryVicinity, TxEnv};
+/// # use aurora_evm::executor::stack::StackExecutorBuilder;
+/// # use aurora_evm::Config;
+/// # use std::collections::BTreeMap;
+/// let config = Config::cancun();
+/// let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+/// let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+/// let executor = StackExecutorBuilder::new(&config, 1_000_000)
+///     .backend(&backend)
+///     .build();
+/// assert_eq!(executor.gas(), 1_000_000);
+/// ```
+///
+/// Executors over other [`super::StackState`] implementations are created
+/// with [`StackExecutor::new_with_precompiles`].
+#[derive(Clone, Copy, Debug)]
+pub struct StackExecutorBuilder<'config, 'precompiles, B, P> {
+    config: &'config Config,
+    gas_limit: u64,
+    backend: B,
+    precompile_set: &'precompiles P,
+}
+
+impl<'config> StackExecutorBuilder<'config, 'static, NoBackend, ()> {
+    /// Builder of an executor with `config`, and `gas_limit` the gas limit
+    /// of the transactions it executes.
+    #[must_use]
+    pub const fn new(config: &'config Config, gas_limit: u64) -> Self {
+        Self {
+            config,
+            gas_limit,
+            backend: NoBackend,
+            precompile_set: &(),
+        }
+    }
+}
+
+impl<'config, 'precompiles, B, P> StackExecutorBuilder<'config, 'precompiles, B, P> {
+    /// Set the gas limit.
+    #[must_use]
+    pub const fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    /// Execute with the precompiles of `precompile_set`.
+    #[must_use]
+    pub fn precompiles<'new, Q: PrecompileSet>(
+        self,
+        precompile_set: &'new Q,
+    ) -> StackExecutorBuilder<'config, 'new, B, Q> {
+        StackExecutorBuilder {
+            config: self.config,
+            gas_limit: self.gas_limit,
+            backend: self.backend,
+            precompile_set,
+        }
+    }
+}
+
+impl<'config, 'precompiles, P> StackExecutorBuilder<'config, 'precompiles, NoBackend, P> {
+    /// Execute over a memory state on top of `backend`.
+    #[must_use]
+    pub const fn backend<B: Backend>(
+        self,
+        backend: &B,
+    ) -> StackExecutorBuilder<'config, 'precompiles, &B, P> {
+        StackExecutorBuilder {
+            config: self.config,
+            gas_limit: self.gas_limit,
+            backend,
+            precompile_set: self.precompile_set,
+        }
+    }
+}
+
+impl<'config, 'precompiles, 'backend, B: Backend, P: PrecompileSet>
+    StackExecutorBuilder<'config, 'precompiles, &'backend B, P>
+{
+    /// Build the executor.
+    #[must_use]
+    pub fn build(
+        self,
+    ) -> StackExecutor<'config, 'precompiles, MemoryStackState<'backend, 'config, B>, P> {
+        let metadata = StackSubstateMetadata::new(self.gas_limit, self.config);
+        StackExecutor::new_with_precompiles(
+            MemoryStackState::new(metadata, self.backend),
+            self.config,
+            self.precompile_set,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::executor::stack::{PrecompileFailure, PrecompileHandle, PrecompileOutput};
+    use crate::prelude::*;
+    use crate::{ExitReason, ExitSucceed};
+    use primitive_types::{H160, U256};
+
+    const ECHO: H160 = H160([0xee; 20]);
+
+    struct Echo;
+
+    impl PrecompileSet for Echo {
+        fn execute(
+            &self,
+            handle: &mut impl PrecompileHandle,
+        ) -> Option<Result<PrecompileOutput, PrecompileFailure>> {
+            (handle.code_address() == ECHO).then(|| {
+                Ok(PrecompileOutput {
+                    exit_status: ExitSucceed::Returned,
+                    output: handle.input().to_vec(),
+                })
+            })
+        }
+
+        fn is_precompile(&self, address: H160) -> bool {
+            address == ECHO
+        }
+
+        fn precompile_addresses(&self) -> Vec<H160> {
+            vec![ECHO]
+        }
+    }
+
+    #[test]
+    fn test_builder() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        // SSTORE(0, 1)
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code: vec![0x60, 0x01, 0x60, 0x00, 0x55],
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let config = Config::cancun();
+
+        let mut executor = StackExecutorBuilder::new(&config, 50_000)
+            .gas_limit(100_000)
+            .backend(&backend)
+            .build();
+        assert_eq!(executor.gas(), 100_000);
+        let (reason, _) = executor.transact_call(
+            caller,
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        assert_eq!(executor.used_gas(), 21_000 + 22_100 + 6);
+
+        // The precompile set can be given before or after the backend
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .precompiles(&Echo)
+            .backend(&backend)
+            .build();
+        let (reason, output) = executor.transact_call(
+            caller,
+            ECHO,
+            U256::zero(),
+            vec![1, 2, 3],
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+        assert_eq!(output, [1, 2, 3]);
+        let executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&backend)
+            .precompiles(&Echo)
+            .build();
+        assert!(executor.precompiles().is_precompile(ECHO));
+    }
+}
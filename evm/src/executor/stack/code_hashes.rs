@@ -0,0 +1,140 @@
+//! Code hashes computed by the executor and reused within a transaction.
+
+use crate::prelude::*;
+use primitive_types::{H160, H256};
+use sha3::{Digest, Keccak256};
+
+/// Maximum number of distinct init codes whose hash is kept per transaction.
+const MAX_INIT_CODE_HASHES: usize = 64;
+
+/// Keccak hash of `code`.
+pub fn keccak(code: &[u8]) -> H256 {
+    H256::from_slice(<[u8; 32]>::from(Keccak256::digest(code)).as_slice())
+}
+
+/// Hashes of the code deployed by a transaction, journaled with its
+/// substates, and of the init code of its `CREATE2` deployments.
+pub struct CodeHashes {
+    /// Code hash of each contract deployed by the transaction.
+    deployed: BTreeMap<H160, H256>,
+    /// Deployed addresses in deployment order, with the hash they replaced.
+    journal: Vec<(H160, Option<H256>)>,
+    /// Journal length when each substate was entered.
+    frames: Vec<usize>,
+    /// Hash of each init code deployed with `CREATE2`.
+    init_code: BTreeMap<Vec<u8>, H256>,
+}
+
+impl CodeHashes {
+    pub const fn new() -> Self {
+        Self {
+            deployed: BTreeMap::new(),
+            journal: Vec::new(),
+            frames: Vec::new(),
+            init_code: BTreeMap::new(),
+        }
+    }
+
+    /// Code hash of the contract deployed at `address`.
+    pub fn deployed(&self, address: H160) -> Option<H256> {
+        self.deployed.get(&address).copied()
+    }
+
+    /// Record the code hash of the contract deployed at `address`.
+    pub fn insert_deployed(&mut self, address: H160, code_hash: H256) {
+        let replaced = self.deployed.insert(address, code_hash);
+        self.journal.push((address, replaced));
+    }
+
+    /// Hash of `init_code`, and whether it was cached.
+    pub fn init_code_hash(&mut self, init_code: &[u8]) -> (H256, bool) {
+        if let Some(code_hash) = self.init_code.get(init_code) {
+            return (*code_hash, true);
+        }
+        let code_hash = keccak(init_code);
+        if self.init_code.len() < MAX_INIT_CODE_HASHES {
+            self.init_code.insert(init_code.to_vec(), code_hash);
+        }
+        (code_hash, false)
+    }
+
+    pub fn enter(&mut self) {
+        self.frames.push(self.journal.len());
+    }
+
+    /// Exit a substate, dropping the hashes of the code it deployed unless
+    /// it `succeeded`.
+    pub fn exit(&mut self, succeeded: bool) {
+        let Some(len) = self.frames.pop() else {
+            return;
+        };
+        if succeeded {
+            return;
+        }
+        for (address, replaced) in self.journal.drain(len..).rev() {
+            match replaced {
+                Some(code_hash) => self.deployed.insert(address, code_hash),
+                None => self.deployed.remove(&address),
+            };
+        }
+    }
+
+    /// Number of entered substates.
+    pub fn frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Drop every hash at the end of a transaction.
+    pub fn clear(&mut self) {
+        self.deployed.clear();
+        self.journal.clear();
+        self.frames.clear();
+        self.init_code.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverted_deployments_are_dropped() {
+        let (first, second) = (H160::from_low_u64_be(1), H160::from_low_u64_be(2));
+        let mut hashes = CodeHashes::new();
+        hashes.insert_deployed(first, H256::repeat_byte(1));
+
+        hashes.enter();
+        hashes.insert_deployed(first, H256::repeat_byte(2));
+        hashes.insert_deployed(second, H256::repeat_byte(3));
+        hashes.enter();
+        hashes.insert_deployed(second, H256::repeat_byte(4));
+        hashes.exit(true);
+        assert_eq!(hashes.deployed(second), Some(H256::repeat_byte(4)));
+        hashes.exit(false);
+
+        assert_eq!(hashes.deployed(first), Some(H256::repeat_byte(1)));
+        assert_eq!(hashes.deployed(second), None);
+        assert_eq!(hashes.frames(), 0);
+
+        hashes.clear();
+        assert_eq!(hashes.deployed(first), None);
+    }
+
+    #[test]
+    fn test_init_code_hash_is_cached() {
+        let mut hashes = CodeHashes::new();
+        let code_hash = keccak(&[0x00]);
+        assert_eq!(hashes.init_code_hash(&[0x00]), (code_hash, false));
+        assert_eq!(hashes.init_code_hash(&[0x00]), (code_hash, true));
+
+        for byte in 1..=u8::MAX {
+            hashes.init_code_hash(&[byte]);
+        }
+        // Only the first init codes are kept
+        assert_eq!(hashes.init_code_hash(&[0x01]), (keccak(&[0x01]), true));
+        assert_eq!(hashes.init_code_hash(&[0xff]), (keccak(&[0xff]), false));
+
+        hashes.clear();
+        assert_eq!(hashes.init_code_hash(&[0x00]), (code_hash, false));
+    }
+}
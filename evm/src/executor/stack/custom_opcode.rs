@@ -0,0 +1,58 @@
+//! Chain-specific opcodes.
+
+use crate::{ExitError, Machine, Opcode, Stack};
+use core::ops::RangeInclusive;
+
+/// Opcodes left unused by Ethereum, available for chain-specific opcodes.
+/// The following unused bytes, up to `0xef`, are reserved for the EOF
+/// opcodes, e.g. `DATALOAD` (`0xd0`) or `EOFCREATE` (`0xec`).
+pub const CUSTOM_OPCODES: RangeInclusive<u8> = 0xc0..=0xcf;
+
+/// Chain-specific opcodes in [`CUSTOM_OPCODES`], executed by a
+/// [`super::StackExecutor`] instead of failing with `InvalidCode`.
+///
+/// An opcode is priced with [`Self::gas_cost`] before it is executed with
+/// [`Self::execute`], and traced like the standard opcodes, with a
+/// `CustomOpcode` runtime event following its `Step` event.
+pub trait CustomOpcodeHandler {
+    /// Gas charged for `opcode` with the current `stack`, `None` if the
+    /// opcode is not handled, so it fails with `InvalidCode`. The memory
+    /// expansion is not priced separately, so it must be part of the cost.
+    fn gas_cost(&self, opcode: Opcode, stack: &Stack) -> Option<u64>;
+
+    /// Execute a handled `opcode`, e.g. pop its inputs from the stack of
+    /// `machine` and push its outputs.
+    ///
+    /// # Errors
+    /// Return `ExitError` to abort the call frame.
+    fn execute(&mut self, opcode: Opcode, machine: &mut Machine) -> Result<(), ExitError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eof_opcodes_are_reserved() {
+        let eof_opcodes = [
+            Opcode::DATALOAD,
+            Opcode::DATALOADN,
+            Opcode::DATASIZE,
+            Opcode::DATACOPY,
+            Opcode::RJUMP,
+            Opcode::RJUMPI,
+            Opcode::RJUMPV,
+            Opcode::CALLF,
+            Opcode::RETF,
+            Opcode::JUMPF,
+            Opcode::DUPN,
+            Opcode::SWAPN,
+            Opcode::EXCHANGE,
+            Opcode::EOFCREATE,
+            Opcode::RETURNCONTRACT,
+        ];
+        for opcode in eof_opcodes {
+            assert!(!CUSTOM_OPCODES.contains(&opcode.as_u8()), "{opcode:?}");
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Chain-specific transaction types.
+
+use crate::backend::{Backend, Basic};
+use crate::executor::stack::StackState;
+use crate::executor::transaction::{InvalidTransaction, Transaction};
+use crate::{Config, ExitError, ExitReason};
+use primitive_types::{H160, U256};
+
+/// Balances of the state, as available to the hooks of a
+/// [`CustomTransactionType`].
+pub trait TransactionHookState {
+    /// Basic information of the account at `address`.
+    fn basic(&self, address: H160) -> Basic;
+
+    /// Add `value` to the balance of `address`.
+    ///
+    /// # Errors
+    /// Return `ExitError::BalanceOverflow` if the balance overflows.
+    fn credit(&mut self, address: H160, value: U256) -> Result<(), ExitError>;
+
+    /// Subtract `value` from the balance of `address`.
+    ///
+    /// # Errors
+    /// Return `ExitError::OutOfFund` if the balance is lower than `value`.
+    fn debit(&mut self, address: H160, value: U256) -> Result<(), ExitError>;
+}
+
+/// Chain-specific transaction type, e.g. the deposit transactions of
+/// OP-stack-like chains, registered with
+/// [`super::StackExecutor::set_custom_transaction_type`] and executed by
+/// [`super::StackExecutor::transact`] for the transactions of its type, see
+/// [`Transaction::custom_type`].
+///
+/// The transaction is validated as a standard one, then
+/// [`Self::pre_execution`] runs, the fees are charged if
+/// [`Self::charges_fees`], the transaction is executed with the intrinsic
+/// gas of [`Self::intrinsic_gas`], and [`Self::post_execution`] runs once
+/// the fees are settled. The type byte must not be the first byte of a
+/// standard transaction, see [`crate::executor::transaction::TransactionType::from_first_byte`].
+pub trait CustomTransactionType {
+    /// Intrinsic gas of `tx`, charged in place of the intrinsic gas of a
+    /// standard `CALL` or `CREATE` transaction. The EIP-7623 gas floor does
+    /// not apply.
+    fn intrinsic_gas(&self, tx: &Transaction, config: &Config) -> u64;
+
+    /// Whether the gas payer of `tx` is charged the fees, `false` e.g. for
+    /// deposits whose gas is paid on another chain.
+    fn charges_fees(&self, _tx: &Transaction) -> bool {
+        true
+    }
+
+    /// Hook running before the fees are charged, e.g. to mint the deposited
+    /// value to the caller.
+    ///
+    /// # Errors
+    /// Return `InvalidTransaction` to reject the transaction. The changes
+    /// made by the hook are discarded when the transaction is rejected,
+    /// by the hook or by the fee charge.
+    fn pre_execution(
+        &mut self,
+        _tx: &Transaction,
+        _state: &mut dyn TransactionHookState,
+    ) -> Result<(), InvalidTransaction> {
+        Ok(())
+    }
+
+    /// Hook running after the execution of `tx` and the fee settlement,
+    /// with its exit reason and the gas it used, see
+    /// [`super::StackExecutor::transaction_used_gas`].
+    fn post_execution(
+        &mut self,
+        _tx: &Transaction,
+        _reason: &ExitReason,
+        _used_gas: u64,
+        _state: &mut dyn TransactionHookState,
+    ) {
+    }
+}
+
+/// State of the executor, as given to the hooks.
+pub(super) struct HookState<'a, S>(pub &'a mut S);
+
+impl<'config, S: StackState<'config>> TransactionHookState for HookState<'_, S> {
+    fn basic(&self, address: H160) -> Basic {
+        self.0.basic(address)
+    }
+
+    fn credit(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
+        self.0.credit(address, value)
+    }
+
+    fn debit(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
+        self.0.debit(address, value)
+    }
+}
@@ -1,38 +1,74 @@
-use crate::backend::Backend;
+#[cfg(feature = "concurrent")]
+use crate::backend::SharedMemoryBackend;
+use crate::backend::{Apply, Backend, Log, TxEnv};
+use crate::core::prelude::Cow;
 use crate::core::utils::{U256_ZERO, U64_MAX};
 use crate::core::{ExitFatal, InterpreterHandler, Machine};
+use crate::executor::fees;
+use crate::executor::stack::code_hashes::{self, CodeHashes};
+use crate::executor::stack::custom_opcode::{CustomOpcodeHandler, CUSTOM_OPCODES};
+use crate::executor::stack::custom_transaction::{CustomTransactionType, HookState};
+use crate::executor::stack::invariants::InvariantViolation;
+use crate::executor::stack::metrics::Metrics;
+use crate::executor::stack::policy::{CreateDecision, CreateInterceptor, TargetPolicy};
 use crate::executor::stack::precompile::{
     PrecompileFailure, PrecompileHandle, PrecompileOutput, PrecompileSet,
 };
+use crate::executor::stack::runtime_pool::RuntimePool;
+#[cfg(feature = "execution-stats")]
+use crate::executor::stack::stats::{ExecutionStats, StatsTracker};
+use crate::executor::stack::system::{
+    CallContextKind, BEACON_ROOTS_ADDRESS, HISTORY_STORAGE_ADDRESS, SYSTEM_ADDRESS,
+};
 use crate::executor::stack::tagged_runtime::{RuntimeKind, TaggedRuntime};
-use crate::gasometer::{self, Gasometer, StorageTarget};
+use crate::executor::stack::MemoryStackState;
+use crate::executor::transaction::{
+    self, InvalidTransaction, NoncePolicy, SenderOverride, SignedTransaction, Transaction,
+    TransactionType,
+};
+use crate::gasometer::{
+    self, FrameTag, GasSnapshot, Gasometer, RefundCounter, StorageTarget, TransactionCost,
+};
 use crate::maybe_borrowed::MaybeBorrowed;
 use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::runtime::CancellationToken;
 use crate::runtime::Resolve;
+#[cfg(feature = "tracing")]
+use crate::tracing::NewAccountCause;
 use crate::{
-    Capture, Config, Context, CreateScheme, ExitError, ExitReason, Handler, Opcode, Runtime,
-    Transfer,
+    analyze_jump_targets, CallScheme, Capture, Config, Context, CreateScheme, ExitError,
+    ExitReason, ExitRevert, ExitSucceed, ExternalCost, Handler, LogRef, Opcode, Runtime,
+    SelfDestructBalance, Stack, Transfer,
 };
-use core::{cmp::min, convert::Infallible};
+use core::{cmp::min, convert::Infallible, ops::Range};
 use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 use smallvec::{smallvec, SmallVec};
 
 macro_rules! emit_exit {
-    ($reason:expr) => {{
+    ($executor:expr, $reason:expr) => {{
         let reason = $reason;
+        #[cfg(feature = "tracing")]
+        let (gas_used, gas_remaining) = $executor.take_exited_frame_gas();
         event!(Exit {
             reason: &reason,
             return_value: &Vec::new(),
+            gas_used,
+            gas_remaining,
         });
         reason
     }};
-    ($reason:expr, $return_value:expr) => {{
+    ($executor:expr, $reason:expr, $return_value:expr) => {{
         let reason = $reason;
         let return_value = $return_value;
+        #[cfg(feature = "tracing")]
+        let (gas_used, gas_remaining) = $executor.take_exited_frame_gas();
         event!(Exit {
             reason: &reason,
             return_value: &return_value,
+            gas_used,
+            gas_remaining,
         });
         (reason, return_value)
     }};
@@ -45,19 +81,187 @@ macro_rules! try_or_fail {
         }
     };
 }
+/// Re-run `$call` with the event listeners of the executor installed, unless
+/// none is set or they are already installed.
+macro_rules! with_event_listeners {
+    ($executor:ident, $call:expr) => {
+        #[cfg(feature = "tracing")]
+        if $executor.event_listeners.is_set() {
+            let mut listeners = core::mem::take(&mut $executor.event_listeners);
+            let result = listeners.using(|| $call);
+            $executor.event_listeners = listeners;
+            return result;
+        }
+    };
+}
 
 const DEFAULT_CALL_STACK_CAPACITY: usize = 4;
 
-const fn l64(gas: u64) -> u64 {
-    gas - gas / 64
-}
-
 pub enum StackExitKind {
     Succeeded,
     Reverted,
     Failed,
 }
 
+/// Fees charged upfront to the gas payer of a transaction, settled once it
+/// is executed.
+struct ChargedFees {
+    payer: H160,
+    max_fee: U256,
+    data_fee: Option<U256>,
+}
+
+/// Outcome of a `CREATE` transaction, see
+/// [`StackExecutor::transact_create_detailed`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreateResult {
+    /// Exit reason of the transaction.
+    pub reason: ExitReason,
+    /// Address of the deployed contract, `None` if the deployment failed.
+    pub address: Option<H160>,
+    /// Hash of the deployed runtime code, `None` if the deployment failed.
+    pub code_hash: Option<H256>,
+    /// Length of the deployed runtime code.
+    pub code_len: usize,
+    /// Gas used by the transaction, including the code deposit, see
+    /// [`StackExecutor::transaction_used_gas`].
+    pub used_gas: u64,
+    /// Gas paid for the code deposits, part of `used_gas`.
+    pub code_deposit_gas: u64,
+    /// Data returned by the constructor when it reverted, e.g. the revert
+    /// reason. Empty otherwise, as the data returned on success is the
+    /// deployed code.
+    pub return_data: Vec<u8>,
+}
+
+impl CreateResult {
+    /// Gas used excluding the code deposits: the intrinsic gas and the
+    /// execution of the init code.
+    #[must_use]
+    pub const fn init_gas(&self) -> u64 {
+        self.used_gas.saturating_sub(self.code_deposit_gas)
+    }
+}
+
+/// Outcome of [`StackExecutor::transact_call_dry_run`].
+#[derive(Clone, Debug)]
+pub struct DryRun {
+    /// Exit reason of the call.
+    pub reason: ExitReason,
+    /// Data returned by the call.
+    pub output: Vec<u8>,
+    /// Gas used by the call.
+    pub used_gas: u64,
+    /// Changes the call would apply to the state of the executor, in
+    /// canonical order, see [`MemoryStackState::deconstruct`].
+    pub changes: Vec<Apply<Vec<(H256, H256)>>>,
+    /// Logs the call would emit.
+    pub logs: Vec<Log>,
+    /// Access list of the call, in the EIP-2930 format: the addresses and
+    /// storage slots it accessed, sorted. The addresses warm in any case,
+    /// i.e. the caller, the called address, the precompiles and, since
+    /// EIP-3651, the coinbase, are only listed for their storage slots.
+    /// Empty before Berlin.
+    pub access_list: Vec<(H160, Vec<H256>)>,
+    /// First operation which changed the state, only recorded by
+    /// [`StackExecutor::transact_call_static_simulation`].
+    pub static_violation: Option<StaticViolation>,
+}
+
+/// Operation changing the state, which fails a static call with
+/// `StaticModeViolation` or `Invalid`, see
+/// [`StackExecutor::transact_call_static_simulation`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StaticViolation {
+    /// Address of the account whose code executed the operation.
+    pub address: H160,
+    /// Position of the opcode in the code.
+    pub pc: usize,
+    /// Opcode of the operation: `SSTORE`, `TSTORE`, `LOG0` to `LOG4`,
+    /// `CREATE`, `CREATE2`, `SELFDESTRUCT`, or `CALL` with value.
+    pub opcode: Opcode,
+    /// Call depth, 0 for the transaction call.
+    pub depth: usize,
+}
+
+impl StaticViolation {
+    /// Whether `opcode` changes the state when executed with `stack`.
+    fn changes_state(opcode: Opcode, stack: &Stack) -> bool {
+        match opcode {
+            Opcode::SSTORE
+            | Opcode::TSTORE
+            | Opcode::LOG0
+            | Opcode::LOG1
+            | Opcode::LOG2
+            | Opcode::LOG3
+            | Opcode::LOG4
+            | Opcode::CREATE
+            | Opcode::CREATE2
+            | Opcode::SELFDESTRUCT => true,
+            Opcode::CALL => stack.peek(2).is_ok_and(|value| value != U256_ZERO),
+            _ => false,
+        }
+    }
+}
+
+/// Outcome of [`StackExecutor::estimate_gas`].
+#[derive(Clone, Debug)]
+pub struct GasEstimate {
+    /// Exit reason of the call with the whole gas limit.
+    pub reason: ExitReason,
+    /// Data returned by the call with the whole gas limit.
+    pub output: Vec<u8>,
+    /// Lowest gas limit the call succeeds with, or the gas it used if it
+    /// fails with the whole gas limit.
+    pub gas: u64,
+    /// Access list generated by a first run of the call, see
+    /// [`DryRun::access_list`], and the lowest gas limit the call succeeds
+    /// with when the access list is included. Only set if requested and the
+    /// call succeeds with it.
+    pub with_access_list: Option<(Vec<(H160, Vec<H256>)>, u64)>,
+}
+
+/// Event of the log stream, see [`StackExecutor::set_log_subscriber`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LogEvent<'a> {
+    /// A log was emitted.
+    Emitted(&'a LogRef<'a>),
+    /// A call frame was reverted or failed, so the given number of most
+    /// recently emitted (and not yet discarded) logs are discarded.
+    Discarded(usize),
+}
+
+/// Callback receiving the log stream of the executor.
+pub type LogSubscriber<'config> = Box<dyn FnMut(LogEvent<'_>) + 'config>;
+
+/// Position of a log kept by a transaction, see
+/// [`StackExecutor::log_positions`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LogPosition {
+    /// Index of the log in the logs of the transaction.
+    pub tx_index: usize,
+    /// Index of the log in the logs of the block, if the block index of
+    /// the logs was given, see [`StackExecutor::set_block_log_index`].
+    pub block_index: Option<usize>,
+    /// Call depth of the frame emitting the log, 0 for the transaction
+    /// frame.
+    pub depth: usize,
+    /// Frame emitting the log, frames being numbered from 0 in the order
+    /// they are entered.
+    pub frame_id: usize,
+}
+
+/// Frames emitting the logs, tracked along with the substates.
+#[derive(Clone, Debug, Default)]
+struct LogEmitters {
+    /// Depth and id of the frame of every kept log, in emission order.
+    frames: Vec<(usize, usize)>,
+    /// Id of every entered frame, and number of logs when it was entered.
+    entered: Vec<(usize, usize)>,
+    /// Id of the next entered frame.
+    next_frame_id: usize,
+}
+
 /// `Authorization` contains already prepared data for EIP-7702.
 /// - `authority`is `ecrecovered` authority address.
 /// - `address` is delegation destination address.
@@ -104,6 +308,14 @@ impl Authorization {
         }
     }
 
+    /// Returns `true` if the authorization clears the delegation of `authority`,
+    /// i.e. `address` is the zero address. The code of `authority` is reset to empty
+    /// instead of writing a delegation designation.
+    #[must_use]
+    pub fn is_delegation_clearing(&self) -> bool {
+        self.address.is_zero()
+    }
+
     /// Returns the delegation code as composing: `0xef0100 ++ address`.
     /// Result code is always 23 bytes.
     #[must_use]
@@ -115,6 +327,9 @@ impl Authorization {
     }
 }
 
+/// Accessed addresses, storage keys and authorities (EIP-2929, EIP-7702).
+/// Sets are iterated in ascending order, address then key, so access reports
+/// built from them are in canonical order.
 #[derive(Default, Clone, Debug)]
 pub struct Accessed {
     pub accessed_addresses: BTreeSet<H160>,
@@ -235,10 +450,13 @@ impl<'config> StackSubstateMetadata<'config> {
 
     #[must_use]
     pub fn spit_child(&self, gas_limit: u64, is_static: bool) -> Self {
+        let mut gasometer = Gasometer::new(gas_limit, self.gasometer.config());
+        // Nested frames of a precompile subcall stay attributed to it
+        gasometer.set_tag(self.gasometer.tag());
         Self {
-            gasometer: Gasometer::new(gas_limit, self.gasometer.config()),
+            gasometer,
             is_static: is_static || self.is_static,
-            depth: self.depth.map_or(Some(0), |n| Some(n + 1)),
+            depth: Some(self.depth.map_or(0, |n| n.saturating_add(1))),
             accessed: self.accessed.as_ref().map(|_| Accessed::default()),
         }
     }
@@ -348,6 +566,16 @@ pub trait StackState<'config>: Backend {
     /// # Errors
     /// Return `ExitError`
     fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError>;
+    /// Add `value` to the balance of `address`.
+    ///
+    /// # Errors
+    /// Return `ExitError::BalanceOverflow` if the balance overflows.
+    fn credit(&mut self, address: H160, value: U256) -> Result<(), ExitError>;
+    /// Subtract `value` from the balance of `address`.
+    ///
+    /// # Errors
+    /// Return `ExitError::OutOfFund` if the balance is lower than `value`.
+    fn debit(&mut self, address: H160, value: U256) -> Result<(), ExitError>;
     fn reset_balance(&mut self, address: H160);
     fn touch(&mut self, address: H160);
 
@@ -371,21 +599,24 @@ pub trait StackState<'config>: Backend {
         Ok(())
     }
 
+    /// Record a cost metered by the embedder besides gas.
+    ///
     /// # Errors
     /// Return `ExitError`
     fn record_external_cost(
         &mut self,
-        #[allow(clippy::used_underscore_binding)] _ref_time: Option<u64>,
-        #[allow(clippy::used_underscore_binding)] _proof_size: Option<u64>,
-        #[allow(clippy::used_underscore_binding)] _storage_growth: Option<u64>,
+        #[allow(clippy::used_underscore_binding)] _cost: ExternalCost,
     ) -> Result<(), ExitError> {
         Ok(())
     }
 
+    /// Refund a cost recorded with [`Self::record_external_cost`], once the
+    /// actual cost of an operation is below the recorded estimate.
+    /// Implementations ignore `cost.storage_growth`, which is only recorded
+    /// for bytes actually written and is never an estimate.
     fn refund_external_cost(
         &mut self,
-        #[allow(clippy::used_underscore_binding)] _ref_time: Option<u64>,
-        #[allow(clippy::used_underscore_binding)] _proof_size: Option<u64>,
+        #[allow(clippy::used_underscore_binding)] _cost: ExternalCost,
     ) {
     }
 
@@ -407,6 +638,20 @@ pub trait StackState<'config>: Backend {
 
     /// EIP-7702 - get authority target address.
     fn get_authority_target(&mut self, address: H160) -> Option<H160>;
+
+    /// Discard the transient storage at the end of a transaction.
+    /// EIP-1153: Transient storage
+    fn clear_transient_storage(&mut self) {}
+
+    /// Check the invariants of the state at the end of a transaction, see
+    /// [`InvariantViolation`]. Only called with the `strict-invariants`
+    /// feature.
+    ///
+    /// # Errors
+    /// Return the first violation found.
+    fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        Ok(())
+    }
 }
 
 /// Stack-based executor.
@@ -414,6 +659,73 @@ pub struct StackExecutor<'config, 'precompiles, S, P> {
     config: &'config Config,
     state: S,
     precompile_set: &'precompiles P,
+    log_subscriber: Option<LogSubscriber<'config>>,
+    /// Number of streamed logs per entered substate.
+    log_frames: Vec<usize>,
+    /// Transaction environment overriding the one of the state.
+    tx_env: Option<TxEnv>,
+    /// Runtimes of finished call frames, reused by the next frames.
+    runtime_pool: RuntimePool,
+    /// Policy restricting call and create targets.
+    target_policy: Option<TargetPolicy>,
+    /// Hook rewriting or denying contract creations.
+    create_interceptor: Option<Box<dyn CreateInterceptor + 'config>>,
+    /// Hooks updating the metrics of the embedder.
+    metrics: Option<Box<dyn Metrics + 'config>>,
+    /// Handler of the chain-specific opcodes.
+    custom_opcodes: Option<Box<dyn CustomOpcodeHandler + 'config>>,
+    /// Handlers of the chain-specific transaction types.
+    custom_transaction_types: BTreeMap<u8, Box<dyn CustomTransactionType + 'config>>,
+    /// Intrinsic gas of the custom transaction being executed.
+    custom_intrinsic_gas: Option<u64>,
+    /// Gas used and remaining of the last exited call frame, reported by
+    /// the next `Exit` event.
+    #[cfg(feature = "tracing")]
+    exited_frame_gas: Option<(u64, u64)>,
+    /// Token aborting the execution.
+    #[cfg(feature = "std")]
+    cancellation_token: Option<CancellationToken>,
+    /// Resource usage statistics.
+    #[cfg(feature = "execution-stats")]
+    stats: StatsTracker,
+    /// Handling of the sender nonce by the transact functions.
+    nonce_policy: NoncePolicy,
+    /// Kind of the last transact or system call.
+    call_context_kind: CallContextKind,
+    /// Code hashes of the contracts deployed by the transaction and of its
+    /// `CREATE2` init codes.
+    code_hashes: CodeHashes,
+    /// Frames emitting the logs of the current transaction.
+    log_emitters: LogEmitters,
+    /// Positions of the logs kept by the last transaction.
+    log_positions: Vec<LogPosition>,
+    /// Block index of the first log of the next transaction.
+    block_log_index: Option<usize>,
+    /// Whether the first operation changing the state is recorded.
+    simulate_static: bool,
+    /// First operation changing the state, if recorded.
+    static_violation: Option<StaticViolation>,
+    /// Gas paid for the code deposits of the last transaction.
+    code_deposit_gas: u64,
+    /// Whether code with statically invalid jumps can't be deployed.
+    reject_invalid_jumps: bool,
+    /// Whether transactions are executed without fees.
+    free_gas: bool,
+    /// Interpreter steps of the last transaction.
+    steps: u64,
+    /// Gas recorded by the executions before the last transaction.
+    transaction_start_gas: GasSnapshot,
+    /// Address of the contract created by the last `CREATE` transaction,
+    /// possibly redirected by the create interceptor.
+    created_address: Option<H160>,
+    /// Code hash of the contract created by the last `CREATE` transaction.
+    created_code_hash: Option<H256>,
+    /// Event listeners of the transactions of this executor.
+    #[cfg(feature = "tracing")]
+    event_listeners: crate::tracing::EventListeners,
+    /// Invariant violated by the last transaction.
+    #[cfg(feature = "strict-invariants")]
+    invariant_violation: Option<InvariantViolation>,
 }
 
 impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
@@ -439,7 +751,462 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             config,
             state,
             precompile_set,
+            log_subscriber: None,
+            log_frames: Vec::new(),
+            tx_env: None,
+            runtime_pool: RuntimePool::new(),
+            target_policy: None,
+            create_interceptor: None,
+            metrics: None,
+            custom_opcodes: None,
+            custom_transaction_types: BTreeMap::new(),
+            custom_intrinsic_gas: None,
+            #[cfg(feature = "tracing")]
+            exited_frame_gas: None,
+            #[cfg(feature = "std")]
+            cancellation_token: None,
+            #[cfg(feature = "execution-stats")]
+            stats: StatsTracker::new(),
+            nonce_policy: NoncePolicy::CONSENSUS,
+            call_context_kind: CallContextKind::Transaction,
+            code_hashes: CodeHashes::new(),
+            log_emitters: LogEmitters::default(),
+            log_positions: Vec::new(),
+            block_log_index: None,
+            simulate_static: false,
+            static_violation: None,
+            code_deposit_gas: 0,
+            reject_invalid_jumps: false,
+            free_gas: false,
+            steps: 0,
+            transaction_start_gas: GasSnapshot {
+                gas_limit: 0,
+                used: 0,
+                memory_gas: 0,
+                refunded: 0,
+            },
+            created_address: None,
+            created_code_hash: None,
+            #[cfg(feature = "tracing")]
+            event_listeners: crate::tracing::EventListeners::new(),
+            #[cfg(feature = "strict-invariants")]
+            invariant_violation: None,
+        }
+    }
+
+    /// Set the transaction environment: gas price, origin and blob hashes.
+    ///
+    /// It overrides the transaction data of the state, so the same backend,
+    /// holding only block data, can serve many transactions.
+    pub fn set_tx_env(&mut self, tx_env: TxEnv) {
+        self.tx_env = Some(tx_env);
+    }
+
+    /// Transaction environment set by [`Self::set_tx_env`].
+    pub const fn tx_env(&self) -> Option<&TxEnv> {
+        self.tx_env.as_ref()
+    }
+
+    /// Pool of runtimes reused across call frames and transactions.
+    pub const fn runtime_pool(&self) -> &RuntimePool {
+        &self.runtime_pool
+    }
+
+    /// Mutable pool of runtimes, e.g. to release pooled allocations.
+    pub const fn runtime_pool_mut(&mut self) -> &mut RuntimePool {
+        &mut self.runtime_pool
+    }
+
+    /// Restrict the targets of message calls and contract creations, or
+    /// lift the restrictions with `None`.
+    pub fn set_target_policy(&mut self, policy: Option<TargetPolicy>) {
+        self.target_policy = policy;
+    }
+
+    /// Policy set by [`Self::set_target_policy`].
+    pub const fn target_policy(&self) -> Option<&TargetPolicy> {
+        self.target_policy.as_ref()
+    }
+
+    /// Mutable policy, e.g. to block more targets between transactions.
+    pub const fn target_policy_mut(&mut self) -> Option<&mut TargetPolicy> {
+        self.target_policy.as_mut()
+    }
+
+    /// Intercept contract creations with `interceptor`, or remove the
+    /// interceptor with `None`.
+    pub fn set_create_interceptor(
+        &mut self,
+        interceptor: Option<Box<dyn CreateInterceptor + 'config>>,
+    ) {
+        self.create_interceptor = interceptor;
+    }
+
+    /// Whether contract creations are intercepted.
+    pub const fn has_create_interceptor(&self) -> bool {
+        self.create_interceptor.is_some()
+    }
+
+    /// Report the executions to `metrics`, or remove the hooks with `None`.
+    pub fn set_metrics(&mut self, metrics: Option<Box<dyn Metrics + 'config>>) {
+        self.metrics = metrics;
+    }
+
+    /// Whether the executions are reported to metrics hooks.
+    pub const fn has_metrics(&self) -> bool {
+        self.metrics.is_some()
+    }
+
+    /// Execute the chain-specific opcodes with `handler`, or make them
+    /// invalid again with `None`.
+    pub fn set_custom_opcode_handler(
+        &mut self,
+        handler: Option<Box<dyn CustomOpcodeHandler + 'config>>,
+    ) {
+        self.custom_opcodes = handler;
+    }
+
+    /// Whether chain-specific opcodes are executed.
+    pub const fn has_custom_opcode_handler(&self) -> bool {
+        self.custom_opcodes.is_some()
+    }
+
+    /// Execute the transactions of the chain-specific type `tx_type` with
+    /// `handler`, or reject them again with `None`.
+    ///
+    /// # Errors
+    /// Return `InvalidTransaction::TypeNotSupported` if `tx_type` is the
+    /// first byte of a standard transaction, i.e. a standard EIP-2718 type
+    /// or the `0xc0..=0xff` RLP list prefix of a legacy transaction.
+    pub fn set_custom_transaction_type(
+        &mut self,
+        tx_type: u8,
+        handler: Option<Box<dyn CustomTransactionType + 'config>>,
+    ) -> Result<(), InvalidTransaction> {
+        if TransactionType::from_first_byte(tx_type).is_some() {
+            return Err(InvalidTransaction::TypeNotSupported);
+        }
+        match handler {
+            Some(handler) => {
+                self.custom_transaction_types.insert(tx_type, handler);
+            }
+            None => {
+                self.custom_transaction_types.remove(&tx_type);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether transactions of `tx_type` are executed: the standard types
+    /// enabled by the config, and the registered custom types.
+    pub fn supports_transaction_type(&self, tx_type: TransactionType) -> bool {
+        match tx_type {
+            TransactionType::Custom(tx_type) => {
+                self.custom_transaction_types.contains_key(&tx_type)
+            }
+            _ => tx_type.is_supported(self.config),
+        }
+    }
+
+    /// Check a signed transaction without reading the state, as
+    /// [`transaction::prevalidate_stateless`], which rejects the custom
+    /// types. A custom type must be registered, and the gas limit of its
+    /// transactions cover the intrinsic gas computed by its handler.
+    ///
+    /// # Errors
+    /// Return the `InvalidTransaction` of the first failed check.
+    pub fn prevalidate(&self, tx: &SignedTransaction) -> Result<(), InvalidTransaction> {
+        let TransactionType::Custom(tx_type) = tx.tx_type else {
+            return transaction::prevalidate_stateless(tx, self.config);
+        };
+        let handler = self
+            .custom_transaction_types
+            .get(&tx_type)
+            .ok_or(InvalidTransaction::TypeNotSupported)?;
+        let transaction = &tx.transaction;
+        transaction::validate_gas_limit(transaction.gas_limit, self.config)?;
+        if transaction.to.is_none() {
+            transaction::validate_init_code_size(&transaction.data, self.config)?;
+        }
+        // The EIP-7623 gas floor does not apply to custom types
+        transaction::validate_gas_limit_covers(
+            transaction.gas_limit,
+            handler.intrinsic_gas(transaction, self.config),
+            0,
+        )
+    }
+
+    /// Handler of `opcode`, if it is a chain-specific opcode.
+    fn custom_opcode_handler(
+        &self,
+        opcode: Opcode,
+    ) -> Option<&(dyn CustomOpcodeHandler + 'config)> {
+        if CUSTOM_OPCODES.contains(&opcode.as_u8()) {
+            self.custom_opcodes.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Send the events of the transactions of this executor to `listeners`
+    /// instead of the listeners installed on the thread with
+    /// [`crate::tracing::using`], or stop with [`EventListeners::new`].
+    ///
+    /// [`EventListeners::new`]: crate::tracing::EventListeners::new
+    #[cfg(feature = "tracing")]
+    pub fn set_event_listeners(&mut self, listeners: crate::tracing::EventListeners) {
+        self.event_listeners = listeners;
+    }
+
+    /// Abort the executions with `ExitFatal::Cancelled` once `token` is
+    /// cancelled, or remove the token with `None`.
+    #[cfg(feature = "std")]
+    pub fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+        self.cancellation_token = token;
+    }
+
+    /// Resource usage statistics of the executions since the executor was
+    /// created or the statistics were taken.
+    #[cfg(feature = "execution-stats")]
+    pub const fn execution_stats(&self) -> &ExecutionStats {
+        self.stats.stats()
+    }
+
+    /// Take the resource usage statistics, resetting them, e.g. after each
+    /// transaction.
+    #[cfg(feature = "execution-stats")]
+    pub fn take_execution_stats(&mut self) -> ExecutionStats {
+        self.stats.take()
+    }
+
+    /// Set the handling of the sender nonce by the transact functions.
+    pub const fn set_nonce_policy(&mut self, policy: NoncePolicy) {
+        self.nonce_policy = policy;
+    }
+
+    /// Policy set by [`Self::set_nonce_policy`].
+    pub const fn nonce_policy(&self) -> NoncePolicy {
+        self.nonce_policy
+    }
+
+    /// Validate the transaction `nonce` against the `caller` nonce, if the
+    /// nonce policy checks nonces. To be called before the transact
+    /// functions, which do not take the transaction nonce.
+    ///
+    /// # Errors
+    /// Return `InvalidTransaction::NonceTooLow` or
+    /// `InvalidTransaction::NonceTooHigh` if the nonces differ.
+    pub fn validate_nonce(&self, caller: H160, nonce: u64) -> Result<(), InvalidTransaction> {
+        self.nonce_policy.validate(&self.state, caller, nonce)
+    }
+
+    /// Kind of the last transact or system call, which determines whether
+    /// its gas is paid, see [`Self::used_gas`].
+    pub const fn call_context_kind(&self) -> CallContextKind {
+        self.call_context_kind
+    }
+
+    /// Gas paid for the code deposits of the contracts deployed by the last
+    /// transaction, including the nested creations, already part of
+    /// [`Self::used_gas`]. Lets deploy tooling break the gas down into
+    /// execution and code storage.
+    pub const fn code_deposit_gas(&self) -> u64 {
+        self.code_deposit_gas
+    }
+
+    /// Code hash of a contract deployed by the current transaction, computed
+    /// once on deployment and reused by `EXTCODEHASH`. `None` if `address`
+    /// was not deployed by the transaction, or if its deployment was
+    /// reverted. The hashes are dropped at the end of each transaction.
+    ///
+    /// Code set directly on the state, bypassing the executor, is not
+    /// reflected.
+    #[must_use]
+    pub fn deployed_code_hash(&self, address: H160) -> Option<H256> {
+        self.code_hashes.deployed(address)
+    }
+
+    /// Set the index in the block of the first log of the next transaction,
+    /// i.e. the number of logs of the previous transactions of the block.
+    /// Every transaction advances it by the number of logs it keeps, so it
+    /// is only set at the start of a block.
+    pub const fn set_block_log_index(&mut self, index: Option<usize>) {
+        self.block_log_index = index;
+    }
+
+    /// Positions of the logs kept by the last transaction, i.e. not
+    /// discarded with a reverted or failed frame, in emission order.
+    #[must_use]
+    pub fn log_positions(&self) -> &[LogPosition] {
+        &self.log_positions
+    }
+
+    /// Start a transaction of `gas_limit`: reset the counters of the last
+    /// transaction, and check the gas limit against the transaction gas cap.
+    fn begin_transaction(&mut self, gas_limit: u64) -> Result<(), ExitError> {
+        self.begin_execution(CallContextKind::Transaction);
+        transaction::validate_gas_limit(gas_limit, self.config)
+            .map_err(|_| ExitError::TransactionGasLimitExceeded)
+    }
+
+    /// Start a transaction or system call of `kind`, resetting the counters
+    /// of the last one.
+    fn begin_execution(&mut self, kind: CallContextKind) {
+        self.call_context_kind = kind;
+        self.code_deposit_gas = 0;
+        self.steps = 0;
+        self.created_address = None;
+        self.created_code_hash = None;
+        self.log_emitters = LogEmitters::default();
+        self.log_positions.clear();
+        self.transaction_start_gas = self.state.metadata().gasometer().gas_snapshot();
+    }
+
+    /// End a transaction with `result`, whether it was executed or rejected
+    /// before execution, and report it to the metrics hooks.
+    fn end_transaction(&mut self, result: (ExitReason, Vec<u8>)) -> (ExitReason, Vec<u8>) {
+        let result = self.end_execution(result);
+        let used_gas = self.transaction_used_gas();
+        if let Some(metrics) = self.metrics.as_mut() {
+            metrics.transaction_executed(&result.0, used_gas);
+        }
+        result
+    }
+
+    /// End a transaction or system call with `result`: discard its transient
+    /// storage, check the invariants, and record its deployments and logs.
+    fn end_execution(&mut self, result: (ExitReason, Vec<u8>)) -> (ExitReason, Vec<u8>) {
+        self.state.clear_transient_storage();
+        // The state of an aborted execution is discarded, as are its frames
+        #[cfg(feature = "strict-invariants")]
+        if !matches!(result.0, ExitReason::Fatal(_)) {
+            self.check_invariants();
+        }
+        self.created_code_hash = self
+            .created_address
+            .and_then(|address| self.code_hashes.deployed(address));
+        self.code_hashes.clear();
+        self.record_log_positions(&result.0);
+        result
+    }
+
+    /// Record the positions of the logs kept by the transaction, ending
+    /// with `reason`, and advance the block index of the logs past them.
+    fn record_log_positions(&mut self, reason: &ExitReason) {
+        let frames = core::mem::take(&mut self.log_emitters.frames);
+        // The logs of an aborted execution are discarded with its state
+        if matches!(reason, ExitReason::Fatal(_)) {
+            return;
+        }
+        let first_block_index = self.block_log_index;
+        self.log_positions = frames
+            .into_iter()
+            .enumerate()
+            .map(|(tx_index, (depth, frame_id))| LogPosition {
+                tx_index,
+                block_index: first_block_index.map(|first| first.saturating_add(tx_index)),
+                depth,
+                frame_id,
+            })
+            .collect();
+        self.block_log_index =
+            first_block_index.map(|first| first.saturating_add(self.log_positions.len()));
+    }
+
+    /// Check the invariants of the executor and its state after a
+    /// transaction.
+    ///
+    /// # Panics
+    /// Panic on a violation in debug builds.
+    #[cfg(feature = "strict-invariants")]
+    fn check_invariants(&mut self) {
+        let frames =
+            self.log_frames.len() + self.code_hashes.frames() + self.log_emitters.entered.len();
+        let refunded_gas = self.state.metadata().gasometer().refunded_gas();
+        let checked = if let Some(depth) = self.state.metadata().depth() {
+            Err(InvariantViolation::DirtySubstate { depth })
+        } else if frames > 0 {
+            Err(InvariantViolation::UnbalancedFrames { frames })
+        } else if refunded_gas < 0 {
+            Err(InvariantViolation::NegativeRefund(refunded_gas))
+        } else {
+            self.state.check_invariants()
+        };
+        debug_assert!(checked.is_ok(), "state invariant violated: {checked:?}");
+        self.invariant_violation = checked.err();
+    }
+
+    /// Invariant violated by the last transaction, `None` if the state is
+    /// consistent. Debug builds panic on the violation instead.
+    #[cfg(feature = "strict-invariants")]
+    pub const fn invariant_violation(&self) -> Option<InvariantViolation> {
+        self.invariant_violation
+    }
+
+    /// Fail the deployments of code with jumps to statically known invalid
+    /// targets with `InvalidJump`, see [`analyze_jump_targets`]. This is a
+    /// deployment policy for private chains, it breaks the consensus rules.
+    pub const fn set_reject_invalid_jumps(&mut self, reject: bool) {
+        self.reject_invalid_jumps = reject;
+    }
+
+    /// Whether code with statically invalid jumps can't be deployed.
+    pub const fn reject_invalid_jumps(&self) -> bool {
+        self.reject_invalid_jumps
+    }
+
+    /// Execute the transactions of [`Self::transact`] without fees, e.g. on
+    /// a dev chain: the gas payer is neither charged nor refunded, the
+    /// coinbase gets no tip, and `GASPRICE` returns zero. The gas is still
+    /// metered, so [`Self::used_gas`] and the gas limit apply as usual, and
+    /// the accounts need no funds except for the transferred value.
+    pub const fn set_free_gas(&mut self, free_gas: bool) {
+        self.free_gas = free_gas;
+    }
+
+    /// Whether transactions are executed without fees, see
+    /// [`Self::set_free_gas`].
+    pub const fn free_gas(&self) -> bool {
+        self.free_gas
+    }
+
+    /// Opcodes executed by the last transaction or system call, over all its
+    /// call frames, as bounded by `Config::step_limit`.
+    pub const fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Revert data if `target` is denied by the target policy.
+    fn denied_target_output(&self, target: H160) -> Option<Vec<u8>> {
+        self.target_policy
+            .as_ref()
+            .filter(|policy| !policy.is_allowed(target))
+            .map(TargetPolicy::revert_output)
+    }
+
+    /// Stream logs to `subscriber` as they are produced.
+    ///
+    /// Logs of reverted or failed call frames are retracted with
+    /// [`LogEvent::Discarded`], so the logs streamed and not discarded are
+    /// exactly the logs returned by the state at the end of the execution.
+    /// The subscriber should be set before the execution starts.
+    pub fn set_log_subscriber(&mut self, subscriber: impl FnMut(LogEvent<'_>) + 'config) {
+        self.log_subscriber = Some(Box::new(subscriber));
+        self.log_frames.clear();
+    }
+
+    /// Pass an emitted log to the log subscriber, and record its position.
+    fn notify_log(&mut self, log: &LogRef<'_>) {
+        if let Some(subscriber) = self.log_subscriber.as_mut() {
+            if let Some(count) = self.log_frames.last_mut() {
+                *count += 1;
+            }
+            subscriber(LogEvent::Emitted(log));
         }
+        let positions = &mut self.log_emitters;
+        let frame_id = positions.entered.last().map_or(0, |&(id, _)| id);
+        let depth = positions.entered.len().saturating_sub(1);
+        positions.frames.push((depth, frame_id));
     }
 
     pub const fn state(&self) -> &S {
@@ -457,7 +1224,20 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 
     /// Create a substate executor from the current executor.
     pub fn enter_substate(&mut self, gas_limit: u64, is_static: bool) {
+        if self.log_subscriber.is_some() {
+            self.log_frames.push(0);
+        }
+        self.code_hashes.enter();
+        let positions = &mut self.log_emitters;
+        positions
+            .entered
+            .push((positions.next_frame_id, positions.frames.len()));
+        positions.next_frame_id += 1;
         self.state.enter(gas_limit, is_static);
+        #[cfg(feature = "execution-stats")]
+        if let Some(depth) = self.state.metadata().depth {
+            self.stats.enter_frame(depth.saturating_add(1));
+        }
     }
 
     /// Exit a substate.
@@ -468,6 +1248,35 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
     /// # Errors
     /// Return `ExitError`
     pub fn exit_substate(&mut self, kind: &StackExitKind) -> Result<(), ExitError> {
+        if let Some(subscriber) = self.log_subscriber.as_mut() {
+            if let Some(count) = self.log_frames.pop() {
+                match kind {
+                    StackExitKind::Succeeded => {
+                        if let Some(parent) = self.log_frames.last_mut() {
+                            *parent += count;
+                        }
+                    }
+                    StackExitKind::Reverted | StackExitKind::Failed => {
+                        if count > 0 {
+                            subscriber(LogEvent::Discarded(count));
+                        }
+                    }
+                }
+            }
+        }
+        // The code deployed in the frame is discarded with it
+        self.code_hashes
+            .exit(matches!(kind, StackExitKind::Succeeded));
+        if let Some((_, len)) = self.log_emitters.entered.pop() {
+            if !matches!(kind, StackExitKind::Succeeded) {
+                self.log_emitters.frames.truncate(len);
+            }
+        }
+        #[cfg(feature = "tracing")]
+        {
+            let gasometer = &self.state.metadata().gasometer;
+            self.exited_frame_gas = Some((gasometer.total_used_gas(), gasometer.gas()));
+        }
         match kind {
             StackExitKind::Succeeded => self.state.exit_commit(),
             StackExitKind::Reverted => self.state.exit_revert(),
@@ -475,8 +1284,16 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         }
     }
 
+    /// Gas used and remaining of the last exited call frame, or zeros if
+    /// the frame failed before gas was given to it.
+    #[cfg(feature = "tracing")]
+    fn take_exited_frame_gas(&mut self) -> (u64, u64) {
+        self.exited_frame_gas.take().unwrap_or_default()
+    }
+
     /// Execute the runtime until it returns.
     pub fn execute(&mut self, runtime: &mut Runtime) -> ExitReason {
+        with_event_listeners!(self, self.execute(runtime));
         let mut call_stack: SmallVec<[TaggedRuntime; DEFAULT_CALL_STACK_CAPACITY]> =
             smallvec!(TaggedRuntime {
                 kind: RuntimeKind::Execute,
@@ -500,6 +1317,11 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         let mut interrupt_runtime = None;
         loop {
             if let Some(rt) = interrupt_runtime.take() {
+                #[cfg(feature = "execution-stats")]
+                if let Some(parent) = call_stack.last() {
+                    self.stats
+                        .suspend_frame(parent.inner.machine().memory().effective_len());
+                }
                 call_stack.push(rt);
             }
             let Some(runtime) = call_stack.last_mut() else {
@@ -523,6 +1345,8 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
                     }
                 }
             };
+            #[cfg(feature = "execution-stats")]
+            let memory = runtime.inner.machine().memory().effective_len();
             let runtime_kind = runtime.kind;
             let (reason, maybe_address, return_data) = match runtime_kind {
                 RuntimeKind::Create(created_address) => {
@@ -543,13 +1367,24 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
                 }
                 RuntimeKind::Execute => (reason, None, runtime.inner.machine().return_value()),
             };
+            #[cfg(feature = "execution-stats")]
+            self.stats.exit_frame(memory, return_data.len());
             // We're done with that runtime now, so can pop it off the call stack
-            call_stack.pop();
+            // and return its allocations to the pool
+            if let Some(TaggedRuntime {
+                inner: MaybeBorrowed::Owned(runtime),
+                ..
+            }) = call_stack.pop()
+            {
+                self.runtime_pool.recycle(runtime);
+            }
             // Now pass the results from that runtime on to the next one in the stack
             let Some(runtime) = call_stack.last_mut() else {
                 return (reason, None, return_data);
             };
-            emit_exit!(&reason, &return_data);
+            #[cfg(feature = "execution-stats")]
+            self.stats.resume_frame();
+            emit_exit!(self, &reason, &return_data);
             let inner_runtime = &mut runtime.inner;
             let maybe_error = match runtime_kind {
                 RuntimeKind::Create(_) => {
@@ -577,17 +1412,29 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         access_list: &[(H160, Vec<H256>)],
     ) -> Result<(), ExitError> {
         let transaction_cost = gasometer::create_transaction_cost(init_code, access_list);
+        self.record_transaction_cost(transaction_cost)
+    }
+
+    /// Record the intrinsic gas of the transaction: the gas of the custom
+    /// transaction type being executed, if any, or `cost`.
+    fn record_transaction_cost(&mut self, cost: TransactionCost) -> Result<(), ExitError> {
+        let custom_intrinsic_gas = self.custom_intrinsic_gas.take();
         let gasometer = &mut self.state.metadata_mut().gasometer;
-        gasometer.record_transaction(transaction_cost)
+        match custom_intrinsic_gas {
+            Some(gas) => gasometer.record_cost(gas),
+            None => gasometer.record_transaction(cost),
+        }
     }
 
     fn maybe_record_init_code_cost(&mut self, init_code: &[u8]) -> Result<(), ExitError> {
+        // EIP-3860
         if let Some(limit) = self.config.max_initcode_size {
-            // EIP-3860
             if init_code.len() > limit {
                 self.state.metadata_mut().gasometer.fail();
                 return Err(ExitError::CreateContractLimit);
             }
+        }
+        if self.config.has_init_code_cost {
             return self
                 .state
                 .metadata_mut()
@@ -606,11 +1453,20 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         gas_limit: u64,
         access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
     ) -> (ExitReason, Vec<u8>) {
-        if self.nonce(caller) >= U64_MAX {
-            return (ExitError::MaxNonce.into(), Vec::new());
+        with_event_listeners!(
+            self,
+            self.transact_create(caller, value, init_code, gas_limit, access_list)
+        );
+        if let Err(e) = self.begin_transaction(gas_limit) {
+            return self.end_transaction((e.into(), Vec::new()));
+        }
+
+        if let Err(e) = self.validate_max_nonce(caller) {
+            return self.end_transaction((e.into(), Vec::new()));
         }
 
-        let address = self.create_address(CreateScheme::Legacy { caller });
+        let scheme = CreateScheme::Legacy { caller };
+        let (address, denied) = self.resolve_create_address(caller, scheme);
 
         event!(TransactCreate {
             caller,
@@ -623,36 +1479,70 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         if let Some(limit) = self.config.max_initcode_size {
             if init_code.len() > limit {
                 self.state.metadata_mut().gasometer.fail();
-                return emit_exit!(ExitError::CreateContractLimit.into(), Vec::new());
+                return self.end_transaction(emit_exit!(
+                    self,
+                    ExitError::CreateContractLimit.into(),
+                    Vec::new()
+                ));
             }
         }
 
         if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
-            return emit_exit!(e.into(), Vec::new());
+            return self.end_transaction(emit_exit!(self, e.into(), Vec::new()));
         }
 
         self.warm_addresses_and_storage(caller, address, access_list);
 
-        match self.create_inner(
+        let result = match self.create_inner(
             caller,
-            CreateScheme::Legacy { caller },
+            scheme,
+            (address, denied),
             value,
             init_code,
             Some(gas_limit),
             false,
         ) {
-            Capture::Exit((s, v)) => emit_exit!(s, v),
+            Capture::Exit((s, v)) => emit_exit!(self, s, v),
             Capture::Trap(rt) => {
                 let mut cs: SmallVec<[TaggedRuntime<'_>; DEFAULT_CALL_STACK_CAPACITY]> =
                     smallvec!(rt.0);
                 let (s, _, v) = self.execute_with_call_stack(&mut cs);
-                emit_exit!(s, v)
+                emit_exit!(self, s, v)
             }
+        };
+        self.end_transaction(result)
+    }
+
+    /// Execute a `CREATE` transaction like [`Self::transact_create`], and
+    /// describe the deployed contract, so deploy tooling can check the
+    /// deployment without querying the state.
+    pub fn transact_create_detailed(
+        &mut self,
+        caller: H160,
+        value: U256,
+        init_code: Vec<u8>,
+        gas_limit: u64,
+        access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
+    ) -> CreateResult {
+        let (reason, return_data) =
+            self.transact_create(caller, value, init_code, gas_limit, access_list);
+        let deployed = self
+            .created_address
+            .filter(|_| reason.is_succeed())
+            .zip(self.created_code_hash);
+        CreateResult {
+            reason,
+            address: deployed.map(|(address, _)| address),
+            code_hash: deployed.map(|(_, code_hash)| code_hash),
+            code_len: deployed.map_or(0, |(address, _)| self.state.code(address).len()),
+            used_gas: self.transaction_used_gas(),
+            code_deposit_gas: self.code_deposit_gas,
+            return_data,
         }
     }
 
-    /// Same as `CREATE` but uses a specified address for created smart contract.
-    #[cfg(feature = "create-fixed")]
+    /// Same as `CREATE` but uses a specified address for created smart contract,
+    /// e.g. for deterministic deployments in migration tooling.
     pub fn transact_create_fixed(
         &mut self,
         caller: H160,
@@ -662,7 +1552,16 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         gas_limit: u64,
         access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
     ) -> (ExitReason, Vec<u8>) {
-        let address = self.create_address(CreateScheme::Fixed(address));
+        with_event_listeners!(
+            self,
+            self.transact_create_fixed(caller, address, value, init_code, gas_limit, access_list)
+        );
+        if let Err(e) = self.begin_transaction(gas_limit) {
+            return self.end_transaction((e.into(), Vec::new()));
+        }
+
+        let scheme = CreateScheme::Fixed(address);
+        let (address, denied) = self.resolve_create_address(caller, scheme);
 
         event!(TransactCreate {
             caller,
@@ -672,28 +1571,41 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             address
         });
 
+        if let Some(limit) = self.config.max_initcode_size {
+            if init_code.len() > limit {
+                self.state.metadata_mut().gasometer.fail();
+                return self.end_transaction(emit_exit!(
+                    self,
+                    ExitError::CreateContractLimit.into(),
+                    Vec::new()
+                ));
+            }
+        }
+
         if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
-            return emit_exit!(e.into(), Vec::new());
+            return self.end_transaction(emit_exit!(self, e.into(), Vec::new()));
         }
 
         self.warm_addresses_and_storage(caller, address, access_list);
 
-        match self.create_inner(
+        let result = match self.create_inner(
             caller,
-            CreateScheme::Fixed(address),
+            scheme,
+            (address, denied),
             value,
             init_code,
             Some(gas_limit),
             false,
         ) {
-            Capture::Exit((s, v)) => emit_exit!(s, v),
+            Capture::Exit((s, v)) => emit_exit!(self, s, v),
             Capture::Trap(rt) => {
                 let mut cs: SmallVec<[TaggedRuntime<'_>; DEFAULT_CALL_STACK_CAPACITY]> =
                     smallvec!(rt.0);
                 let (s, _, v) = self.execute_with_call_stack(&mut cs);
-                emit_exit!(s, v)
+                emit_exit!(self, s, v)
             }
-        }
+        };
+        self.end_transaction(result)
     }
 
     /// Execute a `CREATE2` transaction.
@@ -707,20 +1619,32 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         gas_limit: u64,
         access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
     ) -> (ExitReason, Vec<u8>) {
+        with_event_listeners!(
+            self,
+            self.transact_create2(caller, value, init_code, salt, gas_limit, access_list)
+        );
+        if let Err(e) = self.begin_transaction(gas_limit) {
+            return self.end_transaction((e.into(), Vec::new()));
+        }
+
         if let Some(limit) = self.config.max_initcode_size {
             if init_code.len() > limit {
                 self.state.metadata_mut().gasometer.fail();
-                return emit_exit!(ExitError::CreateContractLimit.into(), Vec::new());
+                return self.end_transaction(emit_exit!(
+                    self,
+                    ExitError::CreateContractLimit.into(),
+                    Vec::new()
+                ));
             }
         }
 
-        let code_hash =
-            H256::from_slice(<[u8; 32]>::from(Keccak256::digest(&init_code)).as_slice());
-        let address = self.create_address(CreateScheme::Create2 {
+        let code_hash = self.init_code_hash(&init_code);
+        let scheme = CreateScheme::Create2 {
             caller,
             code_hash,
             salt,
-        });
+        };
+        let (address, denied) = self.resolve_create_address(caller, scheme);
         event!(TransactCreate2 {
             caller,
             value,
@@ -731,31 +1655,29 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         });
 
         if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
-            return emit_exit!(e.into(), Vec::new());
+            return self.end_transaction(emit_exit!(self, e.into(), Vec::new()));
         }
 
         self.warm_addresses_and_storage(caller, address, access_list);
 
-        match self.create_inner(
+        let result = match self.create_inner(
             caller,
-            CreateScheme::Create2 {
-                caller,
-                code_hash,
-                salt,
-            },
+            scheme,
+            (address, denied),
             value,
             init_code,
             Some(gas_limit),
             false,
         ) {
-            Capture::Exit((s, v)) => emit_exit!(s, v),
+            Capture::Exit((s, v)) => emit_exit!(self, s, v),
             Capture::Trap(rt) => {
                 let mut cs: SmallVec<[TaggedRuntime<'_>; DEFAULT_CALL_STACK_CAPACITY]> =
                     smallvec!(rt.0);
                 let (s, _, v) = self.execute_with_call_stack(&mut cs);
-                emit_exit!(s, v)
+                emit_exit!(self, s, v)
             }
-        }
+        };
+        self.end_transaction(result)
     }
 
     /// Execute a `CALL` transaction with a given parameters
@@ -774,6 +1696,22 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         access_list: Vec<(H160, Vec<H256>)>,
         authorization_list: Vec<Authorization>,
     ) -> (ExitReason, Vec<u8>) {
+        with_event_listeners!(
+            self,
+            self.transact_call(
+                caller,
+                address,
+                value,
+                data,
+                gas_limit,
+                access_list,
+                authorization_list,
+            )
+        );
+        if let Err(e) = self.begin_transaction(gas_limit) {
+            return self.end_transaction((e.into(), Vec::new()));
+        }
+
         event!(TransactCall {
             caller,
             address,
@@ -782,36 +1720,38 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             gas_limit,
         });
 
-        if self.nonce(caller) >= U64_MAX {
-            return (ExitError::MaxNonce.into(), Vec::new());
+        if let Err(e) = self.validate_max_nonce(caller) {
+            return self.end_transaction((e.into(), Vec::new()));
         }
 
         let transaction_cost =
             gasometer::call_transaction_cost(&data, &access_list, authorization_list.len());
-        let gasometer = &mut self.state.metadata_mut().gasometer;
-        match gasometer.record_transaction(transaction_cost) {
-            Ok(()) => (),
-            Err(e) => return emit_exit!(e.into(), Vec::new()),
+        if let Err(e) = self.record_transaction_cost(transaction_cost) {
+            return self.end_transaction(emit_exit!(self, e.into(), Vec::new()));
         }
 
-        if let Err(e) = self.state.inc_nonce(caller) {
-            return (e.into(), Vec::new());
+        if self.increments_nonce() {
+            if let Err(e) = self.state.inc_nonce(caller) {
+                return self.end_transaction((e.into(), Vec::new()));
+            }
         }
 
         self.warm_addresses_and_storage(caller, address, access_list);
         // EIP-7702. authorized accounts
         // NOTE: it must be after `inc_nonce`
         if let Err(e) = self.authorized_accounts(authorization_list) {
-            return (e.into(), Vec::new());
+            return self.end_transaction((e.into(), Vec::new()));
         }
 
         let context = Context {
             caller,
             address,
             apparent_value: value,
+            code_address: address,
+            scheme: Some(CallScheme::Call),
         };
 
-        match self.call_inner(
+        let result = match self.call_inner(
             address,
             Some(Transfer {
                 source: caller,
@@ -825,14 +1765,449 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             false,
             context,
         ) {
-            Capture::Exit((s, v)) => emit_exit!(s, v),
+            Capture::Exit((s, v)) => emit_exit!(self, s, v),
             Capture::Trap(rt) => {
                 let mut cs: SmallVec<[TaggedRuntime<'_>; DEFAULT_CALL_STACK_CAPACITY]> =
                     smallvec!(rt.0);
                 let (s, _, v) = self.execute_with_call_stack(&mut cs);
-                emit_exit!(s, v)
+                emit_exit!(self, s, v)
             }
+        };
+        self.end_transaction(result)
+    }
+
+    /// Execute a `CALL` transaction like [`Self::transact_call`], without
+    /// changing the state of the executor, and return the changes it would
+    /// apply.
+    ///
+    /// The call runs in a new executor on top of the current state, with the
+    /// same config, precompiles, transaction environment and nonce policy.
+    /// The other hooks of this executor, e.g. metrics or custom opcodes, are
+    /// not used. The gas, refunds and accessed addresses of the dry run are
+    /// discarded with it, so [`Self::used_gas`] is unchanged.
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn transact_call_dry_run(
+        &self,
+        caller: H160,
+        address: H160,
+        value: U256,
+        data: Vec<u8>,
+        gas_limit: u64,
+        access_list: Vec<(H160, Vec<H256>)>,
+        authorization_list: Vec<Authorization>,
+    ) -> DryRun {
+        self.dry_run(
+            caller,
+            address,
+            value,
+            data,
+            gas_limit,
+            access_list,
+            authorization_list,
+            false,
+        )
+    }
+
+    /// Simulate a static call of a method which may change the state, e.g.
+    /// to explain why an `eth_call` fails when made as a `STATICCALL`.
+    ///
+    /// The call runs as a non-static dry run, see
+    /// [`Self::transact_call_dry_run`], so the writes proceed on the
+    /// discarded state, and the first operation which would have failed the
+    /// static call is returned in [`DryRun::static_violation`]. Writes of
+    /// precompiles, e.g. their logs, are not recorded.
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn transact_call_static_simulation(
+        &self,
+        caller: H160,
+        address: H160,
+        value: U256,
+        data: Vec<u8>,
+        gas_limit: u64,
+        access_list: Vec<(H160, Vec<H256>)>,
+        authorization_list: Vec<Authorization>,
+    ) -> DryRun {
+        self.dry_run(
+            caller,
+            address,
+            value,
+            data,
+            gas_limit,
+            access_list,
+            authorization_list,
+            true,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn dry_run(
+        &self,
+        caller: H160,
+        address: H160,
+        value: U256,
+        data: Vec<u8>,
+        gas_limit: u64,
+        access_list: Vec<(H160, Vec<H256>)>,
+        authorization_list: Vec<Authorization>,
+        simulate_static: bool,
+    ) -> DryRun {
+        let metadata = StackSubstateMetadata::new(gas_limit, self.config);
+        let state = MemoryStackState::new(metadata, &self.state);
+        let mut executor =
+            StackExecutor::new_with_precompiles(state, self.config, self.precompile_set);
+        if let Some(tx_env) = &self.tx_env {
+            executor.set_tx_env(tx_env.clone());
+        }
+        executor.set_nonce_policy(self.nonce_policy);
+        executor.set_free_gas(self.free_gas);
+        executor.simulate_static = simulate_static;
+
+        let (reason, output) = executor.transact_call(
+            caller,
+            address,
+            value,
+            data,
+            gas_limit,
+            access_list,
+            authorization_list,
+        );
+        let used_gas = executor.used_gas();
+        let access_list = executor.accessed_list(caller, address);
+        let static_violation = executor.static_violation;
+        let (changes, logs) = executor.into_state().deconstruct();
+        DryRun {
+            reason,
+            output,
+            used_gas,
+            changes: changes
+                .into_iter()
+                .map(|apply| match apply {
+                    Apply::Modify {
+                        address,
+                        basic,
+                        code,
+                        storage,
+                        reset_storage,
+                    } => Apply::Modify {
+                        address,
+                        basic,
+                        code,
+                        storage: storage.into_iter().collect(),
+                        reset_storage,
+                    },
+                    Apply::Delete { address } => Apply::Delete { address },
+                })
+                .collect(),
+            logs: logs.into_vec(),
+            access_list,
+            static_violation,
+        }
+    }
+
+    /// Estimate the gas limit a `CALL` transaction needs, as `eth_estimateGas`
+    /// does: the lowest gas limit up to `gas_limit` with which the call
+    /// succeeds, found by binary search over dry runs, see
+    /// [`Self::transact_call_dry_run`]. The state of the executor is unchanged.
+    ///
+    /// With `generate_access_list`, the access list of the call is generated
+    /// by a first run, like `eth_createAccessList`, and the gas limit is also
+    /// estimated with it in place of `access_list`, as the transaction may be
+    /// sent with it. Both estimates are returned.
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn estimate_gas(
+        &self,
+        caller: H160,
+        address: H160,
+        value: U256,
+        data: &[u8],
+        gas_limit: u64,
+        access_list: Vec<(H160, Vec<H256>)>,
+        authorization_list: &[Authorization],
+        generate_access_list: bool,
+    ) -> GasEstimate {
+        let dry_run = |gas_limit, access_list: &[(H160, Vec<H256>)]| {
+            self.transact_call_dry_run(
+                caller,
+                address,
+                value,
+                data.to_vec(),
+                gas_limit,
+                access_list.to_vec(),
+                authorization_list.to_vec(),
+            )
+        };
+        let estimate = |access_list: &[(H160, Vec<H256>)]| {
+            let run = dry_run(gas_limit, access_list);
+            let gas = if run.reason.is_succeed() {
+                lowest_gas_limit(run.used_gas, gas_limit, |gas_limit| {
+                    dry_run(gas_limit, access_list).reason.is_succeed()
+                })
+            } else {
+                run.used_gas
+            };
+            (run, gas)
+        };
+
+        let (run, gas) = estimate(&access_list);
+        let with_access_list = (generate_access_list && run.reason.is_succeed())
+            .then(|| {
+                let (with_run, with_gas) = estimate(&run.access_list);
+                with_run
+                    .reason
+                    .is_succeed()
+                    .then(|| (run.access_list.clone(), with_gas))
+            })
+            .flatten();
+        GasEstimate {
+            reason: run.reason,
+            output: run.output,
+            gas,
+            with_access_list,
+        }
+    }
+
+    /// Validate and execute a transaction, charging its fees.
+    ///
+    /// The gas payer, i.e. the fee payer of a sponsored transaction or the
+    /// caller, is charged the maximum fee at the effective gas price of the
+    /// transaction environment before the execution, plus the EIP-4844 blob
+    /// gas of its blobs, and gets the unused gas refunded after it. The
+    /// priority fee goes to the block coinbase. Only the gas of the
+    /// transaction itself is charged, see [`Self::transaction_used_gas`].
+    /// No fees are charged with [`Self::set_free_gas`].
+    ///
+    /// # Errors
+    /// Return `InvalidTransaction` if the transaction is rejected before
+    /// execution: its gas limit exceeds the transaction gas cap, its nonce
+    /// is invalid under the nonce policy, or the gas payer can't afford the
+    /// maximum fee or the caller the value. The state is then unchanged.
+    /// Transactions of a custom type are rejected with `TypeNotSupported`
+    /// if the type is not registered, see [`CustomTransactionType`] for
+    /// their execution.
+    pub fn transact(
+        &mut self,
+        tx: Transaction,
+    ) -> Result<(ExitReason, Vec<u8>), InvalidTransaction> {
+        transaction::validate_gas_limit(tx.gas_limit, self.config)?;
+        self.validate_nonce(tx.caller, tx.nonce)?;
+
+        let Some(tx_type) = tx.custom_type else {
+            return self.transact_with_fees(tx);
+        };
+        let mut handler = self
+            .custom_transaction_types
+            .remove(&tx_type)
+            .ok_or(InvalidTransaction::TypeNotSupported)?;
+        let result = self.transact_custom(tx, handler.as_mut());
+        self.custom_transaction_types.insert(tx_type, handler);
+        result
+    }
+
+    /// Simulate a transaction from `sender` without charging its fees, e.g.
+    /// for `eth_call`. The sender replaces the caller of `tx`, and its nonce
+    /// and funds are only checked if set in the override, in place of the
+    /// nonce policy.
+    ///
+    /// # Errors
+    /// Return `InvalidTransaction` if the gas limit exceeds the transaction
+    /// gas cap, the transaction is of a custom type, or the sender fails the
+    /// checks of [`SenderOverride::validate`]. The state is then unchanged.
+    pub fn transact_as(
+        &mut self,
+        sender: SenderOverride,
+        tx: Transaction,
+    ) -> Result<(ExitReason, Vec<u8>), InvalidTransaction> {
+        transaction::validate_gas_limit(tx.gas_limit, self.config)?;
+        if tx.custom_type.is_some() {
+            return Err(InvalidTransaction::TypeNotSupported);
+        }
+        sender.validate(&self.state)?;
+        Ok(self.transact_without_fees(Transaction {
+            caller: sender.sender,
+            ..tx
+        }))
+    }
+
+    /// Execute a validated transaction of a custom type with its `handler`.
+    fn transact_custom(
+        &mut self,
+        tx: Transaction,
+        handler: &mut (dyn CustomTransactionType + 'config),
+    ) -> Result<(ExitReason, Vec<u8>), InvalidTransaction> {
+        // The hook runs in a substate, discarded if the transaction is
+        // rejected before execution
+        self.state.enter(0, false);
+        let charged = handler
+            .pre_execution(&tx, &mut HookState(&mut self.state))
+            .and_then(|()| {
+                if handler.charges_fees(&tx) {
+                    self.charge_fees(&tx)
+                } else {
+                    Ok(None)
+                }
+            });
+        let charged = match charged {
+            Ok(charged) => charged,
+            Err(e) => {
+                // Discarding a substate never fails
+                let _ = self.state.exit_discard();
+                return Err(e);
+            }
+        };
+        if let Err(e) = self.state.exit_commit() {
+            return Ok((e.into(), Vec::new()));
+        }
+
+        self.custom_intrinsic_gas = Some(handler.intrinsic_gas(&tx, self.config));
+        let result = self.execute_charged(tx.clone(), charged);
+        self.custom_intrinsic_gas = None;
+        handler.post_execution(
+            &tx,
+            &result.0,
+            self.transaction_used_gas(),
+            &mut HookState(&mut self.state),
+        );
+        Ok(result)
+    }
+
+    /// Charge the fees of a validated transaction, unless the gas is free,
+    /// and execute it.
+    fn transact_with_fees(
+        &mut self,
+        tx: Transaction,
+    ) -> Result<(ExitReason, Vec<u8>), InvalidTransaction> {
+        let charged = self.charge_fees(&tx)?;
+        Ok(self.execute_charged(tx, charged))
+    }
+
+    /// Charge the gas payer of a validated transaction the maximum fee,
+    /// after checking that it can afford it and the caller the value.
+    /// Return `None` if the gas is free.
+    fn charge_fees(&mut self, tx: &Transaction) -> Result<Option<ChargedFees>, InvalidTransaction> {
+        if self.free_gas {
+            if self.state.basic(tx.caller).balance < tx.value {
+                return Err(InvalidTransaction::InsufficientFunds);
+            }
+            return Ok(None);
+        }
+        let gas_price = Handler::gas_price(self);
+        let payer = tx.gas_payer();
+        let data_fee = (!tx.blob_hashes.is_empty()).then(|| {
+            let blob_gas_price = Handler::blob_base_fee(self).unwrap_or_default();
+            fees::blob_fee(tx.blob_hashes.len(), U256::from(blob_gas_price))
+        });
+        let max_fee = fees::max_fee(U256::from(tx.gas_limit), gas_price, data_fee)
+            .ok_or(InvalidTransaction::CostOverflow)?;
+        let payer_cost = if payer == tx.caller {
+            max_fee
+                .checked_add(tx.value)
+                .ok_or(InvalidTransaction::CostOverflow)?
+        } else if self.state.basic(tx.caller).balance < tx.value {
+            return Err(InvalidTransaction::InsufficientFunds);
+        } else {
+            max_fee
+        };
+        if payer_cost > self.state.basic(payer).balance {
+            return Err(InvalidTransaction::InsufficientFunds);
+        }
+        self.state
+            .debit(payer, max_fee)
+            .map_err(|_| InvalidTransaction::InsufficientFunds)?;
+        Ok(Some(ChargedFees {
+            payer,
+            max_fee,
+            data_fee,
+        }))
+    }
+
+    /// Execute a transaction whose fees were `charged`, and settle them.
+    fn execute_charged(
+        &mut self,
+        tx: Transaction,
+        charged: Option<ChargedFees>,
+    ) -> (ExitReason, Vec<u8>) {
+        let result = self.transact_without_fees(tx);
+        if let Some(ChargedFees {
+            payer,
+            max_fee,
+            data_fee,
+        }) = charged
+        {
+            if let Err(e) = self.settle_fees(payer, max_fee, data_fee) {
+                return (e.into(), Vec::new());
+            }
+        }
+        result
+    }
+
+    /// Execute a validated transaction, whose fees are charged separately.
+    fn transact_without_fees(&mut self, tx: Transaction) -> (ExitReason, Vec<u8>) {
+        match tx.to {
+            Some(address) => self.transact_call(
+                tx.caller,
+                address,
+                tx.value,
+                tx.data,
+                tx.gas_limit,
+                tx.access_list,
+                tx.authorization_list,
+            ),
+            None => {
+                self.transact_create(tx.caller, tx.value, tx.data, tx.gas_limit, tx.access_list)
+            }
+        }
+    }
+
+    /// Pay for the executed transaction: credit the miner tip for the used
+    /// gas to the block coinbase, and return the rest of `max_fee`, charged
+    /// upfront, to `payer`. The `data_fee` included in `max_fee`, e.g. the
+    /// EIP-4844 blob fee, is never returned.
+    ///
+    /// # Errors
+    /// Return `ExitError::BalanceOverflow` if a balance overflows, in which
+    /// case neither balance is credited.
+    pub fn settle_fees(
+        &mut self,
+        payer: H160,
+        max_fee: U256,
+        data_fee: Option<U256>,
+    ) -> Result<(), ExitError> {
+        let used_gas = self.transaction_used_gas();
+        let gas_price = Handler::gas_price(self);
+        let miner_tip = fees::miner_tip(
+            used_gas,
+            gas_price,
+            self.block_base_fee_per_gas(),
+            self.config.fee_market,
+        );
+        let actual_fee = fees::actual_fee(used_gas, gas_price);
+        let refund = fees::caller_refund(max_fee, actual_fee, data_fee);
+
+        // The balances may have grown during the execution, so both credits
+        // are checked before either is applied
+        let coinbase = self.block_coinbase();
+        let fits = |address: H160, value: U256| {
+            self.state
+                .basic(address)
+                .balance
+                .checked_add(value)
+                .is_some()
+        };
+        let overflows = if payer == coinbase {
+            refund
+                .checked_add(miner_tip)
+                .is_none_or(|total| !fits(payer, total))
+        } else {
+            !fits(payer, refund) || !fits(coinbase, miner_tip)
+        };
+        if overflows {
+            return Err(ExitError::BalanceOverflow);
         }
+        self.state.credit(payer, refund)?;
+        self.state.credit(coinbase, miner_tip)
     }
 
     /// Execute a system-level call as defined by EIP-4788, EIP-2935, EIP-7002, EIP-7251,
@@ -852,62 +2227,133 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
     /// - No `Transfer` is performed: balance checks on the caller are skipped entirely.
     /// - The caller's nonce is not incremented, and no base transaction cost is recorded.
     /// - The call is not static, meaning the callee can freely modify the state.
+    /// - No gas is paid: [`Self::used_gas`] and [`Self::fee`] are zero.
+    /// - Like a transaction, it ends by discarding its transient storage, but
+    ///   it is not reported to the metrics hooks.
     pub fn system_call(
         &mut self,
         caller: H160,
         address: H160,
         data: Vec<u8>,
     ) -> (ExitReason, Vec<u8>) {
+        with_event_listeners!(self, self.system_call(caller, address, data));
+        self.begin_execution(CallContextKind::System);
+        if self.config.increase_state_access_gas {
+            self.warm_precompiles();
+        }
         let context = Context {
             caller,
             address,
             apparent_value: U256::zero(),
+            code_address: address,
+            scheme: Some(CallScheme::Call),
         };
 
-        match self.call_inner(address, None, data, None, false, false, false, context) {
-            Capture::Exit((s, v)) => emit_exit!(s, v),
+        let result = match self.call_inner(address, None, data, None, false, false, false, context)
+        {
+            Capture::Exit((s, v)) => emit_exit!(self, s, v),
             Capture::Trap(rt) => {
                 let mut cs: SmallVec<[TaggedRuntime<'_>; DEFAULT_CALL_STACK_CAPACITY]> =
                     smallvec!(rt.0);
                 let (s, _, v) = self.execute_with_call_stack(&mut cs);
-                emit_exit!(s, v)
+                emit_exit!(self, s, v)
             }
+        };
+        self.end_execution(result)
+    }
+
+    /// EIP-4788: store the parent beacon block root in the beacon roots
+    /// contract, with a system call at the start of the block. Skipped if
+    /// the contract is not deployed.
+    pub fn apply_beacon_root(&mut self, parent_beacon_block_root: H256) -> (ExitReason, Vec<u8>) {
+        self.system_contract_call(
+            BEACON_ROOTS_ADDRESS,
+            parent_beacon_block_root.as_bytes().to_vec(),
+        )
+    }
+
+    /// EIP-2935: store the parent block hash in the history storage
+    /// contract, with a system call at the start of the block. Skipped if
+    /// the contract is not deployed.
+    pub fn apply_parent_block_hash(&mut self, parent_hash: H256) -> (ExitReason, Vec<u8>) {
+        self.system_contract_call(HISTORY_STORAGE_ADDRESS, parent_hash.as_bytes().to_vec())
+    }
+
+    fn system_contract_call(&mut self, address: H160, data: Vec<u8>) -> (ExitReason, Vec<u8>) {
+        if self.code(address).is_empty() {
+            self.call_context_kind = CallContextKind::System;
+            return (ExitSucceed::Stopped.into(), Vec::new());
         }
+        self.system_call(SYSTEM_ADDRESS, address, data)
     }
 
     /// Get used gas for the current executor, given the price.
+    ///
+    /// It accumulates over the executions of the executor, see
+    /// [`Self::transaction_used_gas`] for the gas of the last transaction.
+    /// Zero after a system call, whose gas is not paid.
     pub fn used_gas(&self) -> u64 {
-        // Avoid uncontrolled `u64` casting
-        let refunded_gas =
-            u64::try_from(self.state.metadata().gasometer.refunded_gas()).unwrap_or_default();
-        let total_used_gas = self.state.metadata().gasometer.total_used_gas();
-        let total_used_gas_refunded = self.state.metadata().gasometer.total_used_gas()
-            - min(
-                total_used_gas / self.config.max_refund_quotient,
-                refunded_gas,
-            );
+        let gasometer = self.state.metadata().gasometer();
+        self.paid_gas(gasometer.total_used_gas(), gasometer.refund_counter())
+    }
+
+    /// Gas used by the last transaction, after its refund and EIP-7623 gas
+    /// floor, i.e. the gas it pays for. Equal to [`Self::used_gas`] for an
+    /// executor executing a single transaction.
+    pub fn transaction_used_gas(&self) -> u64 {
+        let gasometer = self.state.metadata().gasometer();
+        let start = self.transaction_start_gas;
+        self.paid_gas(
+            gasometer
+                .total_used_gas()
+                .saturating_sub(start.total_used()),
+            RefundCounter::new(gasometer.refunded_gas().saturating_sub(start.refunded)),
+        )
+    }
+
+    /// Gas paid for `used_gas` with the `refund` counter.
+    fn paid_gas(&self, used_gas: u64, refund: RefundCounter) -> u64 {
+        if self.call_context_kind == CallContextKind::System {
+            return 0;
+        }
+        let used_gas_refunded = refund.apply(used_gas, self.config.max_refund_quotient);
         // EIP-7623: max(total_used_gas, floor_gas)
-        if self.config.has_floor_gas
-            && total_used_gas_refunded < self.state.metadata().gasometer.floor_gas()
-        {
-            self.state.metadata().gasometer.floor_gas()
+        let floor_gas = self.state.metadata().gasometer().floor_gas();
+        if self.config.has_floor_gas && used_gas_refunded < floor_gas {
+            floor_gas
         } else {
-            total_used_gas_refunded
+            used_gas_refunded
         }
     }
 
-    /// Get fee needed for the current executor, given the price.
+    /// Get the fee of the last transaction, given the price.
     pub fn fee(&self, price: U256) -> U256 {
-        let used_gas = self.used_gas();
-        U256::from(used_gas).saturating_mul(price)
+        fees::actual_fee(self.transaction_used_gas(), price)
     }
 
     /// Get account nonce.
-    /// NOTE: we don't need to cache it as by default it's `MemoryStackState` with cache flow
+    /// NOTE: reads of accounts the execution did not write go to the backend, which
+    /// can memoize them, see `CachedBackend`
     pub fn nonce(&self, address: H160) -> U256 {
         self.state.basic(address).nonce
     }
 
+    /// Whether the caller of the next call or creation gets its nonce
+    /// incremented: the nonce of the transaction sender is handled by the
+    /// nonce policy, see [`NoncePolicy::increment_nonce`].
+    fn increments_nonce(&self) -> bool {
+        self.state.metadata().depth.is_some() || self.nonce_policy.increment_nonce
+    }
+
+    /// EIP-2681: reject a `caller` whose nonce is incremented past the
+    /// maximum nonce.
+    fn validate_max_nonce(&self, caller: H160) -> Result<(), ExitError> {
+        if self.increments_nonce() && self.nonce(caller) >= U64_MAX {
+            return Err(ExitError::MaxNonce);
+        }
+        Ok(())
+    }
+
     /// Check if the existing account is "create collision".
     /// [EIP-7610](https://eips.ethereum.org/EIPS/eip-7610)
     pub fn is_create_collision(&self, address: H160) -> bool {
@@ -943,6 +2389,34 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         }
     }
 
+    /// Accessed addresses and storage slots as an EIP-2930 access list, see
+    /// [`DryRun::access_list`].
+    fn accessed_list(&self, caller: H160, address: H160) -> Vec<(H160, Vec<H256>)> {
+        let Some(accessed) = self.state.metadata().accessed() else {
+            return Vec::new();
+        };
+        let coinbase = self
+            .config
+            .warm_coinbase_address
+            .then(|| self.block_coinbase());
+        let mut access_list: BTreeMap<H160, Vec<H256>> = accessed
+            .accessed_addresses
+            .iter()
+            .copied()
+            .filter(|&accessed| {
+                accessed != caller
+                    && accessed != address
+                    && Some(accessed) != coinbase
+                    && !self.precompile_set.is_precompile(accessed)
+            })
+            .map(|accessed| (accessed, Vec::new()))
+            .collect();
+        for &(accessed, key) in &accessed.accessed_storage {
+            access_list.entry(accessed).or_default().push(key);
+        }
+        access_list.into_iter().collect()
+    }
+
     /// According to `EIP-2930` - `access_list` should be warmed.
     /// This function warms addresses and storage keys.
     ///
@@ -962,6 +2436,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
     ///   1. caller (tx.sender)
     ///   2. address (tx.to or the address being created if it is a contract creation transaction)
     /// - Warm coinbase according to `EIP-3651`
+    /// - Warm precompile addresses according to `EIP-2929`
     /// - Warm `access_list` according to `EIP-2931`
     ///
     /// ## References
@@ -987,10 +2462,20 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
                     .access_addresses([caller, address].iter().copied());
             }
 
+            self.warm_precompiles();
             self.warm_access_list(access_list);
         }
     }
 
+    /// Warm the addresses of the precompile set, which are always warm
+    /// according to `EIP-2929`.
+    fn warm_precompiles(&mut self) {
+        let addresses = self.precompile_set.precompile_addresses();
+        self.state
+            .metadata_mut()
+            .access_addresses(addresses.into_iter());
+    }
+
     /// Authorized accounts behavior.
     ///
     /// According to `EIP-7702` behavior section should be several steps of verifications.
@@ -1010,7 +2495,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
     /// See: [EIP-7702](https://eips.ethereum.org/EIPS/eip-7702#behavior)
     ///
     /// ## Errors
-    /// Return error if nonce increment return error.
+    /// Return error if nonce increment or authorization refund recording return error.
     fn authorized_accounts(
         &mut self,
         authorization_list: Vec<Authorization>,
@@ -1018,8 +2503,6 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         if !self.config.has_authorization_list {
             return Ok(());
         }
-        let mut refunded_accounts = 0;
-
         let state = self.state_mut();
         let mut warm_authority: Vec<H160> = Vec::with_capacity(authorization_list.len());
         for authority in authorization_list {
@@ -1047,22 +2530,30 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             }
 
             // 7. Add PER_EMPTY_ACCOUNT_COST - PER_AUTH_BASE_COST gas to the global refund counter if authority exists in the trie.
-            if !state.is_empty(authority.authority) {
-                refunded_accounts += 1;
-            }
+            // Empty accounts exist in the trie as well, with or without storage.
+            let existing = state.exists(authority.authority);
+            state
+                .metadata_mut()
+                .gasometer
+                .record_authorization(existing)?;
             // 8. Set the code of authority to be `0xef0100 || address`. This is a delegation designation.
             // * As a special case, if address is 0x0000000000000000000000000000000000000000 do not write the designation.
             //   Clear the account’s code.
-            let delegation_clearing = if authority.address.is_zero() {
+            let delegation_clearing = authority.is_delegation_clearing();
+            if delegation_clearing {
                 state.set_code(authority.authority, Vec::new());
-                true
             } else {
                 state.set_code(authority.authority, authority.delegation_code());
-                false
-            };
+            }
             // 9. Increase the nonce of authority by one.
             state.inc_nonce(authority.authority)?;
 
+            event!(ApplyAuthorization {
+                authority: authority.authority,
+                address: authority.address,
+                nonce: authority.nonce
+            });
+
             // Add/Remove to authority access list cache
             if delegation_clearing {
                 state.metadata_mut().remove_authority(authority.authority);
@@ -1076,11 +2567,43 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         self.state
             .metadata_mut()
             .access_addresses(warm_authority.into_iter());
+        Ok(())
+    }
 
-        self.state
-            .metadata_mut()
-            .gasometer
-            .record_authority_refund(refunded_accounts)
+    /// Check that a call frame at `depth` does not exceed the call stack limit.
+    fn check_call_depth(&self, depth: usize) -> Result<(), ExitError> {
+        if depth > self.config.call_stack_limit {
+            return Err(ExitError::CallTooDeep(
+                u64::try_from(depth).unwrap_or(u64::MAX),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Count an interpreter step of the transaction.
+    fn record_step(&mut self) -> Result<(), ExitFatal> {
+        self.steps = self.steps.saturating_add(1);
+        if self
+            .config
+            .step_limit
+            .is_some_and(|limit| self.steps > limit)
+        {
+            return Err(ExitFatal::StepLimitReached);
+        }
+        Ok(())
+    }
+
+    /// Check the cancellation token at the first step of the transaction,
+    /// then every `stride` steps.
+    #[cfg(feature = "std")]
+    fn check_cancellation(&self) -> Result<(), ExitFatal> {
+        let Some(token) = &self.cancellation_token else {
+            return Ok(());
+        };
+        if (self.steps - 1) % token.stride() == 0 && token.is_cancelled() {
+            return Err(ExitFatal::Cancelled);
+        }
+        Ok(())
     }
 
     /// Calculate gas limit and record it in the gasometer.
@@ -1089,39 +2612,62 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         target_gas: Option<u64>,
         take_l64: bool,
     ) -> Result<u64, ExitError> {
-        let initial_after_gas = self.state.metadata().gasometer.gas();
-        let after_gas = if take_l64 && self.config.call_l64_after_gas {
-            if self.config.estimate {
-                let diff = initial_after_gas - l64(initial_after_gas);
-                self.state.metadata_mut().gasometer.record_cost(diff)?;
-                initial_after_gas
-            } else {
-                l64(initial_after_gas)
-            }
+        let available = self.state.metadata().gasometer.gas();
+        let gas_limit = if take_l64 && !self.config.estimate {
+            gasometer::max_call_gas(available, target_gas, self.config)
         } else {
-            initial_after_gas
+            if take_l64 {
+                // Estimation charges the retained 64th but forwards all the gas
+                let retained = available - gasometer::max_call_gas(available, None, self.config);
+                self.state.metadata_mut().gasometer.record_cost(retained)?;
+            }
+            target_gas.map_or(available, |target_gas| min(target_gas, available))
         };
-        let target_gas = target_gas.unwrap_or(after_gas);
-        let gas_limit = min(target_gas, after_gas);
         self.state.metadata_mut().gasometer.record_cost(gas_limit)?;
         Ok(gas_limit)
     }
 
+    /// Address of the contract created by `caller` with `scheme`, after
+    /// the create interceptor redirected it, and the revert output if the
+    /// interceptor denied the creation.
+    fn resolve_create_address(
+        &mut self,
+        caller: H160,
+        scheme: CreateScheme,
+    ) -> (H160, Option<Vec<u8>>) {
+        let address = self.create_address(scheme);
+        let Some(interceptor) = self.create_interceptor.as_mut() else {
+            return (address, None);
+        };
+        match interceptor.intercept(caller, scheme, address) {
+            CreateDecision::Proceed => (address, None),
+            CreateDecision::Redirect(redirected) => (redirected, None),
+            CreateDecision::Deny(output) => (address, Some(output)),
+        }
+    }
+
     fn create_inner(
         &mut self,
         caller: H160,
         scheme: CreateScheme,
+        (address, denied): (H160, Option<Vec<u8>>),
         value: U256,
         init_code: Vec<u8>,
         target_gas: Option<u64>,
         take_l64: bool,
     ) -> Capture<(ExitReason, Vec<u8>), StackExecutorCreateInterrupt<'static>> {
-        if self.nonce(caller) >= U64_MAX {
-            return Capture::Exit((ExitError::MaxNonce.into(), Vec::new()));
+        if let Err(e) = self.validate_max_nonce(caller) {
+            return Capture::Exit((e.into(), Vec::new()));
+        }
+
+        if let Some(output) = denied.or_else(|| self.denied_target_output(address)) {
+            return Capture::Exit((ExitRevert::Reverted.into(), output));
+        }
+        if self.state.metadata().depth.is_none() {
+            self.created_address = Some(address);
         }
 
         // Warm address for EIP-2929
-        let address = self.create_address(scheme);
         self.state
             .metadata_mut()
             .access_addresses([caller, address].iter().copied());
@@ -1139,9 +2685,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             // As Depth incremented in `enter_substate` we must check depth counter
             // early to verify exceeding Stack limit. It allows avoid
             // issue with wrong detection `CallTooDeep` for Create.
-            if depth + 1 > self.config.call_stack_limit {
-                return Capture::Exit((ExitError::CallTooDeep.into(), Vec::new()));
-            }
+            try_or_fail!(self.check_call_depth(depth.saturating_add(1)));
         }
 
         // Check is transfer value is enough
@@ -1152,11 +2696,13 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
         let gas_limit = try_or_fail!(self.calc_gas_limit_and_record(target_gas, take_l64));
 
         // Check nonce and increment it for caller
-        try_or_fail!(self.state.inc_nonce(caller));
+        if self.increments_nonce() {
+            try_or_fail!(self.state.inc_nonce(caller));
+        }
 
         // Check create collision: EIP-7610
         if self.is_create_collision(address) {
-            return Capture::Exit((ExitError::CreateCollision.into(), Vec::new()));
+            return Capture::Exit((ExitError::CreateCollision(address).into(), Vec::new()));
         }
 
         // Enter to execution substate
@@ -1189,8 +2735,10 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             address,
             caller,
             apparent_value: value,
+            code_address: address,
+            scheme: None,
         };
-        let runtime = Runtime::new(
+        let runtime = self.runtime_pool.take(
             Rc::new(init_code),
             Rc::new(Vec::new()),
             context,
@@ -1226,14 +2774,17 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             context: &context,
         });
 
-        let mut gas_limit = try_or_fail!(self.calc_gas_limit_and_record(target_gas, take_l64));
-
-        if let Some(transfer) = transfer.as_ref() {
-            if take_stipend && transfer.value != U256_ZERO {
-                gas_limit = gas_limit.saturating_add(self.config.call_stipend);
-            }
+        if let Some(output) = self.denied_target_output(code_address) {
+            return Capture::Exit((ExitRevert::Reverted.into(), output));
         }
 
+        let gas_limit = try_or_fail!(self.calc_gas_limit_and_record(target_gas, take_l64));
+        let transfers_value = take_stipend
+            && transfer
+                .as_ref()
+                .is_some_and(|transfer| transfer.value != U256_ZERO);
+        let gas_limit = gasometer::call_gas_with_stipend(gas_limit, transfers_value, self.config);
+
         // EIP-7702 - get delegated designation address code
         // Detect loop for Delegated designation
         let code = self.authority_code(code_address);
@@ -1242,13 +2793,22 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             self.warm_target((target_address, None));
         }
 
+        // Same condition as the new account surcharge of the gasometer: a
+        // value transfer or, before EIP-161, any call to a missing account
+        #[cfg(feature = "tracing")]
+        let creates_account = (self.config.empty_considered_exists
+            || transfer
+                .as_ref()
+                .is_some_and(|transfer| transfer.value != U256_ZERO))
+            && !self.exists(context.address);
+
         self.enter_substate(gas_limit, is_static);
         self.state.touch(context.address);
 
         if let Some(depth) = self.state.metadata().depth {
-            if depth > self.config.call_stack_limit {
+            if let Err(e) = self.check_call_depth(depth) {
                 let _ = self.exit_substate(&StackExitKind::Reverted);
-                return Capture::Exit((ExitError::CallTooDeep.into(), Vec::new()));
+                return Capture::Exit((e.into(), Vec::new()));
             }
         }
 
@@ -1263,18 +2823,49 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             }
         }
 
+        #[cfg(feature = "tracing")]
+        if creates_account {
+            let (cause, surcharge) = if take_stipend {
+                (NewAccountCause::Call, gasometer::call_new_account_cost())
+            } else {
+                (NewAccountCause::Transaction, 0)
+            };
+            event!(NewAccountCreated {
+                address: context.address,
+                cause,
+                surcharge,
+            });
+        }
+
         // At this point, the state has been modified in enter_substate to
         // reflect both the is_static parameter of this call and the is_static
         // of the caller context.
         let precompile_is_static = self.state.metadata().is_static();
-        if let Some(result) = self.precompile_set.execute(&mut StackExecutorHandle {
+        let precompile_set = self.precompile_set;
+        let mut handle = StackExecutorHandle {
             executor: self,
             code_address,
             input: &input,
             gas_limit: Some(gas_limit),
             context: &context,
             is_static: precompile_is_static,
-        }) {
+            static_violation: false,
+        };
+        let result = precompile_set.execute(&mut handle);
+        let static_violation = handle.static_violation;
+        if let Some(result) = result {
+            if let Some(metrics) = self.metrics.as_mut() {
+                metrics.precompile_called(code_address);
+            }
+            // A precompile can't mutate state in a static context, even if it
+            // ignored the error returned by the handle.
+            let result = if static_violation {
+                Err(PrecompileFailure::Error {
+                    exit_status: ExitError::StaticModeViolation,
+                })
+            } else {
+                result
+            };
             return match result {
                 Ok(PrecompileOutput {
                     exit_status,
@@ -1302,7 +2893,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
             };
         }
 
-        let runtime = Runtime::new(
+        let runtime = self.runtime_pool.take(
             Rc::new(code),
             Rc::new(input),
             context,
@@ -1351,19 +2942,31 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
                     }
                 }
 
+                if self.reject_invalid_jumps && !analyze_jump_targets(&out).has_valid_jumps() {
+                    self.state.metadata_mut().gasometer.fail();
+                    let _ = self.exit_substate(&StackExitKind::Failed);
+                    return (ExitError::InvalidJump.into(), None, Vec::new());
+                }
+
                 match self
                     .state
                     .metadata_mut()
                     .gasometer
-                    .record_deposit(out.len())
+                    .record_code_deposit(out.len())
                 {
                     Ok(()) => {
+                        self.code_deposit_gas = self
+                            .code_deposit_gas
+                            .saturating_add(gasometer::code_deposit_cost(out.len(), self.config));
                         let exit_result = self.exit_substate(&StackExitKind::Succeeded);
+                        let code_hash = code_hashes::keccak(&out);
                         event!(CreateOutput {
                             address,
                             code: &out,
+                            code_hash,
                         });
                         self.state.set_code(address, out);
+                        self.code_hashes.insert_deployed(address, code_hash);
                         if let Err(e) = exit_result {
                             return (e.into(), None, Vec::new());
                         }
@@ -1433,27 +3036,41 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> InterpreterHandler
     fn before_bytecode(
         &mut self,
         opcode: Opcode,
-        _pc: usize,
+        pc: usize,
         machine: &Machine,
         address: &H160,
-    ) -> Result<(), ExitError> {
+    ) -> Result<(), ExitReason> {
+        self.record_step()?;
+        #[cfg(feature = "std")]
+        self.check_cancellation()?;
+
         #[cfg(feature = "tracing")]
         {
-            use crate::runtime::tracing::Event::Step;
+            use crate::runtime::tracing::{Event::Step, MemoryView};
             crate::runtime::tracing::with(|listener| {
-                #[allow(clippy::used_underscore_binding)]
                 listener.event(Step {
                     address: *address,
                     opcode,
-                    position: &Ok(_pc),
+                    position: &Ok(pc),
                     stack: machine.stack(),
-                    memory: machine.memory(),
+                    memory: MemoryView::new(machine.memory()),
                 });
             });
         }
 
         #[cfg(feature = "print-debug")]
         println!("### {opcode}");
+        if self.simulate_static
+            && self.static_violation.is_none()
+            && StaticViolation::changes_state(opcode, machine.stack())
+        {
+            self.static_violation = Some(StaticViolation {
+                address: *address,
+                pc,
+                opcode,
+                depth: self.state.metadata().depth().unwrap_or(0),
+            });
+        }
         if let Some(cost) = gasometer::static_opcode_cost(opcode) {
             self.state
                 .metadata_mut()
@@ -1490,11 +3107,41 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> InterpreterHandler
             listener.event(StepResult {
                 result,
                 return_value: machine.return_value().as_slice(),
+                return_range: machine.return_range(),
             });
         });
     }
 }
 
+#[cfg(feature = "concurrent")]
+impl<'backend, 'config, 'precompiles, P: PrecompileSet>
+    StackExecutor<
+        'config,
+        'precompiles,
+        MemoryStackState<'backend, 'config, SharedMemoryBackend>,
+        P,
+    >
+{
+    /// Create an executor over a shared snapshot, for read-only executions.
+    ///
+    /// Each thread creates its own executor, while the snapshot is shared.
+    /// Changes made by the execution can be deconstructed from the state, but
+    /// the snapshot itself is never modified.
+    pub fn new_shared(
+        backend: &'backend SharedMemoryBackend,
+        gas_limit: u64,
+        config: &'config Config,
+        precompile_set: &'precompiles P,
+    ) -> Self {
+        let metadata = StackSubstateMetadata::new(gas_limit, config);
+        Self::new_with_precompiles(
+            MemoryStackState::new(metadata, backend),
+            config,
+            precompile_set,
+        )
+    }
+}
+
 pub struct StackExecutorCallInterrupt<'borrow>(TaggedRuntime<'borrow>);
 
 pub struct StackExecutorCreateInterrupt<'borrow>(TaggedRuntime<'borrow>);
@@ -1508,7 +3155,8 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
     type CallFeedback = Infallible;
 
     /// Get account balance
-    /// NOTE: we don't need to cache it as by default it's `MemoryStackState` with cache flow
+    /// NOTE: reads of accounts the execution did not write go to the backend, which
+    /// can memoize them, see `CachedBackend`
     fn balance(&self, address: H160) -> U256 {
         self.state.basic(address).balance
     }
@@ -1534,8 +3182,23 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
         if !self.exists(address) {
             return H256::default();
         }
-        let code = self.code(address);
-        H256::from_slice(<[u8; 32]>::from(Keccak256::digest(code)).as_slice())
+        if let Some(code_hash) = self.deployed_code_hash(address) {
+            if let Some(metrics) = self.metrics.as_mut() {
+                metrics.code_hash_cache_hit();
+            }
+            return code_hash;
+        }
+        code_hashes::keccak(&self.code(address))
+    }
+
+    fn init_code_hash(&mut self, init_code: &[u8]) -> H256 {
+        let (code_hash, cached) = self.code_hashes.init_code_hash(init_code);
+        if cached {
+            if let Some(metrics) = self.metrics.as_mut() {
+                metrics.code_hash_cache_hit();
+            }
+        }
+        code_hash
     }
 
     /// Get account code
@@ -1543,6 +3206,11 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
         self.state.code(address)
     }
 
+    /// Get part of account code
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        self.state.code_slice(address, range)
+    }
+
     /// Get account storage by index
     fn storage(&self, address: H160, index: H256) -> H256 {
         self.state.storage(address, index)
@@ -1550,7 +3218,7 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
 
     /// Check is account storage empty
     fn is_empty_storage(&self, address: H160) -> bool {
-        self.state.is_empty(address)
+        self.state.is_empty_storage(address)
     }
 
     fn original_storage(&self, address: H160, index: H256) -> H256 {
@@ -1580,11 +3248,18 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
     }
 
     fn gas_price(&self) -> U256 {
-        self.state.gas_price()
+        if self.free_gas {
+            return U256_ZERO;
+        }
+        self.tx_env
+            .as_ref()
+            .map_or_else(|| self.state.gas_price(), |tx| tx.effective_gas_price)
     }
 
     fn origin(&self) -> H160 {
-        self.state.origin()
+        self.tx_env
+            .as_ref()
+            .map_or_else(|| self.state.origin(), |tx| tx.origin)
     }
 
     fn block_hash(&self, number: U256) -> H256 {
@@ -1624,20 +3299,43 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
     }
 
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+        self.notify_log(&LogRef {
+            address,
+            topics: &topics,
+            data: Cow::Borrowed(&data),
+        });
         self.state.log(address, topics, data);
         Ok(())
     }
 
+    /// The log is only copied to be stored in the state, after the log
+    /// subscriber borrowed it.
+    fn log_ref(&mut self, log: LogRef<'_>) -> Result<(), ExitError> {
+        self.notify_log(&log);
+        self.state
+            .log(log.address, log.topics.to_vec(), log.data.into_owned());
+        Ok(())
+    }
+
     /// Mark account as deleted
     /// - SELFDESTRUCT - CANCUN hard fork: EIP-6780
     fn mark_delete(&mut self, address: H160, target: H160) -> Result<(), ExitError> {
         let is_created = self.is_created(address);
-        // SELFDESTRUCT - CANCUN hard fork: EIP-6780 - selfdestruct only if contract is created in the same tx
-        if self.config.has_restricted_selfdestruct && !is_created && address == target {
-            // State is not changed:
-            // * if we are after Cancun upgrade specify the target is
-            // same as selfdestructed account. The balance stays unchanged.
-            return Ok(());
+        if address == target {
+            // SELFDESTRUCT - CANCUN hard fork: EIP-6780 - selfdestruct only if contract is created in the same tx
+            let burned = !self.config.has_restricted_selfdestruct
+                || is_created
+                || self.config.selfdestruct_to_self == SelfDestructBalance::Burn;
+            event!(SelfDestructToSelf {
+                address,
+                balance: self.balance(address),
+                burned,
+            });
+            if !burned {
+                // State is not changed: after the Cancun upgrade the balance
+                // stays unchanged, unless the chain burns it.
+                return Ok(());
+            }
         }
 
         let balance = self.balance(address);
@@ -1648,11 +3346,24 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
             balance,
         });
 
+        // Same condition as the new account surcharge of the gasometer
+        #[cfg(feature = "tracing")]
+        let creates_account =
+            (self.config.empty_considered_exists || balance != U256_ZERO) && !self.exists(target);
+
         self.state.transfer(Transfer {
             source: address,
             target,
             value: balance,
         })?;
+        #[cfg(feature = "tracing")]
+        if creates_account {
+            event!(NewAccountCreated {
+                address: target,
+                cause: NewAccountCause::Suicide,
+                surcharge: self.config.gas_suicide_new_account,
+            });
+        }
         self.state.reset_balance(address);
         // For CANCUN hard fork SELFDESTRUCT (EIP-6780) state is not changed
         // or if SELFDESTRUCT in the same TX - account should selfdestruct
@@ -1674,10 +3385,11 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
     ) -> Capture<(ExitReason, Vec<u8>), Self::CreateInterrupt> {
         if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
             let reason: ExitReason = e.into();
-            emit_exit!(reason.clone());
+            emit_exit!(self, reason.clone());
             return Capture::Exit((reason, Vec::new()));
         }
-        self.create_inner(caller, scheme, value, init_code, target_gas, true)
+        let address = self.resolve_create_address(caller, scheme);
+        self.create_inner(caller, scheme, address, value, init_code, target_gas, true)
     }
 
     #[cfg(feature = "tracing")]
@@ -1691,14 +3403,16 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
     ) -> Capture<(ExitReason, Vec<u8>), Self::CreateInterrupt> {
         if let Err(e) = self.maybe_record_init_code_cost(&init_code) {
             let reason: ExitReason = e.into();
-            emit_exit!(reason.clone());
+            emit_exit!(self, reason.clone());
             return Capture::Exit((reason, Vec::new()));
         }
 
-        let capture = self.create_inner(caller, scheme, value, init_code, target_gas, true);
+        let address = self.resolve_create_address(caller, scheme);
+        let capture =
+            self.create_inner(caller, scheme, address, value, init_code, target_gas, true);
 
         if let Capture::Exit((ref reason, ref return_value)) = capture {
-            emit_exit!(reason, return_value);
+            emit_exit!(self, reason, return_value);
         }
 
         capture
@@ -1748,7 +3462,7 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
         );
 
         if let Capture::Exit((ref reason, ref return_value)) = capture {
-            emit_exit!(reason, return_value);
+            emit_exit!(self, reason, return_value);
         }
 
         capture
@@ -1758,6 +3472,19 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
         self.state.record_external_operation(op)
     }
 
+    fn other(&mut self, opcode: Opcode, machine: &mut Machine) -> Result<(), ExitError> {
+        match self.custom_opcodes.as_deref_mut() {
+            Some(handler) if CUSTOM_OPCODES.contains(&opcode.as_u8()) => {
+                handler.execute(opcode, machine)
+            }
+            _ => Err(ExitError::InvalidCode(opcode)),
+        }
+    }
+
+    fn custom_opcode_cost(&self, opcode: Opcode, stack: &Stack) -> Option<u64> {
+        self.custom_opcode_handler(opcode)?.gas_cost(opcode, stack)
+    }
+
     /// Returns `None` if `Cancun` hard fork is not enabled
     /// via `has_blob_base_fee` config.
     ///
@@ -1773,7 +3500,10 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> Handler
 
     fn get_blob_hash(&self, index: usize) -> Option<U256> {
         if self.config.has_shard_blob_transactions {
-            self.state.get_blob_hash(index)
+            match self.tx_env.as_ref() {
+                Some(tx) => tx.blob_hashes.get(index).copied(),
+                None => self.state.get_blob_hash(index),
+            }
         } else {
             None
         }
@@ -1842,6 +3572,8 @@ struct StackExecutorHandle<'inner, 'config, 'precompiles, S, P> {
     gas_limit: Option<u64>,
     context: &'inner Context,
     is_static: bool,
+    /// Set when the precompile attempts to mutate state in a static context.
+    static_violation: bool,
 }
 
 impl<'config, S: StackState<'config>, P: PrecompileSet> PrecompileHandle
@@ -1901,8 +3633,18 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> PrecompileHandle
             context
         });
 
+        // Attribute the gas of the subcall frames to the precompile
+        let previous_tag = self.executor.state.metadata().gasometer.tag();
+        self.executor
+            .state
+            .metadata_mut()
+            .gasometer
+            .set_tag(FrameTag::PrecompileSubcall {
+                precompile: self.code_address,
+            });
+
         // Perform the subcall
-        match Handler::call(
+        let result = match Handler::call(
             self.executor,
             code_address,
             transfer,
@@ -1922,9 +3664,16 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> PrecompileHandle
                     smallvec!(rt.0);
                 let (reason, _, return_data) =
                     self.executor.execute_with_call_stack(&mut call_stack);
-                emit_exit!(reason, return_data)
+                emit_exit!(self.executor, reason, return_data)
             }
-        }
+        };
+
+        self.executor
+            .state
+            .metadata_mut()
+            .gasometer
+            .set_tag(previous_tag);
+        result
     }
 
     /// Record cost to the Runtime gasometer.
@@ -1937,22 +3686,14 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> PrecompileHandle
     }
 
     /// Record Substrate specific cost.
-    fn record_external_cost(
-        &mut self,
-        ref_time: Option<u64>,
-        proof_size: Option<u64>,
-        storage_growth: Option<u64>,
-    ) -> Result<(), ExitError> {
-        self.executor
-            .state
-            .record_external_cost(ref_time, proof_size, storage_growth)
+    fn record_external_cost(&mut self, cost: ExternalCost) -> Result<(), ExitError> {
+        self.executor.state.record_external_cost(cost)
     }
 
-    /// Refund Substrate specific cost.
-    fn refund_external_cost(&mut self, ref_time: Option<u64>, proof_size: Option<u64>) {
-        self.executor
-            .state
-            .refund_external_cost(ref_time, proof_size);
+    /// Refund Substrate specific cost. The storage growth is passed along,
+    /// for the state to ignore.
+    fn refund_external_cost(&mut self, cost: ExternalCost) {
+        self.executor.state.refund_external_cost(cost);
     }
 
     /// Retrieve the remaining gas.
@@ -1961,7 +3702,12 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> PrecompileHandle
     }
 
     /// Record a log.
+    /// Logs are rejected with `ExitError::StaticModeViolation` in a static context.
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+        if self.is_static {
+            self.static_violation = true;
+            return Err(ExitError::StaticModeViolation);
+        }
         Handler::log(self.executor, address, topics, data)
     }
 
@@ -1990,3 +3736,2949 @@ impl<'config, S: StackState<'config>, P: PrecompileSet> PrecompileHandle
         self.gas_limit
     }
 }
+
+/// Binary search of the lowest gas limit with which a call `succeeds`,
+/// given that it does with `gas_limit` and used `used_gas` then. As the gas
+/// used is net of refunds, the call can't succeed with less.
+fn lowest_gas_limit(used_gas: u64, gas_limit: u64, mut succeeds: impl FnMut(u64) -> bool) -> u64 {
+    let mut failing = used_gas.saturating_sub(1);
+    let mut succeeding = gas_limit;
+    while failing + 1 < succeeding {
+        let middle = failing + (succeeding - failing) / 2;
+        if succeeds(middle) {
+            succeeding = middle;
+        } else {
+            failing = middle;
+        }
+    }
+    succeeding
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::Log;
+    use crate::backend::{
+        Apply, ApplyBackend, Backend, BlobParams, MemoryAccount, MemoryBackend, MemoryVicinity,
+        TxEnv, GAS_PER_BLOB,
+    };
+    use crate::core::prelude::Cow;
+    use crate::core::utils::revert;
+    #[cfg(feature = "execution-stats")]
+    use crate::executor::stack::ExecutionStats;
+    use crate::executor::stack::{
+        Authorization, CallContextKind, CreateDecision, CustomOpcodeHandler, CustomTransactionType,
+        LogEvent, Metrics, PrecompileFailure, PrecompileHandle, PrecompileOutput, PrecompileSet,
+        StackExecutor, StackExecutorBuilder, StackExitKind, StackState, StackSubstateMetadata,
+        StaticViolation, TargetPolicy, TransactionHookState, BEACON_ROOTS_ADDRESS,
+        HISTORY_STORAGE_ADDRESS, SYSTEM_ADDRESS, SYSTEM_CALL_GAS_LIMIT,
+    };
+    use crate::executor::transaction::{
+        InvalidTransaction, NoncePolicy, SenderOverride, SignedTransaction, Transaction,
+        TransactionType,
+    };
+    use crate::gasometer::FrameTag;
+    use crate::prelude::*;
+    use crate::{
+        CallScheme, CancellationToken, Capture, Config, ConfigError, Context, ExitError, ExitFatal,
+        ExitReason, ExitRevert, ExitSucceed, Handler, HardFork, LogRef, Machine, Opcode,
+        SelfDestructBalance, Stack,
+    };
+    use primitive_types::{H160, H256, U256};
+    use sha3::{Digest, Keccak256};
+
+    const LOG_PRECOMPILE: H160 = H160([0xff; 20]);
+
+    /// Precompile that emits a log and ignores the result of `handle.log`.
+    struct LogPrecompile;
+
+    impl PrecompileSet for LogPrecompile {
+        fn execute(
+            &self,
+            handle: &mut impl PrecompileHandle,
+        ) -> Option<Result<PrecompileOutput, PrecompileFailure>> {
+            if handle.code_address() != LOG_PRECOMPILE {
+                return None;
+            }
+            let _ = handle.log(LOG_PRECOMPILE, vec![H256::zero()], vec![0x42]);
+            Some(Ok(PrecompileOutput {
+                exit_status: ExitSucceed::Stopped,
+                output: Vec::new(),
+            }))
+        }
+
+        fn is_precompile(&self, address: H160) -> bool {
+            address == LOG_PRECOMPILE
+        }
+
+        fn precompile_addresses(&self) -> Vec<H160> {
+            vec![LOG_PRECOMPILE]
+        }
+    }
+
+    fn memory_vicinity() -> MemoryVicinity {
+        MemoryVicinity {
+            gas_price: U256::from(1),
+            effective_gas_price: U256::zero(),
+            origin: H160::zero(),
+            block_hashes: Vec::new(),
+            block_number: U256::zero(),
+            block_coinbase: H160::zero(),
+            block_timestamp: U256::zero(),
+            block_difficulty: U256::zero(),
+            block_randomness: None,
+            blob_params: None,
+            block_excess_blob_gas: None,
+            block_blob_gas_used: None,
+            block_gas_limit: U256::from(30_000_000),
+            block_base_fee_per_gas: U256::from(1),
+            chain_id: U256::from(1),
+            blob_hashes: vec![],
+        }
+    }
+
+    fn call_log_precompile(is_static: bool) -> (ExitReason, usize) {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .precompiles(&LogPrecompile)
+            .build();
+
+        let context = Context {
+            address: LOG_PRECOMPILE,
+            caller: H160::zero(),
+            apparent_value: U256::zero(),
+            code_address: LOG_PRECOMPILE,
+            scheme: Some(if is_static {
+                CallScheme::StaticCall
+            } else {
+                CallScheme::Call
+            }),
+        };
+        let Capture::Exit((reason, _)) = Handler::call(
+            &mut executor,
+            LOG_PRECOMPILE,
+            None,
+            Vec::new(),
+            Some(100_000),
+            is_static,
+            context,
+        ) else {
+            panic!("precompile call must not trap");
+        };
+
+        let (_, logs) = executor.into_state().deconstruct();
+        (reason, logs.into_iter().count())
+    }
+
+    #[test]
+    fn test_precompile_log_in_non_static_call() {
+        let (reason, logs) = call_log_precompile(false);
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        assert_eq!(logs, 1);
+    }
+
+    #[test]
+    fn test_precompile_log_in_static_call_is_rejected() {
+        let (reason, logs) = call_log_precompile(true);
+        assert_eq!(reason, ExitReason::Error(ExitError::StaticModeViolation));
+        assert_eq!(logs, 0);
+    }
+
+    #[test]
+    fn test_log_subscriber_discards_reverted_frames() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let subscriber_events = events.clone();
+        executor.set_log_subscriber(move |event| {
+            subscriber_events.borrow_mut().push(match event {
+                LogEvent::Emitted(log) => Some(log.data.to_vec()),
+                LogEvent::Discarded(count) => {
+                    assert_eq!(count, 2);
+                    None
+                }
+            });
+        });
+
+        executor.enter_substate(100_000, false);
+        Handler::log(&mut executor, H160::zero(), Vec::new(), vec![1]).unwrap();
+        // Committed child frame, logs are discarded along with the parent frame
+        executor.enter_substate(50_000, false);
+        Handler::log(&mut executor, H160::zero(), Vec::new(), vec![2]).unwrap();
+        executor.exit_substate(&StackExitKind::Succeeded).unwrap();
+        executor.exit_substate(&StackExitKind::Reverted).unwrap();
+
+        executor.enter_substate(100_000, false);
+        let log = LogRef {
+            address: H160::zero(),
+            topics: &[],
+            data: Cow::Borrowed(&[3]),
+        };
+        Handler::log_ref(&mut executor, log).unwrap();
+        // Failed child frame
+        executor.enter_substate(50_000, false);
+        executor.exit_substate(&StackExitKind::Failed).unwrap();
+        executor.exit_substate(&StackExitKind::Succeeded).unwrap();
+
+        let logs: Vec<Log> = executor.into_state().deconstruct().1.into_iter().collect();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].data, vec![3]);
+        assert_eq!(
+            *events.borrow(),
+            vec![Some(vec![1]), Some(vec![2]), None, Some(vec![3])]
+        );
+    }
+
+    #[test]
+    fn test_log_positions() {
+        let (caller, contract) = (H160::from_low_u64_be(1), H160::from_low_u64_be(0xa));
+        // LOG0(1), CALL(0x0b), CALL(0x0c), LOG0(1)
+        let code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xa0, 0x60, 0x00, 0x60, 0x00,
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x0b, 0x5a, 0xf1, 0x50, 0x60, 0x00, 0x60,
+            0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x0c, 0x5a, 0xf1, 0x50, 0x60, 0x01,
+            0x60, 0x00, 0xa0, 0x00,
+        ];
+        let state = BTreeMap::from([
+            (
+                contract,
+                MemoryAccount {
+                    code,
+                    ..Default::default()
+                },
+            ),
+            (
+                // LOG0(2), REVERT
+                H160::from_low_u64_be(0xb),
+                MemoryAccount {
+                    code: vec![
+                        0x60, 0x02, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xa0, 0x60, 0x00,
+                        0x60, 0x00, 0xfd,
+                    ],
+                    ..Default::default()
+                },
+            ),
+            (
+                // LOG0(3)
+                H160::from_low_u64_be(0xc),
+                MemoryAccount {
+                    code: vec![
+                        0x60, 0x03, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xa0, 0x00,
+                    ],
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        executor.set_block_log_index(Some(7));
+        let transact = |executor: &mut StackExecutor<_, _>| {
+            let (reason, _) = executor.transact_call(
+                caller,
+                contract,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert!(reason.is_succeed());
+        };
+
+        transact(&mut executor);
+        let positions = executor.log_positions();
+        // The frames are the transaction frame 0, and its children 1 and 2
+        assert_eq!(
+            positions
+                .iter()
+                .map(|position| (position.depth, position.frame_id))
+                .collect::<Vec<_>>(),
+            [(0, 0), (1, 2), (0, 0)]
+        );
+        assert_eq!(
+            positions
+                .iter()
+                .map(|position| (position.tx_index, position.block_index))
+                .collect::<Vec<_>>(),
+            [(0, Some(7)), (1, Some(8)), (2, Some(9))]
+        );
+
+        // The block index carries over to the next transaction
+        transact(&mut executor);
+        assert_eq!(
+            executor
+                .log_positions()
+                .iter()
+                .map(|position| (position.tx_index, position.block_index))
+                .collect::<Vec<_>>(),
+            [(0, Some(10)), (1, Some(11)), (2, Some(12))]
+        );
+        let logs: Vec<Log> = executor.into_state().deconstruct().1.into_iter().collect();
+        assert_eq!(
+            logs.iter().map(|log| log.data[0]).collect::<Vec<_>>(),
+            [1, 3, 1, 1, 3, 1]
+        );
+    }
+
+    #[test]
+    fn test_precompile_subcall_tag_is_inherited() {
+        let config = Config::cancun();
+        let mut metadata = StackSubstateMetadata::new(1_000_000, &config);
+        assert_eq!(metadata.gasometer().tag(), FrameTag::Regular);
+
+        let tag = FrameTag::PrecompileSubcall {
+            precompile: LOG_PRECOMPILE,
+        };
+        metadata.gasometer_mut().set_tag(tag);
+        let child = metadata.spit_child(100_000, false);
+        assert_eq!(child.gasometer().tag(), tag);
+        assert_eq!(child.spit_child(50_000, true).gasometer().tag(), tag);
+    }
+
+    #[test]
+    fn test_runtimes_are_reused_across_frames_and_transactions() {
+        let caller_contract = H160::from_low_u64_be(0xc0);
+        let callee = H160::from_low_u64_be(0xc1);
+        let mut state = BTreeMap::new();
+        // CALL(gas, 0xc1, 0, 0, 0, 0, 0), then return the result
+        state.insert(
+            caller_contract,
+            MemoryAccount {
+                code: vec![
+                    0x60, 0x00, 0x80, 0x80, 0x80, 0x80, 0x60, 0xc1, 0x5a, 0xf1, 0x60, 0x00, 0x52,
+                    0x60, 0x20, 0x60, 0x00, 0xf3,
+                ],
+                ..Default::default()
+            },
+        );
+        // Expand memory, then stop
+        state.insert(
+            callee,
+            MemoryAccount {
+                code: vec![0x60, 0x01, 0x61, 0x10, 0x00, 0x52, 0x00],
+                ..Default::default()
+            },
+        );
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        assert!(executor.runtime_pool().is_empty());
+
+        for _ in 0..2 {
+            let (reason, output) = executor.transact_call(
+                H160::from_low_u64_be(1),
+                caller_contract,
+                U256::zero(),
+                Vec::new(),
+                1_000_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+            assert_eq!(U256::from_big_endian(&output), U256::one());
+            // One runtime per call depth
+            assert_eq!(executor.runtime_pool().len(), 2);
+        }
+    }
+
+    /// Account or storage access of a randomized program.
+    #[derive(Clone, Copy, Debug)]
+    enum StateAccess {
+        Sload(u8),
+        Sstore(u8, u8),
+        Balance(u8),
+    }
+
+    fn state_access() -> impl proptest::strategy::Strategy<Value = StateAccess> {
+        use proptest::strategy::Strategy;
+        proptest::prop_oneof![
+            (0u8..4).prop_map(StateAccess::Sload),
+            (0u8..4, 0u8..3).prop_map(|(slot, value)| StateAccess::Sstore(slot, value)),
+            (1u8..4).prop_map(StateAccess::Balance),
+        ]
+    }
+
+    /// Outcome of [`run_state_accesses`].
+    struct StateAccessRun {
+        reason: ExitReason,
+        /// Gas used before refunds.
+        total_used_gas: u64,
+        /// Gas used after refunds.
+        used_gas: u64,
+        /// Storage slots of the contract written by the program.
+        storage: Vec<H256>,
+    }
+
+    /// Call a contract running `accesses` over the storage `initial`.
+    fn run_state_accesses(
+        config: &Config,
+        accesses: &[StateAccess],
+        initial: &[u8],
+        access_list: Vec<(H160, Vec<H256>)>,
+    ) -> StateAccessRun {
+        let mut code = Vec::new();
+        for access in accesses {
+            match *access {
+                StateAccess::Sload(slot) => code.extend([0x60, slot, 0x54, 0x50]),
+                StateAccess::Sstore(slot, value) => code.extend([0x60, value, 0x60, slot, 0x55]),
+                StateAccess::Balance(address) => code.extend([0x60, address, 0x31, 0x50]),
+            }
+        }
+        let storage = (0u8..)
+            .zip(initial)
+            .filter(|(_, value)| **value != 0)
+            .map(|(slot, value)| {
+                (
+                    H256::from_low_u64_be(u64::from(slot)),
+                    H256::from_low_u64_be(u64::from(*value)),
+                )
+            })
+            .collect();
+        let contract = H160::from_low_u64_be(0x10);
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code,
+                storage,
+                ..Default::default()
+            },
+        )]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let mut executor = StackExecutorBuilder::new(config, 1_000_000)
+            .backend(&backend)
+            .build();
+        let (reason, _) = executor.transact_call(
+            H160::from_low_u64_be(1),
+            contract,
+            U256::zero(),
+            Vec::new(),
+            1_000_000,
+            access_list,
+            Vec::new(),
+        );
+        StateAccessRun {
+            reason,
+            total_used_gas: executor.state().metadata().gasometer().total_used_gas(),
+            used_gas: executor.used_gas(),
+            storage: (0..4)
+                .map(|slot| {
+                    executor
+                        .state()
+                        .storage(contract, H256::from_low_u64_be(slot))
+                })
+                .collect(),
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_create2_collision(
+            nonce in 0u64..3,
+            code_len in 0usize..3,
+            has_storage: bool,
+            balance in 0u64..1000,
+        ) {
+            let caller = H160::from_low_u64_be(1);
+            let salt = H256::repeat_byte(0x5a);
+            let init_code_hash = H256::from_slice(Keccak256::digest(b"").as_slice());
+            let mut hasher = Keccak256::new();
+            hasher.update([0xff]);
+            hasher.update(caller.as_bytes());
+            hasher.update(salt.as_bytes());
+            hasher.update(init_code_hash.as_bytes());
+            let target = H160::from(H256::from_slice(hasher.finalize().as_slice()));
+
+            let mut storage = BTreeMap::new();
+            if has_storage {
+                storage.insert(H256::zero(), H256::from_low_u64_be(1));
+            }
+            let state = BTreeMap::from([(
+                target,
+                MemoryAccount {
+                    nonce: U256::from(nonce),
+                    balance: U256::from(balance),
+                    code: vec![0x00; code_len],
+                    storage,
+                },
+            )]);
+            let vicinity = memory_vicinity();
+            let backend = MemoryBackend::new(&vicinity, state);
+            let config = Config::cancun();
+            let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+                .backend(&backend)
+                .build();
+
+            let collision = nonce > 0 || code_len > 0 || has_storage;
+            proptest::prop_assert_eq!(executor.is_create_collision(target), collision);
+
+            let (reason, _) = executor.transact_create2(
+                caller,
+                U256::zero(),
+                Vec::new(),
+                salt,
+                1_000_000,
+                Vec::new(),
+            );
+            if collision {
+                proptest::prop_assert_eq!(reason, ExitError::CreateCollision(target).into());
+            } else {
+                proptest::prop_assert!(reason.is_succeed());
+            }
+        }
+
+        #[test]
+        fn prop_create_max_nonce_boundary(offset in 0u64..4) {
+            let caller = H160::from_low_u64_be(1);
+            let nonce = u64::MAX - offset;
+            let state = BTreeMap::from([(
+                caller,
+                MemoryAccount {
+                    nonce: U256::from(nonce),
+                    ..Default::default()
+                },
+            )]);
+            let vicinity = memory_vicinity();
+            let backend = MemoryBackend::new(&vicinity, state);
+            let config = Config::cancun();
+            let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+                .backend(&backend)
+                .build();
+
+            let (reason, _) =
+                executor.transact_create(caller, U256::zero(), Vec::new(), 1_000_000, Vec::new());
+            if offset == 0 {
+                proptest::prop_assert_eq!(reason, ExitError::MaxNonce.into());
+                proptest::prop_assert_eq!(executor.nonce(caller), U256::from(u64::MAX));
+            } else {
+                proptest::prop_assert!(reason.is_succeed());
+                proptest::prop_assert_eq!(executor.nonce(caller), U256::from(nonce + 1));
+            }
+        }
+
+        #[test]
+        fn prop_gas_invariants_across_forks(
+            accesses in proptest::collection::vec(state_access(), 0..16),
+            initial in proptest::collection::vec(0u8..3, 4),
+        ) {
+            let mut previous: Option<(HardFork, StateAccessRun)> = None;
+            for fork in HardFork::ALL {
+                let Some(config) = fork.gasometer_config() else {
+                    continue;
+                };
+                let run = run_state_accesses(&config, &accesses, &initial, Vec::new());
+                proptest::prop_assert_eq!(&run.reason, &ExitReason::Succeed(ExitSucceed::Stopped));
+
+                // Refunds never exceed the cap
+                proptest::prop_assert!(run.used_gas <= run.total_used_gas);
+                proptest::prop_assert!(
+                    run.total_used_gas - run.used_gas
+                        <= run.total_used_gas / config.max_refund_quotient
+                );
+
+                // Estimate mode never under-reports
+                let estimate = Config {
+                    estimate: true,
+                    ..config.clone()
+                };
+                let estimated = run_state_accesses(&estimate, &accesses, &initial, Vec::new());
+                proptest::prop_assert!(estimated.reason.is_succeed());
+                proptest::prop_assert!(estimated.used_gas >= run.used_gas);
+
+                // Warm accesses never cost more than cold ones, once the
+                // access list is paid for
+                if config.increase_state_access_gas {
+                    let slots: Vec<H256> = (0..4).map(H256::from_low_u64_be).collect();
+                    let mut access_list = vec![(H160::from_low_u64_be(0x10), slots)];
+                    access_list.extend((1..4).map(|address| (H160::from_low_u64_be(address), Vec::new())));
+                    let access_list_cost = 4 * config.gas_access_list_address
+                        + 4 * config.gas_access_list_storage_key;
+                    let warm = run_state_accesses(&config, &accesses, &initial, access_list);
+                    proptest::prop_assert!(warm.reason.is_succeed());
+                    proptest::prop_assert_eq!(&warm.storage, &run.storage);
+                    proptest::prop_assert!(warm.total_used_gas - access_list_cost <= run.total_used_gas);
+                }
+
+                // Against the previous fork: the storage written never
+                // changes, and neither do the costs of state accesses after
+                // Berlin, except the refunds lowered by London
+                if let Some((previous_fork, previous_run)) = &previous {
+                    proptest::prop_assert_eq!(&run.storage, &previous_run.storage);
+                    if *previous_fork >= HardFork::Berlin {
+                        proptest::prop_assert_eq!(run.total_used_gas, previous_run.total_used_gas);
+                        if *previous_fork >= HardFork::London {
+                            proptest::prop_assert_eq!(run.used_gas, previous_run.used_gas);
+                        } else {
+                            proptest::prop_assert!(run.used_gas >= previous_run.used_gas);
+                        }
+                    }
+                }
+                previous = Some((fork, run));
+            }
+        }
+    }
+
+    /// Deploy a contract with `code_size` zero bytes of code.
+    fn deploy_code_of_size(config: &Config, code_size: u16) -> (ExitReason, u64) {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let mut executor = StackExecutorBuilder::new(config, 30_000_000)
+            .backend(&backend)
+            .build();
+        // PUSH2 code_size, PUSH1 0, RETURN
+        let [hi, lo] = code_size.to_be_bytes();
+        let init_code = vec![0x61, hi, lo, 0x60, 0x00, 0xf3];
+        let (reason, _) = executor.transact_create(
+            H160::from_low_u64_be(1),
+            U256::zero(),
+            init_code,
+            30_000_000,
+            Vec::new(),
+        );
+        (reason, executor.used_gas())
+    }
+
+    #[test]
+    fn test_code_size_limit_boundaries() {
+        let config = Config::cancun();
+        assert_eq!(config.validate(), Ok(()));
+        let (reason, _) = deploy_code_of_size(&config, 0x6000);
+        assert!(reason.is_succeed());
+        let (reason, _) = deploy_code_of_size(&config, 0x6001);
+        assert_eq!(reason, ExitError::CreateContractLimit.into());
+
+        // Raised limits
+        let config = Config::cancun().with_max_code_size(Some(0x8000));
+        assert_eq!(config.max_initcode_size, Some(0x10000));
+        assert_eq!(config.validate(), Ok(()));
+        let (reason, _) = deploy_code_of_size(&config, 0x8000);
+        assert!(reason.is_succeed());
+        let (reason, _) = deploy_code_of_size(&config, 0x8001);
+        assert_eq!(reason, ExitError::CreateContractLimit.into());
+    }
+
+    #[test]
+    fn test_disabled_code_size_limit_keeps_init_code_cost() {
+        let config = Config::cancun().with_max_code_size(None);
+        assert_eq!(config.max_initcode_size, None);
+        assert!(config.has_init_code_cost);
+        assert_eq!(config.validate(), Ok(()));
+        let (reason, used_gas) = deploy_code_of_size(&config, 0x6001);
+        assert!(reason.is_succeed());
+
+        let without_init_code_cost = Config {
+            has_init_code_cost: false,
+            ..config
+        };
+        let (reason, used_gas_without_cost) = deploy_code_of_size(&without_init_code_cost, 0x6001);
+        assert!(reason.is_succeed());
+        // One word of init code
+        assert_eq!(used_gas - used_gas_without_cost, 2);
+    }
+
+    #[test]
+    fn test_code_size_config_validation() {
+        let config = Config {
+            max_initcode_size: Some(0x5000),
+            ..Config::cancun()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::InitCodeLimitBelowCodeLimit)
+        );
+        let config = Config {
+            create_contract_limit: None,
+            ..Config::cancun()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::InitCodeLimitWithoutCodeLimit)
+        );
+    }
+
+    /// Deploy a contract which increments storage slot 0 and calls itself
+    /// with all available gas, then return the value of the slot, i.e. the
+    /// number of frames executed.
+    fn recursion_depth(call_stack_limit: usize, gas_limit: u64) -> U256 {
+        let contract = H160::from_low_u64_be(0xd0);
+        // SSTORE(0, SLOAD(0) + 1), CALL(gas, address, 0, 0, 0, 0, 0), STOP
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code: vec![
+                    0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x80, 0x80,
+                    0x80, 0x80, 0x30, 0x5a, 0xf1, 0x00,
+                ],
+                ..Default::default()
+            },
+        )]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config {
+            call_stack_limit,
+            ..Config::cancun()
+        };
+        let mut executor = StackExecutorBuilder::new(&config, gas_limit)
+            .backend(&backend)
+            .build();
+        let (reason, _) = executor.transact_call(
+            H160::from_low_u64_be(1),
+            contract,
+            U256::zero(),
+            Vec::new(),
+            gas_limit,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        U256::from_big_endian(executor.storage(contract, H256::zero()).as_bytes())
+    }
+
+    #[test]
+    fn test_call_stack_limit_beyond_1024() {
+        // Enough gas to outlast the 63/64 rule: every frame up to the limit runs
+        assert_eq!(recursion_depth(2000, u64::MAX / 4), U256::from(2001));
+        assert_eq!(recursion_depth(3, u64::MAX / 4), U256::from(4));
+
+        // With a regular amount of gas the 63/64 rule bounds the depth first
+        let depth = recursion_depth(1024, 10_000_000);
+        assert!(depth < U256::from(1025));
+        assert_eq!(recursion_depth(2000, 10_000_000), depth);
+    }
+
+    #[test]
+    fn test_call_too_deep_reports_depth() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config {
+            call_stack_limit: 1,
+            ..Config::cancun()
+        };
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        executor.enter_substate(1_000_000, false);
+        executor.enter_substate(1_000_000, false);
+        assert_eq!(executor.state().metadata().depth(), Some(1));
+
+        let context = Context {
+            address: H160::from_low_u64_be(2),
+            caller: H160::from_low_u64_be(1),
+            apparent_value: U256::zero(),
+            code_address: H160::from_low_u64_be(2),
+            scheme: Some(CallScheme::Call),
+        };
+        let Capture::Exit((reason, _)) = Handler::call(
+            &mut executor,
+            H160::from_low_u64_be(2),
+            None,
+            Vec::new(),
+            Some(100_000),
+            false,
+            context,
+        ) else {
+            panic!("call must not trap");
+        };
+        assert_eq!(reason, ExitError::CallTooDeep(2).into());
+        assert_eq!(executor.state().metadata().depth(), Some(1));
+
+        let Capture::Exit((reason, _)) = Handler::create(
+            &mut executor,
+            H160::from_low_u64_be(1),
+            crate::CreateScheme::Legacy {
+                caller: H160::from_low_u64_be(1),
+            },
+            U256::zero(),
+            Vec::new(),
+            Some(100_000),
+        ) else {
+            panic!("create must not trap");
+        };
+        assert_eq!(reason, ExitError::CallTooDeep(2).into());
+    }
+
+    #[test]
+    fn test_target_policy_denies_calls_and_creates() {
+        let caller = H160::from_low_u64_be(1);
+        let caller_contract = H160::from_low_u64_be(0xc0);
+        let blocked = H160::from_low_u64_be(0xc1);
+        // CALL(gas, 0xc1, 0, 0, 0, 0, 0), then return the result
+        let state = BTreeMap::from([(
+            caller_contract,
+            MemoryAccount {
+                code: vec![
+                    0x60, 0x00, 0x80, 0x80, 0x80, 0x80, 0x60, 0xc1, 0x5a, 0xf1, 0x60, 0x00, 0x52,
+                    0x60, 0x20, 0x60, 0x00, 0xf3,
+                ],
+                ..Default::default()
+            },
+        )]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        executor.set_target_policy(Some(
+            TargetPolicy::blocklist([blocked]).with_message("sanctioned"),
+        ));
+
+        let (reason, output) = executor.transact_call(
+            caller,
+            blocked,
+            U256::zero(),
+            Vec::new(),
+            1_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Revert(ExitRevert::Reverted));
+        assert_eq!(revert::decode(&output).message(), Some("sanctioned"));
+
+        // The nested call fails, the caller continues
+        let (reason, output) = executor.transact_call(
+            caller,
+            caller_contract,
+            U256::zero(),
+            Vec::new(),
+            1_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+        assert_eq!(U256::from_big_endian(&output), U256::zero());
+
+        let created = executor.create_address(crate::CreateScheme::Legacy { caller });
+        let policy = executor.target_policy_mut().unwrap();
+        policy.insert(created);
+        policy.remove(blocked);
+        let (reason, _) =
+            executor.transact_create(caller, U256::zero(), Vec::new(), 1_000_000, Vec::new());
+        assert_eq!(reason, ExitReason::Revert(ExitRevert::Reverted));
+
+        let (reason, _) = executor.transact_call(
+            caller,
+            caller_contract,
+            U256::zero(),
+            Vec::new(),
+            1_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+    }
+
+    #[test]
+    fn test_transaction_gas_cap_is_enforced() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config {
+            max_transaction_gas: Some(100_000),
+            ..Config::cancun()
+        };
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        let caller = H160::from_low_u64_be(1);
+
+        let (reason, _) = executor.transact_call(
+            caller,
+            H160::from_low_u64_be(2),
+            U256::zero(),
+            Vec::new(),
+            100_001,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitError::TransactionGasLimitExceeded.into());
+        let (reason, _) =
+            executor.transact_create(caller, U256::zero(), Vec::new(), 100_001, Vec::new());
+        assert_eq!(reason, ExitError::TransactionGasLimitExceeded.into());
+        // Rejected transactions do not change the state
+        assert_eq!(executor.nonce(caller), U256::zero());
+        assert_eq!(executor.used_gas(), 0);
+
+        let (reason, _) = executor.transact_call(
+            caller,
+            H160::from_low_u64_be(2),
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+    }
+
+    #[test]
+    fn test_touch_creates_empty_account_before_eip161() {
+        let target = H160::from_low_u64_be(0xe0);
+        let vicinity = memory_vicinity();
+        for (config, created) in [(Config::frontier(), true), (Config::istanbul(), false)] {
+            let mut backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+            let mut executor = StackExecutorBuilder::new(&config, 100_000)
+                .backend(&backend)
+                .build();
+
+            let (reason, _) = executor.transact_call(
+                H160::zero(),
+                target,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert!(reason.is_succeed());
+            let (values, logs) = executor.into_state().deconstruct();
+            backend.apply(values, logs, config.delete_empty_accounts());
+            assert_eq!(backend.exists(target), created);
+        }
+    }
+
+    fn selfdestruct_refund(config: &Config) -> i64 {
+        let contract = H160::from_low_u64_be(0xc0);
+        let vicinity = memory_vicinity();
+        let mut state = BTreeMap::new();
+        // SELFDESTRUCT(0)
+        state.insert(
+            contract,
+            MemoryAccount {
+                code: vec![0x60, 0x00, 0xff],
+                ..Default::default()
+            },
+        );
+        let backend = MemoryBackend::new(&vicinity, state);
+        let mut executor = StackExecutorBuilder::new(config, 100_000)
+            .backend(&backend)
+            .build();
+
+        let (reason, _) = executor.transact_call(
+            H160::from_low_u64_be(1),
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Suicided));
+        executor.state().metadata().gasometer().refunded_gas()
+    }
+
+    #[test]
+    fn test_selfdestruct_refund_replay() {
+        assert_eq!(selfdestruct_refund(&Config::frontier()), 24000);
+        assert_eq!(selfdestruct_refund(&Config::istanbul()), 24000);
+        assert_eq!(selfdestruct_refund(&Config::london()), 0);
+
+        let config = Config {
+            gas_suicide_refund: 0,
+            ..Config::istanbul()
+        };
+        assert_eq!(selfdestruct_refund(&config), 0);
+    }
+
+    #[test]
+    fn test_transient_storage_cleared_between_transactions() {
+        let contract = H160::from_low_u64_be(0xc0);
+        let vicinity = memory_vicinity();
+        // TSTORE(0, TLOAD(0) + 1), and return the stored value
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code: vec![
+                    0x60, 0x01, 0x60, 0x00, 0x5c, 0x01, 0x80, 0x60, 0x00, 0x5d, 0x60, 0x00, 0x52,
+                    0x60, 0x20, 0x60, 0x00, 0xf3,
+                ],
+                ..MemoryAccount::default()
+            },
+        )]);
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        for _ in 0..2 {
+            let (reason, output) = executor.transact_call(
+                H160::from_low_u64_be(1),
+                contract,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+            assert_eq!(U256::from_big_endian(&output), U256::one());
+            #[cfg(feature = "strict-invariants")]
+            assert_eq!(executor.invariant_violation(), None);
+        }
+    }
+
+    #[test]
+    fn test_selfdestruct_to_self() {
+        let contract = H160::from_low_u64_be(0xc0);
+        let vicinity = memory_vicinity();
+        let burning = Config {
+            selfdestruct_to_self: SelfDestructBalance::Burn,
+            ..Config::cancun()
+        };
+        // Deleted before EIP-6780, and kept with or without its balance after
+        for (config, exists, balance) in [
+            (Config::shanghai(), false, 0),
+            (Config::cancun(), true, 100),
+            (burning, true, 0),
+        ] {
+            // SELFDESTRUCT(ADDRESS)
+            let state = BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    balance: U256::from(100),
+                    code: vec![0x30, 0xff],
+                    ..MemoryAccount::default()
+                },
+            )]);
+            let mut backend = MemoryBackend::new(&vicinity, state);
+            let mut executor = StackExecutorBuilder::new(&config, 100_000)
+                .backend(&backend)
+                .build();
+
+            let (reason, _) = executor.transact_call(
+                H160::from_low_u64_be(1),
+                contract,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Suicided));
+            let (values, logs) = executor.into_state().deconstruct();
+            backend.apply(values, logs, config.delete_empty_accounts());
+            assert_eq!(backend.exists(contract), exists);
+            assert_eq!(backend.basic(contract).balance, U256::from(balance));
+            assert_eq!(!backend.code(contract).is_empty(), exists);
+        }
+    }
+
+    #[test]
+    fn test_create_interceptor_redirects_and_denies() {
+        let caller = H160::from_low_u64_be(1);
+        let fixed = H160::from_low_u64_be(0xf1);
+        let redirected = H160::from_low_u64_be(0xf2);
+        let denied = H160::from_low_u64_be(0xf3);
+        // Init code returning the runtime code `STOP`
+        let init_code = vec![0x60, 0x01, 0x60, 0x00, 0xf3];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let (reason, _) = executor.transact_create_fixed(
+            caller,
+            fixed,
+            U256::zero(),
+            init_code.clone(),
+            1_000_000,
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+        assert_eq!(executor.state().code(fixed), vec![0x00]);
+
+        executor.set_create_interceptor(Some(Box::new(
+            move |_caller: H160, _scheme: crate::CreateScheme, address: H160| {
+                if address == denied {
+                    CreateDecision::Deny(b"denied".to_vec())
+                } else {
+                    CreateDecision::Redirect(redirected)
+                }
+            },
+        )));
+        assert!(executor.has_create_interceptor());
+
+        let legacy = executor.create_address(crate::CreateScheme::Legacy { caller });
+        let (reason, _) = executor.transact_create(
+            caller,
+            U256::zero(),
+            init_code.clone(),
+            1_000_000,
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+        assert_eq!(executor.state().code(redirected), vec![0x00]);
+        assert!(executor.state().code(legacy).is_empty());
+        // Only the redirected address is warmed
+        assert!(!executor.state().is_cold(redirected));
+        assert!(executor.state().is_cold(legacy));
+
+        let (reason, output) = executor.transact_create_fixed(
+            caller,
+            denied,
+            U256::zero(),
+            init_code,
+            1_000_000,
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Revert(ExitRevert::Reverted));
+        assert_eq!(output, b"denied".to_vec());
+        assert!(executor.state().code(denied).is_empty());
+    }
+
+    #[test]
+    fn test_create_fixed_init_code_limit() {
+        let caller = H160::from_low_u64_be(1);
+        let address = H160::from_low_u64_be(0xf1);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let limit = config.max_initcode_size.unwrap();
+        let mut executor = StackExecutorBuilder::new(&config, 10_000_000)
+            .backend(&backend)
+            .build();
+
+        let (reason, _) = executor.transact_create_fixed(
+            caller,
+            address,
+            U256::zero(),
+            vec![0x00; limit + 1],
+            1_000_000,
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Error(ExitError::CreateContractLimit));
+        assert!(!executor.state().exists(address));
+
+        let mut executor = StackExecutorBuilder::new(&config, 10_000_000)
+            .backend(&backend)
+            .build();
+        let (reason, _) = executor.transact_create_fixed(
+            caller,
+            address,
+            U256::zero(),
+            vec![0x00; limit],
+            1_000_000,
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+    }
+
+    #[test]
+    fn test_step_limit_aborts_execution() {
+        let caller = H160::from_low_u64_be(1);
+        let looping = H160::from_low_u64_be(0xc0);
+        let calling = H160::from_low_u64_be(0xc1);
+        let state = BTreeMap::from([
+            // JUMPDEST, JUMP(0), which never leaves the interpreter loop
+            (
+                looping,
+                MemoryAccount {
+                    code: vec![0x5b, 0x60, 0x00, 0x56],
+                    ..Default::default()
+                },
+            ),
+            // CALL(gas, 0xc0, 0, 0, 0, 0, 0)
+            (
+                calling,
+                MemoryAccount {
+                    code: vec![0x60, 0x00, 0x80, 0x80, 0x80, 0x80, 0x60, 0xc0, 0x5a, 0xf1],
+                    ..Default::default()
+                },
+            ),
+            // SSTORE(0, CALLDATALOAD(0))
+            (
+                BEACON_ROOTS_ADDRESS,
+                MemoryAccount {
+                    code: vec![0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x00],
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config {
+            step_limit: Some(1000),
+            ..Config::cancun()
+        };
+        let mut executor = StackExecutorBuilder::new(&config, 100_000_000)
+            .backend(&backend)
+            .build();
+
+        // Every opcode counts, and the steps of the inner frames too
+        for target in [looping, calling] {
+            let (reason, _) = executor.transact_call(
+                caller,
+                target,
+                U256::zero(),
+                Vec::new(),
+                10_000_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert_eq!(reason, ExitReason::Fatal(ExitFatal::StepLimitReached));
+            assert_eq!(executor.steps(), 1001);
+        }
+
+        // The count restarts with each system call
+        let (reason, _) = executor.apply_beacon_root(H256::repeat_byte(0xbe));
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        assert_eq!(executor.steps(), 5);
+
+        // And with each transaction
+        let (reason, _) = executor.transact_call(
+            caller,
+            looping,
+            U256::zero(),
+            Vec::new(),
+            22_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+        assert!(executor.steps() > 0 && executor.steps() < 1000);
+    }
+
+    #[test]
+    fn test_zk_profile() {
+        let config = Config::cancun().zk_profile();
+        assert_eq!(config.memory_limit, 8 * 1024 * 1024);
+        assert_eq!(config.call_stack_limit, 1024);
+        assert_eq!(config.step_limit, Some(1 << 27));
+        // Tighter limits are kept, and the rules are unchanged otherwise
+        let config = Config {
+            call_stack_limit: 64,
+            step_limit: Some(10),
+            ..Config::cancun()
+        }
+        .zk_profile();
+        assert_eq!(config.call_stack_limit, 64);
+        assert_eq!(config.step_limit, Some(10));
+        let fields = Config::cancun().diff(&Config::cancun().zk_profile());
+        assert_eq!(
+            fields.fields().collect::<Vec<_>>(),
+            ["memory_limit", "step_limit"]
+        );
+    }
+
+    #[test]
+    fn test_cancellation_token_aborts_execution() {
+        let caller = H160::from_low_u64_be(1);
+        let looping = H160::from_low_u64_be(0xc0);
+        let logging = H160::from_low_u64_be(0xc1);
+        let state = BTreeMap::from([
+            // JUMPDEST, JUMP(0)
+            (
+                looping,
+                MemoryAccount {
+                    code: vec![0x5b, 0x60, 0x00, 0x56],
+                    ..Default::default()
+                },
+            ),
+            // LOG0(0, 0), then JUMPDEST, JUMP(5)
+            (
+                logging,
+                MemoryAccount {
+                    code: vec![0x60, 0x00, 0x60, 0x00, 0xa0, 0x5b, 0x60, 0x05, 0x56],
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 100_000_000)
+            .backend(&backend)
+            .build();
+        let token = CancellationToken::new().with_stride(16);
+        executor.set_cancellation_token(Some(token.clone()));
+        token.cancel();
+
+        let (reason, _) = executor.transact_call(
+            caller,
+            looping,
+            U256::zero(),
+            Vec::new(),
+            100_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Fatal(ExitFatal::Cancelled));
+        assert_eq!(executor.steps(), 1);
+
+        // A token cancelled by the log aborts the loop at the next check
+        let mut executor = StackExecutorBuilder::new(&config, 100_000_000)
+            .backend(&backend)
+            .build();
+        let token = CancellationToken::new().with_stride(16);
+        executor.set_cancellation_token(Some(token.clone()));
+        executor.set_log_subscriber(move |_| token.cancel());
+        let (reason, _) = executor.transact_call(
+            caller,
+            logging,
+            U256::zero(),
+            Vec::new(),
+            100_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Fatal(ExitFatal::Cancelled));
+        assert_eq!(executor.steps(), 17);
+
+        // A token that is not cancelled lets the loop run out of gas
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        executor.set_cancellation_token(Some(CancellationToken::new()));
+        let (reason, _) = executor.transact_call(
+            caller,
+            looping,
+            U256::zero(),
+            Vec::new(),
+            1_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+    }
+
+    #[cfg(feature = "execution-stats")]
+    #[test]
+    fn test_execution_stats() {
+        let caller = H160::from_low_u64_be(1);
+        let outer = H160::from_low_u64_be(0xc1);
+        let inner = H160::from_low_u64_be(0xc2);
+        let state = BTreeMap::from([
+            (
+                outer,
+                // MSTORE(0x40, 0), CALL(gas, 0xc2, 0, 0, 0, 0, 0)
+                MemoryAccount {
+                    code: vec![
+                        0x60, 0x00, 0x60, 0x40, 0x52, 0x60, 0x00, 0x80, 0x80, 0x80, 0x80, 0x60,
+                        0xc2, 0x5a, 0xf1, 0x00,
+                    ],
+                    ..Default::default()
+                },
+            ),
+            (
+                inner,
+                // RETURN(0, 0x80)
+                MemoryAccount {
+                    code: vec![0x60, 0x80, 0x60, 0x00, 0xf3],
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let (reason, _) = executor.transact_call(
+            caller,
+            outer,
+            U256::zero(),
+            Vec::new(),
+            1_000_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        // The outer frame holds 0x60 bytes of memory while the inner frame
+        // holds 0x80 bytes
+        let stats = executor.take_execution_stats();
+        assert_eq!(stats.peak_memory, 0xe0);
+        assert_eq!(stats.peak_depth, 2);
+        assert_eq!(stats.return_data_bytes, 0x80);
+        assert_eq!(*executor.execution_stats(), ExecutionStats::default());
+    }
+
+    #[test]
+    fn test_transact_as_sender_override() {
+        let sender = H160::from_low_u64_be(1);
+        let target = H160::from_low_u64_be(2);
+        let vicinity = memory_vicinity();
+        let state = BTreeMap::from([(
+            sender,
+            MemoryAccount {
+                nonce: U256::from(3),
+                balance: U256::from(100),
+                ..Default::default()
+            },
+        )]);
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        let tx = Transaction {
+            caller: H160::from_low_u64_be(9),
+            to: Some(target),
+            value: U256::from(10),
+            gas_limit: 50_000,
+            ..Transaction::default()
+        };
+
+        assert_eq!(
+            executor.transact_as(SenderOverride::new(sender).with_nonce(2), tx.clone()),
+            Err(InvalidTransaction::NonceTooLow)
+        );
+        assert_eq!(
+            executor.transact_as(
+                SenderOverride::new(sender).with_max_cost(U256::from(101)),
+                tx.clone()
+            ),
+            Err(InvalidTransaction::InsufficientFunds)
+        );
+        assert_eq!(executor.nonce(sender), U256::from(3));
+
+        // The nonce of the transaction is ignored, and no fees are charged
+        let (reason, _) = executor
+            .transact_as(SenderOverride::new(sender).with_nonce(3), tx)
+            .unwrap();
+        assert!(reason.is_succeed());
+        assert_eq!(executor.nonce(sender), U256::from(4));
+        assert_eq!(executor.state().basic(sender).balance, U256::from(90));
+        assert_eq!(executor.state().basic(target).balance, U256::from(10));
+    }
+
+    #[test]
+    fn test_replay_nonce_policy_allows_max_nonce() {
+        let caller = H160::from_low_u64_be(1);
+        let vicinity = memory_vicinity();
+        let state = BTreeMap::from([(
+            caller,
+            MemoryAccount {
+                nonce: U256::from(u64::MAX),
+                ..Default::default()
+            },
+        )]);
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        let target = H160::from_low_u64_be(2);
+
+        let call = |executor: &mut StackExecutor<_, _>| {
+            executor
+                .transact_call(
+                    caller,
+                    target,
+                    U256::zero(),
+                    Vec::new(),
+                    100_000,
+                    Vec::new(),
+                    Vec::new(),
+                )
+                .0
+        };
+        let create = |executor: &mut StackExecutor<_, _>| {
+            executor
+                .transact_create(caller, U256::zero(), Vec::new(), 100_000, Vec::new())
+                .0
+        };
+        assert_eq!(call(&mut executor), ExitError::MaxNonce.into());
+        assert_eq!(create(&mut executor), ExitError::MaxNonce.into());
+
+        // The nonce is not incremented, so it can't overflow
+        executor.set_nonce_policy(NoncePolicy::REPLAY);
+        assert!(call(&mut executor).is_succeed());
+        assert!(create(&mut executor).is_succeed());
+        assert_eq!(executor.nonce(caller), U256::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_replay_nonce_policy_keeps_sender_nonce() {
+        let caller = H160::from_low_u64_be(1);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        assert_eq!(executor.nonce_policy(), NoncePolicy::CONSENSUS);
+        assert_eq!(
+            executor.validate_nonce(caller, 1),
+            Err(InvalidTransaction::NonceTooHigh)
+        );
+        executor.set_nonce_policy(NoncePolicy::REPLAY);
+        assert_eq!(executor.validate_nonce(caller, 1), Ok(()));
+
+        let (reason, _) = executor.transact_call(
+            caller,
+            H160::from_low_u64_be(2),
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+        let created = executor.create_address(crate::CreateScheme::Legacy { caller });
+        let (reason, _) =
+            executor.transact_create(caller, U256::zero(), Vec::new(), 100_000, Vec::new());
+        assert!(reason.is_succeed());
+        assert_eq!(executor.nonce(caller), U256::zero());
+        assert_eq!(executor.nonce(created), U256::one());
+
+        executor.set_nonce_policy(NoncePolicy::default());
+        let (reason, _) = executor.transact_call(
+            caller,
+            H160::from_low_u64_be(2),
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+        assert_eq!(executor.nonce(caller), U256::one());
+    }
+
+    #[test]
+    fn test_system_call_transient_storage_is_discarded() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                BEACON_ROOTS_ADDRESS,
+                MemoryAccount {
+                    // TSTORE(0, 1)
+                    code: vec![0x60, 0x01, 0x60, 0x00, 0x5d, 0x00],
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let config = Config::prague();
+        let mut executor = StackExecutorBuilder::new(&config, SYSTEM_CALL_GAS_LIMIT)
+            .backend(&backend)
+            .build();
+        let recorded = Rc::new(RefCell::new(RecordedMetrics::default()));
+        executor.set_metrics(Some(Box::new(MetricsRecorder(recorded.clone()))));
+
+        let (reason, _) = executor.apply_beacon_root(H256::repeat_byte(0xbe));
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        assert_eq!(
+            executor
+                .state_mut()
+                .tload(BEACON_ROOTS_ADDRESS, H256::zero()),
+            Ok(U256::zero())
+        );
+        // System calls are not transactions
+        assert!(recorded.borrow().transactions.is_empty());
+    }
+
+    #[test]
+    fn test_system_calls_are_gas_free() {
+        let root = H256::repeat_byte(0xbe);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([
+                (
+                    BEACON_ROOTS_ADDRESS,
+                    MemoryAccount {
+                        // Store the call data at slot 0
+                        code: vec![0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x00],
+                        ..MemoryAccount::default()
+                    },
+                ),
+                (
+                    HISTORY_STORAGE_ADDRESS,
+                    MemoryAccount {
+                        // Store the call data at slot 0, then revert
+                        code: vec![
+                            0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xfd,
+                        ],
+                        ..MemoryAccount::default()
+                    },
+                ),
+            ]),
+        );
+        let config = Config::prague();
+        let mut executor = StackExecutorBuilder::new(&config, SYSTEM_CALL_GAS_LIMIT)
+            .backend(&backend)
+            .build();
+
+        let (reason, _) = executor.apply_beacon_root(root);
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        assert_eq!(executor.call_context_kind(), CallContextKind::System);
+        assert_eq!(executor.storage(BEACON_ROOTS_ADDRESS, H256::zero()), root);
+        assert_eq!(executor.nonce(SYSTEM_ADDRESS), U256::zero());
+        assert_eq!(executor.used_gas(), 0);
+        assert_eq!(executor.fee(U256::from(1)), U256::zero());
+
+        // A failed system call reverts its state changes
+        let (reason, _) = executor.apply_parent_block_hash(root);
+        assert_eq!(reason, ExitReason::Revert(ExitRevert::Reverted));
+        assert_eq!(
+            executor.storage(HISTORY_STORAGE_ADDRESS, H256::zero()),
+            H256::zero()
+        );
+
+        let (reason, _) = executor.transact_call(
+            H160::from_low_u64_be(1),
+            H160::from_low_u64_be(2),
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+        assert_eq!(executor.call_context_kind(), CallContextKind::Transaction);
+        assert!(executor.used_gas() > 0);
+    }
+
+    #[test]
+    fn test_deployed_code_hash_is_cached() {
+        let caller = H160::from_low_u64_be(1);
+        // Deploy the code `0x00`
+        let init_code = vec![0x60, 0x00, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+        let code_hash = H256::from_slice(Keccak256::digest([0x00_u8]).as_slice());
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let result =
+            executor.transact_create_detailed(caller, U256::zero(), init_code, 100_000, Vec::new());
+        assert!(result.reason.is_succeed());
+        assert_eq!(result.code_hash, Some(code_hash));
+
+        // The hashes are dropped with the transaction, not the code
+        let address = result.address.unwrap();
+        assert_eq!(executor.deployed_code_hash(address), None);
+        assert_eq!(executor.code_hash(address), code_hash);
+    }
+
+    #[test]
+    fn test_code_deposit_gas() {
+        let caller = H160::from_low_u64_be(1);
+        // Deploy 10 zero bytes
+        let init_code = vec![0x60, 0x0a, 0x60, 0x00, 0xf3];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let mut used_gas = Vec::new();
+        for gas_code_deposit in [200, 300] {
+            let config = Config {
+                gas_code_deposit,
+                ..Config::cancun()
+            };
+            let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+                .backend(&backend)
+                .build();
+            let (reason, _) = executor.transact_create(
+                caller,
+                U256::zero(),
+                init_code.clone(),
+                100_000,
+                Vec::new(),
+            );
+            assert!(reason.is_succeed());
+            assert_eq!(executor.code_deposit_gas(), 10 * gas_code_deposit);
+            used_gas.push(executor.used_gas());
+        }
+        assert_eq!(used_gas[1] - used_gas[0], 1_000);
+    }
+
+    #[test]
+    fn test_transact_create_detailed() {
+        let caller = H160::from_low_u64_be(1);
+        // Deploy 10 zero bytes
+        let init_code = vec![0x60, 0x0a, 0x60, 0x00, 0xf3];
+        // Revert with the data `0x2a`
+        let reverting_code = vec![0x60, 0x2a, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xfd];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let address = executor.create_address(crate::CreateScheme::Legacy { caller });
+        let result =
+            executor.transact_create_detailed(caller, U256::zero(), init_code, 100_000, Vec::new());
+        assert_eq!(result.reason, ExitSucceed::Returned.into());
+        assert_eq!(result.address, Some(address));
+        assert_eq!(
+            result.code_hash,
+            Some(H256::from_slice(&Keccak256::digest([0u8; 10])))
+        );
+        assert_eq!(result.code_len, 10);
+        assert_eq!(result.used_gas, executor.used_gas());
+        assert_eq!(result.code_deposit_gas, 10 * config.gas_code_deposit);
+        assert_eq!(
+            result.init_gas(),
+            result.used_gas - 10 * config.gas_code_deposit
+        );
+        assert!(result.return_data.is_empty());
+
+        let result = executor.transact_create_detailed(
+            caller,
+            U256::zero(),
+            reverting_code,
+            100_000,
+            Vec::new(),
+        );
+        assert!(matches!(result.reason, ExitReason::Revert(_)));
+        assert_eq!(result.address, None);
+        assert_eq!(result.code_hash, None);
+        assert_eq!(result.code_len, 0);
+        assert_eq!(result.used_gas, executor.transaction_used_gas());
+        assert!(result.used_gas < executor.used_gas());
+        assert_eq!(result.code_deposit_gas, 0);
+        assert_eq!(result.return_data, [0x2a]);
+
+        // Redirected by the create interceptor
+        let redirected = H160::from_low_u64_be(0x1234);
+        executor.set_create_interceptor(Some(Box::new(
+            move |_caller: H160, _scheme: crate::CreateScheme, _address: H160| {
+                CreateDecision::Redirect(redirected)
+            },
+        )));
+        let result = executor.transact_create_detailed(
+            caller,
+            U256::zero(),
+            vec![0x60, 0x0a, 0x60, 0x00, 0xf3],
+            100_000,
+            Vec::new(),
+        );
+        assert_eq!(result.reason, ExitSucceed::Returned.into());
+        assert_eq!(result.address, Some(redirected));
+        assert_eq!(result.code_len, 10);
+    }
+
+    #[test]
+    fn test_transact_call_static_simulation() {
+        let caller = H160::from_low_u64_be(1);
+        let proxy = H160::from_low_u64_be(2);
+        let contract = H160::from_low_u64_be(3);
+        // CALL(gas, contract, 0, 0, 0, 0, 0)
+        let proxy_code = vec![
+            0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x03, 0x5a, 0xf1,
+            0x00,
+        ];
+        // SSTORE(0, 1), then LOG0 of empty data
+        let code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xa0, 0x00,
+        ];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([
+                (
+                    proxy,
+                    MemoryAccount {
+                        code: proxy_code,
+                        ..MemoryAccount::default()
+                    },
+                ),
+                (
+                    contract,
+                    MemoryAccount {
+                        code,
+                        ..MemoryAccount::default()
+                    },
+                ),
+            ]),
+        );
+        let config = Config::cancun();
+        let executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let simulation = executor.transact_call_static_simulation(
+            caller,
+            proxy,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        // The writes proceed, and the first one is reported
+        assert_eq!(simulation.reason, ExitSucceed::Stopped.into());
+        assert_eq!(simulation.logs.len(), 1);
+        assert_eq!(
+            simulation.static_violation,
+            Some(StaticViolation {
+                address: contract,
+                pc: 4,
+                opcode: Opcode::SSTORE,
+                depth: 1,
+            })
+        );
+        assert_eq!(
+            executor.state().storage(contract, H256::zero()),
+            H256::zero()
+        );
+    }
+
+    #[test]
+    fn test_transact_call_dry_run() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        // SSTORE(0, 1), LOG0 of empty data, then return 32 bytes of memory
+        let code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xa0, 0x60, 0x20, 0x60, 0x00,
+            0xf3,
+        ];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code,
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        // A pending change of the executor is visible to the dry run
+        executor
+            .state_mut()
+            .set_storage(contract, H256::zero(), H256::repeat_byte(2));
+
+        let dry_run = executor.transact_call_dry_run(
+            caller,
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(dry_run.reason, ExitSucceed::Returned.into());
+        assert_eq!(dry_run.output, [0; 32]);
+        assert!(dry_run.used_gas > 21_000);
+        assert_eq!(dry_run.logs.len(), 1);
+        assert_eq!(dry_run.static_violation, None);
+        let storage = dry_run.changes.iter().find_map(|apply| match apply {
+            Apply::Modify {
+                address, storage, ..
+            } if *address == contract => Some(storage.clone()),
+            _ => None,
+        });
+        assert_eq!(
+            storage,
+            Some(vec![(H256::zero(), H256::from_low_u64_be(1))])
+        );
+
+        // Nothing leaked into the executor
+        assert_eq!(executor.used_gas(), 0);
+        assert_eq!(executor.nonce(caller), U256::zero());
+        assert_eq!(
+            executor.state().storage(contract, H256::zero()),
+            H256::repeat_byte(2)
+        );
+        let (_, logs) = executor.into_state().deconstruct();
+        assert_eq!(logs.into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_estimate_gas_with_access_list() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        let other = H160::repeat_byte(0xaa);
+        // POP(BALANCE(other))
+        let mut code = vec![0x73];
+        code.extend_from_slice(other.as_bytes());
+        code.extend_from_slice(&[0x31, 0x50]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code,
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let config = Config::cancun();
+        let executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let estimate = executor.estimate_gas(
+            caller,
+            contract,
+            U256::zero(),
+            &[],
+            100_000,
+            Vec::new(),
+            &[],
+            true,
+        );
+        assert_eq!(estimate.reason, ExitSucceed::Stopped.into());
+        // PUSH20, cold BALANCE and POP
+        assert_eq!(estimate.gas, 21_000 + 3 + 2_600 + 2);
+        // The listed address costs 2400 upfront, and BALANCE is then warm
+        assert_eq!(
+            estimate.with_access_list,
+            Some((vec![(other, Vec::new())], 21_000 + 2_400 + 3 + 100 + 2))
+        );
+        assert_eq!(executor.used_gas(), 0);
+
+        let estimate = executor.estimate_gas(
+            caller,
+            contract,
+            U256::zero(),
+            &[],
+            21_000,
+            Vec::new(),
+            &[],
+            true,
+        );
+        assert_eq!(estimate.reason, ExitError::OutOfGas.into());
+        assert_eq!(estimate.with_access_list, None);
+    }
+
+    #[test]
+    fn test_precompiles_are_warm() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        // BALANCE of the precompile, then stop
+        let mut code = vec![0x73];
+        code.extend_from_slice(LOG_PRECOMPILE.as_bytes());
+        code.extend_from_slice(&[0x31, 0x00]);
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code,
+                ..Default::default()
+            },
+        )]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .precompiles(&LogPrecompile)
+            .build();
+        let (reason, _) = executor.transact_call(
+            caller,
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+        assert!(!executor.state().is_cold(LOG_PRECOMPILE));
+        let warm_gas = executor.used_gas();
+        assert_eq!(warm_gas, 21_000 + 3 + config.gas_storage_read_warm);
+
+        // Without the precompile the address is cold
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        let (reason, _) = executor.transact_call(
+            caller,
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+        assert_eq!(
+            executor.used_gas() - warm_gas,
+            config.gas_account_access_cold - config.gas_storage_read_warm
+        );
+
+        // Precompile sets which don't list their addresses are warm too
+        struct UnlistedPrecompile;
+        impl PrecompileSet for UnlistedPrecompile {
+            fn execute(
+                &self,
+                _: &mut impl PrecompileHandle,
+            ) -> Option<Result<PrecompileOutput, PrecompileFailure>> {
+                None
+            }
+
+            fn is_precompile(&self, address: H160) -> bool {
+                address == LOG_PRECOMPILE
+            }
+        }
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .precompiles(&UnlistedPrecompile)
+            .build();
+        let (reason, _) = executor.transact_call(
+            caller,
+            contract,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+        assert_eq!(executor.used_gas(), warm_gas);
+    }
+
+    #[test]
+    fn test_transaction_used_gas() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        // ADD(1, 2)
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code: vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x00],
+                ..Default::default()
+            },
+        )]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        for transactions in 1..=2 {
+            let (reason, _) = executor.transact_call(
+                caller,
+                contract,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert!(reason.is_succeed());
+            assert_eq!(executor.transaction_used_gas(), 21_009);
+            assert_eq!(executor.used_gas(), transactions * 21_009);
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordedMetrics {
+        transactions: Vec<(ExitReason, u64)>,
+        precompile_calls: Vec<H160>,
+        code_hash_cache_hits: usize,
+    }
+
+    struct MetricsRecorder(Rc<RefCell<RecordedMetrics>>);
+
+    impl Metrics for MetricsRecorder {
+        fn transaction_executed(&mut self, reason: &ExitReason, used_gas: u64) {
+            self.0
+                .borrow_mut()
+                .transactions
+                .push((reason.clone(), used_gas));
+        }
+
+        fn precompile_called(&mut self, address: H160) {
+            self.0.borrow_mut().precompile_calls.push(address);
+        }
+
+        fn code_hash_cache_hit(&mut self) {
+            self.0.borrow_mut().code_hash_cache_hits += 1;
+        }
+    }
+
+    #[test]
+    fn test_metrics_hooks() {
+        let caller = H160::from_low_u64_be(1);
+        // Deploy the code `0x00` with CREATE2 and salt 0, EXTCODEHASH it,
+        // then deploy it again with salt 1
+        let factory = vec![
+            0x69, 0x60, 0x00, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3, 0x60, 0x00, 0x52,
+            0x60, 0x00, 0x60, 0x0a, 0x60, 0x16, 0x60, 0x00, 0xf5, 0x3f, 0x50, 0x60, 0x01, 0x60,
+            0x0a, 0x60, 0x16, 0x60, 0x00, 0xf5, 0x50, 0x60, 0x00, 0x60, 0x00, 0xf3,
+        ];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .precompiles(&LogPrecompile)
+            .build();
+        let recorded = Rc::new(RefCell::new(RecordedMetrics::default()));
+        executor.set_metrics(Some(Box::new(MetricsRecorder(recorded.clone()))));
+        assert!(executor.has_metrics());
+
+        let (reason, _) =
+            executor.transact_create(caller, U256::zero(), factory, 300_000, Vec::new());
+        assert!(reason.is_succeed());
+        let create_gas = executor.transaction_used_gas();
+
+        let (reason, _) = executor.transact_call(
+            caller,
+            LOG_PRECOMPILE,
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+        let call_gas = executor.transaction_used_gas();
+
+        // Rejected before execution
+        let (reason, _) = executor.transact_create(
+            caller,
+            U256::zero(),
+            vec![0x00; config.max_initcode_size.unwrap() + 1],
+            100_000,
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Error(ExitError::CreateContractLimit));
+        let rejected_gas = executor.transaction_used_gas();
+
+        // The intrinsic gas of the data exceeds the gas left
+        let (reason, _) = executor.transact_call(
+            caller,
+            LOG_PRECOMPILE,
+            U256::zero(),
+            vec![0xff; 62_500],
+            100_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+        let intrinsic_gas = executor.transaction_used_gas();
+
+        let recorded = recorded.borrow();
+        assert_eq!(
+            recorded.transactions,
+            [
+                (ExitSucceed::Returned.into(), create_gas),
+                (ExitSucceed::Stopped.into(), call_gas),
+                (ExitError::CreateContractLimit.into(), rejected_gas),
+                (ExitError::OutOfGas.into(), intrinsic_gas),
+            ]
+        );
+        assert_eq!(recorded.precompile_calls, [LOG_PRECOMPILE]);
+        // The deployed code hash, and the init code hash of the second CREATE2
+        assert_eq!(recorded.code_hash_cache_hits, 2);
+    }
+
+    #[test]
+    fn test_intrinsic_gas_failure_ends_transaction() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 20_000)
+            .backend(&backend)
+            .build();
+        let recorded = Rc::new(RefCell::new(RecordedMetrics::default()));
+        executor.set_metrics(Some(Box::new(MetricsRecorder(recorded.clone()))));
+
+        // Below the 21000 gas of a call
+        let (reason, _) = executor.transact_call(
+            H160::from_low_u64_be(1),
+            H160::from_low_u64_be(2),
+            U256::zero(),
+            Vec::new(),
+            20_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(reason, ExitReason::Error(ExitError::OutOfGas));
+        assert_eq!(
+            recorded.borrow().transactions,
+            [(reason, executor.transaction_used_gas())]
+        );
+    }
+
+    #[test]
+    fn test_reject_invalid_jumps() {
+        let caller = H160::from_low_u64_be(1);
+        // Deploy the code `PUSH1 3, JUMP`, jumping out of the code
+        let init_code = vec![
+            0x62, 0x60, 0x03, 0x56, 0x60, 0x00, 0x52, 0x60, 0x03, 0x60, 0x1d, 0xf3,
+        ];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut reasons = Vec::new();
+        for reject in [false, true] {
+            let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+                .backend(&backend)
+                .build();
+            executor.set_reject_invalid_jumps(reject);
+            assert_eq!(executor.reject_invalid_jumps(), reject);
+            let (reason, _) = executor.transact_create(
+                caller,
+                U256::zero(),
+                init_code.clone(),
+                100_000,
+                Vec::new(),
+            );
+            reasons.push(reason);
+        }
+        assert_eq!(
+            reasons,
+            [
+                ExitReason::Succeed(ExitSucceed::Returned),
+                ExitReason::Error(ExitError::InvalidJump),
+            ]
+        );
+    }
+
+    /// Precompile recording the contexts of its calls.
+    struct ContextPrecompile(RefCell<Vec<Context>>);
+
+    impl PrecompileSet for ContextPrecompile {
+        fn execute(
+            &self,
+            handle: &mut impl PrecompileHandle,
+        ) -> Option<Result<PrecompileOutput, PrecompileFailure>> {
+            if handle.code_address() != LOG_PRECOMPILE {
+                return None;
+            }
+            self.0.borrow_mut().push(handle.context().clone());
+            Some(Ok(PrecompileOutput {
+                exit_status: ExitSucceed::Stopped,
+                output: Vec::new(),
+            }))
+        }
+
+        fn is_precompile(&self, address: H160) -> bool {
+            address == LOG_PRECOMPILE
+        }
+
+        fn precompile_addresses(&self) -> Vec<H160> {
+            vec![LOG_PRECOMPILE]
+        }
+    }
+
+    #[test]
+    fn test_context_call_scheme_and_code_address() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        // DELEGATECALL the precompile, then stop
+        let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+        code.extend_from_slice(LOG_PRECOMPILE.as_bytes());
+        code.extend_from_slice(&[0x5a, 0xf4, 0x00]);
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code,
+                ..Default::default()
+            },
+        )]);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let precompile = ContextPrecompile(RefCell::new(Vec::new()));
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .precompiles(&precompile)
+            .build();
+
+        for address in [contract, LOG_PRECOMPILE] {
+            let (reason, _) = executor.transact_call(
+                caller,
+                address,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert!(reason.is_succeed());
+        }
+
+        let contexts = precompile.0.borrow();
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0].address, contract);
+        assert_eq!(contexts[0].caller, caller);
+        assert_eq!(contexts[0].code_address, LOG_PRECOMPILE);
+        assert_eq!(contexts[0].scheme, Some(CallScheme::DelegateCall));
+        assert_eq!(contexts[1].address, LOG_PRECOMPILE);
+        assert_eq!(contexts[1].code_address, LOG_PRECOMPILE);
+        assert_eq!(contexts[1].scheme, Some(CallScheme::Call));
+    }
+
+    #[test]
+    fn test_transact_with_fee_payer() {
+        let caller = H160::from_low_u64_be(1);
+        let target = H160::from_low_u64_be(2);
+        let fee_payer = H160::from_low_u64_be(3);
+        let coinbase = H160::zero();
+        let state = BTreeMap::from([
+            (
+                caller,
+                MemoryAccount {
+                    balance: U256::from(10),
+                    ..Default::default()
+                },
+            ),
+            (
+                fee_payer,
+                MemoryAccount {
+                    balance: U256::from(200_000),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let vicinity = MemoryVicinity {
+            effective_gas_price: U256::from(2),
+            ..memory_vicinity()
+        };
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let tx = Transaction {
+            caller,
+            to: Some(target),
+            value: U256::from(10),
+            gas_limit: 50_000,
+            fee_payer: Some(fee_payer),
+            ..Transaction::default()
+        };
+        assert_eq!(tx.gas_payer(), fee_payer);
+        let (reason, _) = executor.transact(tx.clone()).unwrap();
+        assert!(reason.is_succeed());
+        let balance =
+            |executor: &StackExecutor<_, _>, address| executor.state().basic(address).balance;
+        assert_eq!(balance(&executor, caller), U256::zero());
+        assert_eq!(balance(&executor, target), U256::from(10));
+        assert_eq!(
+            balance(&executor, fee_payer),
+            U256::from(200_000 - 2 * 21_000)
+        );
+        // Base fee of 1 burnt
+        assert_eq!(balance(&executor, coinbase), U256::from(21_000));
+        assert_eq!(executor.nonce(caller), U256::one());
+
+        // The payer can't afford the gas, or the caller the value
+        for tx in [
+            Transaction {
+                nonce: 1,
+                value: U256::zero(),
+                gas_limit: 100_000,
+                ..tx.clone()
+            },
+            Transaction {
+                nonce: 1,
+                ..tx.clone()
+            },
+            Transaction {
+                nonce: 1,
+                value: U256::zero(),
+                fee_payer: None,
+                ..tx.clone()
+            },
+        ] {
+            assert_eq!(
+                executor.transact(tx),
+                Err(InvalidTransaction::InsufficientFunds)
+            );
+        }
+        assert_eq!(executor.transact(tx), Err(InvalidTransaction::NonceTooLow));
+        assert_eq!(
+            balance(&executor, fee_payer),
+            U256::from(200_000 - 2 * 21_000)
+        );
+    }
+
+    #[test]
+    fn test_settle_fees_overflow_credits_nothing() {
+        let caller = H160::from_low_u64_be(1);
+        let coinbase = H160::zero();
+        let state = BTreeMap::from([(
+            coinbase,
+            MemoryAccount {
+                balance: U256::MAX,
+                ..Default::default()
+            },
+        )]);
+        let vicinity = MemoryVicinity {
+            effective_gas_price: U256::from(2),
+            ..memory_vicinity()
+        };
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        let (reason, _) = executor.transact_call(
+            caller,
+            H160::from_low_u64_be(2),
+            U256::zero(),
+            Vec::new(),
+            50_000,
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(reason.is_succeed());
+
+        assert_eq!(
+            executor.settle_fees(caller, U256::from(100_000), None),
+            Err(ExitError::BalanceOverflow)
+        );
+        assert_eq!(executor.state().basic(caller).balance, U256::zero());
+        assert_eq!(executor.state().basic(coinbase).balance, U256::MAX);
+    }
+
+    #[test]
+    fn test_transact_fees_of_each_transaction() {
+        let caller = H160::from_low_u64_be(1);
+        let target = H160::from_low_u64_be(2);
+        let coinbase = H160::zero();
+        let state = BTreeMap::from([(
+            caller,
+            MemoryAccount {
+                balance: U256::from(1_000_000),
+                ..Default::default()
+            },
+        )]);
+        let vicinity = MemoryVicinity {
+            effective_gas_price: U256::from(2),
+            blob_params: Some(BlobParams::CANCUN),
+            block_excess_blob_gas: Some(4_000_000),
+            ..memory_vicinity()
+        };
+        let blob_gas_price = u64::try_from(vicinity.blob_gas_price().unwrap()).unwrap();
+        assert_eq!(blob_gas_price, 3);
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        let balance =
+            |executor: &StackExecutor<_, _>, address| executor.state().basic(address).balance;
+
+        let tx = Transaction {
+            caller,
+            to: Some(target),
+            gas_limit: 50_000,
+            ..Transaction::default()
+        };
+        let (reason, _) = executor.transact(tx.clone()).unwrap();
+        assert!(reason.is_succeed());
+        assert_eq!(executor.fee(U256::from(2)), U256::from(2 * 21_000));
+
+        // Charged for its own gas and its blob gas only
+        let (reason, _) = executor
+            .transact(Transaction {
+                nonce: 1,
+                blob_hashes: vec![U256::one(); 2],
+                ..tx
+            })
+            .unwrap();
+        assert!(reason.is_succeed());
+        assert_eq!(executor.fee(U256::from(2)), U256::from(2 * 21_000));
+        let blob_fee = 2 * GAS_PER_BLOB * blob_gas_price;
+        assert_eq!(
+            balance(&executor, caller),
+            U256::from(1_000_000 - 4 * 21_000 - blob_fee)
+        );
+        // Base fee of 1 burnt
+        assert_eq!(balance(&executor, coinbase), U256::from(2 * 21_000));
+    }
+
+    /// Deposit minting its value to the caller, whose gas is not paid.
+    struct Deposit {
+        used_gas: Rc<RefCell<Option<u64>>>,
+    }
+
+    impl CustomTransactionType for Deposit {
+        fn intrinsic_gas(&self, _tx: &Transaction, _config: &Config) -> u64 {
+            1_000
+        }
+
+        fn charges_fees(&self, _tx: &Transaction) -> bool {
+            false
+        }
+
+        fn pre_execution(
+            &mut self,
+            tx: &Transaction,
+            state: &mut dyn TransactionHookState,
+        ) -> Result<(), InvalidTransaction> {
+            state
+                .credit(tx.caller, tx.value)
+                .map_err(|_| InvalidTransaction::InsufficientFunds)
+        }
+
+        fn post_execution(
+            &mut self,
+            _tx: &Transaction,
+            _reason: &ExitReason,
+            used_gas: u64,
+            _state: &mut dyn TransactionHookState,
+        ) {
+            *self.used_gas.borrow_mut() = Some(used_gas);
+        }
+    }
+
+    #[test]
+    fn test_transact_with_free_gas() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        let coinbase = H160::zero();
+        // SSTORE(0, GASPRICE)
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code: vec![0x3a, 0x60, 0x00, 0x55],
+                ..Default::default()
+            },
+        )]);
+        let vicinity = MemoryVicinity {
+            effective_gas_price: U256::from(2),
+            ..memory_vicinity()
+        };
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let tx = Transaction {
+            caller,
+            to: Some(contract),
+            gas_limit: 100_000,
+            ..Transaction::default()
+        };
+        assert_eq!(
+            executor.transact(tx.clone()),
+            Err(InvalidTransaction::InsufficientFunds)
+        );
+
+        executor.set_free_gas(true);
+        assert!(executor.free_gas());
+        let (reason, _) = executor.transact(tx.clone()).unwrap();
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        // The gas is metered, but nobody pays or earns it
+        assert!(executor.used_gas() > 21_000);
+        assert_eq!(executor.state().basic(caller).balance, U256::zero());
+        assert!(!executor.state().exists(coinbase));
+        assert_eq!(executor.storage(contract, H256::zero()), H256::zero());
+        assert_eq!(executor.nonce(caller), U256::one());
+
+        // The value is still paid
+        assert_eq!(
+            executor.transact(Transaction {
+                nonce: 1,
+                value: U256::one(),
+                ..tx
+            }),
+            Err(InvalidTransaction::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn test_custom_transaction_type() {
+        let caller = H160::from_low_u64_be(1);
+        let target = H160::from_low_u64_be(2);
+        let coinbase = H160::from_low_u64_be(3);
+        let vicinity = MemoryVicinity {
+            effective_gas_price: U256::from(2),
+            block_coinbase: coinbase,
+            ..memory_vicinity()
+        };
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let tx = Transaction {
+            caller,
+            to: Some(target),
+            value: U256::from(10),
+            gas_limit: 50_000,
+            custom_type: Some(0x7e),
+            ..Transaction::default()
+        };
+        let signed = SignedTransaction {
+            tx_type: TransactionType::Custom(0x7e),
+            transaction: tx.clone(),
+            ..SignedTransaction::default()
+        };
+        assert!(!executor.supports_transaction_type(TransactionType::Custom(0x7e)));
+        assert_eq!(
+            executor.prevalidate(&signed),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+        assert_eq!(
+            executor.transact(tx.clone()),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+
+        let used_gas = Rc::new(RefCell::new(None));
+        executor
+            .set_custom_transaction_type(
+                0x7e,
+                Some(Box::new(Deposit {
+                    used_gas: used_gas.clone(),
+                })),
+            )
+            .unwrap();
+        assert!(executor.supports_transaction_type(TransactionType::Custom(0x7e)));
+        assert_eq!(executor.prevalidate(&signed), Ok(()));
+        // Checked against the custom intrinsic gas of 1000
+        let mut underpriced = signed.clone();
+        underpriced.transaction.gas_limit = 999;
+        assert_eq!(
+            executor.prevalidate(&underpriced),
+            Err(InvalidTransaction::IntrinsicGasTooLow)
+        );
+        let (reason, _) = executor.transact(tx).unwrap();
+        assert!(reason.is_succeed());
+        // The custom intrinsic gas replaces the 21000 gas of a call
+        assert_eq!(*used_gas.borrow(), Some(1_000));
+        let balance = |address| executor.state().basic(address).balance;
+        assert_eq!(balance(caller), U256::zero());
+        assert_eq!(balance(target), U256::from(10));
+        assert_eq!(balance(coinbase), U256::zero());
+
+        // The bytes of the standard types are reserved
+        for tx_type in [0x00, 0x02, 0x04, 0xc0, 0xff] {
+            assert_eq!(
+                executor.set_custom_transaction_type(tx_type, None),
+                Err(InvalidTransaction::TypeNotSupported)
+            );
+        }
+    }
+
+    /// Mints the value of the transaction to its caller, who pays the fees.
+    struct Mint;
+
+    impl CustomTransactionType for Mint {
+        fn intrinsic_gas(&self, _tx: &Transaction, _config: &Config) -> u64 {
+            21_000
+        }
+
+        fn pre_execution(
+            &mut self,
+            tx: &Transaction,
+            state: &mut dyn TransactionHookState,
+        ) -> Result<(), InvalidTransaction> {
+            state
+                .credit(tx.caller, tx.value)
+                .map_err(|_| InvalidTransaction::InsufficientFunds)
+        }
+    }
+
+    #[test]
+    fn test_custom_transaction_rejection_discards_hook_changes() {
+        let caller = H160::from_low_u64_be(1);
+        let vicinity = MemoryVicinity {
+            effective_gas_price: U256::from(2),
+            ..memory_vicinity()
+        };
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        executor
+            .set_custom_transaction_type(0x7f, Some(Box::new(Mint)))
+            .unwrap();
+
+        // The minted value can't pay the fees
+        let tx = Transaction {
+            caller,
+            to: Some(H160::from_low_u64_be(2)),
+            value: U256::from(10),
+            gas_limit: 50_000,
+            custom_type: Some(0x7f),
+            ..Transaction::default()
+        };
+        assert_eq!(
+            executor.transact(tx),
+            Err(InvalidTransaction::InsufficientFunds)
+        );
+        assert_eq!(executor.state().basic(caller).balance, U256::zero());
+        assert_eq!(executor.state().metadata().depth(), None);
+    }
+
+    /// Opcode `0xc0` pushing `42`.
+    struct PushAnswer;
+
+    impl CustomOpcodeHandler for PushAnswer {
+        fn gas_cost(&self, opcode: Opcode, _stack: &Stack) -> Option<u64> {
+            (opcode == Opcode(0xc0)).then_some(7)
+        }
+
+        fn execute(&mut self, _opcode: Opcode, machine: &mut Machine) -> Result<(), ExitError> {
+            machine.stack_mut().push(U256::from(42))
+        }
+    }
+
+    #[test]
+    fn test_custom_opcode_handler() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        let unhandled = H160::from_low_u64_be(3);
+        // `0xc0`, then return the top of the stack
+        let code = vec![0xc0, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([
+                (
+                    contract,
+                    MemoryAccount {
+                        code,
+                        ..MemoryAccount::default()
+                    },
+                ),
+                (
+                    unhandled,
+                    MemoryAccount {
+                        code: vec![0xc1],
+                        ..MemoryAccount::default()
+                    },
+                ),
+            ]),
+        );
+        let config = Config::cancun();
+        let mut results = Vec::new();
+        for handler in [false, true] {
+            let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+                .backend(&backend)
+                .build();
+            if handler {
+                executor.set_custom_opcode_handler(Some(Box::new(PushAnswer)));
+            }
+            assert_eq!(executor.has_custom_opcode_handler(), handler);
+            for address in [contract, unhandled] {
+                let gas = executor.used_gas();
+                let (reason, output) = executor.transact_call(
+                    caller,
+                    address,
+                    U256::zero(),
+                    Vec::new(),
+                    100_000,
+                    Vec::new(),
+                    Vec::new(),
+                );
+                results.push((reason, output, executor.used_gas() - gas));
+            }
+        }
+
+        let invalid = |opcode| ExitReason::Error(ExitError::InvalidCode(Opcode(opcode)));
+        assert_eq!(results[0].0, invalid(0xc0));
+        assert_eq!(results[1].0, invalid(0xc1));
+        assert_eq!(results[2].0, ExitSucceed::Returned.into());
+        assert_eq!(results[2].1, U256::from(42).to_big_endian().to_vec());
+        // 7 for the opcode, 12 for the rest of the code and 3 for the memory
+        assert_eq!(results[2].2, 21_000 + 7 + 12 + 3);
+        assert_eq!(results[3].0, invalid(0xc1));
+    }
+
+    #[test]
+    fn test_tx_env_overrides_state_transaction_data() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+        assert_eq!(Handler::origin(&executor), vicinity.origin);
+        assert_eq!(Handler::gas_price(&executor), vicinity.effective_gas_price);
+
+        let tx_env = TxEnv {
+            gas_price: U256::from(7),
+            effective_gas_price: U256::from(5),
+            origin: H160::from_low_u64_be(0xc0),
+            blob_hashes: vec![U256::one()],
+        };
+        executor.set_tx_env(tx_env.clone());
+        assert_eq!(executor.tx_env(), Some(&tx_env));
+        assert_eq!(Handler::origin(&executor), tx_env.origin);
+        assert_eq!(Handler::gas_price(&executor), tx_env.effective_gas_price);
+        assert_eq!(Handler::get_blob_hash(&executor, 0), Some(U256::one()));
+        assert_eq!(Handler::get_blob_hash(&executor, 1), None);
+        // Block data is still provided by the state
+        assert_eq!(Handler::chain_id(&executor), vicinity.chain_id);
+    }
+
+    /// Execute a call transaction with a single authorization to the zero
+    /// address, and return the resulting account of `authority`.
+    fn clear_delegation(authority: MemoryAccount, nonce: u64) -> Option<MemoryAccount> {
+        let authority_address = H160::from_low_u64_be(0xa0);
+        let vicinity = memory_vicinity();
+        let mut state = BTreeMap::new();
+        state.insert(authority_address, authority);
+        let mut backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::prague();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let authorization = Authorization::new(authority_address, H160::zero(), nonce, true);
+        assert!(authorization.is_delegation_clearing());
+        let (reason, _) = executor.transact_call(
+            H160::from_low_u64_be(0xc0),
+            H160::from_low_u64_be(0xb0),
+            U256::zero(),
+            Vec::new(),
+            100_000,
+            Vec::new(),
+            vec![authorization],
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        let accessed = executor.state().metadata().accessed().as_ref().unwrap();
+        assert!(!accessed.is_authority(authority_address));
+
+        let (values, logs) = executor.into_state().deconstruct();
+        backend.apply(values, logs, true);
+        backend.state().get(&authority_address).cloned()
+    }
+
+    #[test]
+    fn test_authorization_to_zero_address_clears_delegation() {
+        let delegated = Authorization::new(H160::zero(), H160::from_low_u64_be(0xde), 0, true);
+        let account = clear_delegation(
+            MemoryAccount {
+                code: delegated.delegation_code(),
+                ..Default::default()
+            },
+            0,
+        )
+        .expect("authority must exist");
+        assert!(account.code.is_empty());
+        assert_eq!(account.nonce, U256::one());
+    }
+
+    #[test]
+    fn test_delegation_clearing_touches_empty_authority() {
+        // The nonce is incremented, so the empty authority is created
+        let account = clear_delegation(MemoryAccount::default(), 0).expect("authority must exist");
+        assert!(account.code.is_empty());
+        assert_eq!(account.nonce, U256::one());
+    }
+
+    #[test]
+    fn test_delegation_to_account_with_storage_only() {
+        let authority = H160::from_low_u64_be(0xa0);
+        let delegate = H160::from_low_u64_be(0xde);
+        let key = |index| H256::from_low_u64_be(index);
+        let vicinity = memory_vicinity();
+        let mut state = BTreeMap::new();
+        // Account with zero balance and nonce, but non-empty storage exists in the trie
+        state.insert(
+            authority,
+            MemoryAccount {
+                storage: [(key(0), key(1))].into_iter().collect(),
+                ..Default::default()
+            },
+        );
+        // SSTORE(1, SLOAD(0))
+        state.insert(
+            delegate,
+            MemoryAccount {
+                code: vec![0x60, 0x00, 0x54, 0x60, 0x01, 0x55],
+                ..Default::default()
+            },
+        );
+        let mut backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::prague();
+        let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+            .backend(&backend)
+            .build();
+
+        let authorization = Authorization::new(authority, delegate, 0, true);
+        let (reason, _) = executor.transact_call(
+            H160::from_low_u64_be(0xc0),
+            authority,
+            U256::zero(),
+            Vec::new(),
+            200_000,
+            Vec::new(),
+            vec![authorization.clone()],
+        );
+        assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+        let refund = config.gas_per_empty_account_cost - config.gas_per_auth_base_cost;
+        assert_eq!(
+            executor.state().metadata().gasometer().refunded_gas(),
+            i64::try_from(refund).unwrap()
+        );
+
+        let (values, logs) = executor.into_state().deconstruct();
+        backend.apply(values, logs, true);
+        let account = &backend.state()[&authority];
+        assert_eq!(account.code, authorization.delegation_code());
+        assert_eq!(account.nonce, U256::one());
+        assert_eq!(
+            account.storage,
+            [(key(0), key(1)), (key(1), key(1))].into_iter().collect()
+        );
+
+        // An empty account without storage exists in the trie too, unlike a
+        // missing one
+        for (state, refunded) in [
+            (
+                BTreeMap::from([(authority, MemoryAccount::default())]),
+                refund,
+            ),
+            (BTreeMap::new(), 0),
+        ] {
+            let backend = MemoryBackend::new(&vicinity, state);
+            let mut executor = StackExecutorBuilder::new(&config, 1_000_000)
+                .backend(&backend)
+                .build();
+            let (reason, _) = executor.transact_call(
+                H160::from_low_u64_be(0xc0),
+                H160::from_low_u64_be(0xb0),
+                U256::zero(),
+                Vec::new(),
+                200_000,
+                Vec::new(),
+                vec![authorization.clone()],
+            );
+            assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Stopped));
+            assert_eq!(
+                executor.state().metadata().gasometer().refunded_gas(),
+                i64::try_from(refunded).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_delegation_clearing_with_invalid_nonce_is_skipped() {
+        let delegated = Authorization::new(H160::zero(), H160::from_low_u64_be(0xde), 0, true);
+        let code = delegated.delegation_code();
+        let account = clear_delegation(
+            MemoryAccount {
+                code: code.clone(),
+                ..Default::default()
+            },
+            1,
+        )
+        .expect("authority must exist");
+        assert_eq!(account.code, code);
+        assert_eq!(account.nonce, U256::zero());
+    }
+}
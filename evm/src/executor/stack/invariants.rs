@@ -0,0 +1,25 @@
+//! Invariants of the state at the end of a transaction.
+
+use primitive_types::H160;
+
+/// State left inconsistent by a transaction.
+///
+/// With the `strict-invariants` feature, the executor checks the state after
+/// each transaction: debug builds panic on a violation, release builds
+/// report it with [`super::StackExecutor::invariant_violation`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InvariantViolation {
+    /// Substates entered by the transaction were not exited, `depth` being
+    /// the depth of the innermost one.
+    DirtySubstate { depth: usize },
+    /// The executor still tracks `frames` substates, e.g. for the log
+    /// subscriber or the deployed code hashes.
+    UnbalancedFrames { frames: usize },
+    /// The refund counter of the transaction is negative.
+    NegativeRefund(i64),
+    /// Transient storage slots survived the transaction.
+    TransientStorageNotCleared { entries: usize },
+    /// An account deleted by the transaction, after EIP-6780, was not
+    /// created by it.
+    DeletedAccountNotCreated(H160),
+}
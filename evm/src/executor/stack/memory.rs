@@ -1,11 +1,15 @@
-use crate::backend::{Apply, Backend, Basic, Log};
+use crate::backend::{
+    delegate_environment, is_canonical_order, slice_code, Apply, Backend, Basic, Log, OrderedLogs,
+};
 use crate::core::utils::{U256_ONE, U256_ZERO, U64_MAX};
 use crate::executor::stack::executor::{
     Accessed, Authorization, StackState, StackSubstateMetadata,
 };
+use crate::executor::stack::invariants::InvariantViolation;
 use crate::prelude::*;
 use crate::{ExitError, Transfer};
 use core::mem;
+use core::ops::Range;
 use primitive_types::{H160, H256, U256};
 
 #[derive(Clone, Debug)]
@@ -15,6 +19,40 @@ pub struct MemoryStackAccount {
     pub reset: bool,
 }
 
+/// Address of the `RIPEMD-160` precompile.
+const RIPEMD160_ADDRESS: H160 = H160([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+
+/// Size of the data accumulated by a memory substate and its parents.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SubstateStats {
+    /// Number of substates, including the root one.
+    pub depth: usize,
+    /// Cached (touched) accounts.
+    pub accounts: usize,
+    /// Storage overlay entries.
+    pub storages: usize,
+    /// Transient storage entries.
+    pub tstorages: usize,
+    /// Logs.
+    pub logs: usize,
+    /// Deleted accounts.
+    pub deletes: usize,
+    /// Created accounts.
+    pub creates: usize,
+}
+
+/// Accounts deleted after a transaction, to debug state root mismatches of
+/// historical replays, which are mostly related to state clearing.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateClearingReport {
+    /// Touched empty accounts, deleted by the `EIP-161` state clearing.
+    pub cleared: Vec<H160>,
+    /// Touched empty accounts kept as the config does not clear them.
+    pub kept_empty: Vec<H160>,
+    /// Accounts deleted by `SELFDESTRUCT`.
+    pub selfdestructed: Vec<H160>,
+}
+
 #[derive(Clone, Debug)]
 pub struct MemoryStackSubstate<'config> {
     metadata: StackSubstateMetadata<'config>,
@@ -64,6 +102,10 @@ impl<'config> MemoryStackSubstate<'config> {
     /// Deconstruct the memory stack substate, return state to be applied. Panic if the
     /// substate is not in the top-level substate.
     ///
+    /// The output is in canonical order: apply operations as checked by
+    /// [`is_canonical_order`], storage of each account in ascending key order,
+    /// and logs in emission order.
+    ///
     /// # Panics
     /// Panic if parent presents
     #[must_use]
@@ -72,7 +114,7 @@ impl<'config> MemoryStackSubstate<'config> {
         backend: &B,
     ) -> (
         impl IntoIterator<Item = Apply<impl IntoIterator<Item = (H256, H256)>>>,
-        impl IntoIterator<Item = Log>,
+        OrderedLogs,
     ) {
         assert!(self.parent.is_none());
 
@@ -129,8 +171,60 @@ impl<'config> MemoryStackSubstate<'config> {
         for address in self.deletes {
             applies.push(Apply::Delete { address });
         }
+        debug_assert!(is_canonical_order(&applies));
+
+        (applies, OrderedLogs::new(self.logs))
+    }
+
+    /// Statistics of the data held by the substate and all its parents, to
+    /// monitor memory usage of long executions.
+    #[must_use]
+    pub fn substate_stats(&self) -> SubstateStats {
+        let mut stats = self
+            .parent
+            .as_ref()
+            .map_or_else(SubstateStats::default, |parent| parent.substate_stats());
+        stats.depth += 1;
+        stats.accounts += self.accounts.len();
+        stats.storages += self.storages.len();
+        stats.tstorages += self.tstorages.len();
+        stats.logs += self.logs.len();
+        stats.deletes += self.deletes.len();
+        stats.creates += self.creates.len();
+        stats
+    }
+
+    /// Drop cached entries which are identical to the backend state, and
+    /// return the number of dropped entries.
+    ///
+    /// Only non-empty accounts are dropped, as empty touched accounts are
+    /// still to be cleared by EIP-161. Compaction only applies to the
+    /// top-level substate, it does nothing if a child substate is entered.
+    pub fn compact<B: Backend>(&mut self, backend: &B) -> usize {
+        if self.parent.is_some() {
+            return 0;
+        }
+        let before = self.accounts.len() + self.storages.len();
+
+        let (accounts, deletes, creates) = (&self.accounts, &self.deletes, &self.creates);
+        self.storages.retain(|(address, key), value| {
+            accounts.get(address).is_some_and(|account| account.reset)
+                || deletes.contains(address)
+                || creates.contains(address)
+                || backend.storage(*address, *key) != *value
+        });
+        self.accounts.retain(|address, account| {
+            account.reset
+                || account.code.is_some()
+                || deletes.contains(address)
+                || creates.contains(address)
+                || account.basic != backend.basic(*address)
+                || (account.basic.balance == U256_ZERO
+                    && account.basic.nonce == U256_ZERO
+                    && backend.code(*address).is_empty())
+        });
 
-        (applies, self.logs)
+        before - self.accounts.len() - self.storages.len()
     }
 
     pub fn enter(&mut self, gas_limit: u64, is_static: bool) {
@@ -269,6 +363,26 @@ impl<'config> MemoryStackSubstate<'config> {
         None
     }
 
+    /// Apply the storage changes of `address` made by the substate and its
+    /// parents to `storage`, the storage of the account in the backend.
+    #[cfg(feature = "std")]
+    fn overlay_storage(&self, address: H160, storage: &mut BTreeMap<H256, H256>) {
+        if let Some(parent) = self.parent.as_ref() {
+            parent.overlay_storage(address, storage);
+        }
+        if self
+            .accounts
+            .get(&address)
+            .is_some_and(|account| account.reset)
+        {
+            storage.clear();
+        }
+        let keys = (address, H256::zero())..=(address, H256::repeat_byte(0xff));
+        for ((_, key), value) in self.storages.range(keys) {
+            storage.insert(*key, *value);
+        }
+    }
+
     #[must_use]
     pub fn known_original_storage(&self, address: H160) -> Option<H256> {
         if let Some(account) = self.accounts.get(&address) {
@@ -393,48 +507,78 @@ impl<'config> MemoryStackSubstate<'config> {
         self.account_mut(address, backend).code = Some(code);
     }
 
+    /// Add `value` to the balance of `address`.
+    ///
     /// # Errors
-    /// Return `ExitError`
+    /// Return `ExitError::BalanceOverflow` if the balance overflows, the balance
+    /// is left unchanged.
+    pub fn credit<B: Backend>(
+        &mut self,
+        address: H160,
+        value: U256,
+        backend: &B,
+    ) -> Result<(), ExitError> {
+        let target = self.account_mut(address, backend);
+        target.basic.balance = target
+            .basic
+            .balance
+            .checked_add(value)
+            .ok_or(ExitError::BalanceOverflow)?;
+        Ok(())
+    }
+
+    /// Subtract `value` from the balance of `address`.
+    ///
+    /// # Errors
+    /// Return `ExitError::OutOfFund` if the balance is lower than `value`, the
+    /// balance is left unchanged.
+    pub fn debit<B: Backend>(
+        &mut self,
+        address: H160,
+        value: U256,
+        backend: &B,
+    ) -> Result<(), ExitError> {
+        let source = self.account_mut(address, backend);
+        source.basic.balance = source
+            .basic
+            .balance
+            .checked_sub(value)
+            .ok_or(ExitError::OutOfFund)?;
+        Ok(())
+    }
+
+    /// # Errors
+    /// Return `ExitError::OutOfFund` if the source balance is too low, or
+    /// `ExitError::BalanceOverflow` if the target balance overflows. Balances
+    /// are left unchanged on error.
     pub fn transfer<B: Backend>(
         &mut self,
         transfer: &Transfer,
         backend: &B,
     ) -> Result<(), ExitError> {
-        {
-            let source = self.account_mut(transfer.source, backend);
-            if source.basic.balance < transfer.value {
-                return Err(ExitError::OutOfFund);
-            }
-            source.basic.balance -= transfer.value;
+        self.debit(transfer.source, transfer.value, backend)?;
+        if let Err(e) = self.credit(transfer.target, transfer.value, backend) {
+            // Restore the debited value, it can't overflow.
+            self.account_mut(transfer.source, backend).basic.balance += transfer.value;
+            return Err(e);
         }
-
-        {
-            let target = self.account_mut(transfer.target, backend);
-            target.basic.balance = target.basic.balance.saturating_add(transfer.value);
-        }
-
         Ok(())
     }
 
     /// Only needed for jsontests.
     /// # Errors
-    /// Return `ExitError`
+    /// Return `ExitError::OutOfFund` if the balance is lower than `value`.
     pub fn withdraw<B: Backend>(
         &mut self,
         address: H160,
         value: U256,
         backend: &B,
     ) -> Result<(), ExitError> {
-        let source = self.account_mut(address, backend);
-        if source.basic.balance < value {
-            return Err(ExitError::OutOfFund);
-        }
-        source.basic.balance -= value;
-
-        Ok(())
+        self.debit(address, value, backend)
     }
 
-    // Only needed for jsontests.
+    /// Add `value` to the balance of `address`, saturating at `U256::MAX`.
+    /// Use [`Self::credit`] to detect the overflow.
     pub fn deposit<B: Backend>(&mut self, address: H160, value: U256, backend: &B) {
         let target = self.account_mut(address, backend);
         target.basic.balance = target.basic.balance.saturating_add(value);
@@ -468,6 +612,36 @@ impl<'config> MemoryStackSubstate<'config> {
         self.tstorages.insert((address, key), value);
     }
 
+    /// Discard the transient storage of the substate.
+    pub fn clear_tstorage(&mut self) {
+        self.tstorages.clear();
+    }
+
+    /// Check the invariants of the root substate at the end of a
+    /// transaction: its transient storage is discarded, and after EIP-6780
+    /// it only deletes the accounts created in the transaction.
+    ///
+    /// # Errors
+    /// Return the first violation found.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        if !self.tstorages.is_empty() {
+            return Err(InvariantViolation::TransientStorageNotCleared {
+                entries: self.tstorages.len(),
+            });
+        }
+        if self
+            .metadata
+            .gasometer()
+            .config()
+            .has_restricted_selfdestruct
+        {
+            if let Some(address) = self.deletes.difference(&self.creates).next() {
+                return Err(InvariantViolation::DeletedAccountNotCreated(*address));
+            }
+        }
+        Ok(())
+    }
+
     /// Get authority target from the current state. If it's `None` just take a look
     /// recursively in the parent state.
     fn get_authority_target_recursive(&self, authority: H160) -> Option<H160> {
@@ -492,40 +666,7 @@ pub struct MemoryStackState<'backend, 'config, B> {
 }
 
 impl<B: Backend> Backend for MemoryStackState<'_, '_, B> {
-    fn gas_price(&self) -> U256 {
-        self.backend.gas_price()
-    }
-    fn origin(&self) -> H160 {
-        self.backend.origin()
-    }
-    fn block_hash(&self, number: U256) -> H256 {
-        self.backend.block_hash(number)
-    }
-    fn block_number(&self) -> U256 {
-        self.backend.block_number()
-    }
-    fn block_coinbase(&self) -> H160 {
-        self.backend.block_coinbase()
-    }
-    fn block_timestamp(&self) -> U256 {
-        self.backend.block_timestamp()
-    }
-    fn block_difficulty(&self) -> U256 {
-        self.backend.block_difficulty()
-    }
-    fn block_randomness(&self) -> Option<H256> {
-        self.backend.block_randomness()
-    }
-    fn block_gas_limit(&self) -> U256 {
-        self.backend.block_gas_limit()
-    }
-    fn block_base_fee_per_gas(&self) -> U256 {
-        self.backend.block_base_fee_per_gas()
-    }
-
-    fn chain_id(&self) -> U256 {
-        self.backend.chain_id()
-    }
+    delegate_environment!(backend: backend);
 
     fn exists(&self, address: H160) -> bool {
         self.substate.known_account(address).is_some() || self.backend.exists(address)
@@ -543,6 +684,13 @@ impl<B: Backend> Backend for MemoryStackState<'_, '_, B> {
             .unwrap_or_else(|| self.backend.code(address))
     }
 
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        if let Some(code) = self.substate.known_code(address) {
+            return slice_code(&code, range);
+        }
+        self.backend.code_slice(address, range)
+    }
+
     fn storage(&self, address: H160, key: H256) -> H256 {
         self.substate
             .known_storage(address, key)
@@ -553,6 +701,15 @@ impl<B: Backend> Backend for MemoryStackState<'_, '_, B> {
         self.backend.is_empty_storage(address)
     }
 
+    #[cfg(feature = "std")]
+    fn iter_storage(&self, address: H160) -> Option<Box<dyn Iterator<Item = (H256, H256)> + '_>> {
+        let mut storage: BTreeMap<_, _> = self.backend.iter_storage(address)?.collect();
+        self.substate.overlay_storage(address, &mut storage);
+        Some(Box::new(
+            storage.into_iter().filter(|(_, value)| !value.is_zero()),
+        ))
+    }
+
     fn original_storage(&self, address: H160, key: H256) -> Option<H256> {
         if let Some(value) = self.substate.known_original_storage(address) {
             return Some(value);
@@ -560,12 +717,6 @@ impl<B: Backend> Backend for MemoryStackState<'_, '_, B> {
 
         self.backend.original_storage(address, key)
     }
-    fn blob_gas_price(&self) -> Option<u128> {
-        self.backend.blob_gas_price()
-    }
-    fn get_blob_hash(&self, index: usize) -> Option<U256> {
-        self.backend.get_blob_hash(index)
-    }
 }
 
 impl<'config, B: Backend> StackState<'config> for MemoryStackState<'_, 'config, B> {
@@ -586,11 +737,17 @@ impl<'config, B: Backend> StackState<'config> for MemoryStackState<'_, 'config,
     }
 
     fn exit_revert(&mut self) -> Result<(), ExitError> {
-        self.substate.exit_revert()
+        let ripemd_touched = self.substate.accounts.contains_key(&RIPEMD160_ADDRESS);
+        self.substate.exit_revert()?;
+        self.keep_ripemd_touched(ripemd_touched);
+        Ok(())
     }
 
     fn exit_discard(&mut self) -> Result<(), ExitError> {
-        self.substate.exit_discard()
+        let ripemd_touched = self.substate.accounts.contains_key(&RIPEMD160_ADDRESS);
+        self.substate.exit_discard()?;
+        self.keep_ripemd_touched(ripemd_touched);
+        Ok(())
     }
 
     #[must_use]
@@ -658,6 +815,14 @@ impl<'config, B: Backend> StackState<'config> for MemoryStackState<'_, 'config,
         self.substate.transfer(&transfer, self.backend)
     }
 
+    fn credit(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
+        self.substate.credit(address, value, self.backend)
+    }
+
+    fn debit(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
+        self.substate.debit(address, value, self.backend)
+    }
+
     fn reset_balance(&mut self, address: H160) {
         self.substate.reset_balance(address, self.backend);
     }
@@ -701,6 +866,14 @@ impl<'config, B: Backend> StackState<'config> for MemoryStackState<'_, 'config,
             None
         }
     }
+
+    fn clear_transient_storage(&mut self) {
+        self.substate.clear_tstorage();
+    }
+
+    fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        self.substate.check_invariants()
+    }
 }
 
 impl<'backend, 'config, B: Backend> MemoryStackState<'backend, 'config, B> {
@@ -716,16 +889,77 @@ impl<'backend, 'config, B: Backend> MemoryStackState<'backend, 'config, B> {
         self.substate.account_mut(address, self.backend)
     }
 
+    /// Deconstruct the state, return state to be applied in canonical order.
+    /// See [`MemoryStackSubstate::deconstruct`].
     #[must_use]
     pub fn deconstruct(
         self,
     ) -> (
         impl IntoIterator<Item = Apply<impl IntoIterator<Item = (H256, H256)>>>,
-        impl IntoIterator<Item = Log>,
+        OrderedLogs,
     ) {
         self.substate.deconstruct(self.backend)
     }
 
+    /// Keep the `RIPEMD-160` precompile touched after the revert of the
+    /// substate touching it, as mainnet did in block 2675119: a call to the
+    /// precompile ran out of gas, and the empty precompile account was still
+    /// cleared by `EIP-161`. Clients keep this exception ever since, and the
+    /// consensus tests rely on it.
+    fn keep_ripemd_touched(&mut self, touched: bool) {
+        if touched
+            && self
+                .substate
+                .metadata
+                .gasometer()
+                .config()
+                .delete_empty_accounts()
+        {
+            self.substate.touch(RIPEMD160_ADDRESS, self.backend);
+        }
+    }
+
+    /// Report the accounts deleted when the state is applied after the
+    /// transaction, with `delete_empty` as given to `ApplyBackend::apply`.
+    /// The touched accounts are the ones applied by [`Self::deconstruct`].
+    #[must_use]
+    pub fn state_clearing_report(&self, delete_empty: bool) -> StateClearingReport {
+        let substate = &self.substate;
+        let touched = substate
+            .accounts
+            .keys()
+            .chain(substate.storages.keys().map(|(address, _)| address))
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let mut report = StateClearingReport {
+            selfdestructed: substate.deletes.iter().copied().collect(),
+            ..StateClearingReport::default()
+        };
+        for address in touched {
+            if substate.deletes.contains(&address) || !self.is_empty(address) {
+                continue;
+            }
+            if delete_empty {
+                report.cleared.push(address);
+            } else {
+                report.kept_empty.push(address);
+            }
+        }
+        report
+    }
+
+    /// Statistics of the substates, see [`MemoryStackSubstate::substate_stats`].
+    #[must_use]
+    pub fn substate_stats(&self) -> SubstateStats {
+        self.substate.substate_stats()
+    }
+
+    /// Drop cached entries identical to the backend state, see
+    /// [`MemoryStackSubstate::compact`].
+    pub fn compact(&mut self) -> usize {
+        self.substate.compact(self.backend)
+    }
+
     /// # Errors
     /// Return `ExitError`
     pub fn withdraw(&mut self, address: H160, value: U256) -> Result<(), ExitError> {
@@ -739,13 +973,16 @@ impl<'backend, 'config, B: Backend> MemoryStackState<'backend, 'config, B> {
 
 #[cfg(test)]
 mod tests {
-    use crate::backend::{Backend, MemoryAccount, MemoryBackend, MemoryVicinity};
+    use crate::backend::{
+        is_canonical_order, sort_applies, Apply, Backend, Basic, MemoryAccount, MemoryBackend,
+        MemoryVicinity,
+    };
     use crate::executor::stack::executor::StackSubstateMetadata;
-    use crate::executor::stack::memory::MemoryStackState;
-    use crate::executor::stack::StackState;
+    use crate::executor::stack::memory::{MemoryStackState, RIPEMD160_ADDRESS};
+    use crate::executor::stack::{InvariantViolation, StackState};
     use crate::prelude::*;
-    use crate::Config;
-    use primitive_types::{H160, U256};
+    use crate::{Config, ExitError, Transfer};
+    use primitive_types::{H160, H256, U256};
 
     fn memory_vicinity() -> MemoryVicinity {
         MemoryVicinity {
@@ -758,7 +995,9 @@ mod tests {
             block_timestamp: U256::zero(),
             block_difficulty: U256::zero(),
             block_randomness: None,
-            blob_gas_price: None,
+            blob_params: None,
+            block_excess_blob_gas: None,
+            block_blob_gas_used: None,
             block_gas_limit: U256::from(30_000_000),
             block_base_fee_per_gas: U256::from(1),
             chain_id: U256::from(1),
@@ -766,6 +1005,99 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "test-utils")]
+    #[test]
+    fn test_stack_state_conformance() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        crate::executor::stack::tests::conformance(|| {
+            let metadata =
+                StackSubstateMetadata::new(crate::executor::stack::tests::GAS_LIMIT, &config);
+            MemoryStackState::new(metadata, &backend)
+        });
+    }
+
+    #[test]
+    fn test_state_clearing_report() {
+        let non_empty = H160::from_low_u64_be(1);
+        let empty = H160::from_low_u64_be(2);
+        let destructed = H160::from_low_u64_be(3);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([
+                (
+                    non_empty,
+                    MemoryAccount {
+                        balance: U256::one(),
+                        ..MemoryAccount::default()
+                    },
+                ),
+                (empty, MemoryAccount::default()),
+                (
+                    destructed,
+                    MemoryAccount {
+                        code: vec![0x00],
+                        ..MemoryAccount::default()
+                    },
+                ),
+            ]),
+        );
+        let config = Config::istanbul();
+        let metadata = StackSubstateMetadata::new(u64::MAX, &config);
+        let mut state = MemoryStackState::new(metadata, &backend);
+        state.touch(non_empty);
+        state.touch(empty);
+        state.touch(destructed);
+        state.set_deleted(destructed);
+
+        let report = state.state_clearing_report(config.delete_empty_accounts());
+        assert_eq!(report.cleared, [empty]);
+        assert!(report.kept_empty.is_empty());
+        assert_eq!(report.selfdestructed, [destructed]);
+
+        let report = state.state_clearing_report(false);
+        assert!(report.cleared.is_empty());
+        assert_eq!(report.kept_empty, [empty]);
+    }
+
+    #[test]
+    fn test_ripemd_touch_survives_failed_call() {
+        let other = H160::from_low_u64_be(2);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([
+                (RIPEMD160_ADDRESS, MemoryAccount::default()),
+                (other, MemoryAccount::default()),
+            ]),
+        );
+
+        for config in [Config::istanbul(), Config::frontier()] {
+            for revert in [true, false] {
+                let metadata = StackSubstateMetadata::new(u64::MAX, &config);
+                let mut state = MemoryStackState::new(metadata, &backend);
+                state.enter(u64::MAX, false);
+                state.touch(RIPEMD160_ADDRESS);
+                state.touch(other);
+                if revert {
+                    state.exit_revert().unwrap();
+                } else {
+                    state.exit_discard().unwrap();
+                }
+
+                let report = state.state_clearing_report(config.delete_empty_accounts());
+                if config.delete_empty_accounts() {
+                    assert_eq!(report.cleared, [RIPEMD160_ADDRESS]);
+                } else {
+                    assert!(report.cleared.is_empty());
+                    assert!(report.kept_empty.is_empty());
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_is_empty_catch_backend_only() {
         let mut state = BTreeMap::new();
@@ -892,4 +1224,278 @@ mod tests {
         // Get code from backend, but in backend code is not empty
         assert_eq!(stack_state.code(addr2), vec![0x42]);
     }
+
+    #[test]
+    fn test_code_slice_backend_and_cached_code() {
+        let mut state = BTreeMap::new();
+
+        let addr1 = H160::from_low_u64_be(1);
+        state.insert(
+            addr1,
+            MemoryAccount {
+                balance: U256::zero(),
+                nonce: U256::zero(),
+                storage: BTreeMap::new(),
+                code: vec![0x01, 0x02, 0x03, 0x04],
+            },
+        );
+        let addr2 = H160::from_low_u64_be(2);
+
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::osaka();
+        let metadata = StackSubstateMetadata::new(0, &config);
+
+        let mut stack_state = MemoryStackState::new(metadata, &backend);
+
+        // Code from backend, clamped to the code length
+        assert_eq!(stack_state.code_slice(addr1, 1..3), vec![0x02, 0x03]);
+        assert_eq!(stack_state.code_slice(addr1, 2..10), vec![0x03, 0x04]);
+        assert!(stack_state.code_slice(addr1, 5..10).is_empty());
+        assert!(stack_state.code_slice(addr2, 0..10).is_empty());
+
+        // Code from the substate cache
+        stack_state.set_code(addr2, vec![0x05, 0x06, 0x07]);
+        assert_eq!(
+            stack_state.code_slice(addr2, 1..usize::MAX),
+            vec![0x06, 0x07]
+        );
+    }
+
+    #[test]
+    fn test_deconstruct_canonical_order() {
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::osaka();
+        let metadata = StackSubstateMetadata::new(0, &config);
+        let mut stack_state = MemoryStackState::new(metadata, &backend);
+
+        let address = H160::from_low_u64_be;
+        for i in [3, 1, 4, 2] {
+            stack_state.deposit(address(i), U256::one());
+        }
+        stack_state.set_deleted(address(6));
+        stack_state.set_deleted(address(5));
+        stack_state.log(address(2), Vec::new(), vec![1]);
+        stack_state.log(address(1), Vec::new(), vec![2]);
+
+        let (applies, logs) = stack_state.deconstruct();
+        let applies: Vec<_> = applies.into_iter().collect();
+        assert!(is_canonical_order(&applies));
+        assert_eq!(
+            applies.iter().map(Apply::address).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5, 6].map(address).to_vec()
+        );
+        assert!(matches!(applies[4], Apply::Delete { .. }));
+        // Logs keep the emission order
+        let data: Vec<_> = logs.as_slice().iter().map(|log| log.data[0]).collect();
+        assert_eq!(data, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_sort_applies() {
+        let address = H160::from_low_u64_be;
+        let modify = |i| Apply::Modify {
+            address: address(i),
+            basic: Basic::default(),
+            code: None,
+            storage: Vec::<(H256, H256)>::new(),
+            reset_storage: false,
+        };
+        let mut applies = vec![
+            Apply::Delete {
+                address: address(1),
+            },
+            modify(3),
+            modify(2),
+        ];
+        assert!(!is_canonical_order(&applies));
+        sort_applies(&mut applies);
+        assert!(is_canonical_order(&applies));
+        assert_eq!(
+            applies.iter().map(Apply::address).collect::<Vec<_>>(),
+            [2, 3, 1].map(address).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_credit_debit_and_transfer_overflow() {
+        let address = H160::from_low_u64_be;
+        let mut state = BTreeMap::new();
+        state.insert(
+            address(1),
+            MemoryAccount {
+                balance: U256::MAX,
+                ..Default::default()
+            },
+        );
+        state.insert(
+            address(2),
+            MemoryAccount {
+                balance: U256::from(10),
+                ..Default::default()
+            },
+        );
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::osaka();
+        let metadata = StackSubstateMetadata::new(0, &config);
+        let mut stack_state = MemoryStackState::new(metadata, &backend);
+
+        assert_eq!(
+            stack_state.credit(address(1), U256::one()),
+            Err(ExitError::BalanceOverflow)
+        );
+        assert_eq!(
+            stack_state.debit(address(2), U256::from(11)),
+            Err(ExitError::OutOfFund)
+        );
+        assert_eq!(
+            stack_state.transfer(Transfer {
+                source: address(2),
+                target: address(1),
+                value: U256::from(5),
+            }),
+            Err(ExitError::BalanceOverflow)
+        );
+        // Balances are unchanged on error
+        assert_eq!(stack_state.basic(address(1)).balance, U256::MAX);
+        assert_eq!(stack_state.basic(address(2)).balance, U256::from(10));
+
+        stack_state.deposit(address(1), U256::one());
+        assert_eq!(stack_state.basic(address(1)).balance, U256::MAX);
+        stack_state.debit(address(2), U256::from(4)).unwrap();
+        stack_state.credit(address(3), U256::from(4)).unwrap();
+        assert_eq!(stack_state.basic(address(2)).balance, U256::from(6));
+        assert_eq!(stack_state.basic(address(3)).balance, U256::from(4));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_iter_storage_overlays_substates() {
+        let address = H160::from_low_u64_be(1);
+        let word = H256::from_low_u64_be;
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                address,
+                MemoryAccount {
+                    storage: BTreeMap::from([(word(1), word(1)), (word(2), word(2))]),
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        assert_eq!(
+            backend.iter_storage(address).unwrap().collect::<Vec<_>>(),
+            [(word(1), word(1)), (word(2), word(2))]
+        );
+        assert_eq!(backend.iter_storage(H160::zero()).unwrap().count(), 0);
+
+        let config = Config::cancun();
+        let metadata = StackSubstateMetadata::new(1_000_000, &config);
+        let mut state = MemoryStackState::new(metadata, &backend);
+        state.set_storage(address, word(2), H256::zero());
+        state.set_storage(address, word(3), word(3));
+        state.enter(100_000, false);
+        state.set_storage(address, word(4), word(4));
+        assert_eq!(
+            state.iter_storage(address).unwrap().collect::<Vec<_>>(),
+            [(word(1), word(1)), (word(3), word(3)), (word(4), word(4))]
+        );
+
+        state.reset_storage(address);
+        state.set_storage(address, word(5), word(5));
+        assert_eq!(
+            state.iter_storage(address).unwrap().collect::<Vec<_>>(),
+            [(word(5), word(5))]
+        );
+        state.exit_revert().unwrap();
+        assert_eq!(state.iter_storage(address).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_substate_stats_and_compaction() {
+        let funded = H160::from_low_u64_be(1);
+        let empty = H160::from_low_u64_be(2);
+        let mut state = BTreeMap::new();
+        state.insert(
+            funded,
+            MemoryAccount {
+                balance: U256::from(10),
+                storage: BTreeMap::from([(H256::zero(), H256::repeat_byte(1))]),
+                ..Default::default()
+            },
+        );
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::osaka();
+        let metadata = StackSubstateMetadata::new(1_000_000, &config);
+        let mut stack_state = MemoryStackState::new(metadata, &backend);
+
+        stack_state.touch(funded);
+        stack_state.touch(empty);
+        // Same value as the backend
+        stack_state.set_storage(funded, H256::zero(), H256::repeat_byte(1));
+        stack_state.set_storage(funded, H256::repeat_byte(2), H256::repeat_byte(2));
+
+        stack_state.enter(100_000, false);
+        stack_state.log(funded, Vec::new(), Vec::new());
+        let stats = stack_state.substate_stats();
+        assert_eq!(stats.depth, 2);
+        assert_eq!(stats.accounts, 2);
+        assert_eq!(stats.storages, 2);
+        assert_eq!(stats.logs, 1);
+        // Nothing is compacted while a child substate is entered
+        assert_eq!(stack_state.compact(), 0);
+        stack_state.exit_commit().unwrap();
+
+        // The unchanged funded account and storage slot are dropped, the
+        // touched empty account is kept for EIP-161 clearing.
+        assert_eq!(stack_state.compact(), 2);
+        let stats = stack_state.substate_stats();
+        assert_eq!(stats.depth, 1);
+        assert_eq!(stats.accounts, 1);
+        assert_eq!(stats.storages, 1);
+        assert_eq!(stack_state.basic(funded).balance, U256::from(10));
+        assert_eq!(
+            stack_state.storage(funded, H256::zero()),
+            H256::repeat_byte(1)
+        );
+        assert_eq!(
+            stack_state.storage(funded, H256::repeat_byte(2)),
+            H256::repeat_byte(2)
+        );
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let address = H160::from_low_u64_be(1);
+        let created = H160::from_low_u64_be(2);
+        let vicinity = memory_vicinity();
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let metadata = StackSubstateMetadata::new(1_000_000, &config);
+        let mut state = MemoryStackState::new(metadata, &backend);
+        assert_eq!(state.check_invariants(), Ok(()));
+
+        state.tstore(address, H256::zero(), U256::one()).unwrap();
+        assert_eq!(
+            state.check_invariants(),
+            Err(InvariantViolation::TransientStorageNotCleared { entries: 1 })
+        );
+        state.clear_transient_storage();
+        assert_eq!(state.tload(address, H256::zero()), Ok(U256::zero()));
+        assert_eq!(state.check_invariants(), Ok(()));
+
+        // Only accounts created in the transaction are deleted after EIP-6780
+        state.set_created(created);
+        state.set_deleted(created);
+        assert_eq!(state.check_invariants(), Ok(()));
+        state.set_deleted(address);
+        assert_eq!(
+            state.check_invariants(),
+            Err(InvariantViolation::DeletedAccountNotCreated(address))
+        );
+    }
 }
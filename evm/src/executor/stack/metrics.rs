@@ -0,0 +1,30 @@
+//! Metrics hooks of the executor.
+
+use crate::ExitReason;
+use primitive_types::H160;
+
+/// Counters updated by a [`super::StackExecutor`], e.g. to export them to
+/// Prometheus.
+///
+/// The hooks are called at low frequency points only: once per transaction,
+/// per precompile call and per cache lookup, never per opcode. Every hook
+/// does nothing by default, so implementors only track what they need.
+pub trait Metrics {
+    /// A transaction ended with `reason`, using `used_gas` on its own, not
+    /// counting the earlier transactions of a reused executor. Also called
+    /// for the transactions rejected before execution, e.g. with an invalid
+    /// nonce or an insufficient gas limit.
+    fn transaction_executed(&mut self, reason: &ExitReason, used_gas: u64) {
+        let _ = (reason, used_gas);
+    }
+
+    /// The precompile at `address` was called.
+    fn precompile_called(&mut self, address: H160) {
+        let _ = address;
+    }
+
+    /// The code hash of a contract deployed by the transaction, or the hash
+    /// of an init code it already deployed with `CREATE2`, was served from
+    /// the cache of the executor instead of hashing the code.
+    fn code_hash_cache_hit(&mut self) {}
+}
@@ -2,15 +2,47 @@
 //! A memory-based state is provided, but can be replaced by a custom
 //! implementation, for example one interacting with a database.
 
+mod builder;
+mod code_hashes;
+mod custom_opcode;
+mod custom_transaction;
 mod executor;
+mod invariants;
 mod memory;
+mod metrics;
+mod policy;
 mod precompile;
+mod runtime_pool;
+#[cfg(feature = "execution-stats")]
+mod stats;
+mod system;
 mod tagged_runtime;
+#[cfg(feature = "test-utils")]
+pub mod tests;
 
+pub use self::builder::{NoBackend, StackExecutorBuilder};
+pub use self::custom_opcode::{CustomOpcodeHandler, CUSTOM_OPCODES};
+pub use self::custom_transaction::{CustomTransactionType, TransactionHookState};
 pub use self::executor::{
-    Accessed, Authorization, StackExecutor, StackExitKind, StackState, StackSubstateMetadata,
+    Accessed, Authorization, CreateResult, DryRun, GasEstimate, LogEvent, LogPosition,
+    LogSubscriber, StackExecutor, StackExitKind, StackState, StackSubstateMetadata,
+    StaticViolation,
 };
-pub use self::memory::{MemoryStackAccount, MemoryStackState, MemoryStackSubstate};
+pub use self::invariants::InvariantViolation;
+pub use self::memory::{
+    MemoryStackAccount, MemoryStackState, MemoryStackSubstate, StateClearingReport, SubstateStats,
+};
+pub use self::metrics::Metrics;
+pub use self::policy::{CreateDecision, CreateInterceptor, TargetPolicy};
+pub(crate) use self::precompile::execute_precompile_fn;
 pub use self::precompile::{
-    PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileOutput, PrecompileSet,
+    PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileMetadata, PrecompileOutput,
+    PrecompileSet,
+};
+pub use self::runtime_pool::RuntimePool;
+#[cfg(feature = "execution-stats")]
+pub use self::stats::ExecutionStats;
+pub use self::system::{
+    CallContextKind, BEACON_ROOTS_ADDRESS, HISTORY_STORAGE_ADDRESS, SYSTEM_ADDRESS,
+    SYSTEM_CALL_GAS_LIMIT,
 };
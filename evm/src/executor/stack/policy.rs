@@ -0,0 +1,143 @@
+//! Policy restricting the targets of message calls and contract creations,
+//! and hook intercepting contract creations.
+
+use crate::core::prelude::Cow;
+use crate::core::utils::revert;
+use crate::prelude::*;
+use crate::runtime::CreateScheme;
+use primitive_types::H160;
+
+/// Default revert message of denied targets.
+const DEFAULT_MESSAGE: &str = "target address is blocked";
+
+/// Policy checked by the executor before every message call and contract
+/// creation, e.g. to block interactions with sanctioned or broken contracts
+/// during incident response.
+///
+/// The target of a call is the address whose code is executed, the target
+/// of a creation is the address of the new contract. A denied call or
+/// creation is reverted before any gas is given to it, with the policy
+/// message encoded as `Error(string)` revert data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TargetPolicy {
+    /// Whether `targets` is an allowlist rather than a blocklist.
+    allowlist: bool,
+    targets: BTreeSet<H160>,
+    message: Cow<'static, str>,
+}
+
+impl TargetPolicy {
+    /// Policy denying `targets` and allowing any other address.
+    #[must_use]
+    pub fn blocklist(targets: impl IntoIterator<Item = H160>) -> Self {
+        Self {
+            allowlist: false,
+            targets: targets.into_iter().collect(),
+            message: Cow::Borrowed(DEFAULT_MESSAGE),
+        }
+    }
+
+    /// Policy allowing only `targets`.
+    #[must_use]
+    pub fn allowlist(targets: impl IntoIterator<Item = H160>) -> Self {
+        Self {
+            allowlist: true,
+            ..Self::blocklist(targets)
+        }
+    }
+
+    /// Set the revert message of denied targets.
+    #[must_use]
+    pub fn with_message(mut self, message: impl Into<Cow<'static, str>>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    /// Revert message of denied targets.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Add `target` to the list, i.e. block it for a blocklist and allow it
+    /// for an allowlist.
+    pub fn insert(&mut self, target: H160) {
+        self.targets.insert(target);
+    }
+
+    /// Remove `target` from the list.
+    pub fn remove(&mut self, target: H160) {
+        self.targets.remove(&target);
+    }
+
+    /// Whether calls to or creations of `target` are allowed.
+    #[must_use]
+    pub fn is_allowed(&self, target: H160) -> bool {
+        self.targets.contains(&target) == self.allowlist
+    }
+
+    /// Revert data of a denied call or creation.
+    #[must_use]
+    pub fn revert_output(&self) -> Vec<u8> {
+        revert::encode_error(&self.message)
+    }
+}
+
+/// Decision of a [`CreateInterceptor`] on a contract creation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CreateDecision {
+    /// Create the contract at the address derived from the create scheme.
+    Proceed,
+    /// Create the contract at the given address instead.
+    Redirect(H160),
+    /// Revert the creation with the given output.
+    Deny(Vec<u8>),
+}
+
+/// Hook called by the executor before every contract creation, including
+/// the creation transactions, once the address of the new contract is
+/// derived from the create scheme.
+///
+/// It allows embedders to assign deterministic addresses, e.g. in migration
+/// tooling, or to deny creations under a policy. A denied creation is
+/// reverted before any gas is given to it. A redirected address is still
+/// subject to the [`TargetPolicy`], the collision checks and `EIP-2929`
+/// warming.
+pub trait CreateInterceptor {
+    /// Decide on the creation of a contract by `caller` at `address`,
+    /// derived from `scheme`.
+    fn intercept(&mut self, caller: H160, scheme: CreateScheme, address: H160) -> CreateDecision;
+}
+
+impl<F: FnMut(H160, CreateScheme, H160) -> CreateDecision> CreateInterceptor for F {
+    fn intercept(&mut self, caller: H160, scheme: CreateScheme, address: H160) -> CreateDecision {
+        self(caller, scheme, address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocklist_and_allowlist() {
+        let blocked = H160::from_low_u64_be(1);
+        let other = H160::from_low_u64_be(2);
+
+        let mut policy = TargetPolicy::blocklist([blocked]);
+        assert!(!policy.is_allowed(blocked));
+        assert!(policy.is_allowed(other));
+        policy.remove(blocked);
+        assert!(policy.is_allowed(blocked));
+
+        let mut policy = TargetPolicy::allowlist([blocked]).with_message("not allowed");
+        assert!(policy.is_allowed(blocked));
+        assert!(!policy.is_allowed(other));
+        policy.insert(other);
+        assert!(policy.is_allowed(other));
+        assert_eq!(
+            revert::decode(&policy.revert_output()).message(),
+            Some("not allowed")
+        );
+    }
+}
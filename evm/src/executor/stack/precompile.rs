@@ -1,5 +1,9 @@
+use crate::core::prelude::Cow;
 use crate::prelude::*;
-use crate::{Context, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Transfer};
+use crate::runtime::HardFork;
+use crate::{
+    Context, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, ExternalCost, Transfer,
+};
 use primitive_types::{H160, H256};
 
 /// A precompile result.
@@ -53,19 +57,18 @@ pub trait PrecompileHandle {
     /// Return `ExitError`
     fn record_cost(&mut self, cost: u64) -> Result<(), ExitError>;
 
-    /// Record Substrate specific cost.
+    /// Record Substrate specific cost, e.g. the proof size of the storage
+    /// the precompile reads.
     ///
     /// # Errors
     /// Return `ExitError`
-    fn record_external_cost(
-        &mut self,
-        ref_time: Option<u64>,
-        proof_size: Option<u64>,
-        storage_growth: Option<u64>,
-    ) -> Result<(), ExitError>;
+    fn record_external_cost(&mut self, cost: ExternalCost) -> Result<(), ExitError>;
 
-    /// Refund Substrate specific cost.
-    fn refund_external_cost(&mut self, ref_time: Option<u64>, proof_size: Option<u64>);
+    /// Refund Substrate specific cost, except the storage growth: the
+    /// execution time and proof size are recorded as upper bounds before
+    /// an operation, while the storage growth is recorded once the storage
+    /// is written, so there is nothing to refund.
+    fn refund_external_cost(&mut self, cost: ExternalCost);
 
     /// Retreive the remaining gas.
     fn remaining_gas(&self) -> u64;
@@ -73,7 +76,8 @@ pub trait PrecompileHandle {
     /// Record a log.
     ///
     /// # Errors
-    /// Return `ExitError`
+    /// Return `ExitError`. In a static context the log is rejected with
+    /// `ExitError::StaticModeViolation` and the precompile call fails.
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError>;
 
     /// Retreive the code address (what is the address of the precompile being called).
@@ -92,6 +96,27 @@ pub trait PrecompileHandle {
     fn gas_limit(&self) -> Option<u64>;
 }
 
+/// Description of a precompile, used by explorers and tracers to annotate
+/// precompile calls.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PrecompileMetadata {
+    /// Name of the precompile, e.g. `MODEXP`.
+    pub name: Cow<'static, str>,
+    /// Hard fork introducing the precompile.
+    pub since: HardFork,
+}
+
+impl PrecompileMetadata {
+    /// Create the metadata of a precompile.
+    #[must_use]
+    pub const fn new(name: &'static str, since: HardFork) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            since,
+        }
+    }
+}
+
 /// A set of precompiles.
 ///
 /// Checks if the provided address is in the precompile set. This should be
@@ -105,6 +130,23 @@ pub trait PrecompileSet {
     /// perform the check while not executing the precompile afterward, since
     /// `execute` already performs a check internally.
     fn is_precompile(&self, address: H160) -> bool;
+
+    /// Addresses of all the precompiles in the set. Per EIP-2929 they are
+    /// always warm, so the executor adds them to the accessed addresses at
+    /// the start of every transaction.
+    ///
+    /// The default returns no addresses: the precompiles are still warm, as
+    /// checked with [`Self::is_precompile`], but are missing from the
+    /// accessed addresses of the state.
+    fn precompile_addresses(&self) -> Vec<H160> {
+        Vec::new()
+    }
+
+    /// Metadata of the precompile at the given address. Return `None` if the
+    /// address is not a precompile or if the set doesn't describe it.
+    fn metadata(&self, _address: H160) -> Option<PrecompileMetadata> {
+        None
+    }
 }
 
 impl PrecompileSet for () {
@@ -131,20 +173,8 @@ impl PrecompileSet for BTreeMap<H160, PrecompileFn> {
     fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
         let address = handle.code_address();
 
-        self.get(&address).map(|precompile| {
-            let input = handle.input();
-            let gas_limit = handle.gas_limit();
-            let context = handle.context();
-            let is_static = handle.is_static();
-
-            match (*precompile)(input, gas_limit, context, is_static) {
-                Ok((output, cost)) => {
-                    handle.record_cost(cost)?;
-                    Ok(output)
-                }
-                Err(err) => Err(err),
-            }
-        })
+        self.get(&address)
+            .map(|precompile| execute_precompile_fn(*precompile, handle))
     }
 
     /// Check if the given address is a precompile. Should only be called to
@@ -153,4 +183,27 @@ impl PrecompileSet for BTreeMap<H160, PrecompileFn> {
     fn is_precompile(&self, address: H160) -> bool {
         self.contains_key(&address)
     }
+
+    fn precompile_addresses(&self) -> Vec<H160> {
+        self.keys().copied().collect()
+    }
+}
+
+/// Execute `precompile` with the call data of `handle` and record its cost.
+pub(crate) fn execute_precompile_fn(
+    precompile: PrecompileFn,
+    handle: &mut impl PrecompileHandle,
+) -> PrecompileResult {
+    let input = handle.input();
+    let gas_limit = handle.gas_limit();
+    let context = handle.context();
+    let is_static = handle.is_static();
+
+    match precompile(input, gas_limit, context, is_static) {
+        Ok((output, cost)) => {
+            handle.record_cost(cost)?;
+            Ok(output)
+        }
+        Err(err) => Err(err),
+    }
 }
@@ -0,0 +1,140 @@
+//! Pool of runtimes reused across call frames.
+
+use crate::prelude::*;
+use crate::{Context, Runtime};
+
+/// Pool of finished runtimes.
+///
+/// Each call frame needs a `Runtime` with its own stack and memory. Taking
+/// runtimes from the pool reuses the allocations of finished frames instead
+/// of allocating new ones, which matters for call-heavy workloads. The pool
+/// holds at most as many runtimes as the deepest call stack seen.
+#[derive(Default)]
+pub struct RuntimePool {
+    runtimes: Vec<Runtime>,
+}
+
+impl RuntimePool {
+    /// Create an empty pool.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            runtimes: Vec::new(),
+        }
+    }
+
+    /// Take a runtime from the pool, reset to run `code` with `data`, or
+    /// create a new one if the pool is empty.
+    pub fn take(
+        &mut self,
+        code: Rc<Vec<u8>>,
+        data: Rc<Vec<u8>>,
+        context: Context,
+        stack_limit: usize,
+        memory_limit: usize,
+    ) -> Runtime {
+        match self.runtimes.pop() {
+            Some(mut runtime)
+                if runtime.machine().stack().limit() == stack_limit
+                    && runtime.machine().memory().limit() == memory_limit =>
+            {
+                runtime.reset(code, data, context);
+                runtime
+            }
+            _ => Runtime::new(code, data, context, stack_limit, memory_limit),
+        }
+    }
+
+    /// Return a finished runtime to the pool.
+    pub fn recycle(&mut self, runtime: Runtime) {
+        self.runtimes.push(runtime);
+    }
+
+    /// Number of pooled runtimes.
+    #[must_use]
+    // TODO: rust-v1.87 - const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn len(&self) -> usize {
+        self.runtimes.len()
+    }
+
+    /// Whether the pool is empty.
+    #[must_use]
+    // TODO: rust-v1.87 - const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn is_empty(&self) -> bool {
+        self.runtimes.is_empty()
+    }
+
+    /// Drop all pooled runtimes, releasing their allocations.
+    pub fn clear(&mut self) {
+        self.runtimes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::context;
+    use crate::{ExitReason, ExitSucceed, Machine, Opcode};
+    use primitive_types::{H160, U256};
+
+    #[test]
+    fn test_machine_reset() {
+        let mut machine = Machine::new(Rc::new(vec![0x01]), Rc::new(Vec::new()), 1024, 10000);
+        machine.stack_mut().push(U256::one()).unwrap();
+        machine.memory_mut().resize_end(64).unwrap();
+        machine.exit(ExitReason::Succeed(ExitSucceed::Stopped));
+
+        machine.reset(Rc::new(vec![0x00]), Rc::new(vec![0x01]));
+        assert!(machine.stack().is_empty());
+        assert!(machine.memory().is_empty());
+        assert_eq!(machine.memory().effective_len(), 0);
+        assert_eq!(machine.position(), &Ok(0));
+        assert_eq!(
+            machine.inspect().map(|(opcode, _)| opcode),
+            Some(Opcode::STOP)
+        );
+        assert!(machine.return_value().is_empty());
+    }
+
+    #[test]
+    fn test_pool_reuses_runtimes() {
+        let mut pool = RuntimePool::new();
+        let runtime = pool.take(
+            Rc::new(vec![0x01]),
+            Rc::new(Vec::new()),
+            context(H160::zero()),
+            1024,
+            10000,
+        );
+        assert!(pool.is_empty());
+        pool.recycle(runtime);
+        assert_eq!(pool.len(), 1);
+
+        let runtime = pool.take(
+            Rc::new(vec![0x00]),
+            Rc::new(Vec::new()),
+            context(H160::zero()),
+            1024,
+            10000,
+        );
+        assert!(pool.is_empty());
+        assert_eq!(
+            runtime.machine().inspect().map(|(opcode, _)| opcode),
+            Some(Opcode::STOP)
+        );
+
+        // A runtime with other limits is not reused
+        pool.recycle(runtime);
+        let runtime = pool.take(
+            Rc::new(vec![0x00]),
+            Rc::new(Vec::new()),
+            context(H160::zero()),
+            1,
+            10000,
+        );
+        assert!(pool.is_empty());
+        assert_eq!(runtime.machine().stack().limit(), 1);
+    }
+}
@@ -0,0 +1,99 @@
+//! Resource usage statistics of the executions.
+
+use crate::prelude::*;
+
+/// Peak resource usage of the executions of a [`super::StackExecutor`],
+/// to size the memory of constrained embeddings such as zkVM guests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ExecutionStats {
+    /// Peak EVM memory, in bytes, summed over the call frames alive at the
+    /// same time.
+    pub peak_memory: usize,
+    /// Peak number of nested call frames.
+    pub peak_depth: usize,
+    /// Total bytes of return data produced by the interpreted call frames.
+    pub return_data_bytes: usize,
+}
+
+/// Tracker of the statistics over the call stack.
+#[derive(Clone, Debug)]
+pub(crate) struct StatsTracker {
+    stats: ExecutionStats,
+    /// Memory of the frames suspended by a call or create, outermost first.
+    suspended_memory: Vec<usize>,
+    suspended_total: usize,
+}
+
+impl StatsTracker {
+    pub const fn new() -> Self {
+        Self {
+            stats: ExecutionStats {
+                peak_memory: 0,
+                peak_depth: 0,
+                return_data_bytes: 0,
+            },
+            suspended_memory: Vec::new(),
+            suspended_total: 0,
+        }
+    }
+
+    /// A frame with `depth` nested frames, including itself, was entered.
+    pub fn enter_frame(&mut self, depth: usize) {
+        self.stats.peak_depth = self.stats.peak_depth.max(depth);
+    }
+
+    /// A frame with `memory` bytes of memory was suspended by a call or
+    /// create.
+    pub fn suspend_frame(&mut self, memory: usize) {
+        self.suspended_memory.push(memory);
+        self.suspended_total += memory;
+    }
+
+    /// A frame exited with `memory` bytes of memory and `return_data`
+    /// bytes of return data. As memory only grows, it is the peak memory of
+    /// the frame, reached while the suspended frames were alive.
+    pub fn exit_frame(&mut self, memory: usize, return_data: usize) {
+        self.stats.peak_memory = self.stats.peak_memory.max(self.suspended_total + memory);
+        self.stats.return_data_bytes += return_data;
+    }
+
+    /// The innermost suspended frame resumed.
+    pub fn resume_frame(&mut self) {
+        if let Some(memory) = self.suspended_memory.pop() {
+            self.suspended_total -= memory;
+        }
+    }
+
+    pub const fn stats(&self) -> &ExecutionStats {
+        &self.stats
+    }
+
+    pub fn take(&mut self) -> ExecutionStats {
+        core::mem::take(&mut self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_tracker() {
+        let mut tracker = StatsTracker::new();
+        tracker.enter_frame(1);
+        tracker.suspend_frame(64);
+        tracker.enter_frame(2);
+        tracker.exit_frame(96, 32);
+        tracker.resume_frame();
+        tracker.exit_frame(128, 0);
+        assert_eq!(
+            tracker.take(),
+            ExecutionStats {
+                peak_memory: 160,
+                peak_depth: 2,
+                return_data_bytes: 32,
+            }
+        );
+        assert_eq!(*tracker.stats(), ExecutionStats::default());
+    }
+}
@@ -0,0 +1,38 @@
+//! Protocol-level system calls, made at the start of the blocks.
+
+use primitive_types::H160;
+
+/// Caller of the system calls, see
+/// [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788).
+pub const SYSTEM_ADDRESS: H160 = H160([
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xfe,
+]);
+
+/// Gas limit the executor must be initialized with before a system call.
+pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
+
+/// Contract storing the beacon block roots, see
+/// [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788).
+pub const BEACON_ROOTS_ADDRESS: H160 = H160([
+    0x00, 0x0f, 0x3d, 0xf6, 0xd7, 0x32, 0x80, 0x7e, 0xf1, 0x31, 0x9f, 0xb7, 0xb8, 0xbb, 0x85, 0x22,
+    0xd0, 0xbe, 0xac, 0x02,
+]);
+
+/// Contract storing the historical block hashes, see
+/// [EIP-2935](https://eips.ethereum.org/EIPS/eip-2935).
+pub const HISTORY_STORAGE_ADDRESS: H160 = H160([
+    0x00, 0x00, 0xf9, 0x08, 0x27, 0xf1, 0xc5, 0x3a, 0x10, 0xcb, 0x7a, 0x02, 0x33, 0x5b, 0x17, 0x53,
+    0x20, 0x00, 0x29, 0x35,
+]);
+
+/// Kind of the last call made by an executor.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CallContextKind {
+    /// A transaction, paying its gas and incrementing the sender nonce.
+    #[default]
+    Transaction,
+    /// A system call, made by the protocol: no gas is paid, no nonce is
+    /// incremented, and the used gas does not count towards the block gas.
+    System,
+}
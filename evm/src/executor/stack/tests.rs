@@ -0,0 +1,252 @@
+//! Conformance checks for custom [`StackState`] implementations.
+//!
+//! The checks drive a state created by a factory the way the executor does,
+//! entering and exiting substates, and panic with a description of the
+//! first violated expectation. The factory must return a state over a
+//! backend without accounts, with a gas limit of at least [`GAS_LIMIT`].
+
+use super::StackState;
+use crate::prelude::*;
+use primitive_types::{H160, H256, U256};
+
+/// Minimal gas limit of the states returned by the factories.
+pub const GAS_LIMIT: u64 = 100_000;
+/// Gas limit of the entered substates.
+const CHILD_GAS_LIMIT: u64 = 10_000;
+
+/// Enter a substate, charging its gas limit to the parent as the executor
+/// does.
+fn enter<'config, S: StackState<'config>>(state: &mut S) {
+    state
+        .metadata_mut()
+        .gasometer_mut()
+        .record_cost(CHILD_GAS_LIMIT)
+        .expect("gas limit of the state is at least `GAS_LIMIT`");
+    state.enter(CHILD_GAS_LIMIT, false);
+}
+
+/// Run all the checks of this module.
+///
+/// # Panics
+/// Panic if the state violates an expectation.
+pub fn conformance<'config, S, F>(mut factory: F)
+where
+    S: StackState<'config>,
+    F: FnMut() -> S,
+{
+    substate_conformance(&mut factory);
+    transient_storage_conformance(&mut factory);
+    refund_conformance(&mut factory);
+}
+
+/// Check that changes of committed substates are kept, and that changes of
+/// reverted or discarded substates are dropped, including the changes of
+/// their committed children.
+///
+/// # Panics
+/// Panic if the state violates an expectation.
+pub fn substate_conformance<'config, S, F>(mut factory: F)
+where
+    S: StackState<'config>,
+    F: FnMut() -> S,
+{
+    let address = H160::from_low_u64_be(0x1000);
+    let key = H256::from_low_u64_be(1);
+    let first = H256::repeat_byte(0x11);
+    let second = H256::repeat_byte(0x22);
+
+    let mut state = factory();
+    enter(&mut state);
+    state.set_storage(address, key, first);
+    state.inc_nonce(address).expect("nonce below the limit");
+    state
+        .credit(address, U256::from(10))
+        .expect("balance below the limit");
+    state.set_created(address);
+    state.exit_commit().expect("commit succeeds");
+    assert_eq!(
+        state.storage(address, key),
+        first,
+        "committed storage is dropped"
+    );
+    assert_eq!(state.basic(address).nonce, U256::one());
+    assert_eq!(
+        state.basic(address).balance,
+        U256::from(10),
+        "committed balance is dropped"
+    );
+    assert!(state.is_created(address), "committed creation is dropped");
+
+    let other = H160::from_low_u64_be(0x1001);
+    for revert in [true, false] {
+        enter(&mut state);
+        state.set_storage(address, key, second);
+        state.inc_nonce(address).expect("nonce below the limit");
+        state
+            .debit(address, U256::from(3))
+            .expect("balance above the debit");
+        state.set_deleted(address);
+        state.set_created(other);
+        state.set_code(other, vec![0x00]);
+        // Committed children of a reverted substate are dropped too
+        enter(&mut state);
+        state.set_storage(other, key, second);
+        state.exit_commit().expect("commit succeeds");
+        if revert {
+            state.exit_revert().expect("revert succeeds");
+        } else {
+            state.exit_discard().expect("discard succeeds");
+        }
+
+        assert_eq!(
+            state.storage(address, key),
+            first,
+            "reverted or discarded storage is kept"
+        );
+        assert_eq!(
+            state.basic(address).nonce,
+            U256::one(),
+            "reverted or discarded nonce is kept"
+        );
+        assert_eq!(
+            state.basic(address).balance,
+            U256::from(10),
+            "reverted or discarded balance is kept"
+        );
+        assert!(
+            !state.deleted(address),
+            "reverted or discarded deletion is kept"
+        );
+        assert!(
+            !state.is_created(other),
+            "reverted or discarded creation is kept"
+        );
+        assert!(
+            state.code(other).is_empty(),
+            "reverted or discarded code is kept"
+        );
+        assert_eq!(
+            state.storage(other, key),
+            H256::zero(),
+            "storage of a committed child of a reverted substate is kept"
+        );
+    }
+}
+
+/// Check that transient storage (EIP-1153) follows the substate semantics
+/// and is separate from the storage.
+///
+/// # Panics
+/// Panic if the state violates an expectation.
+pub fn transient_storage_conformance<'config, S, F>(mut factory: F)
+where
+    S: StackState<'config>,
+    F: FnMut() -> S,
+{
+    let address = H160::from_low_u64_be(0x2000);
+    let key = H256::from_low_u64_be(1);
+    let tload = |state: &mut S| state.tload(address, key).expect("tload succeeds");
+
+    let mut state = factory();
+    assert_eq!(tload(&mut state), U256::zero(), "new transient slot is set");
+    state
+        .tstore(address, key, U256::from(1))
+        .expect("tstore succeeds");
+    assert_eq!(tload(&mut state), U256::from(1), "transient slot not set");
+    assert_eq!(
+        state.storage(address, key),
+        H256::zero(),
+        "transient storage is written to the storage"
+    );
+
+    enter(&mut state);
+    assert_eq!(
+        tload(&mut state),
+        U256::from(1),
+        "transient slot of the parent is not visible"
+    );
+    state
+        .tstore(address, key, U256::from(2))
+        .expect("tstore succeeds");
+    state.exit_commit().expect("commit succeeds");
+    assert_eq!(
+        tload(&mut state),
+        U256::from(2),
+        "committed transient slot is dropped"
+    );
+
+    enter(&mut state);
+    state
+        .tstore(address, key, U256::from(3))
+        .expect("tstore succeeds");
+    state.exit_revert().expect("revert succeeds");
+    assert_eq!(
+        tload(&mut state),
+        U256::from(2),
+        "reverted transient slot is kept"
+    );
+
+    let other = H160::from_low_u64_be(0x2001);
+    assert_eq!(
+        state.tload(other, key).expect("tload succeeds"),
+        U256::zero(),
+        "transient storage is shared between addresses"
+    );
+}
+
+/// Check that the refunds and the unused gas of committed substates are
+/// given back to the parent, and that reverted substates only give back
+/// their unused gas.
+///
+/// # Panics
+/// Panic if the state violates an expectation.
+pub fn refund_conformance<'config, S, F>(mut factory: F)
+where
+    S: StackState<'config>,
+    F: FnMut() -> S,
+{
+    let mut state = factory();
+    let gas = state.metadata().gasometer().gas();
+
+    enter(&mut state);
+    let gasometer = state.metadata_mut().gasometer_mut();
+    gasometer.record_cost(100).expect("cost below the limit");
+    gasometer.record_refund(50).expect("refund succeeds");
+    state.exit_commit().expect("commit succeeds");
+    let gasometer = state.metadata().gasometer();
+    assert_eq!(
+        gasometer.gas(),
+        gas - 100,
+        "unused gas of a committed substate is not given back"
+    );
+    assert_eq!(
+        gasometer.refunded_gas(),
+        50,
+        "refund of a committed substate is dropped"
+    );
+
+    enter(&mut state);
+    let gasometer = state.metadata_mut().gasometer_mut();
+    gasometer.record_cost(100).expect("cost below the limit");
+    gasometer.record_refund(50).expect("refund succeeds");
+    state.exit_revert().expect("revert succeeds");
+    let gasometer = state.metadata().gasometer();
+    assert_eq!(
+        gasometer.gas(),
+        gas - 200,
+        "unused gas of a reverted substate is not given back"
+    );
+    assert_eq!(
+        gasometer.refunded_gas(),
+        50,
+        "refund of a reverted substate is kept"
+    );
+
+    enter(&mut state);
+    state.exit_discard().expect("discard succeeds");
+    assert_eq!(
+        state.metadata().gasometer().gas(),
+        gas - 200 - CHILD_GAS_LIMIT,
+        "gas of a discarded substate is given back"
+    );
+}
@@ -0,0 +1,993 @@
+//! Transaction level validation.
+
+use crate::backend::{Backend, GAS_PER_BLOB, MIN_BLOB_GAS_PRICE, VERSIONED_HASH_VERSION_KZG};
+use crate::executor::eip1559::{self, Eip1559Error};
+use crate::executor::fees;
+use crate::executor::stack::Authorization;
+use crate::gasometer::Gasometer;
+use crate::prelude::*;
+use crate::Config;
+use primitive_types::{H160, H256, U256};
+
+/// Transaction type, as defined by EIP-2718 envelopes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TransactionType {
+    /// Transactions before EIP-2718.
+    #[default]
+    Legacy,
+    /// <https://eips.ethereum.org/EIPS/eip-2930>
+    AccessList,
+    /// <https://eips.ethereum.org/EIPS/eip-1559>
+    DynamicFee,
+    /// <https://eips.ethereum.org/EIPS/eip-4844>
+    Blob,
+    /// <https://eips.ethereum.org/EIPS/eip-7702>
+    SetCode,
+    /// Chain-specific type, executed by the
+    /// [`crate::executor::stack::CustomTransactionType`] registered for it.
+    Custom(u8),
+}
+
+impl TransactionType {
+    /// Transaction type from the first byte of an encoded transaction.
+    /// Return `None` for unknown typed transactions, which may be of a
+    /// custom type.
+    #[must_use]
+    pub const fn from_first_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0xc0..=0xff => Some(Self::Legacy),
+            1 => Some(Self::AccessList),
+            2 => Some(Self::DynamicFee),
+            3 => Some(Self::Blob),
+            4 => Some(Self::SetCode),
+            _ => None,
+        }
+    }
+
+    /// Whether the transaction type is enabled by the config. Custom types
+    /// never are, as they are enabled by registering them with the
+    /// executor.
+    #[must_use]
+    pub const fn is_supported(self, config: &Config) -> bool {
+        match self {
+            Self::Legacy => true,
+            // Typed transactions were introduced in Berlin (EIP-2718)
+            Self::AccessList => config.increase_state_access_gas,
+            Self::DynamicFee => config.base_fee_enabled(),
+            Self::Blob => config.has_shard_blob_transactions,
+            Self::SetCode => config.has_authorization_list,
+            Self::Custom(_) => false,
+        }
+    }
+}
+
+/// Reason of a transaction rejection before execution.
+///
+/// The executor checks the type, gas cap, nonce and funds of the
+/// transactions it executes. The other reasons are for the embedders
+/// validating the rest of the transaction envelope, e.g. its blobs or its
+/// fee parameters, so that all the rejections share one type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InvalidTransaction {
+    /// Transaction type is not supported by the config.
+    TypeNotSupported,
+    /// Transaction gas limit exceeds `Config::max_transaction_gas`.
+    GasLimitExceeded,
+    /// Transaction nonce is lower than the sender nonce.
+    NonceTooLow,
+    /// Transaction nonce is higher than the sender nonce.
+    NonceTooHigh,
+    /// Sender balance does not cover the transaction cost.
+    InsufficientFunds,
+    /// Maximum cost of the transaction, its gas limit times its maximum fee
+    /// plus its value and its maximum blob fee, overflows.
+    CostOverflow,
+    /// Transaction gas limit is lower than its intrinsic gas.
+    IntrinsicGasTooLow,
+    /// Transaction gas limit exceeds the block gas limit.
+    BlockGasLimitExceeded,
+    /// EIP-7623: transaction gas limit is lower than its gas floor.
+    GasFloorMoreThanGasLimit,
+    /// EIP-3607: sender has code, other than an EIP-7702 delegation.
+    SenderNotEoa,
+    /// Access list before Berlin.
+    AccessListNotSupported,
+    /// EIP-1559 fee parameters before London.
+    DynamicFeeNotSupported,
+    /// EIP-1559: `max_priority_fee_per_gas` is greater than
+    /// `max_fee_per_gas`.
+    PriorityFeeTooLarge,
+    /// EIP-1559: `max_fee_per_gas` is lower than the block base fee.
+    GasPriceLessThanBaseFee,
+    /// EIP-4844: blob versioned hashes before Cancun.
+    BlobVersionedHashesNotSupported,
+    /// EIP-4844: `max_fee_per_blob_gas` before Cancun.
+    MaxFeePerBlobGasNotSupported,
+    /// EIP-4844: blob transaction creating a contract.
+    BlobCreateTransaction,
+    /// EIP-4844: blob transaction without blobs.
+    EmptyBlobs,
+    /// EIP-4844: blob versioned hash of an unknown version.
+    BlobVersionNotSupported,
+    /// EIP-4844: more blobs than allowed in a block.
+    TooManyBlobs,
+    /// EIP-4844: blob gas price is greater than `max_fee_per_blob_gas`.
+    BlobGasPriceGreaterThanMax,
+    /// EIP-7702: authorization list before Prague.
+    AuthorizationListNotSupported,
+    /// EIP-7702: set code transaction without authorizations.
+    EmptyAuthorizationList,
+    /// EIP-7702: set code transaction creating a contract.
+    SetCodeCreateTransaction,
+    /// EIP-7702: authorization with a malformed chain id.
+    InvalidAuthorizationChain,
+    /// EIP-7702: authorization with an invalid signature.
+    InvalidAuthorizationSignature,
+    /// Transaction signature is malformed.
+    InvalidSignature,
+    /// EIP-3860: init code of a contract creation exceeds
+    /// `Config::max_initcode_size`.
+    InitCodeSizeExceeded,
+}
+
+impl From<Eip1559Error> for InvalidTransaction {
+    fn from(error: Eip1559Error) -> Self {
+        match error {
+            Eip1559Error::PriorityFeeTooLarge => Self::PriorityFeeTooLarge,
+            Eip1559Error::MaxFeeLessThanBaseFee => Self::GasPriceLessThanBaseFee,
+        }
+    }
+}
+
+/// Transaction executed by `StackExecutor::transact`, which also charges
+/// its fees.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Transaction {
+    /// Sender of the transaction, paying the value and whose nonce is used.
+    pub caller: H160,
+    /// Called address, `None` for a contract creation.
+    pub to: Option<H160>,
+    /// Transferred value.
+    pub value: U256,
+    /// Call data, or init code of a contract creation.
+    pub data: Vec<u8>,
+    /// Gas limit.
+    pub gas_limit: u64,
+    /// Transaction nonce, checked according to the nonce policy of the
+    /// executor.
+    pub nonce: u64,
+    /// See EIP-2930.
+    pub access_list: Vec<(H160, Vec<H256>)>,
+    /// See EIP-7702.
+    pub authorization_list: Vec<Authorization>,
+    /// EIP-4844: versioned hashes of the blobs, whose blob gas is charged
+    /// along with the gas. `BLOBHASH` reads them from the transaction
+    /// environment, see `StackExecutor::set_tx_env`.
+    pub blob_hashes: Vec<U256>,
+    /// Sponsor paying the gas in place of the caller, e.g. the relayer of
+    /// a meta-transaction. It is charged the maximum fee upfront and gets
+    /// the unused gas refunded, while the caller still pays the value.
+    pub fee_payer: Option<H160>,
+    /// Chain-specific transaction type, `None` for the standard types. See
+    /// [`crate::executor::stack::CustomTransactionType`].
+    pub custom_type: Option<u8>,
+}
+
+impl Transaction {
+    /// Account paying the gas: the fee payer if any, otherwise the caller.
+    #[must_use]
+    pub const fn gas_payer(&self) -> H160 {
+        match self.fee_payer {
+            Some(fee_payer) => fee_payer,
+            None => self.caller,
+        }
+    }
+}
+
+/// Order of the secp256k1 curve.
+pub const SECP256K1N: U256 = U256([
+    0xBFD2_5E8C_D036_4141,
+    0xBAAE_DCE6_AF48_A03B,
+    0xFFFF_FFFF_FFFF_FFFE,
+    0xFFFF_FFFF_FFFF_FFFF,
+]);
+
+/// Order of the secp256k1 curve divided by two: since EIP-2, signatures
+/// with a greater `s` are rejected.
+pub const SECP256K1N_HALF: U256 = U256([
+    0xDFE9_2F46_681B_20A0,
+    0x5D57_6E73_57A4_501D,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0x7FFF_FFFF_FFFF_FFFF,
+]);
+
+/// ECDSA signature of a transaction or of an EIP-7702 authorization.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Signature {
+    /// Recovery id: `27` or `28` for legacy transactions, or with EIP-155
+    /// `35 + 2 * chain_id` plus the y parity, and the y parity for typed
+    /// transactions.
+    pub v: u64,
+    /// `r` value.
+    pub r: U256,
+    /// `s` value.
+    pub s: U256,
+}
+
+impl Signature {
+    /// Whether the signature has a valid format for a transaction of
+    /// `tx_type`, without recovering the signer. `r` and `s` must be
+    /// non-zero scalars lower than the curve order, and `s` at most half
+    /// of it since Homestead (EIP-2).
+    #[must_use]
+    pub fn is_well_formed(&self, tx_type: TransactionType, config: &Config) -> bool {
+        let valid_v = match tx_type {
+            TransactionType::Legacy => matches!(self.v, 27 | 28) || self.v >= 35,
+            _ => self.v <= 1,
+        };
+        // EIP-2 came with DELEGATECALL in Homestead
+        let max_s = if config.has_delegate_call {
+            SECP256K1N_HALF
+        } else {
+            SECP256K1N - 1
+        };
+        valid_v && !self.r.is_zero() && self.r < SECP256K1N && !self.s.is_zero() && self.s <= max_s
+    }
+}
+
+/// Signed transaction received by a transaction pool, with the envelope
+/// fields checked by [`prevalidate_stateless`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SignedTransaction {
+    /// Type of the envelope.
+    pub tx_type: TransactionType,
+    /// Transaction executed once the signer is recovered as its caller.
+    pub transaction: Transaction,
+    /// Gas price of legacy and access list transactions, and
+    /// `max_fee_per_gas` of the others.
+    pub max_fee_per_gas: U256,
+    /// EIP-1559 `max_priority_fee_per_gas`, `None` for legacy and access
+    /// list transactions.
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// EIP-4844 `max_fee_per_blob_gas`, `None` for the other types.
+    pub max_fee_per_blob_gas: Option<U256>,
+    /// Signature of the transaction.
+    pub signature: Signature,
+}
+
+/// Check a signed transaction without reading the state, so that
+/// transaction pools can cheaply reject malformed transactions before the
+/// stateful validation, i.e. the nonce, funds and sender checks of the
+/// executor.
+///
+/// The type must be enabled by the config, the gas limit within the
+/// transaction gas cap and above the intrinsic gas, the signature well
+/// formed, the fee parameters consistent, the blobs of a blob transaction
+/// present and of a known version, and the init code of a contract
+/// creation within the EIP-3860 limit. The number of blobs is left to the
+/// block validation.
+///
+/// Custom types are rejected with `InvalidTransaction::TypeNotSupported`,
+/// as they are registered with the executor, see
+/// [`crate::executor::stack::StackExecutor::prevalidate`].
+///
+/// # Errors
+/// Return the `InvalidTransaction` of the first failed check.
+pub fn prevalidate_stateless(
+    tx: &SignedTransaction,
+    config: &Config,
+) -> Result<(), InvalidTransaction> {
+    let SignedTransaction {
+        tx_type,
+        transaction,
+        signature,
+        ..
+    } = tx;
+    validate_type(*tx_type, config)?;
+    validate_gas_limit(transaction.gas_limit, config)?;
+    if transaction.to.is_none() {
+        validate_init_code_size(&transaction.data, config)?;
+    }
+    if !signature.is_well_formed(*tx_type, config) {
+        return Err(InvalidTransaction::InvalidSignature);
+    }
+    validate_envelope(tx)?;
+    validate_fee_cap(tx)?;
+    validate_intrinsic_gas(transaction, config)
+}
+
+/// Reject fields not allowed by the transaction type, and blob transactions
+/// without blobs or with an unknown blob version.
+fn validate_envelope(tx: &SignedTransaction) -> Result<(), InvalidTransaction> {
+    let SignedTransaction {
+        tx_type,
+        transaction,
+        max_fee_per_blob_gas,
+        ..
+    } = tx;
+    if *tx_type == TransactionType::Legacy && !transaction.access_list.is_empty() {
+        return Err(InvalidTransaction::AccessListNotSupported);
+    }
+    if *tx_type != TransactionType::Blob {
+        if !transaction.blob_hashes.is_empty() {
+            return Err(InvalidTransaction::BlobVersionedHashesNotSupported);
+        }
+        if max_fee_per_blob_gas.is_some() {
+            return Err(InvalidTransaction::MaxFeePerBlobGasNotSupported);
+        }
+    }
+    match tx_type {
+        TransactionType::Blob if transaction.to.is_none() => {
+            Err(InvalidTransaction::BlobCreateTransaction)
+        }
+        TransactionType::Blob if transaction.blob_hashes.is_empty() => {
+            Err(InvalidTransaction::EmptyBlobs)
+        }
+        TransactionType::Blob
+            if transaction
+                .blob_hashes
+                .iter()
+                .any(|hash| hash.byte(31) != VERSIONED_HASH_VERSION_KZG) =>
+        {
+            Err(InvalidTransaction::BlobVersionNotSupported)
+        }
+        // The blob gas price is never below its minimum
+        TransactionType::Blob
+            if max_fee_per_blob_gas.unwrap_or_default() < U256::from(MIN_BLOB_GAS_PRICE) =>
+        {
+            Err(InvalidTransaction::BlobGasPriceGreaterThanMax)
+        }
+        TransactionType::SetCode if transaction.to.is_none() => {
+            Err(InvalidTransaction::SetCodeCreateTransaction)
+        }
+        TransactionType::SetCode if transaction.authorization_list.is_empty() => {
+            Err(InvalidTransaction::EmptyAuthorizationList)
+        }
+        TransactionType::SetCode => Ok(()),
+        _ if !transaction.authorization_list.is_empty() => {
+            Err(InvalidTransaction::AuthorizationListNotSupported)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check the fee parameters independently of the block base fee: the
+/// priority fee must not exceed the maximum fee, and the maximum cost of
+/// the transaction must not overflow.
+fn validate_fee_cap(tx: &SignedTransaction) -> Result<(), InvalidTransaction> {
+    let SignedTransaction {
+        tx_type,
+        transaction,
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        max_fee_per_blob_gas,
+        ..
+    } = tx;
+    let is_dynamic_fee = !matches!(
+        tx_type,
+        TransactionType::Legacy | TransactionType::AccessList
+    );
+    if max_priority_fee_per_gas.is_some() && !is_dynamic_fee {
+        return Err(InvalidTransaction::DynamicFeeNotSupported);
+    }
+    eip1559::validate(U256::zero(), *max_fee_per_gas, *max_priority_fee_per_gas)?;
+    let max_blob_fee = U256::from(GAS_PER_BLOB)
+        .checked_mul(U256::from(transaction.blob_hashes.len()))
+        .and_then(|blob_gas| blob_gas.checked_mul(max_fee_per_blob_gas.unwrap_or_default()));
+    max_blob_fee
+        .and_then(|max_blob_fee| {
+            fees::max_fee(
+                U256::from(transaction.gas_limit),
+                *max_fee_per_gas,
+                Some(max_blob_fee),
+            )
+        })
+        .and_then(|max_fee| max_fee.checked_add(transaction.value))
+        .map(|_| ())
+        .ok_or(InvalidTransaction::CostOverflow)
+}
+
+/// EIP-3860: reject contract creations whose init code exceeds the limit
+/// of the config. The executor would fail them after charging their gas.
+///
+/// # Errors
+/// Return `InvalidTransaction::InitCodeSizeExceeded` if `init_code` is
+/// larger than `Config::max_initcode_size`.
+pub const fn validate_init_code_size(
+    init_code: &[u8],
+    config: &Config,
+) -> Result<(), InvalidTransaction> {
+    match config.max_initcode_size {
+        Some(limit) if init_code.len() > limit => Err(InvalidTransaction::InitCodeSizeExceeded),
+        _ => Ok(()),
+    }
+}
+
+/// Reject transactions whose gas limit does not cover their intrinsic gas,
+/// or their EIP-7623 gas floor.
+///
+/// # Errors
+/// Return `InvalidTransaction::IntrinsicGasTooLow` or
+/// `InvalidTransaction::GasFloorMoreThanGasLimit`.
+pub fn validate_intrinsic_gas(
+    transaction: &Transaction,
+    config: &Config,
+) -> Result<(), InvalidTransaction> {
+    let (intrinsic_gas, floor_gas) = Gasometer::calculate_intrinsic_gas_and_gas_floor(
+        &transaction.data,
+        &transaction.access_list,
+        transaction.authorization_list.len(),
+        config,
+        transaction.to.is_none(),
+    );
+    validate_gas_limit_covers(transaction.gas_limit, intrinsic_gas, floor_gas)
+}
+
+/// Reject a `gas_limit` lower than the `intrinsic_gas` or the EIP-7623
+/// `floor_gas` of its transaction, e.g. as computed by
+/// [`Gasometer::calculate_intrinsic_gas_and_gas_floor`].
+///
+/// # Errors
+/// Return `InvalidTransaction::IntrinsicGasTooLow` or
+/// `InvalidTransaction::GasFloorMoreThanGasLimit`.
+pub const fn validate_gas_limit_covers(
+    gas_limit: u64,
+    intrinsic_gas: u64,
+    floor_gas: u64,
+) -> Result<(), InvalidTransaction> {
+    if gas_limit < intrinsic_gas {
+        Err(InvalidTransaction::IntrinsicGasTooLow)
+    } else if gas_limit < floor_gas {
+        Err(InvalidTransaction::GasFloorMoreThanGasLimit)
+    } else {
+        Ok(())
+    }
+}
+
+/// Sender of a simulated transaction, given in place of the signature
+/// recovery, as the `from` field of `eth_call`.
+///
+/// The signature is never checked, the nonce and the balance of the sender
+/// only if set, so unsigned or partially built transactions can be run by
+/// simulation tooling. The transaction is run from the sender by
+/// `StackExecutor::transact_as`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SenderOverride {
+    /// Sender of the transaction.
+    pub sender: H160,
+    /// Transaction nonce, checked against the sender nonce if set.
+    pub nonce: Option<u64>,
+    /// Maximum transaction cost, i.e. gas limit times gas price plus value,
+    /// checked against the sender balance if set.
+    pub max_cost: Option<U256>,
+}
+
+impl SenderOverride {
+    /// Override the sender without any check.
+    #[must_use]
+    pub const fn new(sender: H160) -> Self {
+        Self {
+            sender,
+            nonce: None,
+            max_cost: None,
+        }
+    }
+
+    /// Check the transaction nonce against the sender nonce.
+    #[must_use]
+    pub const fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Check the maximum transaction cost against the sender balance.
+    #[must_use]
+    pub const fn with_max_cost(mut self, max_cost: U256) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    /// Validate the sender against the state of `backend`, e.g. the state
+    /// of the executor.
+    ///
+    /// # Errors
+    /// Return `InvalidTransaction::NonceTooLow` or
+    /// `InvalidTransaction::NonceTooHigh` if the nonce is set and differs
+    /// from the sender nonce, and `InvalidTransaction::InsufficientFunds`
+    /// if the maximum cost is set and exceeds the sender balance.
+    pub fn validate<B: Backend>(&self, backend: &B) -> Result<(), InvalidTransaction> {
+        if self.nonce.is_none() && self.max_cost.is_none() {
+            return Ok(());
+        }
+        let account = backend.basic(self.sender);
+        if let Some(nonce) = self.nonce {
+            validate_nonce(nonce, account.nonce)?;
+        }
+        match self.max_cost {
+            Some(max_cost) if max_cost > account.balance => {
+                Err(InvalidTransaction::InsufficientFunds)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Handling of the sender nonce by the transact functions of the executor.
+///
+/// The default policy is consensus-correct. Simulators and bundlers
+/// replaying candidate transactions out of order can skip the nonce check
+/// and keep the sender nonce unchanged, so the transactions can be replayed
+/// against the same state.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NoncePolicy {
+    /// Check that the transaction nonce equals the sender nonce, by
+    /// `StackExecutor::transact` and `StackExecutor::validate_nonce`. The
+    /// `transact_*` functions take no transaction nonce, so it is checked
+    /// with the latter before them.
+    pub check_nonce: bool,
+    /// Increment the sender nonce, and reject senders at the maximum nonce
+    /// per EIP-2681. The nonce of the creators of nested contract creations
+    /// is always incremented.
+    pub increment_nonce: bool,
+}
+
+impl NoncePolicy {
+    /// Consensus rules: check and increment the sender nonce.
+    pub const CONSENSUS: Self = Self {
+        check_nonce: true,
+        increment_nonce: true,
+    };
+    /// Neither check nor increment the sender nonce.
+    pub const REPLAY: Self = Self {
+        check_nonce: false,
+        increment_nonce: false,
+    };
+
+    /// Validate the transaction `nonce` against the `sender` nonce in the
+    /// state of `backend`, if the policy checks nonces.
+    ///
+    /// # Errors
+    /// Return `InvalidTransaction::NonceTooLow` or
+    /// `InvalidTransaction::NonceTooHigh` if the nonces differ.
+    pub fn validate<B: Backend>(
+        &self,
+        backend: &B,
+        sender: H160,
+        nonce: u64,
+    ) -> Result<(), InvalidTransaction> {
+        if !self.check_nonce {
+            return Ok(());
+        }
+        validate_nonce(nonce, backend.basic(sender).nonce)
+    }
+}
+
+impl Default for NoncePolicy {
+    fn default() -> Self {
+        Self::CONSENSUS
+    }
+}
+
+/// Compare a transaction nonce with the sender nonce.
+fn validate_nonce(nonce: u64, sender_nonce: U256) -> Result<(), InvalidTransaction> {
+    let nonce = U256::from(nonce);
+    if nonce < sender_nonce {
+        Err(InvalidTransaction::NonceTooLow)
+    } else if nonce > sender_nonce {
+        Err(InvalidTransaction::NonceTooHigh)
+    } else {
+        Ok(())
+    }
+}
+
+/// Reject transaction types not supported by the config.
+///
+/// # Errors
+/// Return `InvalidTransaction::TypeNotSupported` if the transaction type is
+/// not enabled, for example typed transactions before Berlin.
+pub const fn validate_type(
+    tx_type: TransactionType,
+    config: &Config,
+) -> Result<(), InvalidTransaction> {
+    if tx_type.is_supported(config) {
+        Ok(())
+    } else {
+        Err(InvalidTransaction::TypeNotSupported)
+    }
+}
+
+/// EIP-3607: reject transactions from senders with code, except EIP-7702
+/// delegations, which can still originate transactions.
+///
+/// # Errors
+/// Return `InvalidTransaction::SenderNotEoa` if `caller` has code.
+pub fn validate_sender<B: Backend>(backend: &B, caller: H160) -> Result<(), InvalidTransaction> {
+    let code = backend.code(caller);
+    if code.is_empty() || Authorization::is_delegated(&code) {
+        Ok(())
+    } else {
+        Err(InvalidTransaction::SenderNotEoa)
+    }
+}
+
+/// Reject transactions with a gas limit above the transaction gas cap of
+/// the config. The cap is independent of the block gas limit.
+///
+/// # Errors
+/// Return `InvalidTransaction::GasLimitExceeded` if `gas_limit` exceeds
+/// `Config::max_transaction_gas`.
+pub const fn validate_gas_limit(gas_limit: u64, config: &Config) -> Result<(), InvalidTransaction> {
+    match config.max_transaction_gas {
+        Some(cap) if gas_limit > cap => Err(InvalidTransaction::GasLimitExceeded),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::prelude::*;
+    use crate::FeeMarket;
+
+    #[test]
+    fn test_typed_transactions_rejected_before_berlin() {
+        let istanbul = Config::istanbul();
+        assert_eq!(validate_type(TransactionType::Legacy, &istanbul), Ok(()));
+        assert_eq!(
+            validate_type(TransactionType::AccessList, &istanbul),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+        assert_eq!(
+            validate_type(TransactionType::AccessList, &Config::berlin()),
+            Ok(())
+        );
+        assert_eq!(
+            validate_type(TransactionType::DynamicFee, &Config::berlin()),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+        assert_eq!(
+            validate_type(TransactionType::DynamicFee, &Config::london()),
+            Ok(())
+        );
+        assert_eq!(
+            validate_type(TransactionType::Blob, &Config::shanghai()),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+        assert_eq!(
+            validate_type(TransactionType::SetCode, &Config::prague()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_legacy_fee_market_rejects_dynamic_fee() {
+        assert_eq!(Config::berlin().fee_market, FeeMarket::Legacy);
+        assert_eq!(Config::london().fee_market, FeeMarket::Eip1559);
+        assert!(Config::london().base_fee_enabled());
+
+        let config = Config {
+            fee_market: FeeMarket::Legacy,
+            ..Config::london()
+        };
+        assert!(!config.base_fee_enabled());
+        assert_eq!(
+            validate_type(TransactionType::DynamicFee, &config),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+        assert_eq!(validate_type(TransactionType::AccessList, &config), Ok(()));
+    }
+
+    #[test]
+    fn test_transaction_gas_cap() {
+        let config = Config::cancun();
+        assert_eq!(config.max_transaction_gas, None);
+        assert_eq!(validate_gas_limit(u64::MAX, &config), Ok(()));
+
+        let config = Config {
+            max_transaction_gas: Some(1_000_000),
+            ..config
+        };
+        assert_eq!(validate_gas_limit(1_000_000, &config), Ok(()));
+        assert_eq!(
+            validate_gas_limit(1_000_001, &config),
+            Err(InvalidTransaction::GasLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_validate_sender() {
+        let eoa = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(2);
+        let delegated = H160::from_low_u64_be(3);
+        let mut delegation = vec![0xef, 0x01, 0x00];
+        delegation.extend_from_slice(contract.as_bytes());
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([
+                (
+                    contract,
+                    MemoryAccount {
+                        code: vec![0x00],
+                        ..MemoryAccount::default()
+                    },
+                ),
+                (
+                    delegated,
+                    MemoryAccount {
+                        code: delegation,
+                        ..MemoryAccount::default()
+                    },
+                ),
+            ]),
+        );
+        assert_eq!(validate_sender(&backend, eoa), Ok(()));
+        assert_eq!(validate_sender(&backend, delegated), Ok(()));
+        assert_eq!(
+            validate_sender(&backend, contract),
+            Err(InvalidTransaction::SenderNotEoa)
+        );
+        assert_eq!(
+            InvalidTransaction::from(Eip1559Error::MaxFeeLessThanBaseFee),
+            InvalidTransaction::GasPriceLessThanBaseFee
+        );
+    }
+
+    #[test]
+    fn test_sender_override() {
+        let sender = H160::from_low_u64_be(1);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                sender,
+                MemoryAccount {
+                    nonce: U256::from(5),
+                    balance: U256::from(1_000),
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+
+        // Unknown senders are accepted without checks
+        let unknown = SenderOverride::new(H160::from_low_u64_be(2));
+        assert_eq!(unknown.validate(&backend), Ok(()));
+        assert_eq!(
+            unknown
+                .with_nonce(0)
+                .with_max_cost(U256::one())
+                .validate(&backend),
+            Err(InvalidTransaction::InsufficientFunds)
+        );
+
+        let checked = SenderOverride::new(sender).with_nonce(5);
+        assert_eq!(checked.validate(&backend), Ok(()));
+        assert_eq!(
+            checked.with_nonce(4).validate(&backend),
+            Err(InvalidTransaction::NonceTooLow)
+        );
+        assert_eq!(
+            checked.with_nonce(6).validate(&backend),
+            Err(InvalidTransaction::NonceTooHigh)
+        );
+        assert_eq!(
+            checked.with_max_cost(U256::from(1_000)).validate(&backend),
+            Ok(())
+        );
+        assert_eq!(
+            checked.with_max_cost(U256::from(1_001)).validate(&backend),
+            Err(InvalidTransaction::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn test_nonce_policy() {
+        let sender = H160::from_low_u64_be(1);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                sender,
+                MemoryAccount {
+                    nonce: U256::from(5),
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+
+        assert_eq!(NoncePolicy::default(), NoncePolicy::CONSENSUS);
+        assert_eq!(NoncePolicy::CONSENSUS.validate(&backend, sender, 5), Ok(()));
+        assert_eq!(
+            NoncePolicy::CONSENSUS.validate(&backend, sender, 7),
+            Err(InvalidTransaction::NonceTooHigh)
+        );
+        assert_eq!(NoncePolicy::REPLAY.validate(&backend, sender, 7), Ok(()));
+    }
+
+    #[test]
+    fn test_transaction_type_from_first_byte() {
+        assert_eq!(
+            TransactionType::from_first_byte(0xf8),
+            Some(TransactionType::Legacy)
+        );
+        assert_eq!(
+            TransactionType::from_first_byte(2),
+            Some(TransactionType::DynamicFee)
+        );
+        assert_eq!(TransactionType::from_first_byte(0x7f), None);
+    }
+
+    #[test]
+    fn test_prevalidate_stateless() {
+        let config = Config::prague();
+        let tx = SignedTransaction {
+            tx_type: TransactionType::DynamicFee,
+            transaction: Transaction {
+                to: Some(H160::from_low_u64_be(1)),
+                gas_limit: 21_000,
+                ..Transaction::default()
+            },
+            max_fee_per_gas: U256::from(10),
+            max_priority_fee_per_gas: Some(U256::one()),
+            max_fee_per_blob_gas: None,
+            signature: Signature {
+                v: 1,
+                r: U256::one(),
+                s: U256::one(),
+            },
+        };
+        assert_eq!(prevalidate_stateless(&tx, &config), Ok(()));
+        assert_eq!(
+            prevalidate_stateless(&tx, &Config::istanbul()),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+
+        let mut invalid = tx.clone();
+        invalid.transaction.gas_limit = 20_999;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::IntrinsicGasTooLow)
+        );
+        // 100 non-zero bytes cost 1600 gas, and a floor of 4000 gas
+        invalid.transaction.gas_limit = 22_600;
+        invalid.transaction.data = vec![1; 100];
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::GasFloorMoreThanGasLimit)
+        );
+        invalid.transaction.to = None;
+        invalid.transaction.data = vec![0; config.max_initcode_size.unwrap() + 1];
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::InitCodeSizeExceeded)
+        );
+
+        let mut invalid = tx.clone();
+        invalid.signature.s = SECP256K1N_HALF + 1;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::InvalidSignature)
+        );
+        invalid.signature = Signature {
+            v: 27,
+            ..tx.signature
+        };
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::InvalidSignature)
+        );
+        // EIP-2 only applies since Homestead
+        let signature = Signature {
+            v: 27,
+            r: U256::one(),
+            s: SECP256K1N_HALF + 1,
+        };
+        assert!(signature.is_well_formed(TransactionType::Legacy, &Config::frontier()));
+        assert!(!signature.is_well_formed(TransactionType::Legacy, &Config::istanbul()));
+
+        let mut invalid = tx.clone();
+        invalid.max_priority_fee_per_gas = Some(U256::from(11));
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::PriorityFeeTooLarge)
+        );
+        invalid.max_priority_fee_per_gas = None;
+        invalid.max_fee_per_gas = U256::MAX;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::CostOverflow)
+        );
+
+        let mut invalid = tx.clone();
+        invalid.tx_type = TransactionType::Legacy;
+        invalid.signature.v = 27;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::DynamicFeeNotSupported)
+        );
+        invalid.tx_type = TransactionType::SetCode;
+        invalid.signature.v = 1;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::EmptyAuthorizationList)
+        );
+        invalid.tx_type = TransactionType::Blob;
+        invalid.transaction.to = None;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::BlobCreateTransaction)
+        );
+
+        // Custom types are checked by the executor registering them
+        let custom = SignedTransaction {
+            tx_type: TransactionType::Custom(0x7f),
+            signature: Signature::default(),
+            ..tx
+        };
+        assert_eq!(
+            prevalidate_stateless(&custom, &config),
+            Err(InvalidTransaction::TypeNotSupported)
+        );
+    }
+
+    #[test]
+    fn test_prevalidate_blobs() {
+        let config = Config::cancun();
+        let blob_hash = U256::from(VERSIONED_HASH_VERSION_KZG) << 248;
+        let tx = SignedTransaction {
+            tx_type: TransactionType::Blob,
+            transaction: Transaction {
+                to: Some(H160::from_low_u64_be(1)),
+                gas_limit: 21_000,
+                blob_hashes: vec![blob_hash],
+                ..Transaction::default()
+            },
+            max_fee_per_gas: U256::from(10),
+            max_priority_fee_per_gas: Some(U256::one()),
+            max_fee_per_blob_gas: Some(U256::one()),
+            signature: Signature {
+                v: 1,
+                r: U256::one(),
+                s: U256::one(),
+            },
+        };
+        assert_eq!(prevalidate_stateless(&tx, &config), Ok(()));
+
+        let mut invalid = tx.clone();
+        invalid.transaction.blob_hashes.clear();
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::EmptyBlobs)
+        );
+        invalid.transaction.blob_hashes = vec![blob_hash, U256::one()];
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::BlobVersionNotSupported)
+        );
+
+        let mut invalid = tx.clone();
+        invalid.max_fee_per_blob_gas = None;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::BlobGasPriceGreaterThanMax)
+        );
+        invalid.max_fee_per_blob_gas = Some(U256::MAX);
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::CostOverflow)
+        );
+
+        let mut invalid = tx.clone();
+        invalid.tx_type = TransactionType::DynamicFee;
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::BlobVersionedHashesNotSupported)
+        );
+        invalid.transaction.blob_hashes.clear();
+        assert_eq!(
+            prevalidate_stateless(&invalid, &config),
+            Err(InvalidTransaction::MaxFeePerBlobGasNotSupported)
+        );
+    }
+}
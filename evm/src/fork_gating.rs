@@ -0,0 +1,144 @@
+//! Opcodes and precompiles enabled by every `Config` preset, checked against
+//! golden tables of the hard forks introducing them. A flag enabling an
+//! opcode or a precompile in the wrong preset, e.g. `MCOPY` before Cancun,
+//! fails here without running the consensus tests.
+
+use crate::backend::{BlockEnv, MemoryBackend, MemoryVicinity, TxEnv};
+use crate::executor::stack::StackExecutorBuilder;
+use crate::gasometer::{self, GasCost};
+use crate::prelude::*;
+use crate::{Config, HardFork, Opcode, Stack};
+use primitive_types::{H160, U256};
+
+/// Hard forks with a `Config` preset, and the preset, in activation order.
+fn presets() -> impl Iterator<Item = (HardFork, Config)> {
+    HardFork::ALL
+        .into_iter()
+        .filter_map(|fork| Some((fork, fork.gasometer_config()?)))
+}
+
+/// Opcodes of Frontier, as ranges of bytes. `INVALID` is designated
+/// invalid, and never priced.
+const FRONTIER_OPCODES: [(u8, u8); 9] = [
+    (0x00, 0x0b),
+    (0x10, 0x1a),
+    (0x20, 0x20),
+    (0x30, 0x3c),
+    (0x40, 0x45),
+    (0x50, 0x5b),
+    (0x60, 0xa4),
+    (0xf0, 0xf3),
+    (0xff, 0xff),
+];
+
+/// Opcodes added by every preset to the previous one.
+fn added_opcodes(fork: HardFork) -> Vec<Opcode> {
+    match fork {
+        HardFork::Frontier => FRONTIER_OPCODES
+            .iter()
+            .flat_map(|&(first, last)| (first..=last).map(Opcode))
+            .collect(),
+        // Homestead, Byzantium, Constantinople and Istanbul
+        HardFork::Istanbul => vec![
+            Opcode::DELEGATECALL,
+            Opcode::REVERT,
+            Opcode::RETURNDATASIZE,
+            Opcode::RETURNDATACOPY,
+            Opcode::STATICCALL,
+            Opcode::SHL,
+            Opcode::SHR,
+            Opcode::SAR,
+            Opcode::EXTCODEHASH,
+            Opcode::CREATE2,
+            Opcode::CHAINID,
+            Opcode::SELFBALANCE,
+        ],
+        HardFork::London => vec![Opcode::BASEFEE],
+        HardFork::Shanghai => vec![Opcode::PUSH0],
+        HardFork::Cancun => vec![
+            Opcode::BLOBHASH,
+            Opcode::BLOBBASEFEE,
+            Opcode::TLOAD,
+            Opcode::TSTORE,
+            Opcode::MCOPY,
+        ],
+        HardFork::Osaka => vec![Opcode::CLZ],
+        _ => Vec::new(),
+    }
+}
+
+/// Opcodes the gasometer prices, and so the executor runs, with `config`.
+fn enabled_opcodes(config: &Config) -> BTreeSet<u8> {
+    let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+    let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+    let mut executor = StackExecutorBuilder::new(config, u64::MAX)
+        .backend(&backend)
+        .build();
+
+    let mut stack = Stack::new(1024);
+    for _ in 0..7 {
+        stack.push(U256::zero()).unwrap();
+    }
+    (0..=u8::MAX)
+        .filter(|&byte| {
+            let opcode = Opcode(byte);
+            gasometer::static_opcode_cost(opcode).is_some()
+                || !matches!(
+                    gasometer::dynamic_opcode_cost(
+                        H160::zero(),
+                        opcode,
+                        &stack,
+                        false,
+                        config,
+                        &mut executor,
+                    ),
+                    Ok((GasCost::Invalid(_), _))
+                )
+        })
+        .collect()
+}
+
+#[test]
+fn test_opcodes_per_preset() {
+    let mut expected = BTreeSet::new();
+    for (fork, config) in presets() {
+        expected.extend(added_opcodes(fork).into_iter().map(|opcode| opcode.0));
+        let enabled = enabled_opcodes(&config);
+        assert_eq!(
+            enabled.difference(&expected).collect::<Vec<_>>(),
+            Vec::<&u8>::new(),
+            "unexpected opcodes enabled in {fork:?}"
+        );
+        assert_eq!(
+            expected.difference(&enabled).collect::<Vec<_>>(),
+            Vec::<&u8>::new(),
+            "expected opcodes disabled in {fork:?}"
+        );
+    }
+}
+
+#[cfg(all(
+    feature = "precompile-blake2f",
+    feature = "precompile-bn128",
+    feature = "precompile-ecrecover",
+    feature = "precompile-identity",
+    feature = "precompile-modexp",
+    feature = "precompile-ripemd160",
+    feature = "precompile-sha256"
+))]
+#[test]
+fn test_precompiles_per_preset() {
+    use crate::executor::stack::PrecompileSet;
+    use crate::precompiles::{precompile_address, StandardPrecompiles};
+
+    for (fork, config) in presets() {
+        // Frontier has 1 to 4, Byzantium adds 5 to 8 and Istanbul 9
+        let last = if fork == HardFork::Frontier { 4 } else { 9 };
+        let expected: Vec<H160> = (1..=last).map(precompile_address).collect();
+        assert_eq!(
+            StandardPrecompiles::new(&config).precompile_addresses(),
+            expected,
+            "precompiles of {fork:?}"
+        );
+    }
+}
@@ -5,7 +5,6 @@ pub const G_LOW: u32 = 5;
 pub const G_MID: u32 = 8;
 pub const G_HIGH: u32 = 10;
 pub const G_JUMPDEST: u32 = 1;
-pub const R_SUICIDE: i32 = 24000;
 pub const G_CREATE: u32 = 32000;
 pub const G_CALLVALUE: u32 = 9000;
 pub const G_NEWACCOUNT: u32 = 25000;
@@ -18,4 +17,3 @@ pub const G_SHA3: u32 = 30;
 pub const G_SHA3WORD: u32 = 6;
 pub const G_COPY: u32 = 3;
 pub const G_BLOCKHASH: u32 = 20;
-pub const G_CODEDEPOSIT: u32 = 200;
@@ -12,11 +12,11 @@ pub fn call_extra_check(gas: U256, after_gas: u64, config: &Config) -> Result<()
     }
 }
 
-pub fn suicide_refund(already_removed: bool) -> i64 {
+pub const fn suicide_refund(already_removed: bool, config: &Config) -> i64 {
     if already_removed {
         0
     } else {
-        i64::from(consts::R_SUICIDE)
+        config.gas_suicide_refund
     }
 }
 
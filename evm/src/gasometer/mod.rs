@@ -43,8 +43,13 @@ macro_rules! event {
 mod consts;
 mod costs;
 mod memory;
+mod refund;
+#[cfg(all(feature = "std", feature = "with-serde"))]
+mod schedule;
 mod utils;
 
+pub use self::refund::RefundCounter;
+
 use crate::core::utils::U256_ZERO;
 use crate::core::{ExitError, Opcode, Stack};
 use crate::prelude::*;
@@ -71,26 +76,67 @@ pub struct Snapshot {
     pub memory_gas: u64,
     pub used_gas: u64,
     pub refunded_gas: i64,
+    pub tag: FrameTag,
 }
 
 #[cfg(feature = "tracing")]
 impl Snapshot {
     #[must_use]
-    const fn new<'config>(gas_limit: u64, inner: &'config Inner<'config>) -> Self {
+    const fn new<'config>(gas_limit: u64, tag: FrameTag, inner: &'config Inner<'config>) -> Self {
         Self {
             gas_limit,
             memory_gas: inner.memory_gas,
             used_gas: inner.used_gas,
-            refunded_gas: inner.refunded_gas,
+            refunded_gas: inner.refunded_gas.get(),
+            tag,
         }
     }
 }
 
+/// Point-in-time breakdown of the gas recorded by a gasometer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct GasSnapshot {
+    /// Gas limit of the gasometer.
+    pub gas_limit: u64,
+    /// Gas used, excluding memory expansion gas.
+    pub used: u64,
+    /// Gas used for memory expansion.
+    pub memory_gas: u64,
+    /// Refunded gas, not yet applied to the used gas.
+    pub refunded: i64,
+}
+
+impl GasSnapshot {
+    /// Total used gas, including memory expansion gas.
+    #[must_use]
+    pub const fn total_used(&self) -> u64 {
+        self.used.saturating_add(self.memory_gas)
+    }
+
+    /// Remaining gas.
+    #[must_use]
+    pub const fn remaining(&self) -> u64 {
+        self.gas_limit.saturating_sub(self.total_used())
+    }
+}
+
+/// Attribution of the gas recorded by a gasometer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FrameTag {
+    /// Gas of a regular call or create frame.
+    #[default]
+    Regular,
+    /// Gas recorded while a subcall made by the precompile at `precompile`
+    /// is executed, including the nested frames of that subcall.
+    PrecompileSubcall { precompile: H160 },
+}
+
 /// EVM gasometer.
 #[derive(Clone, Debug)]
 pub struct Gasometer<'config> {
     gas_limit: u64,
     config: &'config Config,
+    tag: FrameTag,
     inner: Result<Inner<'config>, ExitError>,
 }
 
@@ -101,10 +147,11 @@ impl<'config> Gasometer<'config> {
         Self {
             gas_limit,
             config,
+            tag: FrameTag::Regular,
             inner: Ok(Inner {
                 memory_gas: 0,
                 used_gas: 0,
-                refunded_gas: 0,
+                refunded_gas: RefundCounter::ZERO,
                 floor_gas: 0,
                 config,
             }),
@@ -142,6 +189,19 @@ impl<'config> Gasometer<'config> {
         self.gas_limit
     }
 
+    /// Attribution tag of the recorded gas.
+    #[inline]
+    #[must_use]
+    pub const fn tag(&self) -> FrameTag {
+        self.tag
+    }
+
+    /// Set the attribution tag of the gas recorded from now on.
+    #[inline]
+    pub const fn set_tag(&mut self, tag: FrameTag) {
+        self.tag = tag;
+    }
+
     /// Get floor gas
     #[inline]
     #[must_use]
@@ -172,7 +232,35 @@ impl<'config> Gasometer<'config> {
     #[inline]
     #[must_use]
     pub fn refunded_gas(&self) -> i64 {
-        self.inner.as_ref().map_or(0, |inner| inner.refunded_gas)
+        self.refund_counter().get()
+    }
+
+    /// Refund counter, zero for a failed gasometer.
+    #[inline]
+    #[must_use]
+    pub fn refund_counter(&self) -> RefundCounter {
+        self.inner
+            .as_ref()
+            .map_or(RefundCounter::ZERO, |inner| inner.refunded_gas)
+    }
+
+    /// Breakdown of the recorded gas. A failed gasometer has used all its gas.
+    #[must_use]
+    pub const fn gas_snapshot(&self) -> GasSnapshot {
+        match self.inner.as_ref() {
+            Ok(inner) => GasSnapshot {
+                gas_limit: self.gas_limit,
+                used: inner.used_gas,
+                memory_gas: inner.memory_gas,
+                refunded: inner.refunded_gas.get(),
+            },
+            Err(_) => GasSnapshot {
+                gas_limit: self.gas_limit,
+                used: self.gas_limit,
+                memory_gas: 0,
+                refunded: 0,
+            },
+        }
     }
 
     /// Explicitly fail the gasometer with out of gas. Return `OutOfGas` error.
@@ -215,7 +303,8 @@ impl<'config> Gasometer<'config> {
         });
         log_gas!(self, "record_refund: -{}", refund);
 
-        self.inner_mut()?.refunded_gas += refund;
+        let inner = self.inner_mut()?;
+        inner.refunded_gas = inner.refunded_gas.saturating_add(refund);
         Ok(())
     }
 
@@ -226,23 +315,48 @@ impl<'config> Gasometer<'config> {
     /// Return `ExitError` if `record_refund` operation fails.
     pub fn record_authority_refund(&mut self, refunded_accounts: u64) -> Result<(), ExitError> {
         let refund = i64::try_from(
-            refunded_accounts
-                * (self.config.gas_per_empty_account_cost - self.config.gas_per_auth_base_cost),
+            refunded_accounts.saturating_mul(
+                self.config
+                    .gas_per_empty_account_cost
+                    .saturating_sub(self.config.gas_per_auth_base_cost),
+            ),
         )
         .unwrap_or(i64::MAX);
         self.record_refund(refund)
     }
 
+    /// Record a processed authorization - EIP-7702.
+    /// If the `authority` already exists, `PER_EMPTY_ACCOUNT_COST - PER_AUTH_BASE_COST`
+    /// is refunded, as the empty account cost was charged upfront.
+    ///
+    /// ## Errors
+    /// Return `ExitError` if `record_refund` operation fails.
+    pub fn record_authorization(&mut self, existing: bool) -> Result<(), ExitError> {
+        if existing {
+            self.record_authority_refund(1)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Record the code deposit of a `CREATE` deploying `len` bytes of code,
+    /// see [`code_deposit_cost`].
+    ///
+    /// # Errors
+    /// Return `ExitError`
+    #[inline]
+    pub fn record_code_deposit(&mut self, len: usize) -> Result<(), ExitError> {
+        self.record_cost(code_deposit_cost(len, self.config))
+    }
+
     /// Record `CREATE` code deposit.
     ///
     /// # Errors
     /// Return `ExitError`
-    /// NOTE: in that context usize->u64 `as_conversions` is save
-    #[allow(clippy::as_conversions)]
+    #[deprecated(note = "use `record_code_deposit`")]
     #[inline]
     pub fn record_deposit(&mut self, len: usize) -> Result<(), ExitError> {
-        let cost = len as u64 * u64::from(consts::G_CODEDEPOSIT);
-        self.record_cost(cost)
+        self.record_code_deposit(len)
     }
 
     /// Record opcode gas cost.
@@ -292,7 +406,7 @@ impl<'config> Gasometer<'config> {
 
         inner_mut.used_gas += gas_cost;
         inner_mut.memory_gas = memory_gas;
-        inner_mut.refunded_gas += gas_refund;
+        inner_mut.refunded_gas = inner_mut.refunded_gas.saturating_add(gas_refund);
 
         // NOTE Extended meesage: "Record dynamic cost {gas_cost} - memory_gas {} - gas_refund {}",
         log_gas!(
@@ -435,7 +549,7 @@ impl<'config> Gasometer<'config> {
                             .saturating_mul(access_list_storage_len as u64),
                     );
 
-                if config.max_initcode_size.is_some() {
+                if config.has_init_code_cost {
                     cost = cost.saturating_add(initcode_cost);
                 }
 
@@ -543,7 +657,7 @@ impl<'config> Gasometer<'config> {
         self.inner
             .as_ref()
             .ok()
-            .map(|inner| Snapshot::new(self.gas_limit, inner))
+            .map(|inner| Snapshot::new(self.gas_limit, self.tag, inner))
     }
 }
 
@@ -586,6 +700,51 @@ pub fn create_transaction_cost(data: &[u8], access_list: &[(H160, Vec<H256>)]) -
     }
 }
 
+/// Gas limit of a call or create frame requesting `requested` gas, with
+/// `available` gas left after the cost of the opcode. Since EIP-150, at most
+/// all but one 64th of the available gas is forwarded. `None` requests all
+/// the available gas, as `CREATE` does.
+#[must_use]
+pub const fn max_call_gas(available: u64, requested: Option<u64>, config: &Config) -> u64 {
+    let available = if config.call_l64_after_gas {
+        available - available / 64
+    } else {
+        available
+    };
+    match requested {
+        Some(requested) if requested < available => requested,
+        _ => available,
+    }
+}
+
+/// Gas given to the callee of a call with a `gas_limit` computed by
+/// [`max_call_gas`], including the free stipend of value transfers, which is
+/// not charged to the caller.
+#[must_use]
+pub const fn call_gas_with_stipend(gas_limit: u64, transfers_value: bool, config: &Config) -> u64 {
+    if transfers_value {
+        gas_limit.saturating_add(config.call_stipend)
+    } else {
+        gas_limit
+    }
+}
+
+/// Surcharge of a `CALL` for the account it creates, by transferring value
+/// to it or, before EIP-161, by touching it.
+#[must_use]
+pub fn call_new_account_cost() -> u64 {
+    u64::from(consts::G_NEWACCOUNT)
+}
+
+/// Code deposit cost of `len` bytes of deployed code:
+/// `Config::gas_code_deposit` per byte.
+#[must_use]
+pub fn code_deposit_cost(len: usize, config: &Config) -> u64 {
+    u64::try_from(len)
+        .unwrap_or(u64::MAX)
+        .saturating_mul(config.gas_code_deposit)
+}
+
 /// Init code cost, related to `EIP-3860`
 /// NOTE: in that context `as-conversion` is safe for `usize->u64`
 #[allow(clippy::as_conversions)]
@@ -730,36 +889,74 @@ pub fn static_opcode_cost(opcode: Opcode) -> Option<u32> {
     TABLE[opcode.as_usize()]
 }
 
+#[cfg(feature = "tracing")]
+use self::tracing::record_access;
+
+#[cfg(not(feature = "tracing"))]
+#[inline]
+const fn record_access(
+    _opcode: Opcode,
+    _address: H160,
+    _index: Option<H256>,
+    _is_cold: bool,
+    _config: &Config,
+) {
+}
+
 /// Get and set warm address if it's not warmed.
-fn get_and_set_warm<H: Handler>(handler: &mut H, target: H160) -> (bool, Option<bool>) {
+fn get_and_set_warm<H: Handler>(
+    handler: &mut H,
+    target: H160,
+    opcode: Opcode,
+    config: &Config,
+) -> (bool, Option<bool>) {
     let delegated_designator_is_cold =
         handler
             .get_authority_target(target)
             .map(|authority_target| {
-                if handler.is_cold(authority_target, None) {
+                let is_cold = handler.is_cold(authority_target, None);
+                record_access(opcode, authority_target, None, is_cold, config);
+                if is_cold {
                     handler.warm_target((authority_target, None));
-                    true
-                } else {
-                    false
                 }
+                is_cold
             });
-    let target_is_cold = handler.is_cold(target, None);
-    if target_is_cold {
-        handler.warm_target((target, None));
-    }
+    let target_is_cold = get_and_set_non_delegated_warm(handler, target, opcode, config);
     (target_is_cold, delegated_designator_is_cold)
 }
 
 /// Get and set warm address if it's not warmed for non-delegated opcodes like `EXT*`.
 /// NOTE: Related to EIP-7702
-fn get_and_set_non_delegated_warm<H: Handler>(handler: &mut H, target: H160) -> bool {
+fn get_and_set_non_delegated_warm<H: Handler>(
+    handler: &mut H,
+    target: H160,
+    opcode: Opcode,
+    config: &Config,
+) -> bool {
     let target_is_cold = handler.is_cold(target, None);
+    record_access(opcode, target, None, target_is_cold, config);
     if target_is_cold {
         handler.warm_target((target, None));
     }
     target_is_cold
 }
 
+/// Get and set warm storage slot if it's not warmed.
+fn get_and_set_storage_warm<H: Handler>(
+    handler: &mut H,
+    address: H160,
+    index: H256,
+    opcode: Opcode,
+    config: &Config,
+) -> bool {
+    let target_is_cold = handler.is_cold(address, Some(index));
+    record_access(opcode, address, Some(index), target_is_cold, config);
+    if target_is_cold {
+        handler.warm_target((address, Some(index)));
+    }
+    target_is_cold
+}
+
 /// Calculate the opcode cost.
 ///
 /// # Errors
@@ -798,7 +995,7 @@ pub fn dynamic_opcode_cost<H: Handler>(
         Opcode::SELFBALANCE if config.has_self_balance => GasCost::Low,
         Opcode::SELFBALANCE => GasCost::Invalid(opcode),
 
-        Opcode::BASEFEE if config.has_base_fee => GasCost::Base,
+        Opcode::BASEFEE if config.base_fee_enabled() => GasCost::Base,
         Opcode::BASEFEE => GasCost::Invalid(opcode),
 
         Opcode::BLOBBASEFEE if config.has_blob_base_fee => GasCost::Base,
@@ -820,29 +1017,27 @@ pub fn dynamic_opcode_cost<H: Handler>(
 
         Opcode::EXTCODESIZE => {
             let target = stack.peek_h256(0)?.into();
-            let target_is_cold = get_and_set_non_delegated_warm(handler, target);
+            let target_is_cold = get_and_set_non_delegated_warm(handler, target, opcode, config);
             GasCost::ExtCodeSize { target_is_cold }
         }
         Opcode::BALANCE => {
             let target = stack.peek_h256(0)?.into();
-            let target_is_cold = handler.is_cold(target, None);
-            if target_is_cold {
-                handler.warm_target((target, None));
-            }
+            let target_is_cold = get_and_set_non_delegated_warm(handler, target, opcode, config);
             GasCost::Balance { target_is_cold }
         }
         Opcode::BLOCKHASH => GasCost::BlockHash,
 
         Opcode::EXTCODEHASH if config.has_ext_code_hash => {
             let target = stack.peek_h256(0)?.into();
-            let target_is_cold = get_and_set_non_delegated_warm(handler, target);
+            let target_is_cold = get_and_set_non_delegated_warm(handler, target, opcode, config);
             GasCost::ExtCodeHash { target_is_cold }
         }
         Opcode::EXTCODEHASH => GasCost::Invalid(opcode),
 
         Opcode::CALLCODE => {
             let target = stack.peek_h256(1)?.into();
-            let (target_is_cold, delegated_designator_is_cold) = get_and_set_warm(handler, target);
+            let (target_is_cold, delegated_designator_is_cold) =
+                get_and_set_warm(handler, target, opcode, config);
             GasCost::CallCode {
                 value: stack.peek(2)?,
                 gas: stack.peek(0)?,
@@ -854,9 +1049,10 @@ pub fn dynamic_opcode_cost<H: Handler>(
                 },
             }
         }
-        Opcode::STATICCALL => {
+        Opcode::STATICCALL if config.has_static_call => {
             let target = stack.peek_h256(1)?.into();
-            let (target_is_cold, delegated_designator_is_cold) = get_and_set_warm(handler, target);
+            let (target_is_cold, delegated_designator_is_cold) =
+                get_and_set_warm(handler, target, opcode, config);
             GasCost::StaticCall {
                 gas: stack.peek(0)?,
                 target_is_cold,
@@ -867,12 +1063,13 @@ pub fn dynamic_opcode_cost<H: Handler>(
                 },
             }
         }
+        Opcode::STATICCALL => GasCost::Invalid(opcode),
         Opcode::SHA3 => GasCost::Sha3 {
             len: stack.peek(1)?,
         },
         Opcode::EXTCODECOPY => {
             let target = stack.peek_h256(0)?.into();
-            let target_is_cold = get_and_set_non_delegated_warm(handler, target);
+            let target_is_cold = get_and_set_non_delegated_warm(handler, target, opcode, config);
             GasCost::ExtCodeCopy {
                 target_is_cold,
                 len: stack.peek(3)?,
@@ -886,16 +1083,14 @@ pub fn dynamic_opcode_cost<H: Handler>(
         },
         Opcode::SLOAD => {
             let index = stack.peek_h256(0)?;
-            let target_is_cold = handler.is_cold(address, Some(index));
-            if target_is_cold {
-                handler.warm_target((address, Some(index)));
-            }
+            let target_is_cold = get_and_set_storage_warm(handler, address, index, opcode, config);
             GasCost::SLoad { target_is_cold }
         }
 
         Opcode::DELEGATECALL if config.has_delegate_call => {
             let target = stack.peek_h256(1)?.into();
-            let (target_is_cold, delegated_designator_is_cold) = get_and_set_warm(handler, target);
+            let (target_is_cold, delegated_designator_is_cold) =
+                get_and_set_warm(handler, target, opcode, config);
             GasCost::DelegateCall {
                 gas: stack.peek(0)?,
                 target_is_cold,
@@ -917,10 +1112,7 @@ pub fn dynamic_opcode_cost<H: Handler>(
         Opcode::SSTORE if !is_static => {
             let index = stack.peek_h256(0)?;
             let value = stack.peek_h256(1)?;
-            let target_is_cold = handler.is_cold(address, Some(index));
-            if target_is_cold {
-                handler.warm_target((address, Some(index)));
-            }
+            let target_is_cold = get_and_set_storage_warm(handler, address, index, opcode, config);
             GasCost::SStore {
                 original: handler.original_storage(address, index),
                 current: handler.storage(address, index),
@@ -954,10 +1146,7 @@ pub fn dynamic_opcode_cost<H: Handler>(
         },
         Opcode::SELFDESTRUCT if !is_static => {
             let target = stack.peek_h256(0)?.into();
-            let target_is_cold = handler.is_cold(target, None);
-            if target_is_cold {
-                handler.warm_target((target, None));
-            }
+            let target_is_cold = get_and_set_non_delegated_warm(handler, target, opcode, config);
             GasCost::Suicide {
                 value: handler.balance(address),
                 target_is_cold,
@@ -970,7 +1159,8 @@ pub fn dynamic_opcode_cost<H: Handler>(
         }
         Opcode::CALL if !is_static || (is_static && stack.peek(2)? == U256_ZERO) => {
             let target = stack.peek_h256(1)?.into();
-            let (target_is_cold, delegated_designator_is_cold) = get_and_set_warm(handler, target);
+            let (target_is_cold, delegated_designator_is_cold) =
+                get_and_set_warm(handler, target, opcode, config);
             GasCost::Call {
                 value: stack.peek(2)?,
                 gas: stack.peek(0)?,
@@ -985,7 +1175,9 @@ pub fn dynamic_opcode_cost<H: Handler>(
 
         Opcode::PUSH0 if config.has_push0 => GasCost::Base,
 
-        _ => GasCost::Invalid(opcode),
+        _ => handler
+            .custom_opcode_cost(opcode, stack)
+            .map_or(GasCost::Invalid(opcode), |cost| GasCost::Custom { cost }),
     };
 
     let memory_cost = match opcode {
@@ -1069,7 +1261,7 @@ fn peek_memory_cost(
 struct Inner<'config> {
     memory_gas: u64,
     used_gas: u64,
-    refunded_gas: i64,
+    refunded_gas: RefundCounter,
     config: &'config Config,
     floor_gas: u64,
 }
@@ -1192,6 +1384,7 @@ impl Inner<'_> {
             GasCost::VeryLow => u64::from(consts::G_VERYLOW),
             GasCost::Low => u64::from(consts::G_LOW),
             GasCost::Invalid(opcode) => return Err(ExitError::InvalidCode(opcode)),
+            GasCost::Custom { cost } => cost,
 
             GasCost::ExtCodeSize { target_is_cold } => costs::non_delegated_access_cost(
                 target_is_cold,
@@ -1230,7 +1423,7 @@ impl Inner<'_> {
             } => costs::sstore_refund(original, current, new, self.config),
             GasCost::Suicide {
                 already_removed, ..
-            } if !self.config.decrease_clears_refund => costs::suicide_refund(already_removed),
+            } => costs::suicide_refund(already_removed, self.config),
             _ => 0,
         }
     }
@@ -1249,6 +1442,11 @@ pub enum GasCost {
     Low,
     /// Fail the gasometer.
     Invalid(Opcode),
+    /// Gas cost of a chain-specific opcode, see [`Handler::custom_opcode_cost`].
+    Custom {
+        /// Cost of the opcode.
+        cost: u64,
+    },
 
     /// Gas cost for `EXTCODESIZE`.
     ExtCodeSize {
@@ -1455,3 +1653,148 @@ impl MemoryCost {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::HardFork;
+
+    #[test]
+    fn test_gas_snapshot() {
+        let config = Config::istanbul();
+        let mut gasometer = Gasometer::new(1000, &config);
+        gasometer.record_cost(100).unwrap();
+        gasometer.record_refund(30).unwrap();
+
+        let snapshot = gasometer.gas_snapshot();
+        assert_eq!(
+            snapshot,
+            GasSnapshot {
+                gas_limit: 1000,
+                used: 100,
+                memory_gas: 0,
+                refunded: 30,
+            }
+        );
+        assert_eq!(snapshot.total_used(), gasometer.total_used_gas());
+        assert_eq!(snapshot.remaining(), gasometer.gas());
+
+        gasometer.fail();
+        let snapshot = gasometer.gas_snapshot();
+        assert_eq!(snapshot.total_used(), 1000);
+        assert_eq!(snapshot.remaining(), 0);
+        assert_eq!(snapshot.refunded, 0);
+    }
+
+    #[test]
+    fn test_max_call_gas() {
+        let config = Config::istanbul();
+        // All but one 64th of 6400 is 6300
+        assert_eq!(max_call_gas(6400, None, &config), 6300);
+        assert_eq!(max_call_gas(6400, Some(10_000), &config), 6300);
+        assert_eq!(max_call_gas(6400, Some(1000), &config), 1000);
+        assert_eq!(call_gas_with_stipend(1000, true, &config), 3300);
+        assert_eq!(call_gas_with_stipend(1000, false, &config), 1000);
+
+        // Before EIP-150 the requested gas is capped by the available gas only
+        let config = Config::frontier();
+        assert_eq!(max_call_gas(6400, None, &config), 6400);
+        assert_eq!(max_call_gas(6400, Some(10_000), &config), 6400);
+    }
+
+    #[test]
+    fn test_refund_counter_of_gasometer() {
+        let config = Config::london();
+        let mut gasometer = Gasometer::new(100_000, &config);
+        gasometer.record_cost(50_000).unwrap();
+        gasometer.record_refund(i64::MAX).unwrap();
+        gasometer.record_refund(1).unwrap();
+        assert_eq!(gasometer.refunded_gas(), i64::MAX);
+        assert_eq!(
+            gasometer
+                .refund_counter()
+                .apply(gasometer.total_used_gas(), config.max_refund_quotient),
+            40_000
+        );
+
+        gasometer.fail();
+        assert_eq!(gasometer.refund_counter(), RefundCounter::ZERO);
+    }
+
+    #[test]
+    fn test_record_authorization_refunds_existing_authority() {
+        let config = Config::prague();
+        let mut gasometer = Gasometer::new(100_000, &config);
+        gasometer.record_authorization(false).unwrap();
+        assert_eq!(gasometer.refunded_gas(), 0);
+
+        gasometer.record_authorization(true).unwrap();
+        gasometer.record_authorization(true).unwrap();
+        let refund = config.gas_per_empty_account_cost - config.gas_per_auth_base_cost;
+        assert_eq!(gasometer.refunded_gas(), i64::try_from(2 * refund).unwrap());
+
+        // A base cost above the empty account cost refunds nothing
+        let config = Config {
+            gas_per_auth_base_cost: config.gas_per_empty_account_cost + 1,
+            ..Config::prague()
+        };
+        let mut gasometer = Gasometer::new(100_000, &config);
+        gasometer.record_authorization(true).unwrap();
+        assert_eq!(gasometer.refunded_gas(), 0);
+    }
+
+    #[test]
+    fn test_derived_storage_costs() {
+        for config in HardFork::ALL
+            .into_iter()
+            .filter_map(HardFork::gasometer_config)
+        {
+            // See https://eips.ethereum.org/EIPS/eip-2929
+            if config.increase_state_access_gas {
+                assert_eq!(config.gas_sstore_reset + config.gas_sload_cold, 5000);
+                assert_eq!(config.gas_sload, config.gas_storage_read_warm);
+            }
+            // See https://eips.ethereum.org/EIPS/eip-3529
+            if config.decrease_clears_refund {
+                assert_eq!(
+                    u64::try_from(config.refund_sstore_clears),
+                    Ok(config.gas_sstore_reset + config.gas_access_list_storage_key)
+                );
+                assert_eq!(config.max_refund_quotient, 5);
+            }
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_storage_access_costs(original in 0u64..3, current in 0u64..3, new in 0u64..3) {
+            let original = H256::from_low_u64_be(original);
+            let current = H256::from_low_u64_be(current);
+            let new = H256::from_low_u64_be(new);
+            for config in HardFork::ALL.into_iter().filter_map(HardFork::gasometer_config) {
+                proptest::prop_assert!(
+                    costs::sload_cost(false, &config) <= costs::sload_cost(true, &config)
+                );
+
+                let warm = costs::sstore_cost(original, current, new, u64::MAX, false, &config);
+                let cold = costs::sstore_cost(original, current, new, u64::MAX, true, &config);
+                let (warm, cold) = (warm.unwrap(), cold.unwrap());
+                proptest::prop_assert_eq!(cold - warm, config.gas_sload_cold);
+
+                // The refund of a write never exceeds the cost of setting
+                // the slot and clearing it back
+                let refund = costs::sstore_refund(original, current, new, &config);
+                let set_cost = i64::try_from(config.gas_sstore_set).unwrap();
+                proptest::prop_assert!(refund <= config.refund_sstore_clears + set_cost);
+
+                let estimate = Config {
+                    estimate: true,
+                    ..config.clone()
+                };
+                let estimated =
+                    costs::sstore_cost(original, current, new, u64::MAX, false, &estimate);
+                proptest::prop_assert!(estimated.unwrap() >= warm);
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+/// Gas refund counter of a gasometer.
+///
+/// The counter of a frame can go negative, e.g. when an `SSTORE` restores a
+/// slot its parent frame cleared, and only the counter of the whole
+/// transaction is applied. Additions saturate at the `i64` bounds instead of
+/// overflowing.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct RefundCounter(i64);
+
+impl RefundCounter {
+    /// No refund.
+    pub const ZERO: Self = Self(0);
+
+    /// Counter at `refund`.
+    #[must_use]
+    pub const fn new(refund: i64) -> Self {
+        Self(refund)
+    }
+
+    /// Counter value.
+    #[must_use]
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+
+    /// Counter with `refund` added, which is negative for a refund taken
+    /// back.
+    #[must_use]
+    pub const fn saturating_add(self, refund: i64) -> Self {
+        Self(self.0.saturating_add(refund))
+    }
+
+    /// Gas refunded for `used_gas`: the counter, capped at `used_gas /
+    /// max_refund_quotient` (2 before EIP-3529, 5 after). A negative counter
+    /// refunds nothing, and so does a quotient of zero.
+    #[must_use]
+    pub const fn capped(self, used_gas: u64, max_refund_quotient: u64) -> u64 {
+        let Some(cap) = used_gas.checked_div(max_refund_quotient) else {
+            return 0;
+        };
+        if self.0 <= 0 {
+            return 0;
+        }
+        let refund = self.0.unsigned_abs();
+        if refund < cap {
+            refund
+        } else {
+            cap
+        }
+    }
+
+    /// Gas used once the capped refund is deducted from `used_gas`, which
+    /// never underflows as the refund is capped at a fraction of it.
+    #[must_use]
+    pub const fn apply(self, used_gas: u64, max_refund_quotient: u64) -> u64 {
+        used_gas - self.capped(used_gas, max_refund_quotient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refund_counter_saturates() {
+        let counter = RefundCounter::new(i64::MAX - 1);
+        assert_eq!(counter.saturating_add(10).get(), i64::MAX);
+        assert_eq!(
+            RefundCounter::new(i64::MIN + 1).saturating_add(-10).get(),
+            i64::MIN
+        );
+        assert_eq!(RefundCounter::ZERO.saturating_add(-5).get(), -5);
+        assert_eq!(RefundCounter::default(), RefundCounter::ZERO);
+    }
+
+    #[test]
+    fn test_refund_counter_cap() {
+        // Below, at and above the cap of EIP-3529
+        assert_eq!(RefundCounter::new(1000).capped(100_000, 5), 1000);
+        assert_eq!(RefundCounter::new(20_000).capped(100_000, 5), 20_000);
+        assert_eq!(RefundCounter::new(20_001).capped(100_000, 5), 20_000);
+        assert_eq!(RefundCounter::new(20_001).apply(100_000, 5), 80_000);
+        // And before it
+        assert_eq!(RefundCounter::new(60_000).apply(100_000, 2), 50_000);
+        // Rounded down
+        assert_eq!(RefundCounter::new(10).capped(9, 2), 4);
+        assert_eq!(RefundCounter::new(10).capped(1, 2), 0);
+    }
+
+    #[test]
+    fn test_refund_counter_boundaries() {
+        for quotient in [0, 1, 2, 5, u64::MAX] {
+            for used_gas in [0, 1, u64::MAX - 1, u64::MAX] {
+                for refund in [i64::MIN, -1, 0, 1, i64::MAX] {
+                    let counter = RefundCounter::new(refund);
+                    let capped = counter.capped(used_gas, quotient);
+                    assert!(capped <= used_gas);
+                    assert_eq!(counter.apply(used_gas, quotient), used_gas - capped);
+                    if refund <= 0 || quotient == 0 {
+                        assert_eq!(capped, 0);
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            RefundCounter::new(i64::MAX).capped(u64::MAX, 1),
+            i64::MAX.unsigned_abs()
+        );
+        assert_eq!(
+            RefundCounter::new(i64::MAX).capped(u64::MAX, 2),
+            u64::MAX / 2
+        );
+        assert_eq!(RefundCounter::new(i64::MAX).capped(u64::MAX, u64::MAX), 1);
+    }
+}
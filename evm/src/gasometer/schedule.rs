@@ -0,0 +1,232 @@
+//! Machine-readable gas schedule of a config.
+
+use super::{consts, static_opcode_cost};
+use crate::core::Opcode;
+use crate::prelude::*;
+use crate::runtime::Config;
+use serde::{Serialize, Serializer};
+
+/// Static costs by opcode name, in opcode order.
+struct OpcodeCosts(Vec<(String, u32)>);
+
+impl Serialize for OpcodeCosts {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(name, cost)| (name, cost)))
+    }
+}
+
+#[derive(Serialize)]
+struct DynamicCosts {
+    memory_word: u32,
+    copy_word: u32,
+    sha3: u32,
+    sha3_word: u32,
+    exp: u32,
+    exp_byte: u64,
+    log: u32,
+    log_data: u32,
+    log_topic: u32,
+    blockhash: u32,
+    create: u32,
+    code_deposit_byte: u64,
+    call: u64,
+    call_value: u32,
+    call_stipend: u64,
+    new_account: u32,
+    balance: u64,
+    ext_code: u64,
+    ext_code_hash: u64,
+    sload: u64,
+    sload_cold: u64,
+    sstore_set: u64,
+    sstore_reset: u64,
+    account_access_cold: u64,
+    storage_read_warm: u64,
+    selfdestruct: u64,
+    selfdestruct_new_account: u64,
+}
+
+#[derive(Serialize)]
+struct TransactionCosts {
+    call: u64,
+    create: u64,
+    zero_data: u64,
+    non_zero_data: u64,
+    access_list_address: u64,
+    access_list_storage_key: u64,
+    init_code_word: u64,
+    authorization_empty_account: u64,
+    authorization_base: u64,
+    floor_per_token: u64,
+    max_gas: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct Refunds {
+    sstore_clears: i64,
+    max_refund_quotient: u64,
+    selfdestruct: i64,
+}
+
+#[derive(Serialize)]
+struct GasSchedule {
+    opcodes: OpcodeCosts,
+    dynamic: DynamicCosts,
+    transaction: TransactionCosts,
+    refunds: Refunds,
+    precompiles: BTreeMap<String, BTreeMap<&'static str, u64>>,
+}
+
+impl Config {
+    /// Gas schedule of the config as a JSON object, for documentation and
+    /// for comparing the prices of custom chains. It contains:
+    /// - `opcodes`: static costs, by opcode name. Opcodes with dynamic costs,
+    ///   such as `SLOAD` or `CALL`, are priced by the parameters below;
+    /// - `dynamic`: parameters of the dynamic costs;
+    /// - `transaction`: intrinsic gas parameters;
+    /// - `refunds`: refund parameters;
+    /// - `precompiles`: prices of the precompiles enabled via feature flags,
+    ///   by address.
+    #[must_use]
+    pub fn gas_schedule_json(&self) -> String {
+        let opcodes = (0..=u8::MAX)
+            .map(Opcode)
+            .filter_map(|opcode| {
+                let cost = static_opcode_cost(opcode)?;
+                let name = opcode.to_string();
+                let name = name.split(' ').next().unwrap_or_default();
+                (name != "UNKNOWN").then(|| (name.into(), cost))
+            })
+            .collect();
+
+        let schedule = GasSchedule {
+            opcodes: OpcodeCosts(opcodes),
+            dynamic: DynamicCosts {
+                memory_word: consts::G_MEMORY,
+                copy_word: consts::G_COPY,
+                sha3: consts::G_SHA3,
+                sha3_word: consts::G_SHA3WORD,
+                exp: consts::G_EXP,
+                exp_byte: self.gas_expbyte,
+                log: consts::G_LOG,
+                log_data: consts::G_LOGDATA,
+                log_topic: consts::G_LOGTOPIC,
+                blockhash: consts::G_BLOCKHASH,
+                create: consts::G_CREATE,
+                code_deposit_byte: self.gas_code_deposit,
+                call: self.gas_call,
+                call_value: consts::G_CALLVALUE,
+                call_stipend: self.call_stipend,
+                new_account: consts::G_NEWACCOUNT,
+                balance: self.gas_balance,
+                ext_code: self.gas_ext_code,
+                ext_code_hash: self.gas_ext_code_hash,
+                sload: self.gas_sload,
+                sload_cold: self.gas_sload_cold,
+                sstore_set: self.gas_sstore_set,
+                sstore_reset: self.gas_sstore_reset,
+                account_access_cold: self.gas_account_access_cold,
+                storage_read_warm: self.gas_storage_read_warm,
+                selfdestruct: self.gas_suicide,
+                selfdestruct_new_account: self.gas_suicide_new_account,
+            },
+            transaction: TransactionCosts {
+                call: self.gas_transaction_call,
+                create: self.gas_transaction_create,
+                zero_data: self.gas_transaction_zero_data,
+                non_zero_data: self.gas_transaction_non_zero_data,
+                access_list_address: self.gas_access_list_address,
+                access_list_storage_key: self.gas_access_list_storage_key,
+                init_code_word: if self.has_init_code_cost { 2 } else { 0 },
+                authorization_empty_account: self.gas_per_empty_account_cost,
+                authorization_base: self.gas_per_auth_base_cost,
+                floor_per_token: self.total_cost_floor_per_token,
+                max_gas: self.max_transaction_gas,
+            },
+            refunds: Refunds {
+                sstore_clears: self.refund_sstore_clears,
+                max_refund_quotient: self.max_refund_quotient,
+                selfdestruct: self.gas_suicide_refund,
+            },
+            precompiles: self.precompile_prices(),
+        };
+        // Numbers and strings are always serializable
+        serde_json::to_string(&schedule).unwrap_or_default()
+    }
+
+    #[allow(unused_mut, clippy::unused_self)]
+    fn precompile_prices(&self) -> BTreeMap<String, BTreeMap<&'static str, u64>> {
+        let mut precompiles = BTreeMap::new();
+
+        #[cfg(feature = "precompile-modexp")]
+        if self.has_return_data {
+            use crate::precompiles::modexp;
+            let (min, quad_divisor) = if self.increase_state_access_gas {
+                (modexp::MIN_COST_BERLIN, modexp::GQUADDIVISOR_BERLIN)
+            } else {
+                (0, modexp::GQUADDIVISOR_BYZANTIUM)
+            };
+            precompiles.insert(
+                format!("{:?}", modexp::ADDRESS),
+                BTreeMap::from([("min", min), ("quad_divisor", quad_divisor)]),
+            );
+        }
+
+        #[cfg(feature = "precompile-bn128")]
+        if self.has_return_data {
+            use crate::precompiles::bn128;
+            let (add, mul, pair_base, pair_per_point) = if self.has_chain_id {
+                (
+                    bn128::ADD_COST_ISTANBUL,
+                    bn128::MUL_COST_ISTANBUL,
+                    bn128::PAIR_BASE_COST_ISTANBUL,
+                    bn128::PAIR_PER_POINT_COST_ISTANBUL,
+                )
+            } else {
+                (
+                    bn128::ADD_COST_BYZANTIUM,
+                    bn128::MUL_COST_BYZANTIUM,
+                    bn128::PAIR_BASE_COST_BYZANTIUM,
+                    bn128::PAIR_PER_POINT_COST_BYZANTIUM,
+                )
+            };
+            precompiles.insert(
+                format!("{:?}", bn128::ADD_ADDRESS),
+                BTreeMap::from([("base", add)]),
+            );
+            precompiles.insert(
+                format!("{:?}", bn128::MUL_ADDRESS),
+                BTreeMap::from([("base", mul)]),
+            );
+            precompiles.insert(
+                format!("{:?}", bn128::PAIR_ADDRESS),
+                BTreeMap::from([("base", pair_base), ("per_point", pair_per_point)]),
+            );
+        }
+
+        precompiles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_gas_schedule_json() {
+        let json = Config::cancun().gas_schedule_json();
+        assert!(json.starts_with("{\"opcodes\":{\"STOP\":0,\"ADD\":3,"));
+        let schedule: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(schedule["dynamic"]["sload_cold"], 2100);
+        assert_eq!(schedule["refunds"]["sstore_clears"], 4800);
+        assert_eq!(schedule["refunds"]["selfdestruct"], 0);
+        assert_eq!(schedule["transaction"]["max_gas"], Value::Null);
+
+        let frontier: Value =
+            serde_json::from_str(&Config::frontier().gas_schedule_json()).unwrap();
+        assert_eq!(frontier["dynamic"]["sload"], 50);
+        assert_eq!(frontier["refunds"]["selfdestruct"], 24000);
+        assert_ne!(schedule, frontier);
+    }
+}
@@ -1,6 +1,11 @@
 //! Allows to listen to gasometer events.
+//!
+//! Listeners are installed the same way as in [`crate::tracing`].
 
 use super::Snapshot;
+use crate::core::Opcode;
+use crate::runtime::Config;
+use primitive_types::{H160, H256};
 
 environmental::environmental!(listener: dyn EventListener + 'static);
 
@@ -8,6 +13,12 @@ pub trait EventListener {
     fn event(&mut self, event: Event);
 }
 
+impl<F: FnMut(Event)> EventListener for F {
+    fn event(&mut self, event: Event) {
+        self(event);
+    }
+}
+
 impl Snapshot {
     #[must_use]
     pub const fn gas(&self) -> u64 {
@@ -39,6 +50,53 @@ pub enum Event {
         cost: u64,
         snapshot: Option<Snapshot>,
     },
+    /// Account or storage slot access by `opcode`, classified as in EIP-2929.
+    /// Emitted before the dynamic cost of the opcode is recorded.
+    RecordAccess {
+        opcode: Opcode,
+        address: H160,
+        /// Storage slot, `None` for account accesses.
+        index: Option<H256>,
+        is_cold: bool,
+        /// Gas charged on top of a warm access, zero for warm accesses and
+        /// before Berlin.
+        surcharge: u64,
+    },
+}
+
+/// Emit the access of `address`, or of its storage slot `index`, by `opcode`.
+pub(crate) fn record_access(
+    opcode: Opcode,
+    address: H160,
+    index: Option<H256>,
+    is_cold: bool,
+    config: &Config,
+) {
+    let surcharge = if is_cold && config.increase_state_access_gas {
+        match opcode {
+            // Cold costs are added to the `SSTORE` and `SELFDESTRUCT` costs
+            Opcode::SSTORE => config.gas_sload_cold,
+            Opcode::SELFDESTRUCT => config.gas_account_access_cold,
+            // and replace the warm read cost otherwise
+            Opcode::SLOAD => config
+                .gas_sload_cold
+                .saturating_sub(config.gas_storage_read_warm),
+            _ => config
+                .gas_account_access_cold
+                .saturating_sub(config.gas_storage_read_warm),
+        }
+    } else {
+        0
+    };
+    with(|listener| {
+        listener.event(Event::RecordAccess {
+            opcode,
+            address,
+            index,
+            is_cold,
+            surcharge,
+        });
+    });
 }
 
 // Expose `listener::with` to the crate only.
@@ -50,3 +108,73 @@ pub(crate) fn with<F: FnOnce(&mut (dyn EventListener + 'static))>(f: F) {
 pub fn using<R, F: FnOnce() -> R>(new: &mut (dyn EventListener + 'static), f: F) -> R {
     listener::using(new, f)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::executor::stack::StackExecutorBuilder;
+    use crate::prelude::*;
+    use primitive_types::U256;
+
+    #[test]
+    fn test_record_access() {
+        let contract = H160::from_low_u64_be(2);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let mut state = BTreeMap::new();
+        state.insert(
+            contract,
+            MemoryAccount {
+                nonce: U256::one(),
+                balance: U256::zero(),
+                storage: BTreeMap::new(),
+                // SLOAD(0), SLOAD(0), BALANCE(0x33)
+                code: vec![0x60, 0x00, 0x54, 0x60, 0x00, 0x54, 0x60, 0x33, 0x31, 0x00],
+            },
+        );
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&backend)
+            .build();
+
+        let accesses = Rc::new(RefCell::new(Vec::new()));
+        let mut listener = {
+            let accesses = accesses.clone();
+            move |event: Event| {
+                if let Event::RecordAccess {
+                    opcode,
+                    index,
+                    is_cold,
+                    surcharge,
+                    ..
+                } = event
+                {
+                    accesses
+                        .borrow_mut()
+                        .push((opcode, index, is_cold, surcharge));
+                }
+            }
+        };
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                H160::from_low_u64_be(1),
+                contract,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        assert!(reason.is_succeed());
+        assert_eq!(
+            *accesses.borrow(),
+            [
+                (Opcode::SLOAD, Some(H256::zero()), true, 2000),
+                (Opcode::SLOAD, Some(H256::zero()), false, 0),
+                (Opcode::BALANCE, None, true, 2500),
+            ]
+        );
+    }
+}
@@ -10,6 +10,21 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+// zkVM guests execute deterministically on a single thread, without a host
+#[cfg(all(feature = "zkvm", feature = "concurrent"))]
+compile_error!("the `zkvm` feature excludes `concurrent`, which spawns threads");
+#[cfg(all(feature = "zkvm", feature = "print-debug"))]
+compile_error!("the `zkvm` feature excludes `print-debug`, which writes to the standard output");
+#[cfg(all(feature = "zkvm", feature = "tracing", feature = "std"))]
+compile_error!(
+    "the `zkvm` feature excludes `tracing` with `std`, whose listener is thread-local: \
+     disable the default features to trace from a global listener"
+);
+#[cfg(all(feature = "concurrent", target_arch = "wasm32", target_os = "unknown"))]
+compile_error!(
+    "the `concurrent` feature spawns threads, which wasm32-unknown-unknown doesn't support"
+);
+
 #[cfg(not(feature = "std"))]
 pub mod prelude {
     pub use alloc::{
@@ -49,9 +64,19 @@ macro_rules! event {
     ($x:expr) => {};
 }
 
+#[cfg(feature = "alloy-compat")]
+pub mod alloy_compat;
 pub mod backend;
 pub mod core;
 pub mod executor;
+#[cfg(test)]
+mod fork_gating;
 pub mod gasometer;
 pub mod maybe_borrowed;
+pub mod precompiles;
+pub mod rpc;
 pub mod runtime;
+#[cfg(feature = "test-utils")]
+pub mod test_gen;
+#[cfg(test)]
+mod test_utils;
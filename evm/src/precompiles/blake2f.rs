@@ -0,0 +1,216 @@
+//! `BLAKE2F` precompile.
+//!
+//! [EIP-152]: the `BLAKE2b` compression function `F`, priced at one gas per
+//! round, added in Istanbul.
+//!
+//! [EIP-152]: https://eips.ethereum.org/EIPS/eip-152
+
+use super::{check_gas, failure, precompile_address, success};
+use crate::executor::stack::{PrecompileFailure, PrecompileMetadata, PrecompileOutput};
+use crate::prelude::*;
+use crate::runtime::HardFork;
+use crate::Context;
+use primitive_types::H160;
+
+/// `BLAKE2F` precompile address.
+pub const ADDRESS: H160 = precompile_address(9);
+/// `BLAKE2F` precompile metadata.
+pub const METADATA: PrecompileMetadata = PrecompileMetadata::new("BLAKE2F", HardFork::Istanbul);
+
+/// Cost of a single round.
+pub const ROUND_COST: u64 = 1;
+
+/// Length of the `rounds || h || m || t || f` input.
+const INPUT_LEN: usize = 213;
+
+/// `BLAKE2b` initialization vector.
+const IV: [u64; 8] = [
+    0x6a09_e667_f3bc_c908,
+    0xbb67_ae85_84ca_a73b,
+    0x3c6e_f372_fe94_f82b,
+    0xa54f_f53a_5f1d_36f1,
+    0x510e_527f_ade6_82d1,
+    0x9b05_688c_2b3e_6c1f,
+    0x1f83_d9ab_fb41_bd6b,
+    0x5be0_cd19_137e_2179,
+];
+
+/// Message word permutations, one per round modulo 10.
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// `BLAKE2F` since Istanbul.
+///
+/// # Errors
+/// Return `PrecompileFailure` for an input that is not exactly 213 bytes
+/// long, an invalid final block flag or insufficient gas.
+pub fn blake2f(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    if input.len() != INPUT_LEN {
+        return Err(failure("ERR_BLAKE2F_INVALID_LEN"));
+    }
+    let rounds = u32::from_be_bytes(read_bytes(input, 0));
+    let cost = u64::from(rounds) * ROUND_COST;
+    check_gas(cost, gas_limit)?;
+
+    let last_block = match input[212] {
+        0 => false,
+        1 => true,
+        _ => return Err(failure("ERR_BLAKE2F_FINAL_FLAG")),
+    };
+    let mut h = [0u64; 8];
+    for (i, word) in h.iter_mut().enumerate() {
+        *word = read_word(input, 4 + i * 8);
+    }
+    let mut m = [0u64; 16];
+    for (i, word) in m.iter_mut().enumerate() {
+        *word = read_word(input, 68 + i * 8);
+    }
+    let t = [read_word(input, 196), read_word(input, 204)];
+
+    compress(rounds, &mut h, &m, t, last_block);
+
+    let mut output = Vec::with_capacity(64);
+    for word in h {
+        output.extend_from_slice(&word.to_le_bytes());
+    }
+    Ok(success(output, cost))
+}
+
+fn read_bytes<const N: usize>(input: &[u8], start: usize) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(&input[start..start + N]);
+    bytes
+}
+
+/// Little-endian word at `start`.
+fn read_word(input: &[u8], start: usize) -> u64 {
+    u64::from_le_bytes(read_bytes(input, start))
+}
+
+/// Compression function `F` of RFC 7693, with a configurable number of
+/// rounds.
+fn compress(rounds: u32, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], last_block: bool) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if last_block {
+        v[14] = !v[14];
+    }
+
+    // `u32` always fits in `usize` on the supported targets
+    let rounds = usize::try_from(rounds).unwrap_or(usize::MAX);
+    for s in SIGMA.iter().cycle().take(rounds) {
+        mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// Mixing function `G` of RFC 7693.
+#[allow(clippy::many_single_char_names)]
+const fn mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{context, hex};
+    use crate::ExitError;
+
+    /// EIP-152 test vector 4 input: the single block of `BLAKE2b("abc")`.
+    fn input(rounds: u32) -> Vec<u8> {
+        let mut input = rounds.to_be_bytes().to_vec();
+        let mut h = IV;
+        // Parameter block: 64 bytes digest, no key, fanout and depth 1
+        h[0] ^= 0x0101_0040;
+        for word in h {
+            input.extend_from_slice(&word.to_le_bytes());
+        }
+        let mut m = [0u8; 128];
+        m[..3].copy_from_slice(b"abc");
+        input.extend_from_slice(&m);
+        input.extend_from_slice(&3u64.to_le_bytes());
+        input.extend_from_slice(&0u64.to_le_bytes());
+        input.push(1);
+        input
+    }
+
+    #[test]
+    fn test_blake2f() {
+        let (output, cost) = blake2f(&input(12), None, &context(ADDRESS), false).unwrap();
+        assert_eq!(
+            hex(&output.output),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(cost, 12);
+    }
+
+    #[test]
+    fn test_blake2f_zero_rounds() {
+        let (output, cost) = blake2f(&input(0), None, &context(ADDRESS), false).unwrap();
+        assert_eq!(
+            hex(&output.output),
+            "08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+             d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b"
+        );
+        assert_eq!(cost, 0);
+    }
+
+    #[test]
+    fn test_blake2f_invalid_input() {
+        let mut input = input(12);
+        input.push(0);
+        assert!(blake2f(&input, None, &context(ADDRESS), false).is_err());
+        input.truncate(INPUT_LEN - 1);
+        assert!(blake2f(&input, None, &context(ADDRESS), false).is_err());
+
+        let mut input = self::input(12);
+        input[212] = 2;
+        assert_eq!(
+            blake2f(&input, None, &context(ADDRESS), false).unwrap_err(),
+            failure("ERR_BLAKE2F_FINAL_FLAG")
+        );
+    }
+
+    #[test]
+    fn test_blake2f_out_of_gas() {
+        let result = blake2f(&input(12), Some(11), &context(ADDRESS), false);
+        assert_eq!(result.unwrap_err(), ExitError::OutOfGas.into());
+        assert!(blake2f(&input(12), Some(12), &context(ADDRESS), false).is_ok());
+    }
+}
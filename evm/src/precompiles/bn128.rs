@@ -0,0 +1,307 @@
+//! `alt_bn128` elliptic curve precompiles.
+//!
+//! [EIP-196]: addition and scalar multiplication, [EIP-197]: pairing check,
+//! repriced by [EIP-1108] in Istanbul.
+//!
+//! [EIP-196]: https://eips.ethereum.org/EIPS/eip-196
+//! [EIP-197]: https://eips.ethereum.org/EIPS/eip-197
+//! [EIP-1108]: https://eips.ethereum.org/EIPS/eip-1108
+
+use super::{check_gas, failure, padded_input, precompile_address, success};
+use crate::executor::stack::{PrecompileFailure, PrecompileMetadata, PrecompileOutput};
+use crate::prelude::*;
+use crate::runtime::HardFork;
+use crate::{Context, ExitError};
+use bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+use primitive_types::H160;
+
+/// `ECADD` precompile address.
+pub const ADD_ADDRESS: H160 = precompile_address(6);
+/// `ECMUL` precompile address.
+pub const MUL_ADDRESS: H160 = precompile_address(7);
+/// `ECPAIRING` precompile address.
+pub const PAIR_ADDRESS: H160 = precompile_address(8);
+
+/// `ECADD` precompile metadata.
+pub const ADD_METADATA: PrecompileMetadata = PrecompileMetadata::new("ECADD", HardFork::Byzantium);
+/// `ECMUL` precompile metadata.
+pub const MUL_METADATA: PrecompileMetadata = PrecompileMetadata::new("ECMUL", HardFork::Byzantium);
+/// `ECPAIRING` precompile metadata.
+pub const PAIR_METADATA: PrecompileMetadata =
+    PrecompileMetadata::new("ECPAIRING", HardFork::Byzantium);
+
+/// `ECADD` cost before Istanbul.
+pub const ADD_COST_BYZANTIUM: u64 = 500;
+/// `ECADD` cost since Istanbul (EIP-1108).
+pub const ADD_COST_ISTANBUL: u64 = 150;
+/// `ECMUL` cost before Istanbul.
+pub const MUL_COST_BYZANTIUM: u64 = 40_000;
+/// `ECMUL` cost since Istanbul (EIP-1108).
+pub const MUL_COST_ISTANBUL: u64 = 6_000;
+/// `ECPAIRING` base cost before Istanbul.
+pub const PAIR_BASE_COST_BYZANTIUM: u64 = 100_000;
+/// `ECPAIRING` per pair cost before Istanbul.
+pub const PAIR_PER_POINT_COST_BYZANTIUM: u64 = 80_000;
+/// `ECPAIRING` base cost since Istanbul (EIP-1108).
+pub const PAIR_BASE_COST_ISTANBUL: u64 = 45_000;
+/// `ECPAIRING` per pair cost since Istanbul (EIP-1108).
+pub const PAIR_PER_POINT_COST_ISTANBUL: u64 = 34_000;
+
+/// Length of a single `(G1, G2)` pair in the `ECPAIRING` input.
+const PAIR_ELEMENT_LEN: usize = 192;
+
+/// `ECADD` with Byzantium pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for invalid points or insufficient gas.
+pub fn add_byzantium(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    add(input, gas_limit, ADD_COST_BYZANTIUM)
+}
+
+/// `ECADD` with Istanbul pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for invalid points or insufficient gas.
+pub fn add_istanbul(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    add(input, gas_limit, ADD_COST_ISTANBUL)
+}
+
+/// `ECMUL` with Byzantium pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for invalid input or insufficient gas.
+pub fn mul_byzantium(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    mul(input, gas_limit, MUL_COST_BYZANTIUM)
+}
+
+/// `ECMUL` with Istanbul pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for invalid input or insufficient gas.
+pub fn mul_istanbul(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    mul(input, gas_limit, MUL_COST_ISTANBUL)
+}
+
+/// `ECPAIRING` with Byzantium pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for invalid input or insufficient gas.
+pub fn pair_byzantium(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    pair(
+        input,
+        gas_limit,
+        PAIR_BASE_COST_BYZANTIUM,
+        PAIR_PER_POINT_COST_BYZANTIUM,
+    )
+}
+
+/// `ECPAIRING` with Istanbul pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for invalid input or insufficient gas.
+pub fn pair_istanbul(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    pair(
+        input,
+        gas_limit,
+        PAIR_BASE_COST_ISTANBUL,
+        PAIR_PER_POINT_COST_ISTANBUL,
+    )
+}
+
+fn add(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    cost: u64,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    check_gas(cost, gas_limit)?;
+
+    let input = padded_input::<128>(input);
+    let p1 = read_g1(&input[..64])?;
+    let p2 = read_g1(&input[64..])?;
+
+    Ok(success(encode_g1(p1 + p2)?, cost))
+}
+
+fn mul(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    cost: u64,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    check_gas(cost, gas_limit)?;
+
+    let input = padded_input::<96>(input);
+    let p = read_g1(&input[..64])?;
+    let fr = Fr::from_slice(&input[64..]).map_err(|_| failure("ERR_BN128_INVALID_FR"))?;
+
+    Ok(success(encode_g1(p * fr)?, cost))
+}
+
+fn pair(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    base_cost: u64,
+    per_point_cost: u64,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    if input.len() % PAIR_ELEMENT_LEN != 0 {
+        return Err(failure("ERR_BN128_INVALID_LEN"));
+    }
+    let pairs = u64::try_from(input.len() / PAIR_ELEMENT_LEN)
+        .map_err(|_| failure("ERR_BN128_INVALID_LEN"))?;
+    let cost = per_point_cost
+        .checked_mul(pairs)
+        .and_then(|cost| cost.checked_add(base_cost))
+        .ok_or(PrecompileFailure::Error {
+            exit_status: ExitError::OutOfGas,
+        })?;
+    check_gas(cost, gas_limit)?;
+
+    let mut points = Vec::with_capacity(input.len() / PAIR_ELEMENT_LEN);
+    for chunk in input.chunks_exact(PAIR_ELEMENT_LEN) {
+        let a = read_g1(&chunk[..64])?;
+        // G2 coordinates are encoded as `(imaginary, real)`.
+        let b_x = Fq2::new(read_fq(&chunk[96..128])?, read_fq(&chunk[64..96])?);
+        let b_y = Fq2::new(read_fq(&chunk[160..192])?, read_fq(&chunk[128..160])?);
+        let b = if b_x.is_zero() && b_y.is_zero() {
+            G2::zero()
+        } else {
+            AffineG2::new(b_x, b_y)
+                .map_err(|_| failure("ERR_BN128_INVALID_B"))?
+                .into()
+        };
+        points.push((a, b));
+    }
+
+    let mut output = [0u8; 32].to_vec();
+    if pairing_batch(&points) == Gt::one() {
+        output[31] = 1;
+    }
+    Ok(success(output, cost))
+}
+
+fn read_fq(input: &[u8]) -> Result<Fq, PrecompileFailure> {
+    Fq::from_slice(input).map_err(|_| failure("ERR_BN128_INVALID_FQ"))
+}
+
+/// Read a `G1` point from 64 bytes, `(0, 0)` being the point at infinity.
+fn read_g1(input: &[u8]) -> Result<G1, PrecompileFailure> {
+    let x = read_fq(&input[..32])?;
+    let y = read_fq(&input[32..64])?;
+    if x == Fq::zero() && y == Fq::zero() {
+        return Ok(G1::zero());
+    }
+    AffineG1::new(x, y)
+        .map(Into::into)
+        .map_err(|_| failure("ERR_BN128_INVALID_POINT"))
+}
+
+/// Encode a `G1` point as 64 bytes, the point at infinity being `(0, 0)`.
+fn encode_g1(point: G1) -> Result<Vec<u8>, PrecompileFailure> {
+    let mut output = [0u8; 64].to_vec();
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point
+            .x()
+            .to_big_endian(&mut output[..32])
+            .and_then(|()| point.y().to_big_endian(&mut output[32..]))
+            .map_err(|_| failure("ERR_BN128_ENCODE"))?;
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::context;
+
+    fn generator() -> Vec<u8> {
+        let mut point = [0u8; 64];
+        point[31] = 1;
+        point[63] = 2;
+        point.to_vec()
+    }
+
+    #[test]
+    fn test_add_infinity() {
+        let (output, cost) = add_istanbul(&[], None, &context(ADD_ADDRESS), false).unwrap();
+        assert_eq!(output.output, [0u8; 64].to_vec());
+        assert_eq!(cost, ADD_COST_ISTANBUL);
+    }
+
+    #[test]
+    fn test_add_equals_mul_by_two() {
+        let input = [generator(), generator()].concat();
+        let (sum, _) = add_istanbul(&input, None, &context(ADD_ADDRESS), false).unwrap();
+
+        let mut scalar = [0u8; 32];
+        scalar[31] = 2;
+        let input = [generator(), scalar.to_vec()].concat();
+        let (product, cost) = mul_byzantium(&input, None, &context(ADD_ADDRESS), false).unwrap();
+
+        assert_eq!(sum.output, product.output);
+        assert_eq!(cost, MUL_COST_BYZANTIUM);
+    }
+
+    #[test]
+    fn test_add_invalid_point() {
+        let mut input = generator();
+        input[63] = 3;
+        assert!(add_istanbul(&input, None, &context(ADD_ADDRESS), false).is_err());
+    }
+
+    #[test]
+    fn test_pair_empty_input() {
+        let (output, cost) = pair_istanbul(&[], None, &context(ADD_ADDRESS), false).unwrap();
+        assert_eq!(output.output[31], 1);
+        assert_eq!(cost, PAIR_BASE_COST_ISTANBUL);
+    }
+
+    #[test]
+    fn test_pair_invalid_length() {
+        assert!(pair_istanbul(&[0u8; 10], None, &context(ADD_ADDRESS), false).is_err());
+    }
+
+    #[test]
+    fn test_out_of_gas() {
+        let result = mul_istanbul(
+            &[],
+            Some(MUL_COST_ISTANBUL - 1),
+            &context(ADD_ADDRESS),
+            false,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            PrecompileFailure::Error {
+                exit_status: ExitError::OutOfGas
+            }
+        );
+    }
+}
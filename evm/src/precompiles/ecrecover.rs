@@ -0,0 +1,111 @@
+//! `ECRECOVER` precompile.
+//!
+//! Recovers the address of the `secp256k1` key that signed a message hash.
+//! Invalid signatures are not an error: the precompile returns no output and
+//! still charges its cost.
+
+use super::{check_gas, padded_input, precompile_address, success};
+use crate::executor::stack::{PrecompileFailure, PrecompileMetadata, PrecompileOutput};
+use crate::prelude::*;
+use crate::runtime::HardFork;
+use crate::Context;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use primitive_types::{H160, H256};
+use sha3::{Digest, Keccak256};
+
+/// `ECRECOVER` precompile address.
+pub const ADDRESS: H160 = precompile_address(1);
+/// `ECRECOVER` precompile metadata.
+pub const METADATA: PrecompileMetadata = PrecompileMetadata::new("ECRECOVER", HardFork::Frontier);
+
+/// Cost of a recovery.
+pub const COST: u64 = 3_000;
+
+/// Length of the `hash || v || r || s` input.
+const INPUT_LEN: usize = 128;
+
+/// `ECRECOVER` since Frontier.
+///
+/// # Errors
+/// Return `PrecompileFailure` for insufficient gas.
+pub fn ecrecover(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    check_gas(COST, gas_limit)?;
+    let input = padded_input::<INPUT_LEN>(input);
+    let output = recover(&input).map_or_else(Vec::new, |address| H256::from(address).0.to_vec());
+    Ok(success(output, COST))
+}
+
+fn recover(input: &[u8; INPUT_LEN]) -> Option<H160> {
+    // `v` is a 32 byte word, either 27 or 28
+    if input[32..63].iter().any(|&byte| byte != 0) {
+        return None;
+    }
+    let is_y_odd = match input[63] {
+        27 => false,
+        28 => true,
+        _ => return None,
+    };
+    let signature = Signature::from_slice(&input[64..]).ok()?;
+    // Unlike transactions, the precompile accepts signatures with a high `s`,
+    // which `k256` only recovers from in their normalized form
+    let (signature, is_y_odd) = match signature.normalize_s() {
+        Some(normalized) => (normalized, !is_y_odd),
+        None => (signature, is_y_odd),
+    };
+    let recovery_id = RecoveryId::new(is_y_odd, false);
+    let key = VerifyingKey::recover_from_prehash(&input[..32], &signature, recovery_id).ok()?;
+    let hash = Keccak256::digest(&key.to_encoded_point(false).as_bytes()[1..]);
+    Some(H160::from_slice(&hash[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{context, from_hex};
+    use crate::ExitError;
+
+    fn input() -> Vec<u8> {
+        from_hex(
+            "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c\
+             000000000000000000000000000000000000000000000000000000000000001c\
+             73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f\
+             eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549",
+        )
+    }
+
+    #[test]
+    fn test_ecrecover() {
+        let (output, cost) = ecrecover(&input(), None, &context(ADDRESS), false).unwrap();
+        assert_eq!(
+            output.output,
+            from_hex("000000000000000000000000a94f5374fce5edbc8e2a8697c15331677e6ebf0b")
+        );
+        assert_eq!(cost, COST);
+    }
+
+    #[test]
+    fn test_ecrecover_invalid_signature() {
+        let mut input = input();
+        input[63] = 29;
+        let (output, cost) = ecrecover(&input, None, &context(ADDRESS), false).unwrap();
+        assert!(output.output.is_empty());
+        assert_eq!(cost, COST);
+
+        let mut input = self::input();
+        input[62] = 1;
+        let (output, _) = ecrecover(&input, None, &context(ADDRESS), false).unwrap();
+        assert!(output.output.is_empty());
+
+        // Zero `r` and `s`
+        let (output, _) = ecrecover(&self::input()[..64], None, &context(ADDRESS), false).unwrap();
+        assert!(output.output.is_empty());
+
+        let result = ecrecover(&self::input(), Some(COST - 1), &context(ADDRESS), false);
+        assert_eq!(result.unwrap_err(), ExitError::OutOfGas.into());
+    }
+}
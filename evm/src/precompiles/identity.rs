@@ -0,0 +1,53 @@
+//! `IDENTITY` precompile, returning its input.
+
+use super::{check_gas, linear_cost, precompile_address, success};
+use crate::executor::stack::{PrecompileFailure, PrecompileMetadata, PrecompileOutput};
+use crate::runtime::HardFork;
+use crate::Context;
+use primitive_types::H160;
+
+/// `IDENTITY` precompile address.
+pub const ADDRESS: H160 = precompile_address(4);
+/// `IDENTITY` precompile metadata.
+pub const METADATA: PrecompileMetadata = PrecompileMetadata::new("IDENTITY", HardFork::Frontier);
+
+/// Base cost.
+pub const BASE_COST: u64 = 15;
+/// Cost per 32 byte word of input.
+pub const WORD_COST: u64 = 3;
+
+/// `IDENTITY` since Frontier.
+///
+/// # Errors
+/// Return `PrecompileFailure` for insufficient gas.
+pub fn identity(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    let cost = linear_cost(input.len(), BASE_COST, WORD_COST);
+    check_gas(cost, gas_limit)?;
+    Ok(success(input.to_vec(), cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::context;
+    use crate::ExitError;
+
+    #[test]
+    fn test_identity() {
+        let input = [7u8; 33];
+        let (output, cost) = identity(&input, None, &context(ADDRESS), false).unwrap();
+        assert_eq!(output.output, input.to_vec());
+        assert_eq!(cost, 21);
+
+        let (output, cost) = identity(&[], Some(15), &context(ADDRESS), false).unwrap();
+        assert!(output.output.is_empty());
+        assert_eq!(cost, 15);
+        let result = identity(&[1], Some(17), &context(ADDRESS), false);
+        assert_eq!(result.unwrap_err(), ExitError::OutOfGas.into());
+    }
+}
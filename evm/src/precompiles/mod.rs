@@ -0,0 +1,340 @@
+//! # Standard precompiles
+//!
+//! Native implementations of the Ethereum precompiled contracts. Every
+//! precompile is behind its own feature flag, and [`StandardPrecompiles`]
+//! bundles the enabled ones for a given [`Config`], as contiguous
+//! [`PrecompileRange`]s.
+
+#[cfg(feature = "precompile-blake2f")]
+pub mod blake2f;
+#[cfg(feature = "precompile-bn128")]
+pub mod bn128;
+#[cfg(feature = "precompile-ecrecover")]
+pub mod ecrecover;
+#[cfg(feature = "precompile-identity")]
+pub mod identity;
+#[cfg(feature = "precompile-modexp")]
+pub mod modexp;
+#[cfg(feature = "precompile-ripemd160")]
+pub mod ripemd160;
+#[cfg(feature = "precompile-sha256")]
+pub mod sha256;
+
+use crate::core::prelude::Cow;
+use crate::executor::stack::{
+    execute_precompile_fn, PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileMetadata,
+    PrecompileOutput, PrecompileSet,
+};
+use crate::prelude::*;
+use crate::{Config, ExitError, ExitSucceed};
+use primitive_types::H160;
+
+/// Address of the precompile with the given index, i.e. `0x00..00{index}`.
+#[must_use]
+pub const fn precompile_address(index: u8) -> H160 {
+    let mut address = [0u8; 20];
+    address[19] = index;
+    H160(address)
+}
+
+/// Precompiles at contiguous addresses, e.g. `0x06..=0x08` for the `alt_bn128`
+/// precompiles. A lookup is a prefix comparison and an index, instead of a
+/// map search per precompile.
+///
+/// Addresses of a range only differ in their last byte, so a range holds at
+/// most 256 precompiles.
+#[derive(Clone, Debug)]
+pub struct PrecompileRange {
+    first: H160,
+    precompiles: Vec<(PrecompileFn, PrecompileMetadata)>,
+}
+
+impl PrecompileRange {
+    /// Create an empty range starting at `first`.
+    #[must_use]
+    pub const fn new(first: H160) -> Self {
+        Self {
+            first,
+            precompiles: Vec::new(),
+        }
+    }
+
+    /// Append a precompile at the next address of the range.
+    ///
+    /// # Panics
+    /// Panic if the next address would overflow the last address byte.
+    #[must_use]
+    pub fn with(mut self, precompile: PrecompileFn, metadata: PrecompileMetadata) -> Self {
+        assert!(
+            usize::from(self.first.0[19]) + self.precompiles.len() <= usize::from(u8::MAX),
+            "precompile range overflows the last address byte"
+        );
+        self.precompiles.push((precompile, metadata));
+        self
+    }
+
+    /// Number of precompiles in the range.
+    #[must_use]
+    // TODO: rust-v1.87 - const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn len(&self) -> usize {
+        self.precompiles.len()
+    }
+
+    /// Whether the range has no precompiles.
+    #[must_use]
+    // TODO: rust-v1.87 - const fn
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn is_empty(&self) -> bool {
+        self.precompiles.is_empty()
+    }
+
+    /// Addresses of the precompiles in the range.
+    pub fn addresses(&self) -> impl Iterator<Item = H160> + '_ {
+        (self.first.0[19]..=u8::MAX)
+            .take(self.precompiles.len())
+            .map(|last| {
+                let mut address = self.first;
+                address.0[19] = last;
+                address
+            })
+    }
+
+    fn get(&self, address: H160) -> Option<&(PrecompileFn, PrecompileMetadata)> {
+        if address.0[..19] != self.first.0[..19] {
+            return None;
+        }
+        let offset = address.0[19].checked_sub(self.first.0[19])?;
+        self.precompiles.get(usize::from(offset))
+    }
+}
+
+/// Precompiles enabled via feature flags, selected and priced according to the
+/// hard fork described by a [`Config`].
+#[derive(Clone, Debug, Default)]
+pub struct StandardPrecompiles {
+    ranges: Vec<PrecompileRange>,
+}
+
+impl StandardPrecompiles {
+    /// Create the precompile set for the given config.
+    #[must_use]
+    #[allow(unused_mut)]
+    pub fn new(config: &Config) -> Self {
+        let mut precompiles = Self::default();
+
+        // Frontier
+        #[cfg(feature = "precompile-ecrecover")]
+        precompiles.register_range(
+            PrecompileRange::new(ecrecover::ADDRESS)
+                .with(ecrecover::ecrecover, ecrecover::METADATA),
+        );
+        #[cfg(feature = "precompile-sha256")]
+        precompiles.register_range(
+            PrecompileRange::new(sha256::ADDRESS).with(sha256::sha256, sha256::METADATA),
+        );
+        #[cfg(feature = "precompile-ripemd160")]
+        precompiles.register_range(
+            PrecompileRange::new(ripemd160::ADDRESS)
+                .with(ripemd160::ripemd160, ripemd160::METADATA),
+        );
+        #[cfg(feature = "precompile-identity")]
+        precompiles.register_range(
+            PrecompileRange::new(identity::ADDRESS).with(identity::identity, identity::METADATA),
+        );
+
+        // Byzantium: EIP-198, repriced in Berlin by EIP-2565.
+        #[cfg(feature = "precompile-modexp")]
+        if config.has_return_data {
+            let range = PrecompileRange::new(modexp::ADDRESS);
+            precompiles.register_range(if config.increase_state_access_gas {
+                range.with(modexp::modexp_berlin, modexp::METADATA)
+            } else {
+                range.with(modexp::modexp_byzantium, modexp::METADATA)
+            });
+        }
+
+        // Byzantium: EIP-196 and EIP-197, repriced in Istanbul by EIP-1108.
+        #[cfg(feature = "precompile-bn128")]
+        if config.has_return_data {
+            let range = PrecompileRange::new(bn128::ADD_ADDRESS);
+            precompiles.register_range(if config.has_chain_id {
+                range
+                    .with(bn128::add_istanbul, bn128::ADD_METADATA)
+                    .with(bn128::mul_istanbul, bn128::MUL_METADATA)
+                    .with(bn128::pair_istanbul, bn128::PAIR_METADATA)
+            } else {
+                range
+                    .with(bn128::add_byzantium, bn128::ADD_METADATA)
+                    .with(bn128::mul_byzantium, bn128::MUL_METADATA)
+                    .with(bn128::pair_byzantium, bn128::PAIR_METADATA)
+            });
+        }
+
+        // Istanbul: EIP-152.
+        #[cfg(feature = "precompile-blake2f")]
+        if config.has_chain_id {
+            precompiles.register_range(
+                PrecompileRange::new(blake2f::ADDRESS).with(blake2f::blake2f, blake2f::METADATA),
+            );
+        }
+
+        #[cfg(not(any(
+            feature = "precompile-blake2f",
+            feature = "precompile-bn128",
+            feature = "precompile-modexp"
+        )))]
+        let _ = config;
+
+        precompiles
+    }
+
+    /// Register a range of precompiles, e.g. chain-specific ones next to the
+    /// standard set. Ranges registered first take precedence on overlaps.
+    pub fn register_range(&mut self, range: PrecompileRange) {
+        if !range.is_empty() {
+            self.ranges.push(range);
+        }
+    }
+
+    /// Addresses of the precompiles in the set.
+    pub fn addresses(&self) -> impl Iterator<Item = H160> + '_ {
+        self.ranges.iter().flat_map(PrecompileRange::addresses)
+    }
+
+    fn get(&self, address: H160) -> Option<&(PrecompileFn, PrecompileMetadata)> {
+        self.ranges.iter().find_map(|range| range.get(address))
+    }
+}
+
+impl PrecompileSet for StandardPrecompiles {
+    fn execute(
+        &self,
+        handle: &mut impl PrecompileHandle,
+    ) -> Option<Result<PrecompileOutput, PrecompileFailure>> {
+        self.get(handle.code_address())
+            .map(|(precompile, _)| execute_precompile_fn(*precompile, handle))
+    }
+
+    fn is_precompile(&self, address: H160) -> bool {
+        self.get(address).is_some()
+    }
+
+    fn precompile_addresses(&self) -> Vec<H160> {
+        self.addresses().collect()
+    }
+
+    fn metadata(&self, address: H160) -> Option<PrecompileMetadata> {
+        self.get(address).map(|(_, metadata)| metadata.clone())
+    }
+}
+
+/// Fail with `OutOfGas` if `cost` exceeds the gas limit, before doing the work.
+#[allow(dead_code)]
+fn check_gas(cost: u64, gas_limit: Option<u64>) -> Result<(), PrecompileFailure> {
+    match gas_limit {
+        Some(gas_limit) if cost > gas_limit => Err(ExitError::OutOfGas.into()),
+        _ => Ok(()),
+    }
+}
+
+/// `base + word * ceil(len / 32)`, the cost of the precompiles priced per
+/// input word.
+#[allow(dead_code)]
+fn linear_cost(len: usize, base: u64, word: u64) -> u64 {
+    let words = u64::try_from(len.div_ceil(32)).unwrap_or(u64::MAX);
+    words.saturating_mul(word).saturating_add(base)
+}
+
+/// Input right-padded with zeros (or truncated) to `N` bytes.
+#[allow(dead_code)]
+fn padded_input<const N: usize>(input: &[u8]) -> [u8; N] {
+    let mut padded = [0u8; N];
+    let len = input.len().min(N);
+    padded[..len].copy_from_slice(&input[..len]);
+    padded
+}
+
+/// Successful precompile result.
+#[allow(dead_code)]
+const fn success(output: Vec<u8>, cost: u64) -> (PrecompileOutput, u64) {
+    (
+        PrecompileOutput {
+            exit_status: ExitSucceed::Returned,
+            output,
+        },
+        cost,
+    )
+}
+
+/// Precompile failure with a static message.
+#[allow(dead_code)]
+const fn failure(message: &'static str) -> PrecompileFailure {
+    PrecompileFailure::Error {
+        exit_status: ExitError::Other(Cow::Borrowed(message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::HardFork;
+    use crate::Context;
+
+    fn identity(
+        input: &[u8],
+        _gas_limit: Option<u64>,
+        _context: &Context,
+        _is_static: bool,
+    ) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+        Ok(success(input.to_vec(), 0))
+    }
+
+    #[test]
+    fn test_register_range_and_metadata() {
+        let mut precompiles = StandardPrecompiles::default();
+        precompiles.register_range(
+            PrecompileRange::new(precompile_address(0xfe))
+                .with(identity, PrecompileMetadata::new("FIRST", HardFork::Cancun))
+                .with(
+                    identity,
+                    PrecompileMetadata::new("SECOND", HardFork::Prague),
+                ),
+        );
+
+        assert_eq!(
+            precompiles.addresses().collect::<Vec<_>>(),
+            vec![precompile_address(0xfe), precompile_address(0xff)]
+        );
+        assert!(!precompiles.is_precompile(precompile_address(0xfd)));
+        assert!(!precompiles.is_precompile(H160::repeat_byte(0xff)));
+        assert_eq!(
+            precompiles.metadata(precompile_address(0xff)),
+            Some(PrecompileMetadata::new("SECOND", HardFork::Prague))
+        );
+        assert_eq!(precompiles.metadata(precompile_address(0xfd)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "precompile range overflows")]
+    fn test_range_overflow() {
+        let _ = PrecompileRange::new(precompile_address(0xff))
+            .with(identity, PrecompileMetadata::new("FIRST", HardFork::Cancun))
+            .with(
+                identity,
+                PrecompileMetadata::new("SECOND", HardFork::Cancun),
+            );
+    }
+
+    #[cfg(feature = "precompile-bn128")]
+    #[test]
+    fn test_standard_metadata() {
+        let precompiles = StandardPrecompiles::new(&Config::cancun());
+        let metadata = precompiles.metadata(bn128::PAIR_ADDRESS).unwrap();
+        assert_eq!(metadata.name, "ECPAIRING");
+        assert_eq!(metadata.since, HardFork::Byzantium);
+        assert!(StandardPrecompiles::new(&Config::frontier())
+            .metadata(bn128::ADD_ADDRESS)
+            .is_none());
+    }
+}
@@ -0,0 +1,244 @@
+//! `MODEXP` precompile.
+//!
+//! [EIP-198]: big integer modular exponentiation, repriced by [EIP-2565] in Berlin.
+//!
+//! [EIP-198]: https://eips.ethereum.org/EIPS/eip-198
+//! [EIP-2565]: https://eips.ethereum.org/EIPS/eip-2565
+
+use super::{check_gas, padded_input, precompile_address, success};
+use crate::core::utils::{U256_ONE, U256_ZERO, U64_MAX, USIZE_MAX};
+use crate::executor::stack::{PrecompileFailure, PrecompileMetadata, PrecompileOutput};
+use crate::prelude::*;
+use crate::runtime::HardFork;
+use crate::{Context, ExitError};
+use core::cmp::max;
+use num_bigint::BigUint;
+use primitive_types::{H160, U256};
+
+/// `MODEXP` precompile address.
+pub const ADDRESS: H160 = precompile_address(5);
+/// `MODEXP` precompile metadata.
+pub const METADATA: PrecompileMetadata = PrecompileMetadata::new("MODEXP", HardFork::Byzantium);
+
+/// Quadratic divisor before Berlin (EIP-198).
+pub const GQUADDIVISOR_BYZANTIUM: u64 = 20;
+/// Quadratic divisor since Berlin (EIP-2565).
+pub const GQUADDIVISOR_BERLIN: u64 = 3;
+/// Minimal cost since Berlin (EIP-2565).
+pub const MIN_COST_BERLIN: u64 = 200;
+
+/// Length of the `(base_len, exp_len, mod_len)` header.
+const HEADER_LEN: usize = 96;
+
+/// `MODEXP` with Byzantium pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for insufficient gas.
+pub fn modexp_byzantium(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    modexp(input, gas_limit, byzantium_cost)
+}
+
+/// `MODEXP` with Berlin pricing.
+///
+/// # Errors
+/// Return `PrecompileFailure` for insufficient gas.
+pub fn modexp_berlin(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    modexp(input, gas_limit, berlin_cost)
+}
+
+/// Sizes of the `MODEXP` operands, as declared in the input header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Lengths {
+    base: U256,
+    exp: U256,
+    modulus: U256,
+}
+
+impl Lengths {
+    fn parse(input: &[u8]) -> Self {
+        let header = padded_input::<HEADER_LEN>(input);
+        Self {
+            base: U256::from_big_endian(&header[..32]),
+            exp: U256::from_big_endian(&header[32..64]),
+            modulus: U256::from_big_endian(&header[64..]),
+        }
+    }
+}
+
+fn modexp(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    cost: fn(&Lengths, U256) -> U256,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    let lengths = Lengths::parse(input);
+    let cost = cost(&lengths, iteration_count(input, &lengths));
+    let cost = if cost > U64_MAX {
+        u64::MAX
+    } else {
+        cost.as_u64()
+    };
+    check_gas(cost, gas_limit)?;
+
+    if lengths.base == U256_ZERO && lengths.modulus == U256_ZERO {
+        return Ok(success(Vec::new(), cost));
+    }
+    if lengths.base > USIZE_MAX || lengths.exp > USIZE_MAX || lengths.modulus > USIZE_MAX {
+        return Err(ExitError::OutOfGas.into());
+    }
+    let base_len = lengths.base.as_usize();
+    let exp_len = lengths.exp.as_usize();
+    let mod_len = lengths.modulus.as_usize();
+
+    let exp_start = HEADER_LEN.saturating_add(base_len);
+    let mod_start = exp_start.saturating_add(exp_len);
+    let base = BigUint::from_bytes_be(&read_padded(input, HEADER_LEN, base_len));
+    let exponent = BigUint::from_bytes_be(&read_padded(input, exp_start, exp_len));
+    let modulus = BigUint::from_bytes_be(&read_padded(input, mod_start, mod_len));
+
+    let mut output = Vec::with_capacity(mod_len);
+    output.resize(mod_len, 0);
+    if modulus != BigUint::ZERO {
+        let result = base.modpow(&exponent, &modulus).to_bytes_be();
+        // The result is smaller than the modulus, so it always fits
+        output[mod_len - result.len()..].copy_from_slice(&result);
+    }
+
+    Ok(success(output, cost))
+}
+
+/// Read `len` bytes of `input` starting at `start`, right-padded with zeros.
+fn read_padded(input: &[u8], start: usize, len: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(len);
+    output.resize(len, 0);
+    if start < input.len() {
+        let available = (input.len() - start).min(len);
+        output[..available].copy_from_slice(&input[start..start + available]);
+    }
+    output
+}
+
+/// Adjusted exponent length: the bit length of the exponent head, plus `8` per
+/// exponent byte beyond the first 32.
+fn iteration_count(input: &[u8], lengths: &Lengths) -> U256 {
+    let head_len = lengths.exp.min(U256::from(32)).as_usize();
+    let head = if lengths.base > USIZE_MAX {
+        U256_ZERO
+    } else {
+        U256::from_big_endian(&read_padded(
+            input,
+            HEADER_LEN.saturating_add(lengths.base.as_usize()),
+            head_len,
+        ))
+    };
+    let head_bits = U256::from(head.bits().saturating_sub(1));
+
+    if lengths.exp <= U256::from(32) {
+        head_bits
+    } else {
+        (lengths.exp - 32)
+            .saturating_mul(U256::from(8))
+            .saturating_add(head_bits)
+    }
+}
+
+/// EIP-198 multiplication complexity.
+fn byzantium_cost(lengths: &Lengths, iteration_count: U256) -> U256 {
+    let x = max(lengths.base, lengths.modulus);
+    let complexity = if x <= U256::from(64) {
+        x * x
+    } else if x <= U256::from(1024) {
+        x * x / 4 + x * 96 - 3072
+    } else {
+        (x.saturating_mul(x) / 16).saturating_add(x.saturating_mul(U256::from(480))) - 199_680
+    };
+    complexity.saturating_mul(max(iteration_count, U256_ONE)) / GQUADDIVISOR_BYZANTIUM
+}
+
+/// EIP-2565 multiplication complexity.
+fn berlin_cost(lengths: &Lengths, iteration_count: U256) -> U256 {
+    let words = max(lengths.base, lengths.modulus).saturating_add(U256::from(7)) / 8;
+    let complexity = words.saturating_mul(words);
+    max(
+        complexity.saturating_mul(max(iteration_count, U256_ONE)) / GQUADDIVISOR_BERLIN,
+        U256::from(MIN_COST_BERLIN),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::context;
+
+    fn input(base: &[u8], exp: &[u8], modulus: &[u8]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for len in [base.len(), exp.len(), modulus.len()] {
+            input.extend_from_slice(&U256::from(len).to_big_endian());
+        }
+        input.extend_from_slice(base);
+        input.extend_from_slice(exp);
+        input.extend_from_slice(modulus);
+        input
+    }
+
+    #[test]
+    fn test_modexp() {
+        // 3 ^ 5 mod 7 = 5
+        let input = input(&[3], &[5], &[0, 7]);
+        let (output, cost) = modexp_berlin(&input, None, &context(ADDRESS), false).unwrap();
+        assert_eq!(output.output, [0, 5].to_vec());
+        assert_eq!(cost, MIN_COST_BERLIN);
+    }
+
+    #[test]
+    fn test_modexp_zero_modulus() {
+        let input = input(&[3], &[5], &[0, 0]);
+        let (output, _) = modexp_byzantium(&input, None, &context(ADDRESS), false).unwrap();
+        assert_eq!(output.output, [0, 0].to_vec());
+    }
+
+    #[test]
+    fn test_modexp_empty_input() {
+        let (output, cost) = modexp_byzantium(&[], None, &context(ADDRESS), false).unwrap();
+        assert!(output.output.is_empty());
+        assert_eq!(cost, 0);
+    }
+
+    #[test]
+    fn test_modexp_truncated_input_is_zero_padded() {
+        // 2 ^ 3 mod 0x0500 = 8, the missing modulus byte is read as zero
+        let mut input = input(&[2], &[3], &[5, 0]);
+        input.truncate(input.len() - 1);
+        let (output, _) = modexp_byzantium(&input, None, &context(ADDRESS), false).unwrap();
+        assert_eq!(output.output, [0, 8].to_vec());
+    }
+
+    #[test]
+    fn test_modexp_berlin_divisor() {
+        // EIP-2565 example: 32 byte operands with a 32 byte exponent of all ones
+        let input = input(&[0xff; 32], &[0xff; 32], &[0xff; 32]);
+        let (_, byzantium) = modexp_byzantium(&input, None, &context(ADDRESS), false).unwrap();
+        let (_, berlin) = modexp_berlin(&input, None, &context(ADDRESS), false).unwrap();
+        // complexity 32^2 = 1024, 255 iterations
+        assert_eq!(byzantium, 1024 * 255 / 20);
+        // 4 words, 16 * 255 / 3
+        assert_eq!(berlin, 16 * 255 / 3);
+    }
+
+    #[test]
+    fn test_modexp_huge_lengths_out_of_gas() {
+        let mut input = [0u8; HEADER_LEN];
+        input[..32].fill(0xff);
+        let result = modexp_berlin(&input, Some(1_000_000), &context(ADDRESS), false);
+        assert_eq!(result.unwrap_err(), ExitError::OutOfGas.into());
+    }
+}
@@ -0,0 +1,60 @@
+//! `RIPEMD160` precompile.
+
+use super::{check_gas, linear_cost, precompile_address, success};
+use crate::executor::stack::{PrecompileFailure, PrecompileMetadata, PrecompileOutput};
+use crate::runtime::HardFork;
+use crate::Context;
+use primitive_types::{H160, H256};
+use ripemd::{Digest, Ripemd160};
+
+/// `RIPEMD160` precompile address.
+pub const ADDRESS: H160 = precompile_address(3);
+/// `RIPEMD160` precompile metadata.
+pub const METADATA: PrecompileMetadata = PrecompileMetadata::new("RIPEMD160", HardFork::Frontier);
+
+/// Base cost.
+pub const BASE_COST: u64 = 600;
+/// Cost per 32 byte word of input.
+pub const WORD_COST: u64 = 120;
+
+/// `RIPEMD160` since Frontier. The 20 byte digest is left-padded with zeros
+/// to a 32 byte word.
+///
+/// # Errors
+/// Return `PrecompileFailure` for insufficient gas.
+pub fn ripemd160(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    let cost = linear_cost(input.len(), BASE_COST, WORD_COST);
+    check_gas(cost, gas_limit)?;
+    let mut output = H256::zero();
+    output.0[12..].copy_from_slice(&Ripemd160::digest(input));
+    Ok(success(output.0.to_vec(), cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::context;
+    use crate::ExitError;
+
+    #[test]
+    fn test_ripemd160() {
+        let (output, cost) = ripemd160(&[], None, &context(ADDRESS), false).unwrap();
+        assert_eq!(output.output[..12], [0; 12]);
+        assert_eq!(
+            H160::from_slice(&output.output[12..]),
+            H160([
+                0x9c, 0x11, 0x85, 0xa5, 0xc5, 0xe9, 0xfc, 0x54, 0x61, 0x28, 0x08, 0x97, 0x7e, 0xe8,
+                0xf5, 0x48, 0xb2, 0x25, 0x8d, 0x31,
+            ])
+        );
+        assert_eq!(cost, BASE_COST);
+
+        let result = ripemd160(&[0; 33], Some(839), &context(ADDRESS), false);
+        assert_eq!(result.unwrap_err(), ExitError::OutOfGas.into());
+    }
+}
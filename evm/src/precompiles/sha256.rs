@@ -0,0 +1,60 @@
+//! `SHA256` precompile.
+
+use super::{check_gas, linear_cost, precompile_address, success};
+use crate::executor::stack::{PrecompileFailure, PrecompileMetadata, PrecompileOutput};
+use crate::runtime::HardFork;
+use crate::Context;
+use primitive_types::H160;
+use sha2::{Digest, Sha256};
+
+/// `SHA256` precompile address.
+pub const ADDRESS: H160 = precompile_address(2);
+/// `SHA256` precompile metadata.
+pub const METADATA: PrecompileMetadata = PrecompileMetadata::new("SHA256", HardFork::Frontier);
+
+/// Base cost.
+pub const BASE_COST: u64 = 60;
+/// Cost per 32 byte word of input.
+pub const WORD_COST: u64 = 12;
+
+/// `SHA256` since Frontier.
+///
+/// # Errors
+/// Return `PrecompileFailure` for insufficient gas.
+pub fn sha256(
+    input: &[u8],
+    gas_limit: Option<u64>,
+    _context: &Context,
+    _is_static: bool,
+) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+    let cost = linear_cost(input.len(), BASE_COST, WORD_COST);
+    check_gas(cost, gas_limit)?;
+    Ok(success(Sha256::digest(input).to_vec(), cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::context;
+    use crate::ExitError;
+    use primitive_types::H256;
+
+    #[test]
+    fn test_sha256() {
+        let (output, cost) = sha256(&[], None, &context(ADDRESS), false).unwrap();
+        assert_eq!(
+            H256::from_slice(&output.output),
+            H256([
+                0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f,
+                0xb9, 0x24, 0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b,
+                0x78, 0x52, 0xb8, 0x55,
+            ])
+        );
+        assert_eq!(cost, BASE_COST);
+
+        let (_, cost) = sha256(&[0; 64], None, &context(ADDRESS), false).unwrap();
+        assert_eq!(cost, 84);
+        let result = sha256(&[0; 64], Some(83), &context(ADDRESS), false);
+        assert_eq!(result.unwrap_err(), ExitError::OutOfGas.into());
+    }
+}
@@ -0,0 +1,246 @@
+//! Mapping of execution results to JSON-RPC errors, following the error
+//! shapes returned by `eth_call` and `eth_estimateGas` in geth.
+
+use crate::core::prelude::{Cow, String};
+use crate::core::utils::revert;
+use crate::prelude::*;
+use crate::{ExitError, ExitFatal, ExitReason, Opcode};
+use core::fmt::Write;
+
+/// Error code of a reverted execution, returned along with the revert data.
+pub const EXECUTION_REVERTED: i64 = 3;
+/// Generic server error code, used for failed executions.
+pub const SERVER_ERROR: i64 = -32000;
+/// Internal error code, used for fatal errors.
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// JSON-RPC error object.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RpcError {
+    /// Error code.
+    pub code: i64,
+    /// Error message.
+    pub message: Cow<'static, str>,
+    /// Revert data, only set for reverted executions.
+    pub data: Option<Vec<u8>>,
+}
+
+impl RpcError {
+    /// Error of an execution which exited with `reason` and `output`.
+    /// Return `None` for successful executions.
+    #[must_use]
+    pub fn from_exit(reason: &ExitReason, output: &[u8]) -> Option<Self> {
+        match reason {
+            ExitReason::Succeed(_) => None,
+            ExitReason::Revert(_) => Some(Self::reverted(output)),
+            ExitReason::Error(error) => Some(Self::from(error)),
+            ExitReason::Fatal(fatal) => Some(Self::from(fatal)),
+        }
+    }
+
+    /// Error of a reverted execution. The message includes the decoded
+    /// revert reason, if any.
+    #[must_use]
+    pub fn reverted(output: &[u8]) -> Self {
+        let message = revert::decode(output).message().map_or(
+            Cow::Borrowed("execution reverted"),
+            |reason| {
+                let mut message = String::from("execution reverted: ");
+                message.push_str(reason);
+                Cow::Owned(message)
+            },
+        );
+        Self {
+            code: EXECUTION_REVERTED,
+            message,
+            data: Some(output.to_vec()),
+        }
+    }
+
+    const fn server_error(message: &'static str) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: Cow::Borrowed(message),
+            data: None,
+        }
+    }
+
+    /// Error of an invalid opcode, named like geth names it, e.g.
+    /// `invalid opcode: PUSH0` or `invalid opcode: opcode 0xef not defined`.
+    fn invalid_opcode(opcode: Opcode) -> Self {
+        let mut name = String::new();
+        let _ = write!(name, "{opcode}");
+        let mut message = String::from("invalid opcode: ");
+        match name.split(' ').next() {
+            Some(name) if name != "UNKNOWN" => message.push_str(name),
+            _ => {
+                let _ = write!(message, "opcode {:#x} not defined", opcode.as_u8());
+            }
+        }
+        Self {
+            code: SERVER_ERROR,
+            message: Cow::Owned(message),
+            data: None,
+        }
+    }
+}
+
+impl From<&ExitError> for RpcError {
+    fn from(error: &ExitError) -> Self {
+        let message = match error {
+            ExitError::StackUnderflow => "stack underflow",
+            ExitError::StackOverflow => "stack limit reached",
+            ExitError::InvalidJump => "invalid jump destination",
+            ExitError::OutOfOffset => "return data out of bounds",
+            // Memory offsets past `u64` fail the memory gas calculation in geth
+            ExitError::InvalidRange | ExitError::UsizeOverflow => "gas uint64 overflow",
+            ExitError::DesignatedInvalid => return Self::invalid_opcode(Opcode::INVALID),
+            ExitError::InvalidCode(opcode) => return Self::invalid_opcode(*opcode),
+            ExitError::CallTooDeep(_) => "max call depth exceeded",
+            ExitError::CreateCollision(_) => "contract address collision",
+            ExitError::CreateContractLimit => "max code size exceeded",
+            ExitError::OutOfGas => "out of gas",
+            ExitError::OutOfFund => "insufficient balance for transfer",
+            ExitError::PCUnderflow => "program counter underflow",
+            ExitError::CreateEmpty => "empty contract creation",
+            ExitError::MaxNonce => "nonce uint64 overflow",
+            ExitError::CreateContractStartingWithEF => "invalid code: must not begin with 0xef",
+            ExitError::StaticModeViolation => "write protection",
+            ExitError::BalanceOverflow => "balance overflow",
+            ExitError::TransactionGasLimitExceeded => "transaction gas limit too high",
+            ExitError::Other(message) => {
+                return Self {
+                    code: SERVER_ERROR,
+                    message: message.clone(),
+                    data: None,
+                }
+            }
+        };
+        Self::server_error(message)
+    }
+}
+
+impl From<&ExitFatal> for RpcError {
+    fn from(fatal: &ExitFatal) -> Self {
+        let message = match fatal {
+            ExitFatal::NotSupported => Cow::Borrowed("operation not supported"),
+            ExitFatal::UnhandledInterrupt => Cow::Borrowed("unhandled interrupt"),
+            ExitFatal::CallErrorAsFatal(error) => Self::from(error).message,
+            ExitFatal::Other(message) => message.clone(),
+            ExitFatal::Cancelled => Cow::Borrowed("execution cancelled"),
+            ExitFatal::StepLimitReached => Cow::Borrowed("step limit reached"),
+        };
+        Self {
+            code: INTERNAL_ERROR,
+            message,
+            data: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, ExitRevert, ExitSucceed};
+
+    #[test]
+    fn test_exit_reason_to_rpc_error() {
+        assert_eq!(
+            RpcError::from_exit(&ExitReason::Succeed(ExitSucceed::Returned), &[1]),
+            None
+        );
+
+        let reverted =
+            RpcError::from_exit(&ExitReason::Revert(ExitRevert::Reverted), &[1, 2]).unwrap();
+        assert_eq!(reverted.code, EXECUTION_REVERTED);
+        assert_eq!(reverted.message, "execution reverted");
+        assert_eq!(reverted.data, Some(vec![1, 2]));
+
+        let mut panic = revert::PANIC_SELECTOR.to_vec();
+        panic.extend_from_slice(&primitive_types::U256::one().to_big_endian());
+        let reverted = RpcError::reverted(&panic);
+        assert_eq!(reverted.message, "execution reverted: assert(false)");
+        assert_eq!(reverted.data, Some(panic));
+
+        let out_of_gas = RpcError::from_exit(&ExitError::OutOfGas.into(), &[]).unwrap();
+        assert_eq!(out_of_gas.code, SERVER_ERROR);
+        assert_eq!(out_of_gas.message, "out of gas");
+        assert_eq!(out_of_gas.data, None);
+
+        let depth = u64::try_from(Config::cancun().call_stack_limit + 1).unwrap();
+        let fatal = RpcError::from_exit(
+            &ExitFatal::CallErrorAsFatal(ExitError::CallTooDeep(depth)).into(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(fatal.code, INTERNAL_ERROR);
+        assert_eq!(fatal.message, "max call depth exceeded");
+    }
+
+    #[test]
+    fn test_geth_error_messages() {
+        let errors = [
+            (ExitError::StackUnderflow, "stack underflow"),
+            (ExitError::StackOverflow, "stack limit reached"),
+            (ExitError::InvalidJump, "invalid jump destination"),
+            (ExitError::InvalidRange, "gas uint64 overflow"),
+            (ExitError::DesignatedInvalid, "invalid opcode: INVALID"),
+            (ExitError::CallTooDeep(1), "max call depth exceeded"),
+            (
+                ExitError::CreateCollision(primitive_types::H160::zero()),
+                "contract address collision",
+            ),
+            (ExitError::CreateContractLimit, "max code size exceeded"),
+            (
+                ExitError::InvalidCode(Opcode::PUSH0),
+                "invalid opcode: PUSH0",
+            ),
+            (
+                ExitError::InvalidCode(Opcode(0xef)),
+                "invalid opcode: opcode 0xef not defined",
+            ),
+            (ExitError::OutOfOffset, "return data out of bounds"),
+            (ExitError::OutOfGas, "out of gas"),
+            (ExitError::OutOfFund, "insufficient balance for transfer"),
+            (ExitError::PCUnderflow, "program counter underflow"),
+            (ExitError::CreateEmpty, "empty contract creation"),
+            (ExitError::Other("custom".into()), "custom"),
+            (ExitError::MaxNonce, "nonce uint64 overflow"),
+            (ExitError::UsizeOverflow, "gas uint64 overflow"),
+            (
+                ExitError::CreateContractStartingWithEF,
+                "invalid code: must not begin with 0xef",
+            ),
+            (ExitError::StaticModeViolation, "write protection"),
+            (ExitError::BalanceOverflow, "balance overflow"),
+            (
+                ExitError::TransactionGasLimitExceeded,
+                "transaction gas limit too high",
+            ),
+        ];
+        for (error, message) in errors {
+            let rpc_error = RpcError::from(&error);
+            assert_eq!(rpc_error.code, SERVER_ERROR);
+            assert_eq!(rpc_error.message, message);
+            assert_eq!(rpc_error.data, None);
+        }
+
+        let fatals = [
+            (ExitFatal::NotSupported, "operation not supported"),
+            (ExitFatal::UnhandledInterrupt, "unhandled interrupt"),
+            (
+                ExitFatal::CallErrorAsFatal(ExitError::InvalidJump),
+                "invalid jump destination",
+            ),
+            (ExitFatal::Other("custom".into()), "custom"),
+            (ExitFatal::Cancelled, "execution cancelled"),
+            (ExitFatal::StepLimitReached, "step limit reached"),
+        ];
+        for (fatal, message) in fatals {
+            let rpc_error = RpcError::from(&fatal);
+            assert_eq!(rpc_error.code, INTERNAL_ERROR);
+            assert_eq!(rpc_error.message, message);
+        }
+    }
+}
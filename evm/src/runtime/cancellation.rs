@@ -0,0 +1,116 @@
+//! Cooperative cancellation of executions.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+// `Instant::now` panics on wasm32-unknown-unknown, which has no clock
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+
+/// Default number of opcodes executed between two checks of a token.
+pub const DEFAULT_CANCELLATION_STRIDE: u64 = 1024;
+
+/// Token aborting an execution once cancelled or past its deadline, e.g.
+/// to bound the time of RPC simulations.
+///
+/// The executor checks the token every `stride` opcodes of a transaction,
+/// over all its call frames, and exits with `ExitFatal::Cancelled`, which
+/// aborts all the frames. The state changes of a cancelled execution must
+/// be discarded.
+///
+/// Clones share the cancellation flag, so a token can be cancelled from
+/// another thread while the execution runs. Deadlines are not available on
+/// `wasm32-unknown-unknown`, where the embedder cancels the token instead.
+#[derive(Clone, Debug)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    deadline: Option<Instant>,
+    stride: u64,
+}
+
+impl CancellationToken {
+    /// Token cancelled only by [`Self::cancel`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            deadline: None,
+            stride: DEFAULT_CANCELLATION_STRIDE,
+        }
+    }
+
+    /// Also cancel the token once `deadline` is reached.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    #[must_use]
+    pub const fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Check the token every `stride` opcodes, at least one.
+    /// Smaller strides abort sooner, larger ones are cheaper.
+    #[must_use]
+    pub fn with_stride(mut self, stride: u64) -> Self {
+        self.stride = stride.max(1);
+        self
+    }
+
+    /// Number of opcodes executed between two checks of the token.
+    #[must_use]
+    pub const fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    /// Cancel the executions checking this token or its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token is cancelled or past its deadline.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return true;
+        }
+        false
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new().with_stride(0);
+        assert_eq!(token.stride(), 1);
+        assert!(!token.is_cancelled());
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn test_cancellation_token_deadline() {
+        use std::time::Duration;
+
+        let now = Instant::now();
+        assert!(CancellationToken::new().with_deadline(now).is_cancelled());
+        let later = now + Duration::from_secs(3600);
+        assert!(!CancellationToken::new().with_deadline(later).is_cancelled());
+    }
+}
@@ -0,0 +1,238 @@
+//! Field-by-field comparison of configs.
+
+use super::prelude::*;
+use super::{Config, FeeMarket, SelfDestructBalance};
+use core::fmt;
+
+/// Value of a [`Config`] field.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigValue {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    Usize(usize),
+    OptionalU64(Option<u64>),
+    OptionalUsize(Option<usize>),
+    FeeMarket(FeeMarket),
+    SelfDestructBalance(SelfDestructBalance),
+}
+
+impl fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::U64(value) => write!(f, "{value}"),
+            Self::I64(value) => write!(f, "{value}"),
+            Self::Usize(value) => write!(f, "{value}"),
+            Self::OptionalU64(Some(value)) => write!(f, "{value}"),
+            Self::OptionalUsize(Some(value)) => write!(f, "{value}"),
+            Self::OptionalU64(None) | Self::OptionalUsize(None) => f.write_str("none"),
+            Self::FeeMarket(value) => write!(f, "{value:?}"),
+            Self::SelfDestructBalance(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+/// Field changed between two configs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConfigChange {
+    /// Name of the field.
+    pub field: &'static str,
+    /// Value in the config compared from.
+    pub old: ConfigValue,
+    /// Value in the config compared to.
+    pub new: ConfigValue,
+}
+
+/// Fields changed between two configs, in declaration order, see
+/// [`Config::diff`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConfigDiff {
+    /// Changed fields.
+    pub changes: Vec<ConfigChange>,
+}
+
+impl ConfigDiff {
+    /// Whether the configs are equal.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Change of `field`, `None` if it is unchanged.
+    #[must_use]
+    pub fn get(&self, field: &str) -> Option<&ConfigChange> {
+        self.changes.iter().find(|change| change.field == field)
+    }
+
+    /// Names of the changed fields.
+    pub fn fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.changes.iter().map(|change| change.field)
+    }
+}
+
+/// One `field: old -> new` line per change.
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}: {} -> {}", change.field, change.old, change.new)?;
+        }
+        Ok(())
+    }
+}
+
+/// Names and values of all the fields of a config. The exhaustive
+/// destructuring fails to compile when a field is not listed.
+macro_rules! config_fields {
+    ($config:expr, $($field:ident: $kind:ident),* $(,)?) => {{
+        let Config { $($field),* } = $config;
+        Vec::from([$((stringify!($field), ConfigValue::$kind(*$field))),*])
+    }};
+}
+
+impl Config {
+    /// Names and values of all the fields, in declaration order.
+    #[must_use]
+    pub fn fields(&self) -> Vec<(&'static str, ConfigValue)> {
+        config_fields!(
+            self,
+            gas_ext_code: U64,
+            gas_ext_code_hash: U64,
+            gas_sstore_set: U64,
+            gas_sstore_reset: U64,
+            refund_sstore_clears: I64,
+            max_refund_quotient: U64,
+            gas_balance: U64,
+            gas_sload: U64,
+            gas_sload_cold: U64,
+            gas_suicide: U64,
+            gas_suicide_new_account: U64,
+            gas_suicide_refund: I64,
+            gas_call: U64,
+            gas_expbyte: U64,
+            gas_code_deposit: U64,
+            gas_transaction_create: U64,
+            gas_transaction_call: U64,
+            gas_transaction_zero_data: U64,
+            gas_transaction_non_zero_data: U64,
+            gas_access_list_address: U64,
+            gas_access_list_storage_key: U64,
+            gas_account_access_cold: U64,
+            gas_storage_read_warm: U64,
+            sstore_gas_metering: Bool,
+            sstore_revert_under_stipend: Bool,
+            increase_state_access_gas: Bool,
+            decrease_clears_refund: Bool,
+            disallow_executable_format: Bool,
+            warm_coinbase_address: Bool,
+            err_on_call_with_more_gas: Bool,
+            call_l64_after_gas: Bool,
+            empty_considered_exists: Bool,
+            create_increase_nonce: Bool,
+            stack_limit: Usize,
+            memory_limit: Usize,
+            call_stack_limit: Usize,
+            step_limit: OptionalU64,
+            create_contract_limit: OptionalUsize,
+            max_initcode_size: OptionalUsize,
+            has_init_code_cost: Bool,
+            max_transaction_gas: OptionalU64,
+            call_stipend: U64,
+            has_delegate_call: Bool,
+            has_static_call: Bool,
+            has_create2: Bool,
+            has_revert: Bool,
+            has_return_data: Bool,
+            has_bitwise_shifting: Bool,
+            has_chain_id: Bool,
+            has_self_balance: Bool,
+            has_ext_code_hash: Bool,
+            has_base_fee: Bool,
+            fee_market: FeeMarket,
+            has_push0: Bool,
+            estimate: Bool,
+            has_blob_base_fee: Bool,
+            has_shard_blob_transactions: Bool,
+            has_transient_storage: Bool,
+            has_mcopy: Bool,
+            has_restricted_selfdestruct: Bool,
+            selfdestruct_to_self: SelfDestructBalance,
+            has_authorization_list: Bool,
+            has_clz: Bool,
+            gas_per_empty_account_cost: U64,
+            gas_per_auth_base_cost: U64,
+            has_floor_gas: Bool,
+            total_cost_floor_per_token: U64,
+        )
+    }
+
+    /// Fields changed from `self` to `other`, e.g. to review what a fork
+    /// upgrade changes.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> ConfigDiff {
+        let changes = self
+            .fields()
+            .into_iter()
+            .zip(other.fields())
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((field, old), (_, new))| ConfigChange { field, old, new })
+            .collect();
+        ConfigDiff { changes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HardFork;
+
+    #[test]
+    fn test_diff_of_presets() {
+        assert!(Config::cancun().diff(&Config::cancun()).is_empty());
+
+        let diff = Config::cancun().diff(&Config::prague());
+        assert_eq!(
+            diff.get("has_authorization_list"),
+            Some(&ConfigChange {
+                field: "has_authorization_list",
+                old: ConfigValue::Bool(false),
+                new: ConfigValue::Bool(true),
+            })
+        );
+        assert!(diff.fields().any(|field| field == "has_floor_gas"));
+        assert_eq!(diff.get("gas_sstore_set"), None);
+        assert!(diff
+            .to_string()
+            .contains("has_authorization_list: false -> true\n"));
+
+        let config = Config {
+            max_transaction_gas: Some(1 << 24),
+            ..Config::osaka()
+        };
+        let diff = Config::osaka().diff(&config);
+        assert_eq!(diff.fields().collect::<Vec<_>>(), ["max_transaction_gas"]);
+        assert_eq!(diff.to_string(), "max_transaction_gas: none -> 16777216\n");
+    }
+
+    #[test]
+    fn test_presets_only_enable_features() {
+        // Features are never disabled by a later fork
+        let configs: Vec<_> = HardFork::ALL
+            .into_iter()
+            .filter_map(|fork| Some((fork, fork.gasometer_config()?)))
+            .collect();
+        for pair in configs.windows(2) {
+            let ((_, old), (fork, new)) = (&pair[0], &pair[1]);
+            for change in &old.diff(new).changes {
+                if change.field.starts_with("has_") {
+                    assert_eq!(
+                        change.new,
+                        ConfigValue::Bool(true),
+                        "{} disabled by {fork:?}",
+                        change.field,
+                    );
+                }
+            }
+        }
+    }
+}
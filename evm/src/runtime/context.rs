@@ -43,4 +43,9 @@ pub struct Context {
     pub caller: H160,
     /// Apparent value of the EVM.
     pub apparent_value: U256,
+    /// Address of the executed code. It differs from `address` for
+    /// `CALLCODE` and `DELEGATECALL`, which run the code of another account.
+    pub code_address: H160,
+    /// Scheme of the call, `None` for the init code of a contract creation.
+    pub scheme: Option<CallScheme>,
 }
@@ -17,7 +17,13 @@ pub enum Control<H: Handler> {
 
 fn handle_other<H: Handler>(state: &mut Runtime, opcode: Opcode, handler: &mut H) -> Control<H> {
     match handler.other(opcode, &mut state.machine) {
-        Ok(()) => Control::Continue,
+        Ok(()) => {
+            event!(CustomOpcode {
+                address: state.context.address,
+                opcode,
+            });
+            Control::Continue
+        }
         Err(e) => Control::Exit(e.into()),
     }
 }
@@ -1,12 +1,15 @@
 use super::Control;
+use crate::core::prelude::Cow;
 use crate::core::utils::{U256_ZERO, U64_MAX, USIZE_MAX};
 use crate::prelude::*;
 use crate::{
-    CallScheme, Capture, Context, CreateScheme, ExitError, ExitSucceed, Handler, Runtime, Transfer,
+    CallScheme, Capture, Context, CreateScheme, ExitError, ExitSucceed, Handler, LogRef, Runtime,
+    Transfer,
 };
 use core::cmp::max;
 use primitive_types::{H256, U256};
 use sha3::{Digest, Keccak256};
+use smallvec::SmallVec;
 
 pub fn sha3<H: Handler>(runtime: &mut Runtime) -> Control<H> {
     pop_u256!(runtime, from, len);
@@ -94,7 +97,9 @@ pub fn base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 /// CANCUN hard fork
 /// EIP-7516: BLOBBASEFEE opcode
 pub fn blob_base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
-    let blob_base_fee = U256::from(handler.blob_base_fee().unwrap_or_default());
+    let value = handler.blob_base_fee();
+    event!(BlobBaseFee { value });
+    let blob_base_fee = U256::from(value.unwrap_or_default());
     push_u256!(runtime, blob_base_fee);
     Control::Continue
 }
@@ -118,7 +123,9 @@ pub fn blob_hash<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
     // Get blob_hash from `tx.blob_versioned_hashes[index]`
     // as described:
     // - https://eips.ethereum.org/EIPS/eip-4844#opcode-to-get-versioned-hashes
-    let blob_hash = handler.get_blob_hash(index).unwrap_or(U256_ZERO);
+    let hash = handler.get_blob_hash(index);
+    event!(BlobHash { index, hash });
+    let blob_hash = hash.unwrap_or(U256_ZERO);
     // Set top stack index with `blob_hash` value
     if let Err(e) = runtime.machine.stack_mut().set(0, blob_hash) {
         return Control::Exit(e.into());
@@ -159,12 +166,20 @@ pub fn extcodecopy<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H>
         .machine
         .memory_mut()
         .resize_offset(memory_offset, len));
-    match runtime.machine.memory_mut().copy_data(
-        memory_offset,
-        code_offset,
-        len,
-        &handler.code(address.into()),
-    ) {
+
+    // Only fetch the copied part of the code. An offset beyond `usize` is always
+    // past the end of the code, so the destination is just zero-filled.
+    let code = if code_offset > USIZE_MAX {
+        Vec::new()
+    } else {
+        let code_offset = code_offset.as_usize();
+        handler.code_slice(address.into(), code_offset..code_offset.saturating_add(len))
+    };
+    match runtime
+        .machine
+        .memory_mut()
+        .copy_data(memory_offset, U256_ZERO, len, &code)
+    {
         Ok(()) => (),
         Err(e) => return Control::Exit(e.into()),
     }
@@ -303,6 +318,13 @@ pub fn tload<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
         Ok(value) => value,
         Err(e) => return Control::Exit(e.into()),
     };
+
+    event!(TLoad {
+        address: runtime.context.address,
+        index,
+        value
+    });
+
     // Set top stack index with `transient` value result
     match runtime.machine.stack_mut().set(0, value) {
         Ok(()) => (),
@@ -317,6 +339,13 @@ pub fn tload<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
 pub fn tstore<H: Handler>(runtime: &mut Runtime, handler: &mut H) -> Control<H> {
     pop_h256!(runtime, index);
     pop_u256!(runtime, value);
+
+    event!(TStore {
+        address: runtime.context.address,
+        index,
+        value
+    });
+
     match handler.tstore(runtime.context.address, index, value) {
         Ok(()) => Control::Continue,
         Err(e) => Control::Exit(e.into()),
@@ -366,13 +395,8 @@ pub fn log<H: Handler>(runtime: &mut Runtime, n: u8, handler: &mut H) -> Control
     let len = as_usize_or_fail!(len);
 
     try_or_fail!(runtime.machine.memory_mut().resize_offset(offset, len));
-    let data = if len == 0 {
-        Vec::new()
-    } else {
-        runtime.machine.memory().get(offset, len)
-    };
 
-    let mut topics = Vec::new();
+    let mut topics = SmallVec::<[H256; 4]>::new();
     for _ in 0..n {
         match runtime.machine.stack_mut().pop_h256() {
             Ok(value) => {
@@ -382,7 +406,17 @@ pub fn log<H: Handler>(runtime: &mut Runtime, n: u8, handler: &mut H) -> Control
         }
     }
 
-    match handler.log(runtime.context.address, topics, data) {
+    let data = if len == 0 {
+        Cow::Borrowed(&[][..])
+    } else {
+        runtime.machine.memory().get_cow(offset, len)
+    };
+    let log = LogRef {
+        address: runtime.context.address,
+        topics: &topics,
+        data,
+    };
+    match handler.log_ref(log) {
         Ok(()) => Control::Continue,
         Err(e) => Control::Exit(e.into()),
     }
@@ -432,7 +466,7 @@ pub fn create<H: Handler>(runtime: &mut Runtime, is_create2: bool, handler: &mut
 
     let scheme = if is_create2 {
         pop_h256!(runtime, salt);
-        let code_hash = H256::from_slice(<[u8; 32]>::from(Keccak256::digest(&code)).as_slice());
+        let code_hash = handler.init_code_hash(&code);
         CreateScheme::Create2 {
             caller: runtime.context.address,
             salt,
@@ -521,16 +555,22 @@ pub fn call<H: Handler>(runtime: &mut Runtime, scheme: CallScheme, handler: &mut
             address: to.into(),
             caller: runtime.context.address,
             apparent_value: value,
+            code_address: to.into(),
+            scheme: Some(scheme),
         },
         CallScheme::CallCode => Context {
             address: runtime.context.address,
             caller: runtime.context.address,
             apparent_value: value,
+            code_address: to.into(),
+            scheme: Some(scheme),
         },
         CallScheme::DelegateCall => Context {
             address: runtime.context.address,
             caller: runtime.context.caller,
             apparent_value: runtime.context.apparent_value,
+            code_address: to.into(),
+            scheme: Some(scheme),
         },
     };
 
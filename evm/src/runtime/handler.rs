@@ -1,6 +1,9 @@
+use crate::core::prelude::Cow;
 use crate::prelude::*;
-use crate::{Capture, Context, CreateScheme, ExitError, ExitReason, Machine, Opcode};
+use crate::{Capture, Context, CreateScheme, ExitError, ExitReason, Machine, Opcode, Stack};
+use core::ops::Range;
 use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
 
 /// Transfer from source to target, with given value.
 #[derive(Clone, Debug)]
@@ -13,6 +16,18 @@ pub struct Transfer {
     pub value: U256,
 }
 
+/// Log emitted by `LOG0`..`LOG4`, borrowing its topics and, when possible,
+/// its data from the machine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LogRef<'a> {
+    /// Address emitting the log.
+    pub address: H160,
+    /// Log topics.
+    pub topics: &'a [H256],
+    /// Log data.
+    pub data: Cow<'a, [u8]>,
+}
+
 /// EVM context handler.
 #[auto_impl::auto_impl(& mut, Box)]
 pub trait Handler {
@@ -31,8 +46,16 @@ pub trait Handler {
     fn code_size(&mut self, address: H160) -> U256;
     /// Get code hash of address.
     fn code_hash(&mut self, address: H160) -> H256;
+    /// Get the hash of the init code of a `CREATE2` deployment.
+    fn init_code_hash(&mut self, init_code: &[u8]) -> H256 {
+        H256::from_slice(<[u8; 32]>::from(Keccak256::digest(init_code)).as_slice())
+    }
     /// Get code of address.
     fn code(&self, address: H160) -> Vec<u8>;
+    /// Get the part of code of address within `range`, clamped to the code length.
+    fn code_slice(&self, address: H160, range: Range<usize>) -> Vec<u8> {
+        crate::backend::slice_code(&self.code(address), range)
+    }
     /// Get storage value of address at index.
     fn storage(&self, address: H160, index: H256) -> H256;
     /// Check if the storage of the address is empty.
@@ -90,6 +113,14 @@ pub trait Handler {
     /// # Errors
     /// Return `ExitError`
     fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError>;
+    /// Create a log from its borrowed parts, as emitted by `LOG0`..`LOG4`.
+    /// The default implementation copies the parts to [`Handler::log`].
+    ///
+    /// # Errors
+    /// Return `ExitError`
+    fn log_ref(&mut self, log: LogRef<'_>) -> Result<(), ExitError> {
+        self.log(log.address, log.topics.to_vec(), log.data.into_owned())
+    }
     /// Mark an address to be deleted, with funds transferred to target.
     ///
     /// # Errors
@@ -145,6 +176,16 @@ pub trait Handler {
     ) -> Result<(), ExitError> {
         Err(ExitError::InvalidCode(opcode))
     }
+    /// Gas cost of an opcode handled by [`Handler::other`], charged before
+    /// it is executed. `None` if the opcode is not handled, so it fails with
+    /// `InvalidCode`.
+    fn custom_opcode_cost(
+        &self,
+        #[allow(clippy::used_underscore_binding)] _opcode: Opcode,
+        #[allow(clippy::used_underscore_binding)] _stack: &Stack,
+    ) -> Option<u64> {
+        None
+    }
 
     /// Records some associated `ExternalOperation`.
     ///
@@ -0,0 +1,157 @@
+use crate::Config;
+
+/// Ethereum hard fork, in activation order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(
+    feature = "with-codec",
+    derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HardFork {
+    /// Activated at block 0.
+    Frontier,
+    /// Activated at block 1150000.
+    Homestead,
+    /// EIP-150, activated at block 2463000.
+    TangerineWhistle,
+    /// EIP-158, activated at block 2675000.
+    SpuriousDragon,
+    /// Activated at block 4370000.
+    Byzantium,
+    /// Never activated on mainnet, replaced by Petersburg at block 7280000.
+    Constantinople,
+    /// Activated at block 7280000.
+    Petersburg,
+    /// Activated at block 9069000.
+    Istanbul,
+    /// Activated at block 12244000.
+    Berlin,
+    /// Activated at block 12965000.
+    London,
+    /// Paris, activated at block 15537394.
+    Merge,
+    /// Activated at timestamp 1681338455.
+    Shanghai,
+    /// Activated at timestamp 1710338135.
+    Cancun,
+    /// Activated at timestamp 1746612311.
+    Prague,
+    /// Not activated yet.
+    Osaka,
+}
+
+impl HardFork {
+    /// All hard forks, in activation order.
+    pub const ALL: [Self; 15] = [
+        Self::Frontier,
+        Self::Homestead,
+        Self::TangerineWhistle,
+        Self::SpuriousDragon,
+        Self::Byzantium,
+        Self::Constantinople,
+        Self::Petersburg,
+        Self::Istanbul,
+        Self::Berlin,
+        Self::London,
+        Self::Merge,
+        Self::Shanghai,
+        Self::Cancun,
+        Self::Prague,
+        Self::Osaka,
+    ];
+
+    /// Latest hard fork.
+    pub const LATEST: Self = Self::Osaka;
+
+    /// Name of the hard fork, as used by the Ethereum tests.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Frontier => "Frontier",
+            Self::Homestead => "Homestead",
+            Self::TangerineWhistle => "EIP150",
+            Self::SpuriousDragon => "EIP158",
+            Self::Byzantium => "Byzantium",
+            Self::Constantinople => "Constantinople",
+            Self::Petersburg => "Petersburg",
+            Self::Istanbul => "Istanbul",
+            Self::Berlin => "Berlin",
+            Self::London => "London",
+            Self::Merge => "Merge",
+            Self::Shanghai => "Shanghai",
+            Self::Cancun => "Cancun",
+            Self::Prague => "Prague",
+            Self::Osaka => "Osaka",
+        }
+    }
+
+    /// Hard fork from its name, as returned by [`Self::name`], or one of the
+    /// common aliases: `TangerineWhistle`, `SpuriousDragon`,
+    /// `ConstantinopleFix` (Petersburg) and `Paris`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "TangerineWhistle" => Some(Self::TangerineWhistle),
+            "SpuriousDragon" => Some(Self::SpuriousDragon),
+            "ConstantinopleFix" => Some(Self::Petersburg),
+            "Paris" => Some(Self::Merge),
+            _ => Self::ALL.into_iter().find(|fork| fork.name() == name),
+        }
+    }
+
+    /// Gasometer config of the hard fork. Return `None` for hard forks
+    /// between Frontier and Istanbul, which have no config.
+    #[must_use]
+    pub const fn gasometer_config(self) -> Option<Config> {
+        match self {
+            Self::Frontier => Some(Config::frontier()),
+            Self::Homestead
+            | Self::TangerineWhistle
+            | Self::SpuriousDragon
+            | Self::Byzantium
+            | Self::Constantinople
+            | Self::Petersburg => None,
+            Self::Istanbul => Some(Config::istanbul()),
+            Self::Berlin => Some(Config::berlin()),
+            Self::London => Some(Config::london()),
+            Self::Merge => Some(Config::merge()),
+            Self::Shanghai => Some(Config::shanghai()),
+            Self::Cancun => Some(Config::cancun()),
+            Self::Prague => Some(Config::prague()),
+            Self::Osaka => Some(Config::osaka()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hard_fork_order_and_names() {
+        assert!(HardFork::ALL.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(HardFork::ALL.last(), Some(&HardFork::LATEST));
+        for fork in HardFork::ALL {
+            assert_eq!(HardFork::from_name(fork.name()), Some(fork));
+        }
+        assert_eq!(
+            HardFork::from_name("ConstantinopleFix"),
+            Some(HardFork::Petersburg)
+        );
+        assert_eq!(HardFork::from_name("Paris"), Some(HardFork::Merge));
+        assert_eq!(HardFork::from_name("Unknown"), None);
+    }
+
+    #[test]
+    fn test_hard_fork_gasometer_config() {
+        assert!(HardFork::Byzantium.gasometer_config().is_none());
+        let cancun = HardFork::Cancun.gasometer_config().unwrap();
+        assert!(cancun.has_transient_storage);
+        assert!(
+            !HardFork::Shanghai
+                .gasometer_config()
+                .unwrap()
+                .has_transient_storage
+        );
+    }
+}
@@ -25,16 +25,26 @@ macro_rules! event {
     ($x:expr) => {};
 }
 
+#[cfg(feature = "std")]
+mod cancellation;
+mod config_diff;
 mod context;
 mod eval;
 mod handler;
+mod hard_fork;
 mod interrupt;
+mod staged_opcode;
 
 pub use crate::core::*;
 
+#[cfg(feature = "std")]
+pub use self::cancellation::{CancellationToken, DEFAULT_CANCELLATION_STRIDE};
+pub use self::config_diff::{ConfigChange, ConfigDiff, ConfigValue};
 pub use self::context::{CallScheme, Context, CreateScheme};
-pub use self::handler::{Handler, Transfer};
+pub use self::handler::{Handler, LogRef, Transfer};
+pub use self::hard_fork::HardFork;
 pub use self::interrupt::{Resolve, ResolveCall, ResolveCreate};
+pub use self::staged_opcode::StagedOpcode;
 
 use prelude::*;
 use primitive_types::H160;
@@ -69,6 +79,37 @@ impl Runtime {
         }
     }
 
+    /// Create a new runtime with given code and data, and the jump
+    /// destinations of the code analyzed beforehand, e.g. restored with
+    /// [`Valids::from_cached`].
+    #[must_use]
+    pub fn new_with_valids(
+        code: Rc<Vec<u8>>,
+        data: Rc<Vec<u8>>,
+        valids: Valids,
+        context: Context,
+        stack_limit: usize,
+        memory_limit: usize,
+    ) -> Self {
+        Self {
+            machine: Machine::new_with_valids(code, data, valids, stack_limit, memory_limit),
+            return_data_buffer: Vec::new(),
+            return_data_len: 0,
+            return_data_offset: 0,
+            context,
+        }
+    }
+
+    /// Reset the runtime to run `code` with `data` in `context`, reusing
+    /// the machine allocations. Stack and memory limits are unchanged.
+    pub fn reset(&mut self, code: Rc<Vec<u8>>, data: Rc<Vec<u8>>, context: Context) {
+        self.machine.reset(code, data);
+        self.return_data_buffer.clear();
+        self.return_data_len = 0;
+        self.return_data_offset = 0;
+        self.context = context;
+    }
+
     /// Get a reference to the machine.
     #[must_use]
     pub const fn machine(&self) -> &Machine {
@@ -140,6 +181,40 @@ impl Runtime {
     }
 }
 
+/// Fee market of a chain.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeeMarket {
+    /// Legacy gas pricing: no base fee, the whole gas price is paid to the
+    /// block beneficiary. `BASEFEE` is not available.
+    Legacy,
+    /// EIP-1559: the base fee is burnt and only the priority fee is paid to
+    /// the block beneficiary.
+    Eip1559,
+    /// Chain-specific pricing implemented by the embedder. `BASEFEE` returns
+    /// the backend base fee, but it is not burnt by the fee helpers.
+    Custom,
+}
+
+/// Balance of an account sending a `SELFDESTRUCT` to itself when the
+/// account is not deleted, i.e. after EIP-6780 if it was not created in the
+/// same transaction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SelfDestructBalance {
+    /// The balance stays on the account, as specified by EIP-6780.
+    Retain,
+    /// The balance is burned, as it is when the account is deleted.
+    Burn,
+}
+
+/// Inconsistent configuration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigError {
+    /// `max_initcode_size` is lower than `create_contract_limit`.
+    InitCodeLimitBelowCodeLimit,
+    /// `max_initcode_size` is set while `create_contract_limit` is not.
+    InitCodeLimitWithoutCodeLimit,
+}
+
 /// Runtime configuration.
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Debug)]
@@ -166,10 +241,14 @@ pub struct Config {
     pub gas_suicide: u64,
     /// Gas paid for SUICIDE opcode when it hits a new account.
     pub gas_suicide_new_account: u64,
+    /// Gas refunded for SUICIDE opcode, removed by EIP-3529 in London.
+    pub gas_suicide_refund: i64,
     /// Gas paid for CALL opcode.
     pub gas_call: u64,
     /// Gas paid for EXP opcode for every byte.
     pub gas_expbyte: u64,
+    /// Gas paid per byte of code deployed by a contract creation.
+    pub gas_code_deposit: u64,
     /// Gas paid for a contract creation transaction.
     pub gas_transaction_create: u64,
     /// Gas paid for a message call transaction.
@@ -204,7 +283,9 @@ pub struct Config {
     pub err_on_call_with_more_gas: bool,
     /// Take l64 for callcreate after gas.
     pub call_l64_after_gas: bool,
-    /// Whether empty account is considered exists.
+    /// Whether empty account is considered exists. Before EIP-161 (Spurious
+    /// Dragon), touching an account, e.g. by a zero-value `CALL`, creates it
+    /// even if it stays empty, and empty accounts are never cleared.
     pub empty_considered_exists: bool,
     /// Whether create transactions and create opcode increases nonce by one.
     pub create_increase_nonce: bool,
@@ -214,14 +295,26 @@ pub struct Config {
     pub memory_limit: usize,
     /// Call limit.
     pub call_stack_limit: usize,
-    /// Create contract limit.
+    /// Maximum number of opcodes executed by a transaction or system call,
+    /// over all its call frames. `None` disables the limit.
+    pub step_limit: Option<u64>,
+    /// Create contract limit, see [EIP-170](https://eips.ethereum.org/EIPS/eip-170).
+    /// `None` disables the limit.
     pub create_contract_limit: Option<usize>,
-    /// EIP-3860, maximum size limit of `init_code`.
+    /// EIP-3860, maximum size limit of `init_code`. `None` disables the limit.
     pub max_initcode_size: Option<usize>,
+    /// EIP-3860, charge gas for every word of `init_code`. It is independent
+    /// of `max_initcode_size`, so the gas can be charged without a limit.
+    pub has_init_code_cost: bool,
+    /// Maximum gas limit of a transaction, independent of the block gas
+    /// limit. `None` disables the cap.
+    pub max_transaction_gas: Option<u64>,
     /// Call stipend.
     pub call_stipend: u64,
     /// Has delegate call.
     pub has_delegate_call: bool,
+    /// Has static call. See [EIP-214](https://eips.ethereum.org/EIPS/eip-214)
+    pub has_static_call: bool,
     /// Has create2.
     pub has_create2: bool,
     /// Has revert.
@@ -238,6 +331,9 @@ pub struct Config {
     pub has_ext_code_hash: bool,
     /// Has ext block fee. See [EIP-3198](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3198.md)
     pub has_base_fee: bool,
+    /// Fee market. `BASEFEE` is enabled only if `has_base_fee` is set and
+    /// the fee market is not [`FeeMarket::Legacy`].
+    pub fee_market: FeeMarket,
     /// Has PUSH0 opcode. See [EIP-3855](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3855.md)
     pub has_push0: bool,
     /// Whether the gasometer is running in estimate mode.
@@ -252,6 +348,9 @@ pub struct Config {
     pub has_mcopy: bool,
     /// SELFDESTRUCT restriction: EIP-6780
     pub has_restricted_selfdestruct: bool,
+    /// Balance of an account sending a `SELFDESTRUCT` to itself without
+    /// being deleted, see [`SelfDestructBalance`].
+    pub selfdestruct_to_self: SelfDestructBalance,
     /// EIP-7702
     pub has_authorization_list: bool,
     /// EIP-7939
@@ -282,8 +381,10 @@ impl Config {
             max_refund_quotient: 2,
             gas_suicide: 0,
             gas_suicide_new_account: 0,
+            gas_suicide_refund: 24000,
             gas_call: 40,
             gas_expbyte: 10,
+            gas_code_deposit: 200,
             gas_transaction_create: 21000,
             gas_transaction_call: 21000,
             gas_transaction_zero_data: 4,
@@ -305,10 +406,14 @@ impl Config {
             stack_limit: 1024,
             memory_limit: usize::MAX,
             call_stack_limit: 1024,
+            step_limit: None,
             create_contract_limit: None,
             max_initcode_size: None,
+            has_init_code_cost: false,
+            max_transaction_gas: None,
             call_stipend: 2300,
             has_delegate_call: false,
+            has_static_call: false,
             has_create2: false,
             has_revert: false,
             has_return_data: false,
@@ -317,6 +422,7 @@ impl Config {
             has_self_balance: false,
             has_ext_code_hash: false,
             has_base_fee: false,
+            fee_market: FeeMarket::Legacy,
             has_push0: false,
             estimate: false,
             has_blob_base_fee: false,
@@ -324,6 +430,7 @@ impl Config {
             has_transient_storage: false,
             has_mcopy: false,
             has_restricted_selfdestruct: false,
+            selfdestruct_to_self: SelfDestructBalance::Retain,
             has_authorization_list: false,
             has_clz: false,
             gas_per_empty_account_cost: 0,
@@ -348,8 +455,10 @@ impl Config {
             max_refund_quotient: 2,
             gas_suicide: 5000,
             gas_suicide_new_account: 25000,
+            gas_suicide_refund: 24000,
             gas_call: 700,
             gas_expbyte: 50,
+            gas_code_deposit: 200,
             gas_transaction_create: 53000,
             gas_transaction_call: 21000,
             gas_transaction_zero_data: 4,
@@ -371,10 +480,14 @@ impl Config {
             stack_limit: 1024,
             memory_limit: usize::MAX,
             call_stack_limit: 1024,
+            step_limit: None,
             create_contract_limit: Some(0x6000),
             max_initcode_size: None,
+            has_init_code_cost: false,
+            max_transaction_gas: None,
             call_stipend: 2300,
             has_delegate_call: true,
+            has_static_call: true,
             has_create2: true,
             has_revert: true,
             has_return_data: true,
@@ -383,6 +496,7 @@ impl Config {
             has_self_balance: true,
             has_ext_code_hash: true,
             has_base_fee: false,
+            fee_market: FeeMarket::Legacy,
             has_push0: false,
             estimate: false,
             has_blob_base_fee: false,
@@ -390,6 +504,7 @@ impl Config {
             has_transient_storage: false,
             has_mcopy: false,
             has_restricted_selfdestruct: false,
+            selfdestruct_to_self: SelfDestructBalance::Retain,
             has_authorization_list: false,
             has_clz: false,
             gas_per_auth_base_cost: 0,
@@ -429,6 +544,81 @@ impl Config {
         Self::config_with_derived_values(DerivedConfigInputs::cancun())
     }
 
+    /// Override the maximum size of deployed code, and of `init_code` if it
+    /// is limited, to twice the code size as defined by EIP-3860. `None`
+    /// disables both limits, while EIP-3860 gas is still charged if enabled.
+    #[must_use]
+    pub const fn with_max_code_size(mut self, limit: Option<usize>) -> Self {
+        self.create_contract_limit = limit;
+        if self.max_initcode_size.is_some() {
+            self.max_initcode_size = match limit {
+                Some(limit) => Some(limit.saturating_mul(2)),
+                None => None,
+            };
+        }
+        self
+    }
+
+    /// Bound the resources of the executions, for provers running the EVM
+    /// inside a zkVM guest, whose memory and trace length are fixed:
+    /// - the memory of a call frame is limited to 8 MiB, whose expansion
+    ///   alone costs more than 134M gas;
+    /// - the call stack is limited to 1024 frames, the mainnet limit;
+    /// - a transaction is limited to 2^27 interpreter steps, about 134M,
+    ///   more than its gas allows but for the steps costing no gas.
+    ///
+    /// The bounds are not reached by transactions with less gas, so the
+    /// profile executes them as the adjusted config does. Build the guest
+    /// with the `zkvm` feature to reject the features relying on the host,
+    /// e.g. threads or the thread-local tracing listener.
+    #[must_use]
+    pub const fn zk_profile(mut self) -> Self {
+        const MEMORY_LIMIT: usize = 8 * 1024 * 1024;
+        const CALL_STACK_LIMIT: usize = 1024;
+        const STEP_LIMIT: u64 = 1 << 27;
+        if self.memory_limit > MEMORY_LIMIT {
+            self.memory_limit = MEMORY_LIMIT;
+        }
+        if self.call_stack_limit > CALL_STACK_LIMIT {
+            self.call_stack_limit = CALL_STACK_LIMIT;
+        }
+        self.step_limit = match self.step_limit {
+            Some(limit) if limit < STEP_LIMIT => Some(limit),
+            _ => Some(STEP_LIMIT),
+        };
+        self
+    }
+
+    /// Check that the code size knobs are consistent.
+    ///
+    /// # Errors
+    /// Return `ConfigError` if the `init_code` limit is lower than the code
+    /// size limit, or if the `init_code` limit is set while the code size is
+    /// unlimited.
+    pub const fn validate(&self) -> Result<(), ConfigError> {
+        match (self.create_contract_limit, self.max_initcode_size) {
+            (Some(code_limit), Some(init_code_limit)) if init_code_limit < code_limit => {
+                Err(ConfigError::InitCodeLimitBelowCodeLimit)
+            }
+            (None, Some(_)) => Err(ConfigError::InitCodeLimitWithoutCodeLimit),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether touched empty accounts are deleted (EIP-161), i.e. the
+    /// `delete_empty` argument of [`crate::backend::ApplyBackend::apply`]
+    /// when applying the state changes of a transaction.
+    #[must_use]
+    pub const fn delete_empty_accounts(&self) -> bool {
+        !self.empty_considered_exists
+    }
+
+    /// Whether the `BASEFEE` opcode is enabled.
+    #[must_use]
+    pub const fn base_fee_enabled(&self) -> bool {
+        self.has_base_fee && !matches!(self.fee_market, FeeMarket::Legacy)
+    }
+
     /// Prague hard fork configuration.
     #[must_use]
     pub const fn prague() -> Self {
@@ -478,6 +668,7 @@ impl Config {
             15000
         };
         let max_refund_quotient = if decrease_clears_refund { 5 } else { 2 };
+        let gas_suicide_refund = if decrease_clears_refund { 0 } else { 24000 };
 
         Self {
             gas_ext_code: 0,
@@ -491,8 +682,10 @@ impl Config {
             max_refund_quotient,
             gas_suicide: 5000,
             gas_suicide_new_account: 25000,
+            gas_suicide_refund,
             gas_call: 0,
             gas_expbyte: 50,
+            gas_code_deposit: 200,
             gas_transaction_create: 53000,
             gas_transaction_call: 21000,
             gas_transaction_zero_data: 4,
@@ -514,10 +707,14 @@ impl Config {
             stack_limit: 1024,
             memory_limit: usize::MAX,
             call_stack_limit: 1024,
+            step_limit: None,
             create_contract_limit: Some(0x6000),
             max_initcode_size,
+            has_init_code_cost: max_initcode_size.is_some(),
+            max_transaction_gas: None,
             call_stipend: 2300,
             has_delegate_call: true,
+            has_static_call: true,
             has_create2: true,
             has_revert: true,
             has_return_data: true,
@@ -526,6 +723,11 @@ impl Config {
             has_self_balance: true,
             has_ext_code_hash: true,
             has_base_fee,
+            fee_market: if has_base_fee {
+                FeeMarket::Eip1559
+            } else {
+                FeeMarket::Legacy
+            },
             has_push0,
             estimate: false,
             has_blob_base_fee,
@@ -533,6 +735,7 @@ impl Config {
             has_transient_storage,
             has_mcopy,
             has_restricted_selfdestruct,
+            selfdestruct_to_self: SelfDestructBalance::Retain,
             has_authorization_list,
             has_clz,
             gas_per_empty_account_cost,
@@ -0,0 +1,124 @@
+//! Proposed opcodes which can be enabled on top of any preset, e.g. to trial
+//! them on a testnet before the hard fork scheduling them.
+//!
+//! Staging a new opcode takes:
+//! - a `has_*` flag on [`Config`], `false` in every preset until its fork,
+//!   and listed in the config diff;
+//! - its entry in the eval table, which only runs once the gasometer
+//!   accepted the opcode;
+//! - its arm in `gasometer::dynamic_opcode_cost`, priced if the flag is set
+//!   and `GasCost::Invalid` otherwise;
+//! - a [`StagedOpcode`] variant mapping it to the flag.
+
+use super::{Config, HardFork, Opcode};
+
+/// Opcode proposed by an EIP, enabled by its own `Config` flag.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "with-serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StagedOpcode {
+    /// Count leading zeros. See [EIP-7939](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7939.md)
+    Clz,
+}
+
+impl StagedOpcode {
+    /// All staged opcodes.
+    pub const ALL: [Self; 1] = [Self::Clz];
+
+    /// Opcode byte.
+    #[must_use]
+    pub const fn opcode(self) -> Opcode {
+        match self {
+            Self::Clz => Opcode::CLZ,
+        }
+    }
+
+    /// Number of the EIP proposing the opcode.
+    #[must_use]
+    pub const fn eip(self) -> u32 {
+        match self {
+            Self::Clz => 7939,
+        }
+    }
+
+    /// Hard fork whose preset enables the opcode, `None` if it is not
+    /// scheduled yet.
+    #[must_use]
+    pub const fn hard_fork(self) -> Option<HardFork> {
+        match self {
+            Self::Clz => Some(HardFork::Osaka),
+        }
+    }
+
+    /// Whether `config` enables the opcode.
+    #[must_use]
+    pub const fn is_enabled(self, config: &Config) -> bool {
+        match self {
+            Self::Clz => config.has_clz,
+        }
+    }
+
+    const fn flag(self, config: &mut Config) -> &mut bool {
+        match self {
+            Self::Clz => &mut config.has_clz,
+        }
+    }
+}
+
+impl Config {
+    /// Enable a proposed opcode, whatever the hard fork of the config. The
+    /// rest of the config is unchanged, so a network can run an upstream
+    /// preset with the opcode added.
+    #[must_use]
+    pub const fn with_staged_opcode(mut self, opcode: StagedOpcode) -> Self {
+        *opcode.flag(&mut self) = true;
+        self
+    }
+
+    /// Proposed opcodes the config enables.
+    pub fn staged_opcodes(&self) -> impl Iterator<Item = StagedOpcode> + '_ {
+        StagedOpcode::ALL
+            .into_iter()
+            .filter(|opcode| opcode.is_enabled(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::prelude::*;
+
+    #[test]
+    fn test_staged_opcode_on_preset() {
+        let config = Config::cancun().with_staged_opcode(StagedOpcode::Clz);
+        assert!(StagedOpcode::Clz.is_enabled(&config));
+        assert_eq!(
+            config.staged_opcodes().collect::<Vec<_>>(),
+            [StagedOpcode::Clz]
+        );
+        // Only the flag of the opcode differs from the preset
+        assert_eq!(
+            Config::cancun().diff(&config).fields().collect::<Vec<_>>(),
+            ["has_clz"]
+        );
+        assert_eq!(Config::cancun().staged_opcodes().count(), 0);
+    }
+
+    #[test]
+    fn test_staged_opcodes_enabled_by_their_fork() {
+        for opcode in StagedOpcode::ALL {
+            let Some(fork) = opcode.hard_fork() else {
+                continue;
+            };
+            for (other, config) in HardFork::ALL
+                .into_iter()
+                .filter_map(|other| Some((other, other.gasometer_config()?)))
+            {
+                assert_eq!(
+                    opcode.is_enabled(&config),
+                    other >= fork,
+                    "{opcode:?} in {other:?}"
+                );
+            }
+        }
+    }
+}
@@ -1,7 +1,11 @@
 //! Allows to listen to runtime events.
+//!
+//! Listeners are installed the same way as in [`crate::tracing`].
 
+use crate::core::prelude::{Cow, Vec};
 use crate::{Capture, ExitReason, Memory, Opcode, Stack, Trap};
-use primitive_types::{H160, H256};
+use core::ops::Range;
+use primitive_types::{H160, H256, U256};
 
 environmental::environmental!(listener: dyn EventListener + 'static);
 
@@ -9,6 +13,12 @@ pub trait EventListener {
     fn event(&mut self, event: Event<'_>);
 }
 
+impl<F: FnMut(Event<'_>)> EventListener for F {
+    fn event(&mut self, event: Event<'_>) {
+        self(event);
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Event<'a> {
     Step {
@@ -16,11 +26,12 @@ pub enum Event<'a> {
         opcode: Opcode,
         position: &'a Result<usize, ExitReason>,
         stack: &'a Stack,
-        memory: &'a Memory,
+        memory: MemoryView<'a>,
     },
     StepResult {
         result: &'a Result<(), Capture<ExitReason, Trap>>,
         return_value: &'a [u8],
+        return_range: &'a Range<U256>,
     },
     SLoad {
         address: H160,
@@ -32,6 +43,103 @@ pub enum Event<'a> {
         index: H256,
         value: H256,
     },
+    TLoad {
+        address: H160,
+        index: H256,
+        value: U256,
+    },
+    TStore {
+        address: H160,
+        index: H256,
+        value: U256,
+    },
+    BlobHash {
+        index: usize,
+        hash: Option<U256>,
+    },
+    BlobBaseFee {
+        value: Option<u128>,
+    },
+    /// A chain-specific opcode was executed by `Handler::other`.
+    CustomOpcode {
+        address: H160,
+        opcode: Opcode,
+    },
+}
+
+/// Memory of a [`Event::Step`], copied only on access.
+///
+/// Tracers keeping the memory of every step, e.g. for `structLogs`, can
+/// update their copy from the previous step of the frame with
+/// [`Self::update_snapshot`], instead of copying the whole memory.
+#[derive(Debug, Copy, Clone)]
+pub struct MemoryView<'a> {
+    memory: &'a Memory,
+}
+
+impl<'a> MemoryView<'a> {
+    #[must_use]
+    pub const fn new(memory: &'a Memory) -> Self {
+        Self { memory }
+    }
+
+    /// Underlying memory.
+    #[must_use]
+    pub const fn memory(self) -> &'a Memory {
+        self.memory
+    }
+
+    /// Size of the memory, as returned by `MSIZE`.
+    #[must_use]
+    pub const fn len(self) -> usize {
+        self.memory.effective_len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes of `range`, zero beyond the written memory. Borrowed if the
+    /// whole range was written.
+    #[must_use]
+    pub fn slice(self, range: Range<usize>) -> Cow<'a, [u8]> {
+        self.memory.get_cow(range.start, range.len())
+    }
+
+    /// 32-byte words of the memory, as listed by `structLogs`.
+    pub fn words(self) -> impl Iterator<Item = Cow<'a, [u8]>> {
+        (0..self.len())
+            .step_by(32)
+            .map(move |offset| self.slice(offset..offset + 32))
+    }
+
+    /// Ranges written since the previous step of the frame, in write order.
+    /// Writes by a returning call are reported with the next step of its
+    /// caller.
+    #[must_use]
+    pub fn changes(self) -> &'a [Range<usize>] {
+        self.memory.changes()
+    }
+
+    /// Update `snapshot`, the memory at the previous step of the frame, to
+    /// the memory at this step: resize it, then copy the changed ranges.
+    pub fn update_snapshot(self, snapshot: &mut Vec<u8>) {
+        snapshot.resize(self.len(), 0);
+        for range in self.changes() {
+            let bytes = self.slice(range.clone());
+            if snapshot.len() < range.end {
+                snapshot.resize(range.end, 0);
+            }
+            snapshot[range.clone()].copy_from_slice(&bytes);
+        }
+    }
+
+    /// Copy of the whole memory.
+    #[must_use]
+    pub fn to_vec(self) -> Vec<u8> {
+        self.slice(0..self.len()).into_owned()
+    }
 }
 
 // Expose `listener::with` to allow flexible tracing.
@@ -43,3 +151,142 @@ pub fn with<F: FnOnce(&mut (dyn EventListener + 'static))>(f: F) {
 pub fn using<R, F: FnOnce() -> R>(new: &mut (dyn EventListener + 'static), f: F) -> R {
     listener::using(new, f)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::executor::stack::StackExecutorBuilder;
+    use crate::prelude::*;
+    use crate::Config;
+
+    #[test]
+    fn test_transient_storage_and_blob_events() {
+        let contract = H160::from_low_u64_be(2);
+        // TSTORE(0, 1), TLOAD(0), BLOBHASH(0), BLOBBASEFEE
+        let code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x5d, 0x60, 0x00, 0x5c, 0x50, 0x60, 0x00, 0x49, 0x50, 0x4a,
+            0x00,
+        ];
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code,
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&backend)
+            .build();
+        executor.set_tx_env(TxEnv {
+            blob_hashes: Vec::from([U256::from(7)]),
+            ..TxEnv::default()
+        });
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut listener = {
+            let events = events.clone();
+            move |event: Event<'_>| {
+                let event = match event {
+                    Event::TLoad { index, value, .. } => Some(("TLoad", index, value)),
+                    Event::TStore { index, value, .. } => Some(("TStore", index, value)),
+                    Event::BlobHash { hash, .. } => {
+                        Some(("BlobHash", H256::zero(), hash.unwrap_or_default()))
+                    }
+                    Event::BlobBaseFee { .. } => Some(("BlobBaseFee", H256::zero(), U256::zero())),
+                    _ => None,
+                };
+                events.borrow_mut().extend(event);
+            }
+        };
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                H160::from_low_u64_be(1),
+                contract,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        assert!(reason.is_succeed());
+        assert_eq!(
+            *events.borrow(),
+            [
+                ("TStore", H256::zero(), U256::one()),
+                ("TLoad", H256::zero(), U256::one()),
+                ("BlobHash", H256::zero(), U256::from(7)),
+                ("BlobBaseFee", H256::zero(), U256::zero()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memory_view() {
+        let contract = H160::from_low_u64_be(2);
+        // MSTORE(0, 1), MSTORE8(40, 0xff), MCOPY(64, 0, 32)
+        let code = vec![
+            0x60, 0x01, 0x60, 0x00, 0x52, 0x60, 0xff, 0x60, 0x28, 0x53, 0x60, 0x20, 0x60, 0x00,
+            0x60, 0x40, 0x5e, 0x00,
+        ];
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(
+            &vicinity,
+            BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    code,
+                    ..MemoryAccount::default()
+                },
+            )]),
+        );
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&backend)
+            .build();
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let mut snapshot = Vec::new();
+        let mut listener = {
+            let changes = changes.clone();
+            move |event: Event<'_>| {
+                if let Event::Step { memory, .. } = event {
+                    memory.update_snapshot(&mut snapshot);
+                    assert_eq!(snapshot, memory.to_vec());
+                    assert_eq!(memory.words().count(), memory.len() / 32);
+                    if !memory.changes().is_empty() {
+                        changes.borrow_mut().push(memory.changes().to_vec());
+                    }
+                }
+            }
+        };
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                H160::from_low_u64_be(1),
+                contract,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        assert!(reason.is_succeed());
+        assert_eq!(*changes.borrow(), [[0..32], [40..41], [64..96]]);
+
+        let mut memory = Memory::new(usize::MAX);
+        memory.resize_offset(0, 40).unwrap();
+        memory.set(0, &[1, 2], 2).unwrap();
+        let view = MemoryView::new(&memory);
+        assert_eq!(view.len(), 64);
+        assert!(matches!(view.slice(0..2), Cow::Borrowed([1, 2])));
+        assert_eq!(*view.slice(1..4), [2, 0, 0]);
+        assert_eq!(view.changes(), [0..2]);
+    }
+}
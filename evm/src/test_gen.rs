@@ -0,0 +1,338 @@
+//! Deterministic pseudo-random generator of valid but adversarial bytecode,
+//! used as fuzzing corpus seeds and in benches of the executor.
+//!
+//! The same seed always generates the same programs, so a failing program
+//! can be reproduced from its seed alone.
+
+use crate::prelude::*;
+use crate::Opcode;
+
+/// Stack depth never exceeded by the generated programs.
+pub const MAX_STACK_DEPTH: usize = 1024;
+/// Maximum number of operations of the init code of [`TestGen::create_loop`].
+const MAX_INIT_CODE_OPS: usize = 32;
+
+/// Opcodes popping two values and pushing one.
+const BINARY_OPS: [Opcode; 21] = [
+    Opcode::ADD,
+    Opcode::MUL,
+    Opcode::SUB,
+    Opcode::DIV,
+    Opcode::SDIV,
+    Opcode::MOD,
+    Opcode::SMOD,
+    Opcode::EXP,
+    Opcode::SIGNEXTEND,
+    Opcode::LT,
+    Opcode::GT,
+    Opcode::SLT,
+    Opcode::SGT,
+    Opcode::EQ,
+    Opcode::AND,
+    Opcode::OR,
+    Opcode::XOR,
+    Opcode::BYTE,
+    Opcode::SHL,
+    Opcode::SHR,
+    Opcode::SAR,
+];
+/// Opcodes popping three values and pushing one.
+const TERNARY_OPS: [Opcode; 2] = [Opcode::ADDMOD, Opcode::MULMOD];
+/// Opcodes popping one value and pushing one.
+const UNARY_OPS: [Opcode; 2] = [Opcode::ISZERO, Opcode::NOT];
+
+/// Kind of program generated by [`TestGen::program`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProgramKind {
+    /// See [`TestGen::balanced_stack_ops`].
+    BalancedStackOps,
+    /// See [`TestGen::deep_calls`].
+    DeepCalls,
+    /// See [`TestGen::create_loop`].
+    CreateLoop,
+}
+
+/// Deterministic bytecode generator, seeded with a `u64`.
+#[derive(Clone, Debug)]
+pub struct TestGen {
+    state: u64,
+}
+
+impl TestGen {
+    /// Generator of the programs of `seed`.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next pseudo-random number, from the `SplitMix64` sequence.
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Pseudo-random number in `0..bound`, with `bound` greater than zero.
+    fn below(&mut self, bound: usize) -> usize {
+        let bound = u64::try_from(bound).unwrap_or(u64::MAX);
+        usize::try_from(self.next_u64() % bound).unwrap_or_default()
+    }
+
+    /// Pseudo-random element of `items`.
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    /// Generate a program of a pseudo-random kind.
+    pub fn program(&mut self) -> (ProgramKind, Vec<u8>) {
+        match self.below(3) {
+            0 => {
+                let ops = self.below(512) + 1;
+                (ProgramKind::BalancedStackOps, self.balanced_stack_ops(ops))
+            }
+            1 => (ProgramKind::DeepCalls, self.deep_calls()),
+            _ => {
+                let iterations = u8::try_from(self.below(32) + 1).unwrap_or(u8::MAX);
+                (ProgramKind::CreateLoop, self.create_loop(iterations))
+            }
+        }
+    }
+
+    /// Generate a program of `ops` arithmetic, comparison, bitwise, `PUSH`,
+    /// `DUP`, `SWAP` and `POP` operations, on edge case values, never
+    /// underflowing the stack and leaving it empty before `STOP`.
+    pub fn balanced_stack_ops(&mut self, ops: usize) -> Vec<u8> {
+        let mut code = Vec::new();
+        self.append_balanced_stack_ops(&mut code, ops);
+        code.push(Opcode::STOP.0);
+        code
+    }
+
+    /// Generate a program calling itself recursively with all the gas,
+    /// with `CALL`, `CALLCODE`, `DELEGATECALL` or `STATICCALL`, until the
+    /// call depth limit or the gas runs out. The frames run balanced stack
+    /// operations around the call.
+    pub fn deep_calls(&mut self) -> Vec<u8> {
+        let mut code = Vec::new();
+        let ops = self.below(16);
+        self.append_balanced_stack_ops(&mut code, ops);
+
+        let call = self.pick(&[
+            Opcode::CALL,
+            Opcode::CALLCODE,
+            Opcode::DELEGATECALL,
+            Opcode::STATICCALL,
+        ]);
+        // Output and input ranges
+        for _ in 0..4 {
+            push1(&mut code, 0);
+        }
+        if call == Opcode::CALL || call == Opcode::CALLCODE {
+            // Value
+            push1(&mut code, 0);
+        }
+        code.push(Opcode::ADDRESS.0);
+        code.push(Opcode::GAS.0);
+        code.push(call.0);
+        code.push(Opcode::POP.0);
+
+        let ops = self.below(16);
+        self.append_balanced_stack_ops(&mut code, ops);
+        code.push(Opcode::STOP.0);
+        code
+    }
+
+    /// Generate a program creating `iterations` contracts in a loop, with
+    /// `CREATE` or with `CREATE2` salted by the loop counter. The init code
+    /// runs balanced stack operations and deploys empty code. Zero
+    /// iterations are run as one.
+    pub fn create_loop(&mut self, iterations: u8) -> Vec<u8> {
+        let ops = self.below(MAX_INIT_CODE_OPS);
+        let init_code = self.balanced_stack_ops(ops);
+
+        // Store the init code in memory
+        let mut code = Vec::new();
+        for (index, chunk) in init_code.chunks(32).enumerate() {
+            code.push(Opcode::PUSH32.0);
+            code.extend_from_slice(chunk);
+            code.resize(code.len() + 32 - chunk.len(), 0);
+            push2(&mut code, index * 32);
+            code.push(Opcode::MSTORE.0);
+        }
+
+        // Loop counter
+        push1(&mut code, iterations.max(1));
+        let loop_start = code.len();
+        code.push(Opcode::JUMPDEST.0);
+        let create2 = self.below(2) == 0;
+        if create2 {
+            // Salt
+            code.push(Opcode::DUP1.0);
+        }
+        push2(&mut code, init_code.len());
+        // Offset and value
+        push1(&mut code, 0);
+        push1(&mut code, 0);
+        code.push(
+            if create2 {
+                Opcode::CREATE2
+            } else {
+                Opcode::CREATE
+            }
+            .0,
+        );
+        code.push(Opcode::POP.0);
+
+        // Decrement the counter and loop while it is not zero
+        push1(&mut code, 1);
+        code.push(Opcode::SWAP1.0);
+        code.push(Opcode::SUB.0);
+        code.push(Opcode::DUP1.0);
+        push2(&mut code, loop_start);
+        code.push(Opcode::JUMPI.0);
+        code.push(Opcode::POP.0);
+        code.push(Opcode::STOP.0);
+        code
+    }
+
+    fn append_balanced_stack_ops(&mut self, code: &mut Vec<u8>, ops: usize) {
+        let mut depth = 0;
+        for _ in 0..ops {
+            let choice = self.below(10);
+            if depth < 2 || (choice < 3 && depth < MAX_STACK_DEPTH) {
+                self.push_value(code);
+                depth += 1;
+                continue;
+            }
+            match choice {
+                3 | 4 => {
+                    code.push(self.pick(&BINARY_OPS).0);
+                    depth -= 1;
+                }
+                5 if depth >= 3 => {
+                    code.push(self.pick(&TERNARY_OPS).0);
+                    depth -= 2;
+                }
+                6 if depth < MAX_STACK_DEPTH => {
+                    let n = self.below(depth.min(16)) + 1;
+                    code.push(Opcode::DUP1.0 + u8::try_from(n - 1).unwrap_or_default());
+                    depth += 1;
+                }
+                7 => {
+                    let n = self.below((depth - 1).min(16)) + 1;
+                    code.push(Opcode::SWAP1.0 + u8::try_from(n - 1).unwrap_or_default());
+                }
+                8 => {
+                    code.push(Opcode::POP.0);
+                    depth -= 1;
+                }
+                _ => code.push(self.pick(&UNARY_OPS).0),
+            }
+        }
+        code.resize(code.len() + depth, Opcode::POP.0);
+    }
+
+    /// Push an edge case or random value, with a random `PUSH` width.
+    fn push_value(&mut self, code: &mut Vec<u8>) {
+        let width = self.below(32) + 1;
+        code.push(Opcode::PUSH1.0 + u8::try_from(width - 1).unwrap_or_default());
+        let start = code.len();
+        match self.below(5) {
+            0 => code.resize(start + width, 0),
+            1 => {
+                code.resize(start + width, 0);
+                code[start + width - 1] = 1;
+            }
+            2 => code.resize(start + width, 0xff),
+            3 => {
+                code.resize(start + width, 0);
+                code[start] = 0x80;
+            }
+            _ => {
+                while code.len() < start + width {
+                    let bytes = self.next_u64().to_be_bytes();
+                    let len = bytes.len().min(start + width - code.len());
+                    code.extend_from_slice(&bytes[..len]);
+                }
+            }
+        }
+    }
+}
+
+/// Generate `count` programs of `seed`.
+#[must_use]
+pub fn corpus(seed: u64, count: usize) -> Vec<Vec<u8>> {
+    let mut generator = TestGen::new(seed);
+    (0..count).map(|_| generator.program().1).collect()
+}
+
+fn push1(code: &mut Vec<u8>, value: u8) {
+    code.push(Opcode::PUSH1.0);
+    code.push(value);
+}
+
+/// Push a code offset or length, which fit in two bytes for the generated
+/// programs.
+fn push2(code: &mut Vec<u8>, value: usize) {
+    code.push(Opcode::PUSH2.0);
+    code.extend_from_slice(&u16::try_from(value).unwrap_or(u16::MAX).to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::executor::stack::StackExecutorBuilder;
+    use crate::{Config, ExitReason, ExitSucceed};
+    use primitive_types::{H160, U256};
+
+    fn run(code: Vec<u8>) -> ExitReason {
+        let contract = H160::from_low_u64_be(0xc0);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let state = BTreeMap::from([(
+            contract,
+            MemoryAccount {
+                code,
+                ..MemoryAccount::default()
+            },
+        )]);
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 10_000_000)
+            .backend(&backend)
+            .build();
+        executor
+            .transact_call(
+                H160::from_low_u64_be(1),
+                contract,
+                U256::zero(),
+                Vec::new(),
+                10_000_000,
+                Vec::new(),
+                Vec::new(),
+            )
+            .0
+    }
+
+    #[test]
+    fn test_corpus_is_deterministic() {
+        assert_eq!(corpus(42, 16), corpus(42, 16));
+        assert_ne!(corpus(42, 16), corpus(43, 16));
+        assert_eq!(TestGen::new(7).next_u64(), TestGen::new(7).next_u64());
+    }
+
+    #[test]
+    fn test_programs_run_to_completion() {
+        let mut generator = TestGen::new(0x5eed);
+        for _ in 0..64 {
+            let (kind, code) = generator.program();
+            assert_eq!(
+                run(code.clone()),
+                ExitReason::Succeed(ExitSucceed::Stopped),
+                "{kind:?} program {code:02x?}"
+            );
+        }
+    }
+}
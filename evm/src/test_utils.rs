@@ -0,0 +1,29 @@
+//! Helpers shared by the unit tests of the crate.
+
+use crate::prelude::*;
+use crate::{CallScheme, Context};
+use primitive_types::{H160, U256};
+
+/// Context of a plain call to `address`, without value.
+pub fn context(address: H160) -> Context {
+    Context {
+        address,
+        caller: H160::zero(),
+        apparent_value: U256::zero(),
+        code_address: address,
+        scheme: Some(CallScheme::Call),
+    }
+}
+
+/// Lowercase hex encoding of `bytes`.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Bytes of the hex string `hex`, which has an even length.
+pub fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
@@ -1,5 +1,16 @@
 //! Allows to listen to runtime events.
+//!
+//! A listener is installed for the duration of a closure with [`using`], and
+//! any `FnMut(Event)` closure can be used as a listener. With the `std`
+//! feature the listener is stored in a thread-local, without it in a global
+//! slot, so the `tracing` feature is also available on `no_std` targets such
+//! as embedded or zkVM guests, which run a single thread.
+//!
+//! Listeners can also be given to a single executor with
+//! [`crate::executor::stack::StackExecutor::set_event_listeners`], so that
+//! executors sharing a thread don't observe each other's events.
 
+use crate::prelude::*;
 use crate::runtime::{CreateScheme, ExitReason, Transfer};
 use crate::Context;
 use primitive_types::{H160, H256, U256};
@@ -10,6 +21,12 @@ pub trait EventListener {
     fn event(&mut self, event: Event<'_>);
 }
 
+impl<F: FnMut(Event<'_>)> EventListener for F {
+    fn event(&mut self, event: Event<'_>) {
+        self(event);
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Event<'a> {
     Call {
@@ -33,13 +50,31 @@ pub enum Event<'a> {
         target: H160,
         balance: U256,
     },
+    /// A `SELFDESTRUCT` of `address` named itself as the target, before the
+    /// `Suicide` event if the account is deleted or its balance burned.
+    /// `burned` is whether its `balance` is burned, which is always the case
+    /// when the account is deleted, and otherwise, after EIP-6780, depends on
+    /// `Config::selfdestruct_to_self`.
+    SelfDestructToSelf {
+        address: H160,
+        balance: U256,
+        burned: bool,
+    },
+    /// A contract was deployed, with `code_hash` the hash of its `code`.
     CreateOutput {
         address: H160,
         code: &'a [u8],
+        code_hash: H256,
     },
+    /// A call frame or transaction exited. `gas_used` and `gas_remaining`
+    /// are the gas of the exited frame, excluding the intrinsic gas of the
+    /// transaction. Both are zero if the frame failed before gas was given
+    /// to it.
     Exit {
         reason: &'a ExitReason,
         return_value: &'a [u8],
+        gas_used: u64,
+        gas_remaining: u64,
     },
     TransactCall {
         caller: H160,
@@ -71,6 +106,33 @@ pub enum Event<'a> {
         is_static: bool,
         context: &'a Context,
     },
+    /// An account which did not exist is created by a value transfer or,
+    /// before EIP-161, by a call touching it, after the `Call` or `Suicide`
+    /// event creating it. `surcharge` is the gas the creation cost on top of
+    /// the `CALL` or `SELFDESTRUCT`. The creation is reverted with the frame.
+    NewAccountCreated {
+        address: H160,
+        cause: NewAccountCause,
+        surcharge: u64,
+    },
+    /// An `EIP-7702` authorization was applied: the code of `authority` is
+    /// set to a delegation to `address`, or cleared for the zero address.
+    ApplyAuthorization {
+        authority: H160,
+        address: H160,
+        nonce: u64,
+    },
+}
+
+/// Cause of a [`Event::NewAccountCreated`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NewAccountCause {
+    /// Called by the transaction, which pays no surcharge.
+    Transaction,
+    /// Called by a `CALL`.
+    Call,
+    /// Beneficiary of a `SELFDESTRUCT`.
+    Suicide,
 }
 
 // Expose `listener::with` to the crate only.
@@ -82,3 +144,398 @@ pub(crate) fn with<F: FnOnce(&mut (dyn EventListener + 'static))>(f: F) {
 pub fn using<R, F: FnOnce() -> R>(new: &mut (dyn EventListener + 'static), f: F) -> R {
     listener::using(new, f)
 }
+
+/// Listeners of the executor, interpreter and gasometer events of a single
+/// executor, installed for the duration of each of its transactions in place
+/// of the listeners given to [`using`].
+#[derive(Default)]
+pub struct EventListeners {
+    /// Listener of the executor events.
+    pub evm: Option<Box<dyn EventListener>>,
+    /// Listener of the interpreter events, see [`crate::runtime::tracing`].
+    pub runtime: Option<Box<dyn crate::runtime::tracing::EventListener>>,
+    /// Listener of the gasometer events, see [`crate::gasometer::tracing`].
+    pub gasometer: Option<Box<dyn crate::gasometer::tracing::EventListener>>,
+}
+
+impl EventListeners {
+    /// No listeners.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            evm: None,
+            runtime: None,
+            gasometer: None,
+        }
+    }
+
+    /// Whether any listener is set.
+    #[must_use]
+    pub const fn is_set(&self) -> bool {
+        self.evm.is_some() || self.runtime.is_some() || self.gasometer.is_some()
+    }
+
+    /// Run `f` with the listeners installed.
+    pub fn using<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let Self {
+            evm,
+            runtime,
+            gasometer,
+        } = self;
+        let f = move || match gasometer.as_deref_mut() {
+            Some(listener) => crate::gasometer::tracing::using(listener, f),
+            None => f(),
+        };
+        let f = move || match runtime.as_deref_mut() {
+            Some(listener) => crate::runtime::tracing::using(listener, f),
+            None => f(),
+        };
+        match evm.as_deref_mut() {
+            Some(listener) => using(listener, f),
+            None => f(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BlockEnv, MemoryAccount, MemoryBackend, MemoryVicinity, TxEnv};
+    use crate::executor::stack::{Authorization, StackExecutor, StackExecutorBuilder};
+    use crate::prelude::*;
+    use crate::Config;
+
+    /// Listener recording the events picked by `pick`, and its record.
+    fn recorder<T: 'static>(
+        pick: impl Fn(Event<'_>) -> Option<T> + 'static,
+    ) -> (Rc<RefCell<Vec<T>>>, impl FnMut(Event<'_>) + 'static) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let record = events.clone();
+        let listener = move |event: Event<'_>| record.borrow_mut().extend(pick(event));
+        (events, listener)
+    }
+
+    #[test]
+    fn test_closure_listener() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&backend)
+            .build();
+
+        let (events, mut listener) = recorder(|event| {
+            Some(match event {
+                Event::TransactCall { .. } => "TransactCall",
+                Event::Call { .. } => "Call",
+                Event::Exit { .. } => "Exit",
+                _ => "Other",
+            })
+        });
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                H160::from_low_u64_be(1),
+                H160::from_low_u64_be(2),
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        assert!(reason.is_succeed());
+        assert_eq!(*events.borrow(), ["TransactCall", "Call", "Exit"]);
+    }
+
+    #[test]
+    fn test_executor_listeners() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::cancun();
+        let new_executor = || {
+            StackExecutorBuilder::new(&config, 100_000)
+                .backend(&backend)
+                .build()
+        };
+        let transact_calls = || {
+            recorder(|event| match event {
+                Event::TransactCall { address, .. } => Some(address),
+                _ => None,
+            })
+        };
+
+        let (first_events, first_listener) = transact_calls();
+        let mut first = new_executor();
+        first.set_event_listeners(EventListeners {
+            evm: Some(Box::new(first_listener)),
+            ..EventListeners::new()
+        });
+        let (second_events, second_listener) = transact_calls();
+        let mut second = new_executor();
+        second.set_event_listeners(EventListeners {
+            evm: Some(Box::new(second_listener)),
+            ..EventListeners::new()
+        });
+
+        let call = |executor: &mut StackExecutor<'_, '_, _, ()>, address| {
+            let (reason, _) = executor.transact_call(
+                H160::from_low_u64_be(1),
+                address,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            );
+            assert!(reason.is_succeed());
+        };
+        call(&mut first, H160::from_low_u64_be(2));
+        call(&mut second, H160::from_low_u64_be(3));
+        call(&mut first, H160::from_low_u64_be(4));
+
+        assert_eq!(
+            *first_events.borrow(),
+            [H160::from_low_u64_be(2), H160::from_low_u64_be(4)]
+        );
+        assert_eq!(*second_events.borrow(), [H160::from_low_u64_be(3)]);
+    }
+
+    #[test]
+    fn test_exit_events_report_frame_gas() {
+        let outer = H160::from_low_u64_be(0xc1);
+        let inner = H160::from_low_u64_be(0xc2);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let state = BTreeMap::from([
+            (
+                outer,
+                // CALL(1000, 0xc2, 0, 0, 0, 0, 0)
+                MemoryAccount {
+                    code: vec![
+                        0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0xc2,
+                        0x61, 0x03, 0xe8, 0xf1, 0x00,
+                    ],
+                    ..MemoryAccount::default()
+                },
+            ),
+            (
+                inner,
+                // RETURN(0, 0)
+                MemoryAccount {
+                    code: vec![0x60, 0x00, 0x60, 0x00, 0xf3],
+                    ..MemoryAccount::default()
+                },
+            ),
+        ]);
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::cancun();
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&backend)
+            .build();
+
+        let (exits, mut listener) = recorder(|event| match event {
+            Event::Exit {
+                gas_used,
+                gas_remaining,
+                ..
+            } => Some((gas_used, gas_remaining)),
+            _ => None,
+        });
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                H160::from_low_u64_be(1),
+                outer,
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        assert!(reason.is_succeed());
+        let exits = exits.borrow();
+        assert_eq!(exits.len(), 2);
+        assert_eq!(exits[0], (6, 994));
+        let (outer_used, outer_remaining) = exits[1];
+        assert!(outer_used > 1_000);
+        assert_eq!(outer_used + outer_remaining, 100_000 - 21_000);
+    }
+
+    #[test]
+    fn test_apply_authorization_event() {
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+        let config = Config::prague();
+        let mut executor = StackExecutorBuilder::new(&config, 100_000)
+            .backend(&backend)
+            .build();
+        let authority = H160::from_low_u64_be(0xa1);
+        let delegate = H160::from_low_u64_be(0xde);
+
+        let (applied, mut listener) = recorder(|event| match event {
+            Event::ApplyAuthorization {
+                authority,
+                address,
+                nonce,
+            } => Some((authority, address, nonce)),
+            _ => None,
+        });
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                H160::from_low_u64_be(1),
+                H160::from_low_u64_be(2),
+                U256::zero(),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                vec![
+                    Authorization::new(authority, delegate, 0, true),
+                    // Skipped: the nonce of the authority is 1 by now
+                    Authorization::new(authority, H160::zero(), 0, true),
+                ],
+            )
+        });
+        assert!(reason.is_succeed());
+        assert_eq!(*applied.borrow(), [(authority, delegate, 0)]);
+    }
+
+    #[test]
+    fn test_new_account_created_event() {
+        let caller = H160::from_low_u64_be(1);
+        let contract = H160::from_low_u64_be(0xc1);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        let state = BTreeMap::from([
+            (
+                caller,
+                MemoryAccount {
+                    balance: U256::from(10),
+                    ..MemoryAccount::default()
+                },
+            ),
+            (
+                contract,
+                // CALL(gas, 0xd1, 1, 0, 0, 0, 0), CALL(gas, 0xd1, 1, 0, 0, 0, 0),
+                // SELFDESTRUCT(0xd2)
+                MemoryAccount {
+                    code: vec![
+                        0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x60, 0xd1,
+                        0x5a, 0xf1, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x01,
+                        0x60, 0xd1, 0x5a, 0xf1, 0x60, 0xd2, 0xff,
+                    ],
+                    ..MemoryAccount::default()
+                },
+            ),
+        ]);
+        let backend = MemoryBackend::new(&vicinity, state);
+        let config = Config::shanghai();
+        let mut executor = StackExecutorBuilder::new(&config, 200_000)
+            .backend(&backend)
+            .build();
+
+        let (created, mut listener) = recorder(|event| match event {
+            Event::NewAccountCreated {
+                address,
+                cause,
+                surcharge,
+            } => Some((address, cause, surcharge)),
+            _ => None,
+        });
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                caller,
+                contract,
+                U256::from(5),
+                Vec::new(),
+                200_000,
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        assert!(reason.is_succeed());
+        // The contract exists, and the second call finds 0xd1 created
+        assert_eq!(
+            *created.borrow(),
+            [
+                (H160::from_low_u64_be(0xd1), NewAccountCause::Call, 25_000),
+                (
+                    H160::from_low_u64_be(0xd2),
+                    NewAccountCause::Suicide,
+                    25_000
+                ),
+            ]
+        );
+
+        created.borrow_mut().clear();
+        let (reason, _) = using(&mut listener, || {
+            executor.transact_call(
+                caller,
+                H160::from_low_u64_be(0xd3),
+                U256::from(1),
+                Vec::new(),
+                100_000,
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        assert!(reason.is_succeed());
+        assert_eq!(
+            *created.borrow(),
+            [(H160::from_low_u64_be(0xd3), NewAccountCause::Transaction, 0)]
+        );
+    }
+
+    #[test]
+    fn test_selfdestruct_to_self_event() {
+        let contract = H160::from_low_u64_be(0xc1);
+        let vicinity = MemoryVicinity::new(BlockEnv::default(), TxEnv::default());
+        for (selfdestruct_to_self, burned) in [
+            (crate::SelfDestructBalance::Retain, false),
+            (crate::SelfDestructBalance::Burn, true),
+        ] {
+            // SELFDESTRUCT(ADDRESS)
+            let state = BTreeMap::from([(
+                contract,
+                MemoryAccount {
+                    balance: U256::from(7),
+                    code: vec![0x30, 0xff],
+                    ..MemoryAccount::default()
+                },
+            )]);
+            let backend = MemoryBackend::new(&vicinity, state);
+            let config = Config {
+                selfdestruct_to_self,
+                ..Config::cancun()
+            };
+            let mut executor = StackExecutorBuilder::new(&config, 100_000)
+                .backend(&backend)
+                .build();
+
+            let (events, mut listener) = recorder(|event| match event {
+                Event::SelfDestructToSelf {
+                    address,
+                    balance,
+                    burned,
+                } => Some((address, balance, burned)),
+                Event::Suicide { balance, .. } => Some((H160::zero(), balance, true)),
+                _ => None,
+            });
+            let (reason, _) = using(&mut listener, || {
+                executor.transact_call(
+                    H160::from_low_u64_be(1),
+                    contract,
+                    U256::zero(),
+                    Vec::new(),
+                    100_000,
+                    Vec::new(),
+                    Vec::new(),
+                )
+            });
+            assert!(reason.is_succeed());
+            let mut expected = vec![(contract, U256::from(7), burned)];
+            // Burning goes through the `Suicide` event
+            if burned {
+                expected.push((H160::zero(), U256::from(7), true));
+            }
+            assert_eq!(*events.borrow(), expected);
+        }
+    }
+}